@@ -3,7 +3,7 @@ use rlink::core::function::{Context, FlatMapFunction, SendableElementStream};
 use rlink::utils::date_time::current_timestamp_millis;
 use rlink::utils::stream::MemoryStream;
 use rlink_connector_kafka::buffer_gen::kafka_message;
-use rlink_connector_kafka::build_kafka_record;
+use rlink_connector_kafka::{build_kafka_record, PARTITION_UNASSIGNED};
 use rlink_example_utils::buffer_gen::model;
 
 use crate::entry::SerDeEntity;
@@ -39,7 +39,7 @@ impl FlatMapFunction for OutputMapperFunction {
             key.as_bytes(),
             body.as_bytes(),
             "",
-            0,
+            PARTITION_UNASSIGNED,
             0,
         )
         .unwrap();
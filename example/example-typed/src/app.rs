@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use rlink::core::backend::{CheckpointBackend, KeyedStateBackend};
+use rlink::core::data_stream::TDataStream;
+use rlink::core::env::{StreamApp, StreamExecutionEnvironment};
+use rlink::core::properties::{Properties, SystemProperties};
+use rlink::core::runtime::ClusterDescriptor;
+use rlink::functions::sink::print_sink;
+use rlink_example_utils::rand_input_format::RandInputFormat;
+
+use crate::entity::{Alert, Entity};
+
+/// Filter and map the typed records derived by `RlinkRecord` instead of the raw `Record`s
+#[derive(Clone, Debug)]
+pub struct TypedStreamApp {}
+
+#[async_trait]
+impl StreamApp for TypedStreamApp {
+    async fn prepare_properties(&self, properties: &mut Properties) {
+        // the `application_name` must be set in `prepare_properties`
+        properties.set_application_name("rlink-typed");
+
+        properties.set_keyed_state_backend(KeyedStateBackend::Memory);
+        properties.set_checkpoint_interval(Duration::from_secs(15));
+        properties.set_checkpoint(CheckpointBackend::Memory);
+        properties.set_pub_sub_channel_size(10000);
+    }
+
+    fn build_stream(&self, _properties: &Properties, env: &mut StreamExecutionEnvironment) {
+        env.register_source(RandInputFormat::new(1))
+            .typed_filter(|entity: &Entity| entity.value >= 90)
+            .typed_map(|entity: Entity| Alert {
+                timestamp: entity.timestamp,
+                name: entity.name.to_uppercase(),
+                value: entity.value,
+            })
+            .add_sink(print_sink());
+    }
+
+    async fn pre_worker_startup(&self, cluster_descriptor: &ClusterDescriptor) {
+        println!("{}", cluster_descriptor.coordinator_manager.web_address);
+    }
+}
@@ -0,0 +1,15 @@
+/// The typed view of `model`
+#[derive(Clone, Debug, RlinkRecord)]
+pub struct Entity {
+    pub timestamp: u64,
+    pub name: String,
+    pub value: i64,
+}
+
+/// The entity of a high value
+#[derive(Clone, Debug, RlinkRecord)]
+pub struct Alert {
+    pub timestamp: u64,
+    pub name: String,
+    pub value: i64,
+}
@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate async_trait;
+
+mod app;
+mod entity;
+
+#[tokio::main]
+async fn main() {
+    rlink::core::env::execute(app::TypedStreamApp {}).await;
+}
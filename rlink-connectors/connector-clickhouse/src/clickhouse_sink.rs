@@ -10,19 +10,49 @@ use rlink::channel::{named_channel, TryRecvError};
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
 use rlink::core::element::{Element, FnSchema, Record};
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::core::runtime::CheckpointId;
 use rlink::{core, utils};
+use tokio::sync::oneshot;
 
 pub type CkBlock = clickhouse_rs::Block;
 
+/// The max delay between the retries of a failed batch
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
 pub trait ClickhouseConverter: Send + Sync {
     fn create_batch(&self, batch_size: usize) -> Box<dyn ClickhouseBatch>;
 }
 
 pub trait ClickhouseBatch: Send + Sync {
-    fn append(&mut self, record: Record);
+    /// append the record to the batch, the batch is unchanged if it fails
+    fn append(&mut self, record: Record) -> anyhow::Result<()>;
     fn flush(&mut self) -> CkBlock;
 }
 
+/// Message handed over from the `ClickhouseSink` to the `ClickhouseSinkTask`
+pub enum ClickhouseSinkMessage {
+    Record(Record),
+    /// write the buffered records and ack the result, sent when the sink snapshots the checkpoint
+    Flush(CheckpointId, oneshot::Sender<anyhow::Result<()>>),
+}
+
+/// Retry the failed batch `max_retries` times, the delay begins with `backoff` and doubles
+/// after each retry, up to 60s
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
 #[derive(NamedFunction)]
 pub struct ClickhouseSink {
     url: String,
@@ -30,7 +60,11 @@ pub struct ClickhouseSink {
     batch_size: usize,
     batch_timeout: Duration,
     converter: Arc<Box<dyn ClickhouseConverter>>,
-    sender: Option<ChannelSender<Record>>,
+    retry_policy: RetryPolicy,
+    deduplication: bool,
+    sender: Option<ChannelSender<ClickhouseSinkMessage>>,
+    /// the write task has exited, only when the channel is closed
+    task_exited: bool,
 }
 
 impl ClickhouseSink {
@@ -47,8 +81,53 @@ impl ClickhouseSink {
             batch_size,
             batch_timeout,
             converter: Arc::new(builder),
+            retry_policy: RetryPolicy::default(),
+            deduplication: false,
             sender: None,
+            task_exited: false,
+        }
+    }
+
+    /// Retry the failed batches with backoff instead of failing the next checkpoint immediately.
+    /// The batch is kept and written again after the retries fail, the later records and
+    /// checkpoints wait for it.
+    pub fn retry(mut self, max_retries: usize, backoff: Duration) -> Self {
+        self.retry_policy = RetryPolicy {
+            max_retries,
+            backoff,
+        };
+        self
+    }
+
+    /// Insert each batch with an `insert_deduplication_token` of
+    /// `{checkpoint_id}-{task_number}-{batch sequence}`, where the `checkpoint_id` is the last
+    /// checkpoint before the batch. The replayed batches after recovery get the same tokens and
+    /// are ignored by the Replicated*MergeTree tables, or the MergeTree tables with the
+    /// `non_replicated_deduplication_window` setting.
+    ///
+    /// The tokens are only reproducible when the batches are, so the batches are cut by the
+    /// `batch_size` and the checkpoints only, the `batch_timeout` is ignored.
+    pub fn deduplication(mut self) -> Self {
+        self.deduplication = true;
+        self
+    }
+
+    /// wait for the records handed over before are written to clickhouse
+    async fn flush(&mut self, checkpoint_id: CheckpointId) -> anyhow::Result<()> {
+        if self.task_exited {
+            return Err(anyhow!("clickhouse write task has exited"));
+        }
+
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        let message = ClickhouseSinkMessage::Flush(checkpoint_id, ack_sender);
+        if self.sender.as_ref().unwrap().send(message).await.is_err() {
+            self.task_exited = true;
+            return Err(anyhow!("clickhouse write task has exited"));
         }
+
+        ack_receiver
+            .await
+            .map_err(|_e| anyhow!("clickhouse write task has exited"))?
     }
 }
 
@@ -75,21 +154,29 @@ impl OutputFormat for ClickhouseSink {
             self.batch_timeout,
             self.converter.clone(),
             receiver,
-        );
+        )
+        .retry(self.retry_policy);
+        if self.deduplication {
+            // the batches after the restored checkpoint are replayed with the same tokens
+            task = task.deduplication(context.task_id.task_number(), context.checkpoint_id);
+        }
+
         tokio::spawn(async move {
-            task.run().await.unwrap();
+            if let Err(e) = task.run().await {
+                error!("clickhouse write task exit. {}", e);
+            }
         });
 
         Ok(())
     }
 
     async fn write_element(&mut self, element: Element) {
-        self.sender
-            .as_ref()
-            .unwrap()
-            .send(element.into_record())
-            .await
-            .unwrap();
+        let message = ClickhouseSinkMessage::Record(element.into_record());
+        if self.sender.as_ref().unwrap().send(message).await.is_err() && !self.task_exited {
+            // surfaced as a failure of the next checkpoint
+            error!("clickhouse write task has exited, the records are discarded");
+            self.task_exited = true;
+        }
     }
 
     async fn close(&mut self) -> core::Result<()> {
@@ -112,8 +199,11 @@ impl CheckpointFunction for ClickhouseSink {
 
     async fn snapshot_state(
         &mut self,
-        _context: &FunctionSnapshotContext,
+        context: &FunctionSnapshotContext,
     ) -> Option<CheckpointHandle> {
+        if let Err(e) = self.flush(context.checkpoint_id).await {
+            context.decline(format!("flush clickhouse error. {}", e).as_str());
+        }
         None
     }
 }
@@ -124,7 +214,18 @@ pub struct ClickhouseSinkTask {
     batch_size: usize,
     batch_timeout: Duration,
     converter: Arc<Box<dyn ClickhouseConverter>>,
-    receiver: ChannelReceiver<Record>,
+    receiver: ChannelReceiver<ClickhouseSinkMessage>,
+    retry_policy: RetryPolicy,
+    deduplication: Option<Deduplication>,
+    /// the batch failed after the retries of the `retry_policy`, it's retried until written
+    /// before the later records are read, so a flush is acked only after it's written
+    failed_batch: Option<FailedBatch>,
+    /// the records failed to append to the batch since the last flush, they are discarded and
+    /// the next flush fails with the first error
+    discarded: usize,
+    discard_error: Option<String>,
+    /// the `ClickhouseSink` has been dropped
+    disconnected: bool,
 }
 
 impl ClickhouseSinkTask {
@@ -134,7 +235,7 @@ impl ClickhouseSinkTask {
         batch_size: usize,
         batch_timeout: Duration,
         builder: Arc<Box<dyn ClickhouseConverter>>,
-        handover: ChannelReceiver<Record>,
+        handover: ChannelReceiver<ClickhouseSinkMessage>,
     ) -> Self {
         let opts = Options::from_str(url).expect("parse clickhouse url error");
         let pool = Pool::new(opts);
@@ -145,9 +246,25 @@ impl ClickhouseSinkTask {
             batch_timeout,
             converter: builder,
             receiver: handover,
+            retry_policy: RetryPolicy::default(),
+            deduplication: None,
+            failed_batch: None,
+            discarded: 0,
+            discard_error: None,
+            disconnected: false,
         }
     }
 
+    pub fn retry(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn deduplication(mut self, task_number: u16, checkpoint_id: CheckpointId) -> Self {
+        self.deduplication = Some(Deduplication::new(task_number, checkpoint_id));
+        self
+    }
+
     // pub async fn run(&mut self, tasks: usize) {
     //     let mut join_handlers = Vec::new();
     //     for _ in 0..tasks {
@@ -171,24 +288,75 @@ impl ClickhouseSinkTask {
     // }
 
     pub async fn run(&mut self) -> anyhow::Result<()> {
-        let mut client = self.pool.get_handle().await?;
+        let mut client = self.connect().await;
         loop {
-            match self.batch_send(client.borrow_mut()).await {
+            let result = match self.failed_batch.take() {
+                Some(failed_batch) => self.resend(client.borrow_mut(), failed_batch).await,
+                None => self.batch_send(client.borrow_mut()).await,
+            };
+
+            match result {
                 Ok(len) => {
+                    if self.disconnected {
+                        info!("clickhouse channel has been disconnected, write task exit");
+                        return Ok(());
+                    }
+
                     if len == 0 {
                         tokio::time::sleep(Duration::from_secs(1)).await;
                     }
                 }
                 Err(e) => {
+                    if self.disconnected {
+                        return Err(e);
+                    }
+
                     error!("write clickhouse error. {}", e);
+                    if let Err(e) = self.reconnection(client.borrow_mut()).await {
+                        error!("reconnection clickhouse failed, keep retrying. {}", e);
+                    }
+                }
+            }
+        }
+    }
 
-                    // todo reconnection
-                    self.reconnection(client.borrow_mut()).await?;
+    /// Wait for the connection of clickhouse, the records are blocked in the channel meanwhile
+    async fn connect(&mut self) -> ClientHandle {
+        let mut backoff = self.retry_policy.backoff;
+        loop {
+            match self.pool.get_handle().await {
+                Ok(client) => return client,
+                Err(e) => {
+                    error!("connect clickhouse error, retry after {:?}. {}", backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
                 }
             }
         }
     }
 
+    /// Retry the failed batch, the later records are blocked until it's written
+    async fn resend(
+        &mut self,
+        client: &mut ClientHandle,
+        failed_batch: FailedBatch,
+    ) -> anyhow::Result<usize> {
+        let FailedBatch { block, token, size } = failed_batch;
+        match self
+            .insert_with_retry(client, block.clone(), token.clone())
+            .await
+        {
+            Ok(_) => {
+                info!("the failed batch of {} records is written", size);
+                Ok(size)
+            }
+            Err(e) => {
+                self.failed_batch = Some(FailedBatch { block, token, size });
+                Err(e)
+            }
+        }
+    }
+
     async fn reconnection(&mut self, client: &mut ClientHandle) -> anyhow::Result<()> {
         let mut err = None;
         for _ in 0..180 {
@@ -215,39 +383,174 @@ impl ClickhouseSinkTask {
         let mut batch_block = self.converter.create_batch(self.batch_size);
         let begin_timestamp = utils::date_time::current_timestamp();
         let mut size = 0;
-        for n in 0..self.batch_size {
+        let mut flush_ack = None;
+        while size < self.batch_size {
             match self.receiver.try_recv() {
-                Ok(record) => {
-                    batch_block.append(record);
-                    size = n;
+                Ok(ClickhouseSinkMessage::Record(record)) => match batch_block.append(record) {
+                    Ok(()) => size += 1,
+                    Err(e) => {
+                        error!(
+                            "append the record to clickhouse batch error, discard it. {}",
+                            e
+                        );
+                        self.discarded += 1;
+                        if self.discard_error.is_none() {
+                            self.discard_error = Some(e.to_string());
+                        }
+                    }
+                },
+                Ok(ClickhouseSinkMessage::Flush(checkpoint_id, ack)) => {
+                    flush_ack = Some((checkpoint_id, ack));
+                    break;
                 }
                 Err(TryRecvError::Empty) => {
                     tokio::time::sleep(Duration::from_millis(100)).await;
                     let current_timestamp = utils::date_time::current_timestamp();
-                    if current_timestamp - begin_timestamp > self.batch_timeout {
+                    if self.deduplication.is_none()
+                        && current_timestamp - begin_timestamp > self.batch_timeout
+                    {
                         break;
                     }
                 }
                 Err(TryRecvError::Disconnected) => {
-                    return Err(anyhow!("clickhouse channel has been disconnected"));
+                    self.disconnected = true;
+                    break;
                 }
             }
         }
 
-        if size > 0 {
+        let result = if size > 0 {
             let block = batch_block.flush();
-            client.insert(self.table.as_str(), block).await?;
+            let token = self.deduplication.as_mut().map(|x| x.next_token());
+            let result = self
+                .insert_with_retry(client, block.clone(), token.clone())
+                .await;
+            if result.is_err() {
+                self.failed_batch = Some(FailedBatch { block, token, size });
+            }
+            result
+        } else {
+            Ok(())
+        };
+
+        if let Some((checkpoint_id, ack)) = flush_ack {
+            if let Some(deduplication) = self.deduplication.as_mut() {
+                deduplication.reset(checkpoint_id);
+            }
+
+            let flush_result = match (&result, self.discard_error.take()) {
+                (Err(e), _) => Err(anyhow!("{}", e)),
+                (Ok(_), Some(e)) => Err(anyhow!(
+                    "{} records are discarded, the first error: {}",
+                    self.discarded,
+                    e
+                )),
+                (Ok(_), None) => Ok(()),
+            };
+            self.discarded = 0;
+            if ack.send(flush_result).is_err() {
+                warn!("the flush ack receiver has been dropped");
+            }
         }
 
-        Ok(size)
+        result.map(|_| size)
+    }
+
+    async fn insert_with_retry(
+        &mut self,
+        client: &mut ClientHandle,
+        block: CkBlock,
+        token: Option<String>,
+    ) -> anyhow::Result<()> {
+        let mut backoff = self.retry_policy.backoff;
+        let mut retries = 0;
+        loop {
+            match self.insert(client, block.clone(), token.as_ref()).await {
+                Ok(_) => return Ok(()),
+                Err(e) if retries < self.retry_policy.max_retries => {
+                    retries += 1;
+                    warn!(
+                        "write clickhouse error, retry {}/{} after {:?}. {}",
+                        retries, self.retry_policy.max_retries, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
+
+                    if let Err(e) = client.check_connection().await {
+                        error!("reconnection error. {:?}", e);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn insert(
+        &self,
+        client: &mut ClientHandle,
+        block: CkBlock,
+        token: Option<&String>,
+    ) -> anyhow::Result<()> {
+        if let Some(token) = token {
+            // the setting is kept by the session of the connection,
+            // so it's set before each insert in case of reconnection
+            client
+                .execute(format!("SET insert_deduplication_token = '{}'", token))
+                .await?;
+        }
+
+        client.insert(self.table.as_str(), block).await?;
+        Ok(())
+    }
+}
+
+/// A batch failed to write, kept with its deduplication token to be written again
+struct FailedBatch {
+    block: CkBlock,
+    token: Option<String>,
+    size: usize,
+}
+
+/// Generate the deduplication tokens of the batches between two checkpoints
+#[derive(Clone, Debug)]
+struct Deduplication {
+    task_number: u16,
+    checkpoint_id: CheckpointId,
+    batch_sequence: u64,
+}
+
+impl Deduplication {
+    fn new(task_number: u16, checkpoint_id: CheckpointId) -> Self {
+        Deduplication {
+            task_number,
+            checkpoint_id,
+            batch_sequence: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> String {
+        let token = format!(
+            "{}-{}-{}",
+            self.checkpoint_id.0, self.task_number, self.batch_sequence
+        );
+        self.batch_sequence += 1;
+        token
+    }
+
+    fn reset(&mut self, checkpoint_id: CheckpointId) {
+        self.checkpoint_id = checkpoint_id;
+        self.batch_sequence = 0;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use clickhouse_rs::Options;
+    use rlink::core::runtime::CheckpointId;
     use std::str::FromStr;
 
+    use crate::clickhouse_sink::Deduplication;
+
     #[test]
     pub fn options_test() {
         let opt = Options::from_str(
@@ -256,4 +559,14 @@ mod tests {
         .unwrap();
         println!("{:?}", opt);
     }
+
+    #[test]
+    pub fn deduplication_token_test() {
+        let mut deduplication = Deduplication::new(3, CheckpointId(10));
+        assert_eq!(deduplication.next_token(), "10-3-0");
+        assert_eq!(deduplication.next_token(), "10-3-1");
+
+        deduplication.reset(CheckpointId(11));
+        assert_eq!(deduplication.next_token(), "11-3-0");
+    }
 }
@@ -8,6 +8,7 @@ extern crate async_trait;
 extern crate anyhow;
 
 pub mod clickhouse_sink;
+pub mod schema_converter;
 
 use chrono::{DateTime, NaiveDateTime, TimeZone};
 use chrono_tz::Asia;
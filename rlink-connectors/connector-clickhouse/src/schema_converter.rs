@@ -0,0 +1,239 @@
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::Record;
+
+use crate::clickhouse_sink::{CkBlock, ClickhouseBatch, ClickhouseConverter};
+
+/// Convert the `Record` to the columns of the same names as the schema fields, the field types
+/// are mapped to the ClickHouse column types:
+///
+/// | rlink                  | ClickHouse                 |
+/// |------------------------|----------------------------|
+/// | Boolean                | UInt8 (Bool)               |
+/// | Int8 ~ Int64           | Int8 ~ Int64               |
+/// | UInt8 ~ UInt64         | UInt8 ~ UInt64             |
+/// | Float32, Float64       | Float32, Float64           |
+/// | String, Binary         | String                     |
+///
+/// The block is casted to the table column types when inserting, so the compatible types, such as
+/// `DateTime` for `UInt32`, are also accepted.
+#[derive(Clone, Debug)]
+pub struct SchemaClickhouseConverter {
+    schema: Schema,
+}
+
+impl SchemaClickhouseConverter {
+    pub fn new(schema: Schema) -> Self {
+        SchemaClickhouseConverter { schema }
+    }
+}
+
+impl ClickhouseConverter for SchemaClickhouseConverter {
+    fn create_batch(&self, batch_size: usize) -> Box<dyn ClickhouseBatch> {
+        Box::new(SchemaClickhouseBatch::new(self.schema.clone(), batch_size))
+    }
+}
+
+enum ColumnValues {
+    Boolean(Vec<u8>),
+    Int8(Vec<i8>),
+    UInt8(Vec<u8>),
+    Int16(Vec<i16>),
+    UInt16(Vec<u16>),
+    Int32(Vec<i32>),
+    UInt32(Vec<u32>),
+    Int64(Vec<i64>),
+    UInt64(Vec<u64>),
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Binary(Vec<Vec<u8>>),
+    String(Vec<String>),
+}
+
+impl ColumnValues {
+    fn new(data_type: &DataType, capacity: usize) -> Self {
+        match data_type {
+            DataType::Boolean => Self::Boolean(Vec::with_capacity(capacity)),
+            DataType::Int8 => Self::Int8(Vec::with_capacity(capacity)),
+            DataType::UInt8 => Self::UInt8(Vec::with_capacity(capacity)),
+            DataType::Int16 => Self::Int16(Vec::with_capacity(capacity)),
+            DataType::UInt16 => Self::UInt16(Vec::with_capacity(capacity)),
+            DataType::Int32 => Self::Int32(Vec::with_capacity(capacity)),
+            DataType::UInt32 => Self::UInt32(Vec::with_capacity(capacity)),
+            DataType::Int64 => Self::Int64(Vec::with_capacity(capacity)),
+            DataType::UInt64 => Self::UInt64(Vec::with_capacity(capacity)),
+            DataType::Float32 => Self::Float32(Vec::with_capacity(capacity)),
+            DataType::Float64 => Self::Float64(Vec::with_capacity(capacity)),
+            DataType::Binary => Self::Binary(Vec::with_capacity(capacity)),
+            DataType::String => Self::String(Vec::with_capacity(capacity)),
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        match self {
+            Self::Boolean(v) => v.truncate(len),
+            Self::Int8(v) => v.truncate(len),
+            Self::UInt8(v) => v.truncate(len),
+            Self::Int16(v) => v.truncate(len),
+            Self::UInt16(v) => v.truncate(len),
+            Self::Int32(v) => v.truncate(len),
+            Self::UInt32(v) => v.truncate(len),
+            Self::Int64(v) => v.truncate(len),
+            Self::UInt64(v) => v.truncate(len),
+            Self::Float32(v) => v.truncate(len),
+            Self::Float64(v) => v.truncate(len),
+            Self::Binary(v) => v.truncate(len),
+            Self::String(v) => v.truncate(len),
+        }
+    }
+}
+
+pub struct SchemaClickhouseBatch {
+    schema: Schema,
+    batch_size: usize,
+    columns: Vec<ColumnValues>,
+    /// the number of the appended records
+    rows: usize,
+}
+
+impl SchemaClickhouseBatch {
+    pub fn new(schema: Schema, batch_size: usize) -> Self {
+        let columns = Self::create_columns(&schema, batch_size);
+        SchemaClickhouseBatch {
+            schema,
+            batch_size,
+            columns,
+            rows: 0,
+        }
+    }
+
+    fn create_columns(schema: &Schema, batch_size: usize) -> Vec<ColumnValues> {
+        schema
+            .fields()
+            .iter()
+            .map(|field| ColumnValues::new(field.data_type(), batch_size))
+            .collect()
+    }
+
+    fn append0(&mut self, mut record: Record) -> std::io::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            match column {
+                ColumnValues::Boolean(v) => v.push(reader.get_bool(index)? as u8),
+                ColumnValues::Int8(v) => v.push(reader.get_i8(index)?),
+                ColumnValues::UInt8(v) => v.push(reader.get_u8(index)?),
+                ColumnValues::Int16(v) => v.push(reader.get_i16(index)?),
+                ColumnValues::UInt16(v) => v.push(reader.get_u16(index)?),
+                ColumnValues::Int32(v) => v.push(reader.get_i32(index)?),
+                ColumnValues::UInt32(v) => v.push(reader.get_u32(index)?),
+                ColumnValues::Int64(v) => v.push(reader.get_i64(index)?),
+                ColumnValues::UInt64(v) => v.push(reader.get_u64(index)?),
+                ColumnValues::Float32(v) => v.push(reader.get_f32(index)?),
+                ColumnValues::Float64(v) => v.push(reader.get_f64(index)?),
+                ColumnValues::Binary(v) => v.push(reader.get_binary(index)?.to_vec()),
+                ColumnValues::String(v) => v.push(reader.get_str(index)?.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ClickhouseBatch for SchemaClickhouseBatch {
+    fn append(&mut self, record: Record) -> anyhow::Result<()> {
+        if let Err(e) = self.append0(record) {
+            // remove the values of the record appended to the leading columns
+            for column in self.columns.iter_mut() {
+                column.truncate(self.rows);
+            }
+            return Err(anyhow!("the record is not matched with the schema. {}", e));
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> CkBlock {
+        let columns = std::mem::replace(
+            &mut self.columns,
+            Self::create_columns(&self.schema, self.batch_size),
+        );
+        self.rows = 0;
+
+        let mut block = CkBlock::with_capacity(self.batch_size);
+        for (field, column) in self.schema.fields().iter().zip(columns) {
+            let name = field.name();
+            block = match column {
+                ColumnValues::Boolean(v) => block.column(name, v),
+                ColumnValues::Int8(v) => block.column(name, v),
+                ColumnValues::UInt8(v) => block.column(name, v),
+                ColumnValues::Int16(v) => block.column(name, v),
+                ColumnValues::UInt16(v) => block.column(name, v),
+                ColumnValues::Int32(v) => block.column(name, v),
+                ColumnValues::UInt32(v) => block.column(name, v),
+                ColumnValues::Int64(v) => block.column(name, v),
+                ColumnValues::UInt64(v) => block.column(name, v),
+                ColumnValues::Float32(v) => block.column(name, v),
+                ColumnValues::Float64(v) => block.column(name, v),
+                ColumnValues::Binary(v) => {
+                    let v: Vec<&[u8]> = v.iter().map(|x| x.as_slice()).collect();
+                    block.column(name, v)
+                }
+                ColumnValues::String(v) => block.column(name, v),
+            };
+        }
+
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::clickhouse_sink::ClickhouseConverter;
+    use crate::schema_converter::SchemaClickhouseConverter;
+
+    #[test]
+    pub fn schema_converter_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+            Field::new("valid", DataType::Boolean),
+        ]);
+        let converter = SchemaClickhouseConverter::new(schema.clone());
+        let mut batch = converter.create_batch(10);
+
+        for id in 0..3 {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(schema.as_type_ids());
+            writer.set_i64(id).unwrap();
+            writer.set_str(format!("name-{}", id).as_str()).unwrap();
+            writer.set_bool(id % 2 == 0).unwrap();
+            batch.append(record).unwrap();
+        }
+
+        // the `name` is not an utf8 string, the batch is unchanged
+        let binary_schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::Binary),
+            Field::new("valid", DataType::Boolean),
+        ]);
+        let mut record = Record::new();
+        let mut writer = record.as_writer(binary_schema.as_type_ids());
+        writer.set_i64(3).unwrap();
+        writer.set_binary(&[0xff, 0xfe]).unwrap();
+        writer.set_bool(true).unwrap();
+        assert!(batch.append(record).is_err());
+
+        let block = batch.flush();
+        assert_eq!(block.column_count(), 3);
+        assert_eq!(block.row_count(), 3);
+        assert_eq!(block.get::<i64, _>(2, "id").unwrap(), 2);
+        assert_eq!(block.get::<String, _>(1, "name").unwrap(), "name-1");
+        assert_eq!(block.get::<u8, _>(0, "valid").unwrap(), 1);
+
+        // the batch is reset after flushing
+        assert_eq!(batch.flush().row_count(), 0);
+    }
+}
@@ -7,7 +7,7 @@ use std::time::Duration;
 use elasticsearch::http::headers::HeaderMap;
 use elasticsearch::http::request::JsonBody;
 use elasticsearch::http::transport::{SingleNodeConnectionPool, TransportBuilder};
-use elasticsearch::http::Url;
+use elasticsearch::http::{StatusCode, Url};
 use elasticsearch::{BulkParts, Elasticsearch};
 use rlink::channel::receiver::ChannelReceiver;
 use rlink::channel::sender::ChannelSender;
@@ -17,10 +17,17 @@ use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnap
 use rlink::core::element::{Element, FnSchema, Record};
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
 use serde_json::Value;
+use tokio::sync::{oneshot, Mutex};
+
+/// The max delay between the retries of the failed documents
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
 
 pub struct ElasticsearchModel {
     pub index: String,
     pub es_type: &'static str,
+    /// the document `_id`, the document of the same id is replaced by the later one,
+    /// so the replayed records after recovery are written idempotently
+    pub id: Option<String>,
     pub body: Value,
 }
 
@@ -44,6 +51,10 @@ impl Index {
         self.index.insert("_type".to_string(), type_value);
     }
 
+    pub fn set_id(&mut self, id_value: String) {
+        self.index.insert("_id".to_string(), id_value);
+    }
+
     pub fn to_json(&self) -> Result<Value, serde_json::Error> {
         serde_json::to_value(self)
     }
@@ -53,13 +64,92 @@ pub trait ElasticsearchConverter: Send + Sync {
     fn to_json(&self, record: &mut Record) -> ElasticsearchModel;
 }
 
+/// Retry the documents rejected with the status `429` or `5xx`, and the bulk requests failed
+/// to send, `max_retries` times. The delay begins with `backoff` and doubles after each retry,
+/// up to 60s. The `FailureAction::Retry` of the `ElasticsearchFailureHandler` is also bounded by
+/// `max_retries`
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// A document failed in the bulk request, and not retried by the `RetryPolicy`
+#[derive(Clone, Debug)]
+pub struct BulkItemFailure {
+    pub index: String,
+    pub id: Option<String>,
+    /// the item status of the bulk response
+    pub status: u16,
+    /// the item error of the bulk response
+    pub error: Value,
+    /// the times the document has been retried
+    pub retries: usize,
+}
+
+impl BulkItemFailure {
+    pub fn is_retryable(&self) -> bool {
+        is_retryable(self.status)
+    }
+}
+
+/// What to do with the failed document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureAction {
+    /// fail the next checkpoint
+    Fail,
+    /// discard the document
+    Drop,
+    /// send the document again after the backoff delay, it fails when the `max_retries` of the
+    /// `RetryPolicy` is exhausted
+    Retry,
+    /// write the record to the dead-letter `OutputFormat`
+    DeadLetter,
+}
+
+pub trait ElasticsearchFailureHandler: Send + Sync {
+    fn on_failure(&self, failure: &BulkItemFailure) -> FailureAction;
+}
+
+impl<F> ElasticsearchFailureHandler for F
+where
+    F: Fn(&BulkItemFailure) -> FailureAction + Send + Sync,
+{
+    fn on_failure(&self, failure: &BulkItemFailure) -> FailureAction {
+        self(failure)
+    }
+}
+
+type DeadLetterOutputFormat = Arc<Mutex<Box<dyn OutputFormat>>>;
+
+/// Message handed over from the `ElasticsearchOutputFormat` to the `ElasticsearchWriteThread`
+pub enum ElasticsearchSinkMessage {
+    Record(Record),
+    /// write the buffered records and ack the result, sent when the sink snapshots
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+}
+
 #[derive(NamedFunction)]
 pub struct ElasticsearchOutputFormat {
     address: String,
     headers: HashMap<String, String>,
 
     builder: Arc<Box<dyn ElasticsearchConverter>>,
-    sender: Option<ChannelSender<Record>>,
+    retry_policy: RetryPolicy,
+    failure_handler: Option<Arc<dyn ElasticsearchFailureHandler>>,
+    dead_letter: Option<DeadLetterOutputFormat>,
+    sender: Option<ChannelSender<ElasticsearchSinkMessage>>,
+    /// the write thread has exited, all the later checkpoints are declined
+    thread_exited: bool,
 }
 
 impl ElasticsearchOutputFormat {
@@ -72,8 +162,54 @@ impl ElasticsearchOutputFormat {
             address: address.to_string(),
             headers,
             builder: Arc::new(builder),
+            retry_policy: RetryPolicy::default(),
+            failure_handler: None,
+            dead_letter: None,
             sender: None,
+            thread_exited: false,
+        }
+    }
+
+    pub fn retry(mut self, max_retries: usize, backoff: Duration) -> Self {
+        self.retry_policy = RetryPolicy {
+            max_retries,
+            backoff,
+        };
+        self
+    }
+
+    /// Handle the failed documents, all of them fail the next checkpoint by default
+    pub fn failure_handler<H>(mut self, failure_handler: H) -> Self
+    where
+        H: ElasticsearchFailureHandler + 'static,
+    {
+        self.failure_handler = Some(Arc::new(failure_handler));
+        self
+    }
+
+    /// The `OutputFormat` the records of the `FailureAction::DeadLetter` documents are written
+    /// to, it's opened, snapshotted and closed with the sink.
+    pub fn dead_letter(mut self, output_format: Box<dyn OutputFormat>) -> Self {
+        self.dead_letter = Some(Arc::new(Mutex::new(output_format)));
+        self
+    }
+
+    /// wait for the records handed over before are written to elasticsearch
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.thread_exited {
+            return Err(anyhow!("elasticsearch write thread has exited"));
+        }
+
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        let message = ElasticsearchSinkMessage::Flush(ack_sender);
+        if self.sender.as_ref().unwrap().send(message).await.is_err() {
+            self.thread_exited = true;
+            return Err(anyhow!("elasticsearch write thread has exited"));
         }
+
+        ack_receiver
+            .await
+            .map_err(|_e| anyhow!("elasticsearch write thread has exited"))?
     }
 }
 
@@ -83,13 +219,24 @@ impl OutputFormat for ElasticsearchOutputFormat {
         let (sender, receiver) = named_channel(self.name(), context.task_id.to_tags(), 10000);
         self.sender = Some(sender);
 
+        if let Some(dead_letter) = &self.dead_letter {
+            dead_letter.lock().await.open(context).await?;
+        }
+
         let mut write_thead = ElasticsearchWriteThread::new(
             self.address.as_str(),
             self.headers.clone(),
             receiver,
             3000,
         )
-        .expect("build elasticsearch connection error");
+        .expect("build elasticsearch connection error")
+        .retry(self.retry_policy);
+        if let Some(failure_handler) = &self.failure_handler {
+            write_thead = write_thead.failure_handler(failure_handler.clone());
+        }
+        if let Some(dead_letter) = &self.dead_letter {
+            write_thead = write_thead.dead_letter(dead_letter.clone());
+        }
 
         let convert = self.builder.clone();
         tokio::spawn(async move {
@@ -100,15 +247,18 @@ impl OutputFormat for ElasticsearchOutputFormat {
     }
 
     async fn write_element(&mut self, element: Element) {
-        self.sender
-            .as_ref()
-            .unwrap()
-            .send(element.into_record())
-            .await
-            .unwrap();
+        let message = ElasticsearchSinkMessage::Record(element.into_record());
+        if self.sender.as_ref().unwrap().send(message).await.is_err() && !self.thread_exited {
+            // surfaced as a failure of the next checkpoint
+            error!("elasticsearch write thread has exited, the records are discarded");
+            self.thread_exited = true;
+        }
     }
 
     async fn close(&mut self) -> core::Result<()> {
+        if let Some(dead_letter) = &self.dead_letter {
+            dead_letter.lock().await.close().await?;
+        }
         Ok(())
     }
 
@@ -128,23 +278,104 @@ impl CheckpointFunction for ElasticsearchOutputFormat {
 
     async fn snapshot_state(
         &mut self,
-        _context: &FunctionSnapshotContext,
+        context: &FunctionSnapshotContext,
     ) -> Option<CheckpointHandle> {
-        None
+        if let Err(e) = self.flush().await {
+            context.decline(format!("flush elasticsearch error. {}", e).as_str());
+        }
+
+        // the dead-letter records of the flushed documents have been written. The sink is
+        // stateless, so the handle is the dead-letter's, which is restored by its `open`
+        match &self.dead_letter {
+            Some(dead_letter) => dead_letter.lock().await.snapshot_state(context).await,
+            None => None,
+        }
     }
 }
 
+/// A document of the bulk request, the record is kept for the dead-letter `OutputFormat`
+struct BulkItem {
+    action: Value,
+    body: Value,
+    index: String,
+    id: Option<String>,
+    record: Record,
+    retries: usize,
+}
+
+/// The failed document of the bulk response
+#[derive(Clone, Debug, PartialEq)]
+struct BulkItemError {
+    status: u16,
+    error: Value,
+}
+
+fn is_retryable(status: u16) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS.as_u16() || status >= 500
+}
+
+/// Parse the per-item result of the bulk response, `None` for the succeeded items
+fn parse_bulk_response(
+    response_body: &Value,
+    item_size: usize,
+) -> anyhow::Result<Vec<Option<BulkItemError>>> {
+    let items = response_body["items"]
+        .as_array()
+        .ok_or(anyhow!("no items field in es response"))?;
+    if items.len() != item_size {
+        return Err(anyhow!(
+            "the items of es response mismatch, expect {}, found {}",
+            item_size,
+            items.len()
+        ));
+    }
+
+    items
+        .iter()
+        .map(|item| {
+            // the item is keyed by the action name, such as `{"index": {"status": 201}}`
+            let result = item
+                .as_object()
+                .and_then(|x| x.values().next())
+                .ok_or(anyhow!("unknown item in es response, {}", item))?;
+            let status = result["status"]
+                .as_u64()
+                .ok_or(anyhow!("no status field in es response item, {}", item))?
+                as u16;
+
+            if (200..300).contains(&status) {
+                Ok(None)
+            } else {
+                Ok(Some(BulkItemError {
+                    status,
+                    error: result["error"].clone(),
+                }))
+            }
+        })
+        .collect()
+}
+
 pub struct ElasticsearchWriteThread {
     client: Elasticsearch,
     batch_size: usize,
-    receiver: ChannelReceiver<Record>,
+    receiver: ChannelReceiver<ElasticsearchSinkMessage>,
+    retry_policy: RetryPolicy,
+    failure_handler: Option<Arc<dyn ElasticsearchFailureHandler>>,
+    dead_letter: Option<DeadLetterOutputFormat>,
+    /// the documents of the bulk request failed after the retries of the `retry_policy`, they're
+    /// sent again until written before the later records are read
+    failed_items: Option<Vec<BulkItem>>,
+    /// the first error of the documents failed since the last flush, fails the next flush
+    write_error: Option<String>,
+    /// the `ElasticsearchOutputFormat` has been dropped
+    disconnected: bool,
 }
 
 impl ElasticsearchWriteThread {
     pub fn new(
         address: &str,
         headers: HashMap<String, String>,
-        receiver: ChannelReceiver<Record>,
+        receiver: ChannelReceiver<ElasticsearchSinkMessage>,
         batch_size: usize,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header_map = HeaderMap::new();
@@ -164,85 +395,413 @@ impl ElasticsearchWriteThread {
             client,
             batch_size,
             receiver,
+            retry_policy: RetryPolicy::default(),
+            failure_handler: None,
+            dead_letter: None,
+            failed_items: None,
+            write_error: None,
+            disconnected: false,
         })
     }
 
+    pub fn retry(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn failure_handler(
+        mut self,
+        failure_handler: Arc<dyn ElasticsearchFailureHandler>,
+    ) -> Self {
+        self.failure_handler = Some(failure_handler);
+        self
+    }
+
+    pub fn dead_letter(mut self, dead_letter: Arc<Mutex<Box<dyn OutputFormat>>>) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+
     pub async fn run(&mut self, converters: Arc<Box<dyn ElasticsearchConverter>>) {
         let converter = converters.clone();
         self.run0(converter).await;
     }
 
     pub async fn run0(&mut self, converter: Arc<Box<dyn ElasticsearchConverter>>) {
+        let mut backoff = self.retry_policy.backoff;
         loop {
-            match self.batch_send(&converter).await {
+            let result = match self.failed_items.take() {
+                Some(failed_items) => self.resend(failed_items).await,
+                None => self.batch_send(&converter).await,
+            };
+
+            match result {
                 Ok(len) => {
+                    backoff = self.retry_policy.backoff;
+                    if self.disconnected {
+                        info!("elasticsearch channel has been disconnected, write thread exit");
+                        break;
+                    }
+
                     if len == 0 {
                         tokio::time::sleep(Duration::from_secs(1)).await;
                     }
                 }
                 Err(e) => {
-                    error!("write elasticsearch error. {}", e);
-                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    if self.disconnected {
+                        error!("write elasticsearch error, write thread exit. {}", e);
+                        break;
+                    }
+
+                    error!(
+                        "write elasticsearch error, retry after {:?}. {}",
+                        backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
                 }
             }
         }
     }
 
+    /// Send the failed documents again, the later records are blocked until they're written
+    async fn resend(&mut self, bulk_items: Vec<BulkItem>) -> anyhow::Result<usize> {
+        let len = bulk_items.len();
+        self.flush(bulk_items).await?;
+        info!("the failed {} elasticsearch documents are written", len);
+
+        Ok(len)
+    }
+
     async fn batch_send(
         &mut self,
         converter: &Box<dyn ElasticsearchConverter>,
     ) -> anyhow::Result<usize> {
-        let mut bulk_bodies = Vec::with_capacity(self.batch_size);
+        let mut bulk_items = Vec::with_capacity(self.batch_size);
+        let mut flush_ack = None;
         for _ in 0..self.batch_size {
             match self.receiver.try_recv() {
-                Ok(mut record) => {
+                Ok(ElasticsearchSinkMessage::Record(mut record)) => {
                     let ElasticsearchModel {
                         index,
                         es_type,
+                        id,
                         body,
                     } = converter.to_json(record.borrow_mut());
 
                     let mut index_model = Index::new();
                     index_model.set_index(index.clone());
                     index_model.set_type(es_type.to_string());
-                    bulk_bodies.push(JsonBody::new(index_model.to_json().unwrap()));
+                    if let Some(id) = &id {
+                        index_model.set_id(id.clone());
+                    }
 
-                    bulk_bodies.push(JsonBody::new(body));
+                    bulk_items.push(BulkItem {
+                        action: index_model.to_json().unwrap(),
+                        body,
+                        index,
+                        id,
+                        record,
+                        retries: 0,
+                    });
+                }
+                Ok(ElasticsearchSinkMessage::Flush(ack)) => {
+                    flush_ack = Some(ack);
+                    break;
                 }
                 Err(TryRecvError::Empty) => {
                     break;
                 }
                 Err(TryRecvError::Disconnected) => {
-                    return Err(anyhow!("clickhouse channel has been disconnected"));
+                    self.disconnected = true;
+                    break;
                 }
             }
         }
 
-        let len = bulk_bodies.len();
-        self.flush(bulk_bodies).await?;
+        let len = bulk_items.len();
+        let result = self.flush(bulk_items).await;
+
+        if let Some(ack) = flush_ack {
+            let flush_result = match (&result, self.write_error.take()) {
+                (Err(e), _) => Err(anyhow!("{}", e)),
+                (Ok(_), Some(e)) => Err(anyhow!("{}", e)),
+                (Ok(_), None) => Ok(()),
+            };
+            if ack.send(flush_result).is_err() {
+                warn!("the flush ack receiver has been dropped");
+            }
+        }
 
-        Ok(len)
+        result.map(|_| len)
+    }
+
+    /// Write the documents, retry the failed ones by the `RetryPolicy` and
+    /// the `ElasticsearchFailureHandler`.
+    ///
+    /// The documents of a bulk request failed with a retryable error after the retries are kept
+    /// in `failed_items` and the error is returned. The other failed documents are not written
+    /// again, the error is recorded in `write_error` to fail the next flush.
+    async fn flush(&mut self, bulk_items: Vec<BulkItem>) -> anyhow::Result<()> {
+        let mut pending_items = bulk_items;
+        let mut backoff = self.retry_policy.backoff;
+        let mut failed = Vec::new();
+        while !pending_items.is_empty() {
+            let mut retry_items = Vec::new();
+            match self.bulk(pending_items.as_slice()).await {
+                Ok(item_errors) => {
+                    for (item, item_error) in pending_items.into_iter().zip(item_errors) {
+                        if let Some(item_error) = item_error {
+                            if let Some(item) = self.on_failure(item, item_error, &mut failed).await
+                            {
+                                retry_items.push(item);
+                            }
+                        }
+                    }
+                }
+                Err((e, retryable)) => {
+                    if !retryable {
+                        self.set_write_error(format!(
+                            "{} elasticsearch documents failed. {}",
+                            pending_items.len(),
+                            e
+                        ));
+                        break;
+                    }
+
+                    let retries = pending_items[0].retries;
+                    if retries >= self.retry_policy.max_retries {
+                        self.failed_items = Some(pending_items);
+                        return Err(e);
+                    }
+                    warn!("elasticsearch bulk request error. {}", e);
+                    retry_items = pending_items;
+                }
+            }
+
+            if !retry_items.is_empty() {
+                warn!(
+                    "retry {} elasticsearch documents after {:?}",
+                    retry_items.len(),
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
+                retry_items.iter_mut().for_each(|item| item.retries += 1);
+            }
+            pending_items = retry_items;
+        }
+
+        if let Some(failure) = failed.first() {
+            self.set_write_error(format!(
+                "{} elasticsearch documents failed, the first one: {:?}",
+                failed.len(),
+                failure
+            ));
+        }
+
+        Ok(())
     }
 
-    async fn flush(&self, body_bulk: Vec<JsonBody<Value>>) -> anyhow::Result<bool> {
-        if body_bulk.len() == 0 {
-            return Ok(true);
+    fn set_write_error(&mut self, e: String) {
+        error!("{}", e);
+        if self.write_error.is_none() {
+            self.write_error = Some(e);
         }
+    }
+
+    /// Send the bulk request, return the error and whether the request can be retried
+    /// if the request failed
+    async fn bulk(
+        &self,
+        bulk_items: &[BulkItem],
+    ) -> Result<Vec<Option<BulkItemError>>, (anyhow::Error, bool)> {
+        let mut body_bulk = Vec::with_capacity(bulk_items.len() * 2);
+        for item in bulk_items {
+            body_bulk.push(JsonBody::new(&item.action));
+            body_bulk.push(JsonBody::new(&item.body));
+        }
+
         let response = self
             .client
             .bulk(BulkParts::None)
             .body(body_bulk)
             .send()
-            .await?;
-        let response_body = response.json::<Value>().await?;
+            .await
+            .map_err(|e| (anyhow::Error::from(e), true))?;
+
+        let status_code = response.status_code();
+        if !status_code.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err((
+                anyhow!("elasticsearch bulk error, status {}, {}", status_code, text),
+                is_retryable(status_code.as_u16()),
+            ));
+        }
+
+        let response_body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| (anyhow::Error::from(e), false))?;
         let errors = response_body["errors"]
             .as_bool()
-            .ok_or(anyhow!("no errors field in es response"))?;
+            .ok_or((anyhow!("no errors field in es response"), false))?;
+        if !errors {
+            return Ok(bulk_items.iter().map(|_| None).collect());
+        }
 
-        if errors {
-            Err(anyhow!("elasticsearch send error, {}", response_body))
-        } else {
-            Ok(true)
+        parse_bulk_response(&response_body, bulk_items.len()).map_err(|e| (e, false))
+    }
+
+    /// Handle the failed document, return the document if it should be retried
+    async fn on_failure(
+        &self,
+        item: BulkItem,
+        item_error: BulkItemError,
+        failed: &mut Vec<BulkItemFailure>,
+    ) -> Option<BulkItem> {
+        let failure = BulkItemFailure {
+            index: item.index.clone(),
+            id: item.id.clone(),
+            status: item_error.status,
+            error: item_error.error,
+            retries: item.retries,
+        };
+
+        let action = failure_action(
+            &self.retry_policy,
+            self.failure_handler.as_deref(),
+            &failure,
+        );
+        match action {
+            FailureAction::Fail => failed.push(failure),
+            FailureAction::Drop => {
+                warn!("drop the failed elasticsearch document. {:?}", failure);
+            }
+            FailureAction::Retry => return Some(item),
+            FailureAction::DeadLetter => match &self.dead_letter {
+                Some(dead_letter) => {
+                    warn!(
+                        "forward the failed elasticsearch document to the dead-letter. {:?}",
+                        failure
+                    );
+                    dead_letter
+                        .lock()
+                        .await
+                        .write_element(Element::from(item.record))
+                        .await;
+                }
+                None => {
+                    error!("no dead-letter output format, fail the document");
+                    failed.push(failure);
+                }
+            },
         }
+
+        None
+    }
+}
+
+/// The action of the failed document, the retries of the `RetryPolicy` come first, and the
+/// `FailureAction::Retry` of the handler falls back to `FailureAction::Fail` when the
+/// `max_retries` is exhausted
+fn failure_action(
+    retry_policy: &RetryPolicy,
+    failure_handler: Option<&dyn ElasticsearchFailureHandler>,
+    failure: &BulkItemFailure,
+) -> FailureAction {
+    let retries_exhausted = failure.retries >= retry_policy.max_retries;
+    if failure.is_retryable() && !retries_exhausted {
+        return FailureAction::Retry;
+    }
+
+    let action = match failure_handler {
+        Some(failure_handler) => failure_handler.on_failure(failure),
+        None => FailureAction::Fail,
+    };
+    if action == FailureAction::Retry && retries_exhausted {
+        warn!(
+            "the retries of the elasticsearch document are exhausted, fail it. {:?}",
+            failure
+        );
+        return FailureAction::Fail;
+    }
+
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::elasticsearch_sink::{
+        failure_action, parse_bulk_response, BulkItemError, BulkItemFailure, FailureAction,
+        RetryPolicy,
+    };
+
+    #[test]
+    pub fn parse_bulk_response_test() {
+        let response_body = serde_json::json!({
+            "errors": true,
+            "items": [
+                {"index": {"_index": "i", "_id": "1", "status": 201}},
+                {"index": {"_index": "i", "_id": "2", "status": 429, "error": {"type": "es_rejected_execution_exception"}}},
+                {"index": {"_index": "i", "_id": "3", "status": 400, "error": {"type": "mapper_parsing_exception"}}}
+            ]
+        });
+
+        let item_errors = parse_bulk_response(&response_body, 3).unwrap();
+        assert_eq!(item_errors[0], None);
+        assert_eq!(
+            item_errors[1],
+            Some(BulkItemError {
+                status: 429,
+                error: serde_json::json!({"type": "es_rejected_execution_exception"}),
+            })
+        );
+        assert_eq!(item_errors[2].as_ref().unwrap().status, 400);
+
+        assert!(parse_bulk_response(&response_body, 2).is_err());
+    }
+
+    #[test]
+    pub fn failure_action_test() {
+        let retry_policy = RetryPolicy {
+            max_retries: 2,
+            backoff: Duration::from_secs(1),
+        };
+        let failure = |status, retries| BulkItemFailure {
+            index: "i".to_string(),
+            id: None,
+            status,
+            error: serde_json::Value::Null,
+            retries,
+        };
+        let retry_handler = |_failure: &BulkItemFailure| FailureAction::Retry;
+        let drop_handler = |_failure: &BulkItemFailure| FailureAction::Drop;
+
+        // the retryable documents are retried by the `RetryPolicy`
+        assert_eq!(
+            failure_action(&retry_policy, None, &failure(429, 1)),
+            FailureAction::Retry
+        );
+        assert_eq!(
+            failure_action(&retry_policy, None, &failure(429, 2)),
+            FailureAction::Fail
+        );
+        assert_eq!(
+            failure_action(&retry_policy, Some(&drop_handler), &failure(429, 2)),
+            FailureAction::Drop
+        );
+
+        // the retries requested by the handler are bounded by the `max_retries`
+        assert_eq!(
+            failure_action(&retry_policy, Some(&retry_handler), &failure(400, 1)),
+            FailureAction::Retry
+        );
+        assert_eq!(
+            failure_action(&retry_policy, Some(&retry_handler), &failure(400, 2)),
+            FailureAction::Fail
+        );
     }
 }
@@ -0,0 +1,50 @@
+use std::fs::File;
+
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+
+use crate::format::{to_string_value, CountWrite, FileWriter};
+
+pub struct CsvFileWriter {
+    schema: Schema,
+    writer: csv::Writer<CountWrite<File>>,
+}
+
+impl CsvFileWriter {
+    pub fn new(schema: Schema, file: File, delimiter: u8, header: bool) -> anyhow::Result<Self> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(CountWrite::new(file));
+        if header {
+            writer.write_record(schema.fields().iter().map(|field| field.name()))?;
+        }
+
+        Ok(CsvFileWriter { schema, writer })
+    }
+}
+
+impl FileWriter for CsvFileWriter {
+    fn write(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        for (index, field) in self.schema.fields().iter().enumerate() {
+            let value = to_string_value(&reader, index, field.data_type())?;
+            self.writer.write_field(value)?;
+        }
+        self.writer.write_record(None::<&[u8]>)?;
+        Ok(())
+    }
+
+    fn size(&self) -> u64 {
+        self.writer.get_ref().count()
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let file = self
+            .writer
+            .into_inner()
+            .map_err(|e| anyhow!("flush csv file error. {}", e.error()))?
+            .into_inner();
+        file.sync_all()?;
+        Ok(())
+    }
+}
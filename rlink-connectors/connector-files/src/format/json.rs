@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use serde_json::{Map, Value};
+
+use crate::format::{to_json_value, CountWrite, FileWriter};
+
+pub struct JsonLinesFileWriter {
+    schema: Schema,
+    writer: BufWriter<CountWrite<File>>,
+}
+
+impl JsonLinesFileWriter {
+    pub fn new(schema: Schema, file: File) -> Self {
+        JsonLinesFileWriter {
+            schema,
+            writer: BufWriter::new(CountWrite::new(file)),
+        }
+    }
+}
+
+impl FileWriter for JsonLinesFileWriter {
+    fn write(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        let mut object = Map::with_capacity(self.schema.fields().len());
+        for (index, field) in self.schema.fields().iter().enumerate() {
+            let value = to_json_value(&reader, index, field.data_type())?;
+            object.insert(field.name().to_string(), value);
+        }
+
+        serde_json::to_writer(&mut self.writer, &Value::Object(object))?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn size(&self) -> u64 {
+        self.writer.get_ref().count() + self.writer.buffer().len() as u64
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let file = self
+            .writer
+            .into_inner()
+            .map_err(|e| anyhow!("flush json file error. {}", e.error()))?
+            .into_inner();
+        file.sync_all()?;
+        Ok(())
+    }
+}
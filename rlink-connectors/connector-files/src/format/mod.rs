@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::io::Write;
+
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{BufferReader, Record};
+use serde_json::{Map, Value};
+
+pub mod csv;
+pub mod json;
+pub mod parquet;
+
+/// The encoding of the part files, the records are encoded by the `Schema` of the sink input
+#[derive(Clone, Debug)]
+pub enum FileFormat {
+    /// CSV with the field names as the first line if `header` is true
+    Csv { delimiter: u8, header: bool },
+    /// a JSON object per line, keyed by the field names
+    JsonLines,
+    /// Parquet of the required columns, `row_group_size` records are buffered in memory for each
+    /// row group
+    Parquet { row_group_size: usize },
+}
+
+impl FileFormat {
+    pub fn csv() -> Self {
+        FileFormat::Csv {
+            delimiter: b',',
+            header: true,
+        }
+    }
+
+    pub fn json_lines() -> Self {
+        FileFormat::JsonLines
+    }
+
+    pub fn parquet() -> Self {
+        FileFormat::Parquet {
+            row_group_size: parquet::ROW_GROUP_SIZE_DEFAULT,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv { .. } => "csv",
+            Self::JsonLines => "json",
+            Self::Parquet { .. } => "parquet",
+        }
+    }
+
+    pub fn create_writer(
+        &self,
+        schema: &Schema,
+        file: File,
+    ) -> anyhow::Result<Box<dyn FileWriter>> {
+        let writer: Box<dyn FileWriter> = match self {
+            Self::Csv { delimiter, header } => Box::new(csv::CsvFileWriter::new(
+                schema.clone(),
+                file,
+                *delimiter,
+                *header,
+            )?),
+            Self::JsonLines => Box::new(json::JsonLinesFileWriter::new(schema.clone(), file)),
+            Self::Parquet { row_group_size } => Box::new(parquet::ParquetFileWriter::new(
+                schema.clone(),
+                file,
+                *row_group_size,
+            )?),
+        };
+        Ok(writer)
+    }
+}
+
+/// Write the records to a part file
+pub trait FileWriter: Send + Sync {
+    fn write(&mut self, record: &mut Record) -> anyhow::Result<()>;
+
+    /// The size of the part file, including the buffered records not written yet
+    fn size(&self) -> u64;
+
+    /// Write the buffered records and the file footer if any, then sync the file
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+/// Count the bytes written to the inner writer
+pub(crate) struct CountWrite<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountWrite<W> {
+    pub fn new(inner: W) -> Self {
+        CountWrite { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.count += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Read the field as a JSON value, the `Binary` field is read as a lossy UTF-8 string
+pub(crate) fn to_json_value(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> anyhow::Result<Value> {
+    let value = match data_type {
+        DataType::Boolean => Value::from(reader.get_bool(index)?),
+        DataType::Int8 => Value::from(reader.get_i8(index)?),
+        DataType::UInt8 => Value::from(reader.get_u8(index)?),
+        DataType::Int16 => Value::from(reader.get_i16(index)?),
+        DataType::UInt16 => Value::from(reader.get_u16(index)?),
+        DataType::Int32 => Value::from(reader.get_i32(index)?),
+        DataType::UInt32 => Value::from(reader.get_u32(index)?),
+        DataType::Int64 => Value::from(reader.get_i64(index)?),
+        DataType::UInt64 => Value::from(reader.get_u64(index)?),
+        DataType::Float32 => Value::from(reader.get_f32(index)?),
+        DataType::Float64 => Value::from(reader.get_f64(index)?),
+        DataType::Binary => Value::from(String::from_utf8_lossy(reader.get_binary(index)?)),
+        DataType::String => Value::from(reader.get_str(index)?),
+    };
+    Ok(value)
+}
+
+/// Read the field as a string, the string field is not quoted
+pub(crate) fn to_string_value(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> anyhow::Result<String> {
+    match to_json_value(reader, index, data_type)? {
+        Value::String(v) => Ok(v),
+        v => Ok(v.to_string()),
+    }
+}
+
+/// Write the values to a `Record` of the `schema`, the values are in the order of the schema
+/// fields and converted to the field types, the missing or null value is written as the default
+/// value of the field type.
+pub(crate) fn to_record(schema: &Schema, values: &[&Value]) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+    for (index, field) in schema.fields().iter().enumerate() {
+        let value = values.get(index).copied().unwrap_or(&Value::Null);
+        let rt = match field.data_type() {
+            DataType::Boolean => writer.set_bool(to_bool(value)?),
+            DataType::Int8 => writer.set_i8(to_i64(value)? as i8),
+            DataType::UInt8 => writer.set_u8(to_u64(value)? as u8),
+            DataType::Int16 => writer.set_i16(to_i64(value)? as i16),
+            DataType::UInt16 => writer.set_u16(to_u64(value)? as u16),
+            DataType::Int32 => writer.set_i32(to_i64(value)? as i32),
+            DataType::UInt32 => writer.set_u32(to_u64(value)? as u32),
+            DataType::Int64 => writer.set_i64(to_i64(value)?),
+            DataType::UInt64 => writer.set_u64(to_u64(value)?),
+            DataType::Float32 => writer.set_f32(to_f64(value)? as f32),
+            DataType::Float64 => writer.set_f64(to_f64(value)?),
+            DataType::Binary => writer.set_binary(to_str(value).as_bytes()),
+            DataType::String => writer.set_str(to_str(value).as_str()),
+        };
+        rt.map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+    }
+
+    Ok(record)
+}
+
+/// Write the object to a `Record` of the `schema`, the object fields are matched by the schema
+/// field names
+pub(crate) fn object_to_record(
+    schema: &Schema,
+    object: &Map<String, Value>,
+) -> anyhow::Result<Record> {
+    let values: Vec<&Value> = schema
+        .fields()
+        .iter()
+        .map(|field| object.get(field.name()).unwrap_or(&Value::Null))
+        .collect();
+    to_record(schema, values.as_slice())
+}
+
+fn to_i64(value: &Value) -> anyhow::Result<i64> {
+    match value {
+        Value::Null => Ok(0),
+        Value::Bool(v) => Ok(*v as i64),
+        Value::Number(v) => v
+            .as_i64()
+            .or_else(|| v.as_f64().map(|v| v as i64))
+            .ok_or(anyhow!("{} out of range", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => Err(anyhow!("{} can't be converted to integer", value)),
+    }
+}
+
+fn to_u64(value: &Value) -> anyhow::Result<u64> {
+    match value {
+        Value::Number(v) if v.is_u64() => Ok(v.as_u64().unwrap()),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as u64),
+    }
+}
+
+fn to_f64(value: &Value) -> anyhow::Result<f64> {
+    match value {
+        Value::Number(v) => v.as_f64().ok_or(anyhow!("{} out of range", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as f64),
+    }
+}
+
+fn to_bool(value: &Value) -> anyhow::Result<bool> {
+    match value {
+        Value::Bool(v) => Ok(*v),
+        Value::String(v) => match v.trim() {
+            "true" | "1" => Ok(true),
+            "false" | "0" | "" => Ok(false),
+            _ => Err(anyhow!("{} can't be converted to bool", v)),
+        },
+        _ => to_i64(value).map(|v| v != 0),
+    }
+}
+
+fn to_str(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(v) => v.clone(),
+        _ => value.to_string(),
+    }
+}
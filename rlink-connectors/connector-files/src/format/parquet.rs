@@ -0,0 +1,275 @@
+use std::fs::File;
+use std::sync::Arc;
+
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{
+    BoolType, ByteArray, ByteArrayType, DoubleType, FloatType, Int32Type, Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::Record;
+
+use crate::format::FileWriter;
+
+pub const ROW_GROUP_SIZE_DEFAULT: usize = 64 * 1024;
+
+/// Convert the `Schema` to the Parquet message type, all the columns are required
+///
+/// | rlink                  | Parquet                          |
+/// |------------------------|----------------------------------|
+/// | Boolean                | BOOLEAN                          |
+/// | Int8, Int16, Int32     | INT32 (INT(8/16/32, true))       |
+/// | UInt8, UInt16, UInt32  | INT32 (INT(8/16/32, false))      |
+/// | Int64, UInt64          | INT64 (INT(64, true/false))      |
+/// | Float32, Float64       | FLOAT, DOUBLE                    |
+/// | String                 | BYTE_ARRAY (STRING)              |
+/// | Binary                 | BYTE_ARRAY                       |
+pub fn to_parquet_schema(schema: &Schema) -> anyhow::Result<Type> {
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let (physical_type, logical_type) = match field.data_type() {
+            DataType::Boolean => (PhysicalType::BOOLEAN, None),
+            DataType::Int8 => (PhysicalType::INT32, Some(integer(8, true))),
+            DataType::UInt8 => (PhysicalType::INT32, Some(integer(8, false))),
+            DataType::Int16 => (PhysicalType::INT32, Some(integer(16, true))),
+            DataType::UInt16 => (PhysicalType::INT32, Some(integer(16, false))),
+            DataType::Int32 => (PhysicalType::INT32, None),
+            DataType::UInt32 => (PhysicalType::INT32, Some(integer(32, false))),
+            DataType::Int64 => (PhysicalType::INT64, None),
+            DataType::UInt64 => (PhysicalType::INT64, Some(integer(64, false))),
+            DataType::Float32 => (PhysicalType::FLOAT, None),
+            DataType::Float64 => (PhysicalType::DOUBLE, None),
+            DataType::Binary => (PhysicalType::BYTE_ARRAY, None),
+            DataType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+        };
+
+        let field_type = Type::primitive_type_builder(field.name(), physical_type)
+            .with_repetition(Repetition::REQUIRED)
+            .with_logical_type(logical_type)
+            .build()?;
+        fields.push(Arc::new(field_type));
+    }
+
+    let message_type = Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()?;
+    Ok(message_type)
+}
+
+fn integer(bit_width: i8, is_signed: bool) -> LogicalType {
+    LogicalType::Integer {
+        bit_width,
+        is_signed,
+    }
+}
+
+enum ColumnValues {
+    Boolean(Vec<bool>),
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    ByteArray(Vec<ByteArray>),
+}
+
+impl ColumnValues {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => Self::Boolean(Vec::new()),
+            DataType::Int8
+            | DataType::UInt8
+            | DataType::Int16
+            | DataType::UInt16
+            | DataType::Int32
+            | DataType::UInt32 => Self::Int32(Vec::new()),
+            DataType::Int64 | DataType::UInt64 => Self::Int64(Vec::new()),
+            DataType::Float32 => Self::Float(Vec::new()),
+            DataType::Float64 => Self::Double(Vec::new()),
+            DataType::Binary | DataType::String => Self::ByteArray(Vec::new()),
+        }
+    }
+}
+
+/// Buffer the records by columns, and write them as a row group for every `row_group_size`
+/// records
+pub struct ParquetFileWriter {
+    schema: Schema,
+    writer: SerializedFileWriter<File>,
+    row_group_size: usize,
+    columns: Vec<ColumnValues>,
+    buffered_rows: usize,
+    buffered_bytes: u64,
+}
+
+impl ParquetFileWriter {
+    pub fn new(schema: Schema, file: File, row_group_size: usize) -> anyhow::Result<Self> {
+        let parquet_schema = to_parquet_schema(&schema)?;
+        let properties = WriterProperties::builder().build();
+        let writer =
+            SerializedFileWriter::new(file, Arc::new(parquet_schema), Arc::new(properties))?;
+
+        let columns = Self::create_columns(&schema);
+        Ok(ParquetFileWriter {
+            schema,
+            writer,
+            row_group_size,
+            columns,
+            buffered_rows: 0,
+            buffered_bytes: 0,
+        })
+    }
+
+    fn create_columns(schema: &Schema) -> Vec<ColumnValues> {
+        schema
+            .fields()
+            .iter()
+            .map(|field| ColumnValues::new(field.data_type()))
+            .collect()
+    }
+
+    fn write_row_group(&mut self) -> anyhow::Result<()> {
+        if self.buffered_rows == 0 {
+            return Ok(());
+        }
+
+        let columns = std::mem::replace(&mut self.columns, Self::create_columns(&self.schema));
+        let mut row_group_writer = self.writer.next_row_group()?;
+        for column in columns {
+            let mut column_writer = row_group_writer.next_column()?.ok_or(anyhow!(
+                "the parquet columns are not matched with the schema"
+            ))?;
+            match column {
+                ColumnValues::Boolean(v) => column_writer
+                    .typed::<BoolType>()
+                    .write_batch(&v, None, None)?,
+                ColumnValues::Int32(v) => column_writer
+                    .typed::<Int32Type>()
+                    .write_batch(&v, None, None)?,
+                ColumnValues::Int64(v) => column_writer
+                    .typed::<Int64Type>()
+                    .write_batch(&v, None, None)?,
+                ColumnValues::Float(v) => column_writer
+                    .typed::<FloatType>()
+                    .write_batch(&v, None, None)?,
+                ColumnValues::Double(v) => column_writer
+                    .typed::<DoubleType>()
+                    .write_batch(&v, None, None)?,
+                ColumnValues::ByteArray(v) => column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&v, None, None)?,
+            };
+            column_writer.close()?;
+        }
+        row_group_writer.close()?;
+
+        self.buffered_rows = 0;
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+}
+
+impl FileWriter for ParquetFileWriter {
+    fn write(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        let mut bytes = 0;
+        for (index, field) in self.schema.fields().iter().enumerate() {
+            match (&mut self.columns[index], field.data_type()) {
+                (ColumnValues::Boolean(v), _) => v.push(reader.get_bool(index)?),
+                (ColumnValues::Int32(v), DataType::Int8) => v.push(reader.get_i8(index)? as i32),
+                (ColumnValues::Int32(v), DataType::UInt8) => v.push(reader.get_u8(index)? as i32),
+                (ColumnValues::Int32(v), DataType::Int16) => v.push(reader.get_i16(index)? as i32),
+                (ColumnValues::Int32(v), DataType::UInt16) => v.push(reader.get_u16(index)? as i32),
+                (ColumnValues::Int32(v), DataType::UInt32) => v.push(reader.get_u32(index)? as i32),
+                (ColumnValues::Int32(v), _) => v.push(reader.get_i32(index)?),
+                (ColumnValues::Int64(v), DataType::UInt64) => v.push(reader.get_u64(index)? as i64),
+                (ColumnValues::Int64(v), _) => v.push(reader.get_i64(index)?),
+                (ColumnValues::Float(v), _) => v.push(reader.get_f32(index)?),
+                (ColumnValues::Double(v), _) => v.push(reader.get_f64(index)?),
+                (ColumnValues::ByteArray(v), DataType::String) => {
+                    let value = reader.get_str(index)?;
+                    bytes += value.len();
+                    v.push(ByteArray::from(value))
+                }
+                (ColumnValues::ByteArray(v), _) => {
+                    let value = reader.get_binary(index)?;
+                    bytes += value.len();
+                    v.push(ByteArray::from(value.to_vec()))
+                }
+            }
+            bytes += 8;
+        }
+
+        self.buffered_rows += 1;
+        self.buffered_bytes += bytes as u64;
+        if self.buffered_rows >= self.row_group_size {
+            self.write_row_group()?;
+        }
+
+        Ok(())
+    }
+
+    fn size(&self) -> u64 {
+        self.writer.bytes_written() as u64 + self.buffered_bytes
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.write_row_group()?;
+        let file = self.writer.into_inner()?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::format::FileFormat;
+
+    #[test]
+    pub fn parquet_writer_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UInt16),
+            Field::new("name", DataType::String),
+            Field::new("score", DataType::Float64),
+        ]);
+
+        let path =
+            std::env::temp_dir().join(format!("rlink-parquet-{}.parquet", std::process::id()));
+        let file = std::fs::File::create(path.as_path()).unwrap();
+        let mut writer = FileFormat::Parquet { row_group_size: 2 }
+            .create_writer(&schema, file)
+            .unwrap();
+        for id in 0..3u16 {
+            let mut record = Record::new();
+            let mut record_writer = record.as_writer(schema.as_type_ids());
+            record_writer.set_u16(id).unwrap();
+            record_writer
+                .set_str(format!("name-{}", id).as_str())
+                .unwrap();
+            record_writer.set_f64(id as f64 / 2.0).unwrap();
+            writer.write(&mut record).unwrap();
+        }
+        assert!(writer.size() > 0);
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::try_from(path.as_path()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2].get_ushort(0).unwrap(), 2);
+        assert_eq!(rows[2].get_string(1).unwrap(), "name-2");
+        assert_eq!(rows[2].get_double(2).unwrap(), 1.0);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
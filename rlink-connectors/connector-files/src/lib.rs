@@ -0,0 +1,20 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod format;
+pub mod sink;
+pub mod source;
+
+pub use format::FileFormat;
+pub use sink::bucket::BucketAssigner;
+pub use sink::output_format::FileOutputFormat;
+pub use sink::rolling::RollingPolicy;
+pub use source::input_format::FileInputFormat;
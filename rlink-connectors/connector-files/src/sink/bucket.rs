@@ -0,0 +1,68 @@
+use chrono::{TimeZone, Utc};
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+
+use crate::format::to_string_value;
+
+/// Assign the record to a bucket, that is the sub directory of the base path the record is
+/// written to
+#[derive(Clone, Debug)]
+pub enum BucketAssigner {
+    /// all the records are written to the base path
+    Base,
+    /// bucket by the event time of the `field`, which is a timestamp in milliseconds, formatted
+    /// in UTC by the `chrono` `format`, such as `%Y-%m-%d/%H`
+    EventTime { field: String, format: String },
+    /// bucket by the value of the `field` as `{field}={value}`
+    Column { field: String },
+}
+
+impl BucketAssigner {
+    pub fn event_time(field: &str, format: &str) -> Self {
+        BucketAssigner::EventTime {
+            field: field.to_string(),
+            format: format.to_string(),
+        }
+    }
+
+    pub fn column(field: &str) -> Self {
+        BucketAssigner::Column {
+            field: field.to_string(),
+        }
+    }
+
+    /// The bucket path relative to the base path, empty for the base path itself
+    pub fn bucket_id(&self, record: &mut Record, schema: &Schema) -> anyhow::Result<String> {
+        let field_name = match self {
+            Self::Base => return Ok("".to_string()),
+            Self::EventTime { field, .. } => field,
+            Self::Column { field } => field,
+        };
+
+        let index = schema
+            .fields()
+            .iter()
+            .position(|field| field.name().eq(field_name))
+            .ok_or(anyhow!("bucket field `{}` not found", field_name))?;
+        let reader = record.as_reader(schema.as_type_ids());
+        let value = to_string_value(&reader, index, schema.field(index).data_type())?;
+
+        match self {
+            Self::EventTime { format, .. } => {
+                let timestamp = value
+                    .parse::<i64>()
+                    .map_err(|e| anyhow!("invalid event time `{}`. {}", value, e))?;
+                let date_time = Utc
+                    .timestamp_millis_opt(timestamp)
+                    .single()
+                    .ok_or(anyhow!("invalid event time `{}`", value))?;
+                Ok(date_time.format(format).to_string())
+            }
+            _ => Ok(format!(
+                "{}={}",
+                field_name,
+                value.replace(['/', '\\'], "_")
+            )),
+        }
+    }
+}
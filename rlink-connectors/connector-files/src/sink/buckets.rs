@@ -0,0 +1,322 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rlink::core::checkpoint::CheckpointHandle;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use rlink::utils::date_time::current_timestamp_millis;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::format::{FileFormat, FileWriter};
+use crate::sink::bucket::BucketAssigner;
+use crate::sink::rolling::RollingPolicy;
+use crate::sink::storage::PartStorage;
+
+struct InProgressPart<P> {
+    part: P,
+    writer: Box<dyn FileWriter>,
+    open_timestamp: u64,
+}
+
+/// The part files waiting for their checkpoints to complete
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FileSinkSnapshot<P> {
+    part_counter: u64,
+    /// checkpoint id -> the part files finished before the checkpoint
+    pending: BTreeMap<u64, Vec<P>>,
+}
+
+impl<P> FileSinkSnapshot<P>
+where
+    P: Serialize + DeserializeOwned,
+{
+    pub fn from_handle(handle: &CheckpointHandle) -> anyhow::Result<Self> {
+        serde_json::from_str(handle.handle.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    pub fn to_handle(&self) -> CheckpointHandle {
+        CheckpointHandle {
+            handle: serde_json::to_string(self).unwrap(),
+        }
+    }
+}
+
+/// Write the records to the part files of the buckets, and commit the part files to the
+/// `PartStorage` when the checkpoints they belong to are completed
+pub struct Buckets<S: PartStorage> {
+    storage: S,
+    format: FileFormat,
+    bucket_assigner: BucketAssigner,
+    rolling_policy: RollingPolicy,
+    part_prefix: String,
+    task_number: u16,
+    schema: Schema,
+
+    part_counter: u64,
+    /// bucket id -> the in-progress part file
+    in_progress: HashMap<String, InProgressPart<S::InProgress>>,
+    /// the part files finished after the last checkpoint
+    finished: Vec<S::Pending>,
+    pending: BTreeMap<u64, Vec<S::Pending>>,
+}
+
+impl<S: PartStorage> Buckets<S> {
+    pub fn new(
+        storage: S,
+        format: FileFormat,
+        bucket_assigner: BucketAssigner,
+        rolling_policy: RollingPolicy,
+        part_prefix: String,
+        task_number: u16,
+        schema: Schema,
+    ) -> Self {
+        Buckets {
+            storage,
+            format,
+            bucket_assigner,
+            rolling_policy,
+            part_prefix,
+            task_number,
+            schema,
+            part_counter: 0,
+            in_progress: HashMap::new(),
+            finished: Vec::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub async fn write(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let bucket_id = self.bucket_assigner.bucket_id(record, &self.schema)?;
+        if !self.in_progress.contains_key(&bucket_id) {
+            let part = self.open_part(bucket_id.as_str()).await?;
+            self.in_progress.insert(bucket_id.clone(), part);
+        }
+
+        let part = self.in_progress.get_mut(&bucket_id).unwrap();
+        part.writer.write(record)?;
+        self.storage
+            .written(&mut part.part, part.writer.size())
+            .await?;
+
+        let now = current_timestamp_millis();
+        if self
+            .rolling_policy
+            .should_roll(part.writer.size(), part.open_timestamp, now)
+        {
+            self.roll(bucket_id.as_str()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Roll all the part files and keep them pending with the `checkpoint_id`, then commit the
+    /// pending part files of the checkpoints before the `completed_checkpoint_id`. The job may
+    /// still recover from the checkpoint before the `completed_checkpoint_id`, so the part files
+    /// of the `completed_checkpoint_id` are committed by the next completed checkpoint
+    pub async fn snapshot(
+        &mut self,
+        checkpoint_id: u64,
+        completed_checkpoint_id: Option<u64>,
+    ) -> anyhow::Result<FileSinkSnapshot<S::Pending>> {
+        self.roll_all().await?;
+        let finished = std::mem::take(&mut self.finished);
+        if !finished.is_empty() {
+            self.pending
+                .entry(checkpoint_id)
+                .or_default()
+                .extend(finished);
+        }
+
+        if let Some(completed_checkpoint_id) = completed_checkpoint_id {
+            let remaining = self.pending.split_off(&completed_checkpoint_id);
+            let completed = std::mem::replace(&mut self.pending, remaining);
+            for part in completed.values().flatten() {
+                self.storage.commit(part).await?;
+            }
+        }
+
+        Ok(FileSinkSnapshot {
+            part_counter: self.part_counter,
+            pending: self.pending.clone(),
+        })
+    }
+
+    /// Commit the pending part files of the restored checkpoint, the job recovers from it so it
+    /// and the checkpoints before it are final, and clean up the part files of the task written
+    /// after it
+    pub async fn restore(
+        &mut self,
+        snapshot: Option<FileSinkSnapshot<S::Pending>>,
+    ) -> anyhow::Result<()> {
+        if let Some(snapshot) = snapshot {
+            for part in snapshot.pending.values().flatten() {
+                self.storage.commit(part).await?;
+            }
+            self.part_counter = snapshot.part_counter;
+        }
+
+        let name_prefix = format!("{}-{}-", self.part_prefix, self.task_number);
+        self.storage.clean_up(name_prefix.as_str()).await
+    }
+
+    /// Commit all the part files when the stream is finished
+    pub async fn close(&mut self) -> anyhow::Result<()> {
+        self.roll_all().await?;
+        let pending = std::mem::take(&mut self.pending);
+        let finished = std::mem::take(&mut self.finished);
+        for part in pending.values().flatten().chain(finished.iter()) {
+            self.storage.commit(part).await?;
+        }
+        Ok(())
+    }
+
+    async fn roll(&mut self, bucket_id: &str) -> anyhow::Result<()> {
+        if let Some(part) = self.in_progress.remove(bucket_id) {
+            part.writer.finish()?;
+            let part = self.storage.finish(part.part).await?;
+            self.finished.push(part);
+        }
+        Ok(())
+    }
+
+    async fn roll_all(&mut self) -> anyhow::Result<()> {
+        let bucket_ids: Vec<String> = self.in_progress.keys().cloned().collect();
+        for bucket_id in bucket_ids {
+            self.roll(bucket_id.as_str()).await?;
+        }
+        Ok(())
+    }
+
+    async fn open_part(
+        &mut self,
+        bucket_id: &str,
+    ) -> anyhow::Result<InProgressPart<S::InProgress>> {
+        // skip the names used by the part files of the former runs
+        let (part, file) = loop {
+            let name = format!(
+                "{}-{}-{}.{}",
+                self.part_prefix,
+                self.task_number,
+                self.part_counter,
+                self.format.extension()
+            );
+            self.part_counter += 1;
+
+            if let Some(created) = self.storage.create(bucket_id, name.as_str()).await? {
+                break created;
+            }
+        };
+
+        let writer = self.format.create_writer(&self.schema, file)?;
+        Ok(InProgressPart {
+            part,
+            writer,
+            open_timestamp: current_timestamp_millis(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::format::FileFormat;
+    use crate::sink::bucket::BucketAssigner;
+    use crate::sink::buckets::Buckets;
+    use crate::sink::rolling::RollingPolicy;
+    use crate::sink::storage::LocalPartStorage;
+
+    fn list_files(path: &Path) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    pub async fn buckets_commit_test() {
+        let base_path = std::env::temp_dir().join(format!("rlink-buckets-{}", std::process::id()));
+        let schema = Schema::new(vec![
+            Field::new("city", DataType::String),
+            Field::new("value", DataType::Int64),
+        ]);
+        let create_buckets = || {
+            Buckets::new(
+                LocalPartStorage::new(base_path.clone()),
+                FileFormat::json_lines(),
+                BucketAssigner::column("city"),
+                RollingPolicy::default(),
+                "part".to_string(),
+                1,
+                schema.clone(),
+            )
+        };
+        let create_record = |city: &str, value: i64| {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(schema.as_type_ids());
+            writer.set_str(city).unwrap();
+            writer.set_i64(value).unwrap();
+            record
+        };
+
+        let mut buckets = create_buckets();
+        buckets.restore(None).await.unwrap();
+        buckets.write(&mut create_record("bj", 1)).await.unwrap();
+        buckets.write(&mut create_record("sh", 2)).await.unwrap();
+
+        // pending until the checkpoint is completed
+        let snapshot = buckets.snapshot(1, None).await.unwrap();
+        assert_eq!(
+            list_files(base_path.join("city=bj").as_path()),
+            vec![".part-1-0.json.inprogress"]
+        );
+
+        // the job may still recover from the checkpoint before checkpoint 1
+        buckets.write(&mut create_record("bj", 3)).await.unwrap();
+        buckets.snapshot(2, Some(1)).await.unwrap();
+        assert_eq!(
+            list_files(base_path.join("city=bj").as_path()),
+            vec![".part-1-0.json.inprogress", ".part-1-2.json.inprogress"]
+        );
+
+        buckets.write(&mut create_record("bj", 4)).await.unwrap();
+        buckets.snapshot(3, Some(2)).await.unwrap();
+        assert_eq!(
+            list_files(base_path.join("city=bj").as_path()),
+            vec![
+                ".part-1-2.json.inprogress",
+                ".part-1-3.json.inprogress",
+                "part-1-0.json"
+            ]
+        );
+        let content = std::fs::read_to_string(base_path.join("city=bj/part-1-0.json")).unwrap();
+        assert_eq!(content, "{\"city\":\"bj\",\"value\":1}\n");
+
+        // recover from checkpoint 1 after checkpoint 2 is completed, the part files of
+        // checkpoint 2 and 3 are discarded
+        let mut buckets = create_buckets();
+        buckets.restore(Some(snapshot)).await.unwrap();
+        assert_eq!(
+            list_files(base_path.join("city=bj").as_path()),
+            vec!["part-1-0.json"]
+        );
+        assert_eq!(
+            list_files(base_path.join("city=sh").as_path()),
+            vec!["part-1-1.json"]
+        );
+
+        buckets.write(&mut create_record("bj", 5)).await.unwrap();
+        buckets.close().await.unwrap();
+        assert_eq!(
+            list_files(base_path.join("city=bj").as_path()),
+            vec!["part-1-0.json", "part-1-2.json"]
+        );
+
+        std::fs::remove_dir_all(base_path).unwrap();
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bucket;
+pub mod buckets;
+pub mod output_format;
+pub mod rolling;
+pub mod storage;
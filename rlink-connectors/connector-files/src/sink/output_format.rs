@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::{Element, FnSchema};
+use rlink::core::function::{Context, OutputFormat};
+
+use crate::format::FileFormat;
+use crate::sink::bucket::BucketAssigner;
+use crate::sink::buckets::{Buckets, FileSinkSnapshot};
+use crate::sink::rolling::RollingPolicy;
+use crate::sink::storage::LocalPartStorage;
+
+/// Write the records to the part files in the bucket directories of the `base_path`, encoded by
+/// the `FileFormat` with the input `Schema`.
+///
+/// The part files are written as the hidden `.{prefix}-{task_number}-{counter}.{ext}.inprogress`
+/// files, rolled by the `RollingPolicy` and on every checkpoint, and moved to the final
+/// `{prefix}-{task_number}-{counter}.{ext}` names only when a later checkpoint is completed, the
+/// job may still recover from the checkpoint before the latest completed one, so the records are
+/// committed exactly once. The in-progress files not committed are removed when recovering.
+#[derive(NamedFunction)]
+pub struct FileOutputFormat {
+    base_path: PathBuf,
+    format: FileFormat,
+    bucket_assigner: BucketAssigner,
+    rolling_policy: RollingPolicy,
+    part_prefix: String,
+
+    buckets: Option<Buckets<LocalPartStorage>>,
+    /// the first write error, the records are lost, so all the later checkpoints are declined
+    write_error: Option<String>,
+}
+
+impl FileOutputFormat {
+    pub fn new(base_path: &str, format: FileFormat) -> Self {
+        FileOutputFormat {
+            base_path: PathBuf::from(base_path),
+            format,
+            bucket_assigner: BucketAssigner::Base,
+            rolling_policy: RollingPolicy::default(),
+            part_prefix: "part".to_string(),
+            buckets: None,
+            write_error: None,
+        }
+    }
+
+    pub fn bucket_assigner(mut self, bucket_assigner: BucketAssigner) -> Self {
+        self.bucket_assigner = bucket_assigner;
+        self
+    }
+
+    pub fn rolling_policy(mut self, rolling_policy: RollingPolicy) -> Self {
+        self.rolling_policy = rolling_policy;
+        self
+    }
+
+    pub fn part_prefix(mut self, part_prefix: &str) -> Self {
+        self.part_prefix = part_prefix.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl OutputFormat for FileOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        self.buckets = Some(Buckets::new(
+            LocalPartStorage::new(self.base_path.clone()),
+            self.format.clone(),
+            self.bucket_assigner.clone(),
+            self.rolling_policy,
+            self.part_prefix.clone(),
+            context.task_id.task_number(),
+            context.input_schema.first().clone(),
+        ));
+
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.write_error {
+            return Err(core::Error::from(format!("restore file sink error. {}", e)));
+        }
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        if self.write_error.is_some() {
+            return;
+        }
+
+        let mut record = element.into_record();
+        if let Err(e) = self.buckets.as_mut().unwrap().write(&mut record).await {
+            // surfaced as a failure of the next checkpoint
+            error!("write file error, the later records are discarded. {}", e);
+            self.write_error = Some(e.to_string());
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        if let Some(e) = &self.write_error {
+            return Err(core::Error::from(format!("write file error. {}", e)));
+        }
+
+        self.buckets.as_mut().unwrap().close().await?;
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for FileOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let snapshot = match handle {
+            Some(handle) => match FileSinkSnapshot::from_handle(handle) {
+                Ok(snapshot) => {
+                    info!(
+                        "load file sink from checkpoint({:?}): {:?}",
+                        context.checkpoint_id, snapshot
+                    );
+                    Some(snapshot)
+                }
+                Err(e) => {
+                    self.write_error = Some(format!("parse file sink checkpoint error. {}", e));
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        if let Err(e) = self.buckets.as_mut().unwrap().restore(snapshot).await {
+            self.write_error = Some(e.to_string());
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        if let Some(e) = &self.write_error {
+            context.decline(format!("write file error. {}", e).as_str());
+            return None;
+        }
+
+        let snapshot = self
+            .buckets
+            .as_mut()
+            .unwrap()
+            .snapshot(
+                context.checkpoint_id.0,
+                context.completed_checkpoint_id.map(|x| x.0),
+            )
+            .await;
+        match snapshot {
+            Ok(snapshot) => Some(snapshot.to_handle()),
+            Err(e) => {
+                context.decline(format!("snapshot file sink error. {}", e).as_str());
+                self.write_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+/// Roll the in-progress part file to a new one when it's too large or opened for too long.
+///
+/// The part files are always rolled on checkpoints too, so that they can be committed when the
+/// checkpoint is completed.
+#[derive(Clone, Copy, Debug)]
+pub struct RollingPolicy {
+    /// the max size in bytes of a part file
+    pub max_part_size: u64,
+    /// the max duration a part file is opened
+    pub rollover_interval: Duration,
+}
+
+impl Default for RollingPolicy {
+    fn default() -> Self {
+        RollingPolicy {
+            max_part_size: 128 * 1024 * 1024,
+            rollover_interval: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl RollingPolicy {
+    pub fn new(max_part_size: u64, rollover_interval: Duration) -> Self {
+        RollingPolicy {
+            max_part_size,
+            rollover_interval,
+        }
+    }
+
+    pub(crate) fn should_roll(&self, part_size: u64, open_timestamp: u64, now: u64) -> bool {
+        part_size >= self.max_part_size
+            || now.saturating_sub(open_timestamp) >= self.rollover_interval.as_millis() as u64
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const IN_PROGRESS_SUFFIX: &str = ".inprogress";
+
+/// The storage of the part files written by the `Buckets`. A part file is written to a local
+/// file, finished when it's rolled, and committed to be visible when the checkpoint it belongs
+/// to is completed.
+#[async_trait]
+pub trait PartStorage: Send + Sync {
+    /// The part file being written
+    type InProgress: Send + Sync;
+    /// The finished part file waiting for its checkpoint, it's kept in the checkpoint
+    type Pending: Clone + Debug + Serialize + DeserializeOwned + Send + Sync;
+
+    /// Create the part file `name` in the bucket and the local file to write, or `None` if the
+    /// name is used by a part file of the former runs
+    async fn create(
+        &self,
+        bucket_id: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<(Self::InProgress, File)>>;
+
+    /// Called after a record is written to the local file, the `size` includes the records
+    /// buffered by the writer. The storage may transfer the written data of the part file
+    /// before it's finished
+    async fn written(&self, _part: &mut Self::InProgress, _size: u64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Finish the part file after the local file is written completely
+    async fn finish(&self, part: Self::InProgress) -> anyhow::Result<Self::Pending>;
+
+    /// Make the part file visible, it's idempotent for the replayed commits after recovery
+    async fn commit(&self, part: &Self::Pending) -> anyhow::Result<()>;
+
+    /// Remove the uncommitted part files whose names start with the `name_prefix`
+    async fn clean_up(&self, name_prefix: &str) -> anyhow::Result<()>;
+}
+
+/// A part file, it's written as the hidden in-progress file and moved to the final name when
+/// committed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PartFile {
+    in_progress_path: PathBuf,
+    final_path: PathBuf,
+}
+
+/// The part files in the bucket directories of the `base_path` on the local file system
+pub(crate) struct LocalPartStorage {
+    base_path: PathBuf,
+}
+
+impl LocalPartStorage {
+    pub fn new(base_path: PathBuf) -> Self {
+        LocalPartStorage { base_path }
+    }
+}
+
+#[async_trait]
+impl PartStorage for LocalPartStorage {
+    type InProgress = PartFile;
+    type Pending = PartFile;
+
+    async fn create(
+        &self,
+        bucket_id: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<(PartFile, File)>> {
+        let bucket_path = self.base_path.join(bucket_id);
+        std::fs::create_dir_all(bucket_path.as_path())?;
+
+        let part_file = PartFile {
+            in_progress_path: bucket_path.join(format!(".{}{}", name, IN_PROGRESS_SUFFIX)),
+            final_path: bucket_path.join(name),
+        };
+        if part_file.final_path.exists() {
+            return Ok(None);
+        }
+        let file = create_new(part_file.in_progress_path.as_path())?;
+        Ok(file.map(|file| (part_file, file)))
+    }
+
+    async fn finish(&self, part: PartFile) -> anyhow::Result<PartFile> {
+        Ok(part)
+    }
+
+    /// Move the in-progress file to the final name
+    async fn commit(&self, part: &PartFile) -> anyhow::Result<()> {
+        if part.in_progress_path.exists() {
+            std::fs::rename(part.in_progress_path.as_path(), part.final_path.as_path())?;
+            info!("commit part file {:?}", part.final_path);
+            Ok(())
+        } else if part.final_path.exists() {
+            Ok(())
+        } else {
+            Err(anyhow!("part file {:?} is lost", part.in_progress_path))
+        }
+    }
+
+    async fn clean_up(&self, name_prefix: &str) -> anyhow::Result<()> {
+        let prefix = format!(".{}", name_prefix);
+        remove_in_progress_files(self.base_path.as_path(), prefix.as_str())
+    }
+}
+
+fn create_new(path: &Path) -> anyhow::Result<Option<File>> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(anyhow!("create part file {:?} error. {}", path, e)),
+    }
+}
+
+fn remove_in_progress_files(path: &Path, prefix: &str) -> anyhow::Result<()> {
+    if !path.is_dir() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_in_progress_files(path.as_path(), prefix)?;
+            continue;
+        }
+
+        let file_name = path.file_name().and_then(|x| x.to_str()).unwrap_or("");
+        if file_name.starts_with(prefix) && file_name.ends_with(IN_PROGRESS_SUFFIX) {
+            info!("remove the uncommitted part file {:?}", path);
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use rlink::channel::named_channel;
+use rlink::core::data_types::Schema;
+use rlink::core::function::SendableElementStream;
+use rlink::metrics::Tag;
+
+use crate::format::FileFormat;
+use crate::source::reader::FileReaderTask;
+use crate::source::state::FileSourceState;
+use crate::source::stream::FileRecordStream;
+
+/// The storage of the files read by the source. The files are fetched to the local file system
+/// before read, and released after read. It's called in the blocking thread of the reader.
+pub trait FileFetcher: Send + Sync {
+    /// Fetch the file of the `path`, returns the local file to read, or `None` if the file is
+    /// not found
+    fn fetch(&self, path: &str) -> anyhow::Result<Option<PathBuf>>;
+
+    /// Release the local file of the `path` after it's read
+    fn release(&self, local_path: &Path) -> anyhow::Result<()>;
+}
+
+/// The files on the local file system are read in place
+pub(crate) struct LocalFileFetcher;
+
+impl FileFetcher for LocalFileFetcher {
+    fn fetch(&self, path: &str) -> anyhow::Result<Option<PathBuf>> {
+        let file = PathBuf::from(path);
+        Ok(if file.exists() { Some(file) } else { None })
+    }
+
+    fn release(&self, _local_path: &Path) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read the `paths` fetched by the `fetcher` in the blocking thread, decoded by the `format`
+/// with the `schema`. The read offsets are recorded in the `state`, so the recovered task
+/// resumes from the offsets, and the stream ends when all the files are read.
+pub fn fetched_file_stream(
+    fetcher: Box<dyn FileFetcher>,
+    paths: Vec<String>,
+    format: FileFormat,
+    schema: Schema,
+    state: FileSourceState,
+    tags: Vec<Tag>,
+    buffer_size: usize,
+) -> SendableElementStream {
+    let reader_task = FileReaderTask {
+        // the files are not rescanned
+        path: String::new(),
+        format,
+        schema,
+        split_paths: paths,
+        listed_paths: Default::default(),
+        task_number: 0,
+        num_tasks: 1,
+        monitor_interval: None,
+        fetcher,
+        state: state.clone(),
+    };
+    let (sender, receiver) = named_channel("FileSource_Handover", tags, buffer_size);
+
+    // the stream ends when the sender is dropped
+    tokio::task::spawn_blocking(move || reader_task.run(sender));
+
+    Box::pin(FileRecordStream::new(receiver, state))
+}
@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use rlink::channel::named_channel;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::FnSchema;
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::core::properties::Properties;
+use rlink::metrics::Tag;
+
+use crate::format::FileFormat;
+use crate::source::fetcher::LocalFileFetcher;
+use crate::source::reader::{list_files, FileReaderTask};
+use crate::source::split::assign_files;
+use crate::source::state::FileSourceState;
+use crate::source::stream::FileRecordStream;
+
+const BUFFER_SIZE_DEFAULT: usize = 10000;
+
+/// The split property of the files of the split, a JSON array
+const SPLIT_PATHS: &str = "paths";
+/// The split property of the files of all the splits in monitor mode, a JSON array
+const LISTED_PATHS: &str = "listed_paths";
+
+/// Read the records of the files in the `path`, which is a glob pattern, a directory or a single
+/// file, decoded by the `FileFormat` with the `Schema`. The CSV columns are matched by position,
+/// and the JSON-lines and Parquet fields are matched by the schema field names. The `.gz` files
+/// are decompressed.
+///
+/// The files are listed when the splits are created, and assigned to the splits by the hash of
+/// the paths, so a file is always read by the same task across the restarts. The read offset of
+/// each file is checkpointed, so the recovered task resumes from the offsets. With `monitor`, the
+/// `path` is rescanned periodically for the new files, which should be moved into the directory
+/// after they are written completely. The new files are assigned to the tasks by the hash of the
+/// paths too, and the files removed from the directory are removed from the checkpoint.
+/// Otherwise the stream ends when all the files of the split are read.
+///
+/// A failed listing or read is logged and retried from the last record read.
+#[derive(NamedFunction)]
+pub struct FileInputFormat {
+    path: String,
+    format: FileFormat,
+    schema: Schema,
+    parallelism: u16,
+    monitor_interval: Option<Duration>,
+    buffer_size: usize,
+
+    reader_task: Option<FileReaderTask>,
+    state: FileSourceState,
+    restore_error: Option<String>,
+}
+
+impl FileInputFormat {
+    pub fn new(path: &str, format: FileFormat, schema: Schema, parallelism: u16) -> Self {
+        FileInputFormat {
+            path: path.to_string(),
+            format,
+            schema,
+            parallelism,
+            monitor_interval: None,
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            reader_task: None,
+            state: FileSourceState::default(),
+            restore_error: None,
+        }
+    }
+
+    /// Watch the `path` for the new files with the `interval`, the stream never ends
+    pub fn monitor(mut self, interval: Duration) -> Self {
+        self.monitor_interval = Some(interval);
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+}
+
+#[async_trait]
+impl InputFormat for FileInputFormat {
+    async fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.restore_error {
+            return Err(core::Error::from(format!(
+                "restore file source error. {}",
+                e
+            )));
+        }
+
+        let split_paths = split_property(input_split.properties(), SPLIT_PATHS)?;
+        info!("file source split paths {:?}", split_paths);
+        let listed_paths = match self.monitor_interval {
+            Some(_) => split_property(input_split.properties(), LISTED_PATHS)?,
+            None => vec![],
+        };
+
+        self.reader_task = Some(FileReaderTask {
+            path: self.path.clone(),
+            format: self.format.clone(),
+            schema: self.schema.clone(),
+            split_paths,
+            listed_paths: listed_paths.into_iter().collect::<HashSet<String>>(),
+            task_number: context.task_id.task_number(),
+            num_tasks: context.task_id.num_tasks(),
+            monitor_interval: self.monitor_interval,
+            fetcher: Box::new(LocalFileFetcher),
+            state: self.state.clone(),
+        });
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let reader_task = self.reader_task.take().unwrap();
+        let tags = vec![
+            Tag::new("path", self.path.as_str()),
+            Tag::new("task_number", reader_task.task_number),
+        ];
+        let (sender, receiver) = named_channel("FileSource_Handover", tags, self.buffer_size);
+
+        // the stream ends when the sender is dropped
+        tokio::task::spawn_blocking(move || reader_task.run(sender));
+
+        Box::pin(FileRecordStream::new(receiver, self.state.clone()))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for FileInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            match self.state.update_from_snapshot(handle.handle.as_str()) {
+                Ok(()) => info!(
+                    "load file source state from checkpoint({:?}): {}",
+                    context.checkpoint_id, handle.handle
+                ),
+                Err(e) => self.restore_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        Some(CheckpointHandle {
+            handle: self.state.snapshot(),
+        })
+    }
+}
+
+impl InputSplitSource for FileInputFormat {
+    /// The files are listed and assigned to the splits by the hash of the paths, the paths of
+    /// each split are in the `paths` property as a JSON array. In monitor mode, the paths of all
+    /// the splits are also in the `listed_paths` property, the files found later are new.
+    fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        let files: Vec<String> = list_files(self.path.as_str())?
+            .into_iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        info!("file source files {:?}", files);
+
+        let listed_paths = serde_json::to_string(&files).unwrap();
+
+        let input_splits = assign_files(files.as_slice(), min_num_splits)
+            .into_iter()
+            .enumerate()
+            .map(|(index, paths)| {
+                let mut properties = Properties::new();
+                properties.set_str(SPLIT_PATHS, serde_json::to_string(&paths).unwrap().as_str());
+                if self.monitor_interval.is_some() {
+                    properties.set_str(LISTED_PATHS, listed_paths.as_str());
+                }
+                InputSplit::new(index as u16, properties)
+            })
+            .collect();
+        Ok(input_splits)
+    }
+}
+
+fn split_property(properties: &Properties, key: &str) -> core::Result<Vec<String>> {
+    let paths = properties.get_string(key)?;
+    serde_json::from_str(paths.as_str())
+        .map_err(|e| core::Error::from(format!("parse the split `{}` error. {}", key, e)))
+}
@@ -0,0 +1,6 @@
+pub mod fetcher;
+pub mod input_format;
+pub(crate) mod reader;
+pub mod split;
+pub mod state;
+pub mod stream;
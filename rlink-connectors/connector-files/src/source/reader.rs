@@ -0,0 +1,601 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use flate2::read::MultiGzDecoder;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use rlink::channel::sender::ChannelSender;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use serde_json::Value;
+
+use crate::format::{object_to_record, to_record, FileFormat};
+use crate::source::fetcher::FileFetcher;
+use crate::source::split::split_of;
+use crate::source::state::FileSourceState;
+use crate::source::stream::FileRecord;
+
+/// List the files of the `path`, which is a glob pattern, a directory or a single file. The
+/// hidden files, whose names start with `.` or `_`, are skipped, and the sub-directories of the
+/// directory are not listed.
+pub(crate) fn list_files(path: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if path.contains(['*', '?', '[']) {
+        for entry in glob::glob(path)? {
+            let file = entry?;
+            if file.is_file() && !is_hidden(file.as_path()) {
+                files.push(file);
+            }
+        }
+    } else if Path::new(path).is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.is_file() && !is_hidden(file.as_path()) {
+                files.push(file);
+            }
+        }
+    } else {
+        files.push(PathBuf::from(path));
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    let file_name = path.file_name().and_then(|x| x.to_str()).unwrap_or("");
+    file_name.starts_with('.') || file_name.starts_with('_')
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().and_then(|x| x.to_str()) == Some("gz")
+}
+
+/// Open the file and skip to the `position` of the decompressed stream
+fn open_at(path: &Path, position: u64) -> anyhow::Result<Box<dyn BufRead>> {
+    let mut file = File::open(path)?;
+    if is_gzip(path) {
+        let mut reader = BufReader::new(MultiGzDecoder::new(BufReader::new(file)));
+        let skipped = std::io::copy(&mut (&mut reader).take(position), &mut std::io::sink())?;
+        if skipped < position {
+            return Err(anyhow!("position {} is beyond the end of file", position));
+        }
+        Ok(Box::new(reader))
+    } else {
+        file.seek(SeekFrom::Start(position))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Read the records of the file from the `position`, and call the `emit` with each record and
+/// the position of the next record. The malformed records are discarded.
+///
+/// Returns the position of the end of the file
+pub(crate) fn read_file(
+    path: &Path,
+    format: &FileFormat,
+    schema: &Schema,
+    position: u64,
+    emit: &mut dyn FnMut(Record, u64) -> anyhow::Result<()>,
+) -> anyhow::Result<u64> {
+    match format {
+        FileFormat::Csv { delimiter, header } => {
+            read_csv(path, *delimiter, *header, schema, position, emit)
+        }
+        FileFormat::JsonLines => read_json_lines(path, schema, position, emit),
+        FileFormat::Parquet { .. } => read_parquet(path, schema, position, emit),
+    }
+}
+
+fn read_csv(
+    path: &Path,
+    delimiter: u8,
+    header: bool,
+    schema: &Schema,
+    position: u64,
+    emit: &mut dyn FnMut(Record, u64) -> anyhow::Result<()>,
+) -> anyhow::Result<u64> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        // the header is skipped only when reading from the beginning
+        .has_headers(header && position == 0)
+        .flexible(true)
+        .from_reader(open_at(path, position)?);
+
+    let mut string_record = csv::StringRecord::new();
+    loop {
+        match reader.read_record(&mut string_record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) if e.is_io_error() => return Err(e.into()),
+            Err(e) => {
+                warn!("discard the malformed record of file {:?}. {}", path, e);
+                continue;
+            }
+        }
+
+        let next_position = position + reader.position().byte();
+        let values: Vec<Value> = string_record
+            .iter()
+            .map(|x| Value::String(x.to_string()))
+            .collect();
+        let values: Vec<&Value> = values.iter().collect();
+        match to_record(schema, values.as_slice()) {
+            Ok(record) => emit(record, next_position)?,
+            Err(e) => warn!("discard the malformed record of file {:?}. {}", path, e),
+        }
+    }
+
+    Ok(position + reader.position().byte())
+}
+
+fn read_json_lines(
+    path: &Path,
+    schema: &Schema,
+    mut position: u64,
+    emit: &mut dyn FnMut(Record, u64) -> anyhow::Result<()>,
+) -> anyhow::Result<u64> {
+    let mut reader = open_at(path, position)?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let len = reader.read_line(&mut line)?;
+        if len == 0 {
+            break;
+        }
+        position += len as u64;
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let record = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(object)) => object_to_record(schema, &object),
+            Ok(_) => Err(anyhow!("not a JSON object")),
+            Err(e) => Err(e.into()),
+        };
+        match record {
+            Ok(record) => emit(record, position)?,
+            Err(e) => warn!("discard the malformed line of file {:?}. {}", path, e),
+        }
+    }
+
+    Ok(position)
+}
+
+fn read_parquet(
+    path: &Path,
+    schema: &Schema,
+    position: u64,
+    emit: &mut dyn FnMut(Record, u64) -> anyhow::Result<()>,
+) -> anyhow::Result<u64> {
+    if is_gzip(path) {
+        return Err(anyhow!("the gzip Parquet file {:?} is not supported", path));
+    }
+
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let mut row_number = position;
+    for row in reader.get_row_iter(None)?.skip(position as usize) {
+        row_number += 1;
+        let record = match row?.to_json_value() {
+            Value::Object(object) => object_to_record(schema, &object),
+            _ => Err(anyhow!("not a Parquet group")),
+        };
+        match record {
+            Ok(record) => emit(record, row_number)?,
+            Err(e) => warn!("discard the malformed row of file {:?}. {}", path, e),
+        }
+    }
+
+    Ok(row_number)
+}
+
+/// The interval to retry when the files fail to list or read
+const READ_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Read the files of the split, and send the records to the stream. In monitor mode, the new
+/// files found by the rescans are assigned to the tasks by the hash of the paths. The files
+/// finished or read in the run are skipped.
+pub(crate) struct FileReaderTask {
+    pub path: String,
+    pub format: FileFormat,
+    pub schema: Schema,
+    /// the files of the split
+    pub split_paths: Vec<String>,
+    /// the files of all the splits, the files found later are new
+    pub listed_paths: HashSet<String>,
+    pub task_number: u16,
+    pub num_tasks: u16,
+    /// rescan the path for the new files with the interval, or finish after the files of the
+    /// split are read
+    pub monitor_interval: Option<Duration>,
+    pub fetcher: Box<dyn FileFetcher>,
+    pub state: FileSourceState,
+}
+
+impl FileReaderTask {
+    fn is_owner(&self, path: &str) -> bool {
+        if self.listed_paths.contains(path) {
+            return false;
+        }
+
+        split_of(path, self.num_tasks) == self.task_number
+    }
+
+    /// Run in the blocking thread until all the files are read, or the stream is closed. The
+    /// failed read is retried from the position of the last record sent.
+    pub fn run(self, sender: ChannelSender<FileRecord>) {
+        let handle = tokio::runtime::Handle::current();
+        let send = |file_record: FileRecord| {
+            handle
+                .block_on(sender.send(file_record))
+                .map_err(|_e| ReadError::Closed)
+        };
+
+        let mut progress = ReadProgress::default();
+        let mut split_read = false;
+        loop {
+            let result = if split_read {
+                self.read_new_files(&mut progress, &send)
+            } else {
+                self.read_files(self.split_paths.as_slice(), &mut progress, &send)
+            };
+            match result {
+                Ok(()) => split_read = true,
+                Err(ReadError::Closed) => return,
+                Err(ReadError::Read(e)) => {
+                    error!(
+                        "read file error, retry after {:?}. {}",
+                        READ_RETRY_INTERVAL, e
+                    );
+                    std::thread::sleep(READ_RETRY_INTERVAL);
+                    continue;
+                }
+            }
+
+            match self.monitor_interval {
+                Some(interval) => std::thread::sleep(interval),
+                None => {
+                    info!("all the files of the split are read");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// List the `path` and read the new files owned by the task. The files not listed any more
+    /// are removed from the state, they won't be read again.
+    fn read_new_files(
+        &self,
+        progress: &mut ReadProgress,
+        send: &dyn Fn(FileRecord) -> Result<(), ReadError>,
+    ) -> Result<(), ReadError> {
+        let files: Vec<String> = list_files(self.path.as_str())?
+            .into_iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+
+        let listed: HashSet<&str> = files.iter().map(|x| x.as_str()).collect();
+        self.state.retain(|path| listed.contains(path));
+        progress.retain(|path| listed.contains(path));
+
+        let new_files: Vec<String> = files
+            .iter()
+            .filter(|path| !progress.is_visited(path.as_str()) && self.is_owner(path.as_str()))
+            .cloned()
+            .collect();
+        self.read_files(new_files.as_slice(), progress, send)
+    }
+
+    fn read_files(
+        &self,
+        paths: &[String],
+        progress: &mut ReadProgress,
+        send: &dyn Fn(FileRecord) -> Result<(), ReadError>,
+    ) -> Result<(), ReadError> {
+        for path in paths {
+            if progress.is_visited(path.as_str()) {
+                continue;
+            }
+
+            let offset = self.state.get(path.as_str()).unwrap_or_default();
+            if offset.finished {
+                progress.finish(path.as_str());
+                continue;
+            }
+
+            let file = match self.fetcher.fetch(path.as_str())? {
+                Some(file) => file,
+                None => {
+                    warn!("file {} is not found, skip it", path);
+                    progress.finish(path.as_str());
+                    continue;
+                }
+            };
+
+            // resume from the last record sent, the state lags behind the records in the stream
+            let position = progress.position(path.as_str()).unwrap_or(offset.position);
+            info!("read file {} from position {}", path, position);
+
+            let shared_path = Arc::new(path.clone());
+            let mut closed = false;
+            let end_position = read_file(
+                file.as_path(),
+                &self.format,
+                &self.schema,
+                position,
+                &mut |record, position| {
+                    let file_record = FileRecord::Record {
+                        path: shared_path.clone(),
+                        position,
+                        record,
+                    };
+                    if send(file_record).is_err() {
+                        closed = true;
+                        return Err(anyhow!("the file stream is closed"));
+                    }
+                    progress.update(path.as_str(), position);
+                    Ok(())
+                },
+            );
+            self.fetcher.release(file.as_path())?;
+            if closed {
+                return Err(ReadError::Closed);
+            }
+
+            send(FileRecord::End {
+                path: shared_path,
+                position: end_position?,
+            })?;
+            progress.finish(path.as_str());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ReadError {
+    /// the stream is closed, the reader stops
+    Closed,
+    Read(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ReadError {
+    fn from(e: anyhow::Error) -> Self {
+        ReadError::Read(e)
+    }
+}
+
+/// The progress of the reader, ahead of the `FileSourceState` by the records in the stream
+#[derive(Default)]
+pub(crate) struct ReadProgress {
+    /// the files finished or skipped in the run
+    visited: HashSet<String>,
+    /// the position after the last record sent of the file being read
+    reading: Option<(String, u64)>,
+}
+
+impl ReadProgress {
+    pub fn is_visited(&self, path: &str) -> bool {
+        self.visited.contains(path)
+    }
+
+    pub fn position(&self, path: &str) -> Option<u64> {
+        match &self.reading {
+            Some((reading_path, position)) if reading_path == path => Some(*position),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, path: &str, position: u64) {
+        match &mut self.reading {
+            Some((reading_path, reading_position)) if reading_path == path => {
+                *reading_position = position
+            }
+            _ => self.reading = Some((path.to_string(), position)),
+        }
+    }
+
+    pub fn finish(&mut self, path: &str) {
+        self.visited.insert(path.to_string());
+        if self.position(path).is_some() {
+            self.reading = None;
+        }
+    }
+
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.visited.retain(|path| f(path.as_str()));
+        if let Some((path, _position)) = &self.reading {
+            if !f(path.as_str()) {
+                self.reading = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    use crate::format::FileFormat;
+    use crate::source::fetcher::LocalFileFetcher;
+    use crate::source::reader::{list_files, read_file, FileReaderTask, ReadProgress};
+    use crate::source::state::FileSourceState;
+    use crate::source::stream::FileRecord;
+
+    fn read_all(
+        path: &std::path::Path,
+        format: &FileFormat,
+        schema: &Schema,
+        position: u64,
+    ) -> Vec<(i64, String, u64)> {
+        let mut rows = Vec::new();
+        read_file(
+            path,
+            format,
+            schema,
+            position,
+            &mut |mut record: Record, next| {
+                let reader = record.as_reader(schema.as_type_ids());
+                rows.push((
+                    reader.get_i64(0).unwrap(),
+                    reader.get_str(1).unwrap().to_string(),
+                    next,
+                ));
+                Ok(())
+            },
+        )
+        .unwrap();
+        rows
+    }
+
+    #[test]
+    pub fn read_file_test() {
+        let dir = std::env::temp_dir().join(format!("rlink-file-source-{}", std::process::id()));
+        std::fs::create_dir_all(dir.as_path()).unwrap();
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+        ]);
+
+        // csv, resume from the position of the second record
+        let csv_path = dir.join("a.csv");
+        std::fs::write(csv_path.as_path(), "id,name\n1,a\n2,b\n3,c\n").unwrap();
+        let rows = read_all(csv_path.as_path(), &FileFormat::csv(), &schema, 0);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], (1, "a".to_string(), 12));
+        let rows = read_all(csv_path.as_path(), &FileFormat::csv(), &schema, 12);
+        assert_eq!(
+            rows,
+            vec![(2, "b".to_string(), 16), (3, "c".to_string(), 20)]
+        );
+
+        // gzip json-lines, the malformed line is discarded
+        let json_path = dir.join("b.json.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"{\"name\":\"x\",\"id\":7}\nbad\n{\"id\":\"8\"}\n")
+            .unwrap();
+        std::fs::write(json_path.as_path(), encoder.finish().unwrap()).unwrap();
+        let rows = read_all(json_path.as_path(), &FileFormat::json_lines(), &schema, 0);
+        assert_eq!(
+            rows,
+            vec![(7, "x".to_string(), 20), (8, "".to_string(), 35)]
+        );
+        let rows = read_all(json_path.as_path(), &FileFormat::json_lines(), &schema, 20);
+        assert_eq!(rows, vec![(8, "".to_string(), 35)]);
+
+        // parquet written by the sink format, the position is the row number
+        let parquet_path = dir.join("c.parquet");
+        let file = std::fs::File::create(parquet_path.as_path()).unwrap();
+        let mut writer = FileFormat::parquet().create_writer(&schema, file).unwrap();
+        for id in 0..3 {
+            let mut record = Record::new();
+            let mut record_writer = record.as_writer(schema.as_type_ids());
+            record_writer.set_i64(id).unwrap();
+            record_writer.set_str(format!("n{}", id).as_str()).unwrap();
+            writer.write(&mut record).unwrap();
+        }
+        writer.finish().unwrap();
+        let rows = read_all(parquet_path.as_path(), &FileFormat::parquet(), &schema, 1);
+        assert_eq!(
+            rows,
+            vec![(1, "n1".to_string(), 2), (2, "n2".to_string(), 3)]
+        );
+
+        // the hidden files are skipped
+        std::fs::write(dir.join(".hidden.csv"), "").unwrap();
+        let files = list_files(dir.to_str().unwrap()).unwrap();
+        assert_eq!(files, vec![csv_path.clone(), json_path, parquet_path]);
+        let pattern = format!("{}/*.csv", dir.to_str().unwrap());
+        assert_eq!(list_files(pattern.as_str()).unwrap(), vec![csv_path]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn read_new_files_test() {
+        let dir = std::env::temp_dir().join(format!("rlink-file-monitor-{}", std::process::id()));
+        std::fs::create_dir_all(dir.as_path()).unwrap();
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+        ]);
+
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        std::fs::write(path("a.csv"), "1,a\n").unwrap();
+        std::fs::write(path("b.csv"), "2,b\n").unwrap();
+
+        // the finished file removed from the directory
+        let state = FileSourceState::default();
+        state.finish(path("removed.csv").as_str(), 4);
+        let reader_task = FileReaderTask {
+            path: dir.to_string_lossy().to_string(),
+            format: FileFormat::Csv {
+                delimiter: b',',
+                header: false,
+            },
+            schema,
+            split_paths: vec![path("a.csv")],
+            listed_paths: vec![path("a.csv")].into_iter().collect::<HashSet<String>>(),
+            task_number: 0,
+            num_tasks: 1,
+            monitor_interval: None,
+            fetcher: Box::new(LocalFileFetcher),
+            state: state.clone(),
+        };
+
+        let sent = RefCell::new(Vec::new());
+        let send = |file_record: FileRecord| {
+            match file_record {
+                FileRecord::Record { path, position, .. } => sent
+                    .borrow_mut()
+                    .push(format!("record:{}:{}", path, position)),
+                FileRecord::End { path, position } => {
+                    sent.borrow_mut().push(format!("end:{}:{}", path, position))
+                }
+            }
+            Ok(())
+        };
+
+        // the file of the split is not new
+        let mut progress = ReadProgress::default();
+        reader_task
+            .read_new_files(&mut progress, &send)
+            .ok()
+            .unwrap();
+        assert_eq!(
+            sent.take(),
+            vec![
+                format!("record:{}:4", path("b.csv")),
+                format!("end:{}:4", path("b.csv"))
+            ]
+        );
+        assert!(state.get(path("removed.csv").as_str()).is_none());
+
+        // the visited file is skipped
+        reader_task
+            .read_new_files(&mut progress, &send)
+            .ok()
+            .unwrap();
+        assert!(sent.take().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
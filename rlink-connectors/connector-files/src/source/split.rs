@@ -0,0 +1,42 @@
+/// The split of the file by the hash of the `path`, so a file is always read by the same task,
+/// which keeps the read offset of the file in its checkpoint, whatever the other files listed
+pub fn split_of(path: &str, num_splits: u16) -> u16 {
+    let hash = rlink::utils::hash::hash_code(path.as_bytes()).unwrap_or_default();
+    (hash % num_splits as u32) as u16
+}
+
+/// Assign the files to `num_splits` splits by the `split_of` the paths
+pub fn assign_files(paths: &[String], num_splits: u16) -> Vec<Vec<String>> {
+    let mut splits = vec![vec![]; num_splits as usize];
+    for path in paths {
+        splits[split_of(path.as_str(), num_splits) as usize].push(path.clone());
+    }
+    splits
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::split::{assign_files, split_of};
+
+    #[test]
+    pub fn assign_files_test() {
+        let paths: Vec<String> = (0..20).map(|i| format!("{}.json", i)).collect();
+
+        let splits = assign_files(paths.as_slice(), 3);
+        assert_eq!(splits.len(), 3);
+        assert_eq!(splits.iter().map(|x| x.len()).sum::<usize>(), paths.len());
+        for (split, paths) in splits.iter().enumerate() {
+            for path in paths {
+                assert_eq!(split_of(path.as_str(), 3), split as u16);
+            }
+        }
+
+        // the files are kept in their splits when the other files are removed
+        let removed = &paths[0..5];
+        let splits2 = assign_files(&paths[5..], 3);
+        for (paths, paths2) in splits.into_iter().zip(splits2) {
+            let paths: Vec<String> = paths.into_iter().filter(|x| !removed.contains(x)).collect();
+            assert_eq!(paths, paths2);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// The read offset of a file, the `position` is the byte offset of the next record in the
+/// (decompressed) file for CSV and JSON-lines, or the next row number for Parquet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileOffset {
+    pub position: u64,
+    /// all the records of the file are emitted
+    pub finished: bool,
+}
+
+/// The read offsets of all the files assigned to a task, updated when the records are emitted
+#[derive(Clone, Debug, Default)]
+pub struct FileSourceState {
+    files: Arc<Mutex<BTreeMap<String, FileOffset>>>,
+}
+
+impl FileSourceState {
+    pub fn get(&self, path: &str) -> Option<FileOffset> {
+        let files = self.files.lock().unwrap();
+        files.get(path).cloned()
+    }
+
+    pub fn update(&self, path: &str, position: u64) {
+        let mut files = self.files.lock().unwrap();
+        let offset = files.entry(path.to_string()).or_default();
+        offset.position = position;
+    }
+
+    pub fn finish(&self, path: &str, position: u64) {
+        let mut files = self.files.lock().unwrap();
+        files.insert(
+            path.to_string(),
+            FileOffset {
+                position,
+                finished: true,
+            },
+        );
+    }
+
+    /// Retain only the files specified by the predicate
+    pub fn retain<F>(&self, f: F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut files = self.files.lock().unwrap();
+        files.retain(|path, _offset| f(path.as_str()));
+    }
+
+    /// The snapshot is a JSON object of the file path to the `FileOffset`
+    pub fn snapshot(&self) -> String {
+        let files = self.files.lock().unwrap();
+        serde_json::to_string(&*files).unwrap()
+    }
+
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let snapshot: BTreeMap<String, FileOffset> = serde_json::from_str(snapshot_handle)?;
+        let mut files = self.files.lock().unwrap();
+        files.extend(snapshot);
+        Ok(())
+    }
+}
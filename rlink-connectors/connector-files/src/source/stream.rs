@@ -0,0 +1,66 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::{Element, Record};
+use rlink::core::function::ElementStream;
+
+use crate::source::state::FileSourceState;
+
+/// The message from the file reader to the stream
+pub(crate) enum FileRecord {
+    /// a record of the file and the position of the next record
+    Record {
+        path: Arc<String>,
+        position: u64,
+        record: Record,
+    },
+    /// the end of the file
+    End { path: Arc<String>, position: u64 },
+}
+
+/// The records of all the files assigned to the task, the read offsets of the files are updated
+/// when the records are emitted
+pub struct FileRecordStream {
+    receiver: ChannelReceiver<FileRecord>,
+    state: FileSourceState,
+}
+
+impl FileRecordStream {
+    pub(crate) fn new(receiver: ChannelReceiver<FileRecord>, state: FileSourceState) -> Self {
+        FileRecordStream { receiver, state }
+    }
+}
+
+impl ElementStream for FileRecordStream {}
+
+impl Stream for FileRecordStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let file_record = match self.as_mut().receiver.poll_recv(cx) {
+                Poll::Ready(Some(file_record)) => file_record,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match file_record {
+                FileRecord::Record {
+                    path,
+                    position,
+                    record,
+                } => {
+                    self.state.update(path.as_str(), position);
+                    return Poll::Ready(Some(Element::Record(record)));
+                }
+                FileRecord::End { path, position } => {
+                    info!("file {} is finished", path);
+                    self.state.finish(path.as_str(), position);
+                }
+            }
+        }
+    }
+}
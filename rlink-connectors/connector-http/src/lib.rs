@@ -0,0 +1,16 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub(crate) mod record;
+pub mod sink;
+pub mod source;
+pub(crate) mod template;
+
+pub use sink::output_format::HttpOutputFormat;
+pub use source::input_format::HttpInputFormat;
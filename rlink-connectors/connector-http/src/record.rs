@@ -0,0 +1,142 @@
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{BufferReader, Record};
+use serde_json::{Map, Value};
+
+/// Write the JSON object to a `Record` of the `schema`, the values are matched by the schema
+/// field names and converted to the field types, the missing or null value is written as the
+/// default value of the field type.
+pub(crate) fn to_record(schema: &Schema, object: &Map<String, Value>) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+
+    for field in schema.fields() {
+        let value = object.get(field.name()).unwrap_or(&Value::Null);
+        let rt = match field.data_type() {
+            DataType::Boolean => writer.set_bool(to_bool(value)?),
+            DataType::Int8 => writer.set_i8(to_i64(value)? as i8),
+            DataType::UInt8 => writer.set_u8(to_u64(value)? as u8),
+            DataType::Int16 => writer.set_i16(to_i64(value)? as i16),
+            DataType::UInt16 => writer.set_u16(to_u64(value)? as u16),
+            DataType::Int32 => writer.set_i32(to_i64(value)? as i32),
+            DataType::UInt32 => writer.set_u32(to_u64(value)? as u32),
+            DataType::Int64 => writer.set_i64(to_i64(value)?),
+            DataType::UInt64 => writer.set_u64(to_u64(value)?),
+            DataType::Float32 => writer.set_f32(to_f64(value)? as f32),
+            DataType::Float64 => writer.set_f64(to_f64(value)?),
+            DataType::Binary => writer.set_binary(to_string(value).as_bytes()),
+            DataType::String => writer.set_str(to_string(value).as_str()),
+        };
+        rt.map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+    }
+
+    Ok(record)
+}
+
+/// Read the `Record` of the `schema` as a JSON object of the schema field names
+pub(crate) fn to_json_object(schema: &Schema, record: &mut Record) -> anyhow::Result<Value> {
+    let reader = record.as_reader(schema.as_type_ids());
+    let mut object = Map::with_capacity(schema.fields().len());
+    for (index, field) in schema.fields().iter().enumerate() {
+        let value = to_json_value(&reader, index, field.data_type())?;
+        object.insert(field.name().to_string(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+pub(crate) fn to_json_value(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> anyhow::Result<Value> {
+    let value = match data_type {
+        DataType::Boolean => Value::from(reader.get_bool(index)?),
+        DataType::Int8 => Value::from(reader.get_i8(index)?),
+        DataType::UInt8 => Value::from(reader.get_u8(index)?),
+        DataType::Int16 => Value::from(reader.get_i16(index)?),
+        DataType::UInt16 => Value::from(reader.get_u16(index)?),
+        DataType::Int32 => Value::from(reader.get_i32(index)?),
+        DataType::UInt32 => Value::from(reader.get_u32(index)?),
+        DataType::Int64 => Value::from(reader.get_i64(index)?),
+        DataType::UInt64 => Value::from(reader.get_u64(index)?),
+        DataType::Float32 => Value::from(reader.get_f32(index)?),
+        DataType::Float64 => Value::from(reader.get_f64(index)?),
+        DataType::Binary => Value::from(String::from_utf8_lossy(reader.get_binary(index)?)),
+        DataType::String => Value::from(reader.get_str(index)?),
+    };
+    Ok(value)
+}
+
+fn to_i64(value: &Value) -> anyhow::Result<i64> {
+    match value {
+        Value::Null => Ok(0),
+        Value::Bool(v) => Ok(*v as i64),
+        Value::Number(v) => v
+            .as_i64()
+            .or_else(|| v.as_f64().map(|v| v as i64))
+            .ok_or(anyhow!("{} can't be converted to integer", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => Err(anyhow!("{} can't be converted to integer", value)),
+    }
+}
+
+fn to_u64(value: &Value) -> anyhow::Result<u64> {
+    match value {
+        Value::Number(v) if v.is_u64() => Ok(v.as_u64().unwrap()),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as u64),
+    }
+}
+
+fn to_f64(value: &Value) -> anyhow::Result<f64> {
+    match value {
+        Value::Number(v) => v
+            .as_f64()
+            .ok_or(anyhow!("{} can't be converted to float", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as f64),
+    }
+}
+
+fn to_bool(value: &Value) -> anyhow::Result<bool> {
+    match value {
+        Value::Bool(v) => Ok(*v),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v != 0),
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(v) => v.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use serde_json::json;
+
+    use crate::record::{to_json_object, to_record};
+
+    #[test]
+    pub fn record_json_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+            Field::new("score", DataType::Float64),
+            Field::new("valid", DataType::Boolean),
+        ]);
+
+        let object = json!({"id": "10", "name": "rlink", "score": 3, "unknown": 1});
+        let mut record = to_record(&schema, object.as_object().unwrap()).unwrap();
+        assert_eq!(
+            to_json_object(&schema, &mut record).unwrap(),
+            json!({"id": 10, "name": "rlink", "score": 3.0, "valid": false})
+        );
+
+        let object = json!({"id": [1]});
+        assert!(to_record(&schema, object.as_object().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod output_format;
+pub mod request;
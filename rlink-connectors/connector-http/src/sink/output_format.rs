@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{Client, Method};
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::{Element, FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use crate::record::to_json_object;
+use crate::sink::request::{
+    send_with_retry, HttpRequest, RequestError, RetryPolicy, RETRY_BACKOFF_MAX,
+};
+use crate::template::{ResolvedTemplate, Template};
+
+const BATCH_SIZE_DEFAULT: usize = 1;
+const CONCURRENCY_DEFAULT: usize = 4;
+const MAX_RETRIES_DEFAULT: usize = 3;
+const RETRY_BACKOFF_DEFAULT: Duration = Duration::from_millis(100);
+const TIMEOUT_DEFAULT: Duration = Duration::from_secs(30);
+
+/// The rendered url and headers of a record, the records of the same target are batched
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct RequestTarget {
+    url: String,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+/// Send the records as JSON objects to the HTTP endpoints, the url and the header values are
+/// templates of the `{name}` placeholders of the columns, such as
+/// `http://alert/api/{tenant}/events`.
+///
+/// Only the `http` urls are supported.
+///
+/// A request carries a JSON object, or a JSON array of at most `batch_size` objects of the same
+/// url and headers if batching. At most `concurrency` requests are in flight, the failed
+/// requests of connection errors, timeouts, `429` and `5xx` are retried with the exponential
+/// backoff, and kept to retry again after `max_retries`, the later records and checkpoints wait
+/// for them. The batches are sent and the in-flight requests are waited on every checkpoint, so
+/// the records before the checkpoint are sent at least once. The records failed to render and
+/// the requests rejected by the other status are discarded, and fail the next checkpoint.
+#[derive(NamedFunction)]
+pub struct HttpOutputFormat {
+    url: String,
+    method: Method,
+    headers: Vec<(String, String)>,
+    batch_size: usize,
+    concurrency: usize,
+    retry_policy: RetryPolicy,
+
+    schema: Schema,
+    url_template: Option<ResolvedTemplate>,
+    header_templates: Vec<(HeaderName, ResolvedTemplate)>,
+    client: Option<Client<HttpConnector>>,
+    batches: HashMap<RequestTarget, Vec<Value>>,
+    in_flight: Arc<Semaphore>,
+    /// the number of the records discarded since the last checkpoint and the first error, the
+    /// next checkpoint is declined
+    discarded: Arc<Mutex<Option<(usize, String)>>>,
+}
+
+impl HttpOutputFormat {
+    pub fn new(url: &str) -> Self {
+        HttpOutputFormat {
+            url: url.to_string(),
+            method: Method::POST,
+            headers: vec![],
+            batch_size: BATCH_SIZE_DEFAULT,
+            concurrency: CONCURRENCY_DEFAULT,
+            retry_policy: RetryPolicy {
+                max_retries: MAX_RETRIES_DEFAULT,
+                backoff: RETRY_BACKOFF_DEFAULT,
+                timeout: TIMEOUT_DEFAULT,
+            },
+            schema: Schema::empty(),
+            url_template: None,
+            header_templates: vec![],
+            client: None,
+            batches: HashMap::new(),
+            in_flight: Arc::new(Semaphore::new(CONCURRENCY_DEFAULT)),
+            discarded: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// `POST` by default
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Add a header, the `value` is a template of the columns
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send the records of the same url and headers in JSON arrays of at most `batch_size`,
+    /// the record is sent as a JSON object if `1`, the default
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The max number of the requests in flight
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self.in_flight = Arc::new(Semaphore::new(self.concurrency));
+        self
+    }
+
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.retry_policy.max_retries = max_retries;
+        self
+    }
+
+    /// The delay of the first retry, doubled after each retry up to 60s
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_policy.backoff = backoff;
+        self
+    }
+
+    /// The timeout of each attempt
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.retry_policy.timeout = timeout;
+        self
+    }
+
+    /// Add the record to the batch of its target, return the batch if it's full
+    fn append(
+        &mut self,
+        record: &mut Record,
+    ) -> anyhow::Result<Option<(RequestTarget, Vec<Value>)>> {
+        let target = {
+            let reader = record.as_reader(self.schema.as_type_ids());
+            let url = self
+                .url_template
+                .as_ref()
+                .unwrap()
+                .render(&self.schema, &reader, true)?;
+            let mut headers = Vec::with_capacity(self.header_templates.len());
+            for (name, template) in &self.header_templates {
+                let value = template.render(&self.schema, &reader, false)?;
+                let value = HeaderValue::from_str(value.as_str())
+                    .map_err(|e| anyhow!("invalid value of header {}. {}", name, e))?;
+                headers.push((name.clone(), value));
+            }
+            RequestTarget { url, headers }
+        };
+        let object = to_json_object(&self.schema, record)?;
+
+        let batch = self.batches.entry(target.clone()).or_default();
+        batch.push(object);
+        if batch.len() >= self.batch_size {
+            let batch = self.batches.remove(&target).unwrap();
+            return Ok(Some((target, batch)));
+        }
+        Ok(None)
+    }
+
+    /// Send the batch in the background once a request slot is available
+    async fn dispatch(&self, target: RequestTarget, mut batch: Vec<Value>) {
+        let records = batch.len();
+        let body = if self.batch_size == 1 {
+            batch.pop().unwrap()
+        } else {
+            Value::Array(batch)
+        };
+        let request = HttpRequest {
+            method: self.method.clone(),
+            url: target.url,
+            headers: target.headers,
+            body: hyper::body::Bytes::from(body.to_string()),
+        };
+
+        let permit = self.in_flight.clone().acquire_owned().await.unwrap();
+        let client = self.client.clone().unwrap();
+        let retry_policy = self.retry_policy;
+        let discarded = self.discarded.clone();
+        tokio::spawn(async move {
+            // the permit is held until the request is sent, so the later records wait for it
+            loop {
+                match send_with_retry(&client, &request, retry_policy).await {
+                    Ok(()) => break,
+                    Err(RequestError::Retryable(e)) => {
+                        error!(
+                            "request {} error after {} retries, retry again after {:?}. {}",
+                            request.url, retry_policy.max_retries, RETRY_BACKOFF_MAX, e
+                        );
+                        tokio::time::sleep(RETRY_BACKOFF_MAX).await;
+                    }
+                    Err(RequestError::Fatal(e)) => {
+                        discard(&discarded, records, e);
+                        break;
+                    }
+                }
+            }
+            drop(permit);
+        });
+    }
+
+    /// Send all the batches and wait for the requests in flight, fail if any record is
+    /// discarded since the last flush
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        let batches = std::mem::take(&mut self.batches);
+        for (target, batch) in batches {
+            self.dispatch(target, batch).await;
+        }
+
+        let permits = self
+            .in_flight
+            .acquire_many(self.concurrency as u32)
+            .await
+            .unwrap();
+        drop(permits);
+
+        match self.discarded.lock().unwrap().take() {
+            Some((discarded, e)) => Err(anyhow!(
+                "{} records are discarded, the first error: {}",
+                discarded,
+                e
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Count the discarded records and keep the first error, it's surfaced as a failure of the next
+/// checkpoint
+fn discard(discarded: &Mutex<Option<(usize, String)>>, records: usize, e: anyhow::Error) {
+    error!("write http error, {} records are discarded. {}", records, e);
+    let mut discarded = discarded.lock().unwrap();
+    match discarded.as_mut() {
+        Some((count, _e)) => *count += records,
+        None => *discarded = Some((records, e.to_string())),
+    }
+}
+
+#[async_trait]
+impl OutputFormat for HttpOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        self.schema = context.input_schema.first().clone();
+
+        // the client has no TLS
+        if !self.url.to_ascii_lowercase().starts_with("http://") {
+            return Err(core::Error::from(format!(
+                "only the `http://` urls are supported, but got {}",
+                self.url
+            )));
+        }
+        self.url_template = Some(Template::parse(self.url.as_str())?.resolve(&self.schema)?);
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| core::Error::from(format!("invalid header {}. {}", name, e)))?;
+            let template = Template::parse(value.as_str())?.resolve(&self.schema)?;
+            self.header_templates.push((header_name, template));
+        }
+
+        self.client = Some(Client::new());
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let mut record = element.into_record();
+        match self.append(&mut record) {
+            Ok(Some((target, batch))) => self.dispatch(target, batch).await,
+            Ok(None) => {}
+            Err(e) => discard(&self.discarded, 1, e),
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush()
+            .await
+            .map_err(|e| core::Error::from(format!("write http error. {}", e)))
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for HttpOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        if let Err(e) = self.flush().await {
+            context.decline(format!("write http error. {}", e).as_str());
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::template::Template;
+    use crate::HttpOutputFormat;
+
+    #[test]
+    pub fn append_test() {
+        let schema = Schema::new(vec![
+            Field::new("tenant", DataType::String),
+            Field::new("id", DataType::Int64),
+        ]);
+        let mut output_format = HttpOutputFormat::new("http://alert/{tenant}/events").batch_size(2);
+        output_format.url_template = Some(
+            Template::parse(output_format.url.as_str())
+                .unwrap()
+                .resolve(&schema)
+                .unwrap(),
+        );
+        output_format.schema = schema;
+
+        let mut record = |tenant: &str, id: i64| {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(output_format.schema.as_type_ids());
+            writer.set_str(tenant).unwrap();
+            writer.set_i64(id).unwrap();
+            output_format.append(&mut record).unwrap()
+        };
+
+        assert!(record("a", 1).is_none());
+        assert!(record("b", 2).is_none());
+        let (target, batch) = record("a", 3).unwrap();
+        assert_eq!(target.url, "http://alert/a/events");
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[1]["id"], 3);
+        assert_eq!(output_format.batches.len(), 1);
+    }
+}
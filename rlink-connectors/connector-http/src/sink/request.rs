@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use hyper::{Body, Client, Method, Request, StatusCode};
+
+/// The request of a batch, built once and sent by every attempt
+#[derive(Clone, Debug)]
+pub(crate) struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub body: hyper::body::Bytes,
+}
+
+impl HttpRequest {
+    fn to_request(&self) -> anyhow::Result<Request<Body>> {
+        let mut builder = Request::builder()
+            .method(self.method.clone())
+            .uri(self.url.as_str())
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(Body::from(self.body.clone()))
+            .map_err(|e| anyhow!("build request of {} error. {}", self.url, e))
+    }
+}
+
+/// The max delay between the retries
+pub(crate) const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// How the failed requests are retried, the delay is doubled after each retry up to
+/// `RETRY_BACKOFF_MAX`
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RetryPolicy {
+    pub max_retries: usize,
+    pub backoff: Duration,
+    pub timeout: Duration,
+}
+
+pub(crate) enum RequestError {
+    /// the connection failures, timeouts, `429` and `5xx`
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+/// The `429 Too Many Requests`, `408 Request Timeout` and the server errors are retried, the
+/// other client errors are not
+pub(crate) fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+pub(crate) async fn send_with_retry(
+    client: &Client<HttpConnector>,
+    request: &HttpRequest,
+    retry_policy: RetryPolicy,
+) -> Result<(), RequestError> {
+    let mut retries = 0;
+    loop {
+        let e = match send(client, request, retry_policy.timeout).await {
+            Ok(()) => return Ok(()),
+            Err(RequestError::Retryable(e)) if retries < retry_policy.max_retries => e,
+            Err(e) => return Err(e),
+        };
+
+        let delay = retry_delay(retry_policy.backoff, retries);
+        warn!(
+            "request {} error, retry {} after {:?}. {}",
+            request.url,
+            retries + 1,
+            delay,
+            e
+        );
+        tokio::time::sleep(delay).await;
+        retries += 1;
+    }
+}
+
+/// The delay of the `retries`-th retry
+pub(crate) fn retry_delay(backoff: Duration, retries: usize) -> Duration {
+    let delay = backoff.saturating_mul(2u32.saturating_pow(retries as u32));
+    std::cmp::min(delay, RETRY_BACKOFF_MAX)
+}
+
+async fn send(
+    client: &Client<HttpConnector>,
+    request: &HttpRequest,
+    timeout: Duration,
+) -> Result<(), RequestError> {
+    let req = request.to_request().map_err(RequestError::Fatal)?;
+    let response = match tokio::time::timeout(timeout, client.request(req)).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            return Err(RequestError::Retryable(anyhow!(
+                "request {} error. {}",
+                request.url,
+                e
+            )))
+        }
+        Err(_e) => {
+            return Err(RequestError::Retryable(anyhow!(
+                "request {} timeout",
+                request.url
+            )))
+        }
+    };
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .unwrap_or_default();
+    let e = anyhow!(
+        "request {} failed with {}. {}",
+        request.url,
+        status,
+        String::from_utf8_lossy(body.as_ref())
+    );
+    if is_retryable(status) {
+        Err(RequestError::Retryable(e))
+    } else {
+        Err(RequestError::Fatal(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::StatusCode;
+
+    use std::time::Duration;
+
+    use crate::sink::request::{is_retryable, retry_delay, RETRY_BACKOFF_MAX};
+
+    #[test]
+    pub fn is_retryable_test() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    pub fn retry_delay_test() {
+        let backoff = Duration::from_millis(100);
+        assert_eq!(retry_delay(backoff, 0), backoff);
+        assert_eq!(retry_delay(backoff, 3), Duration::from_millis(800));
+        assert_eq!(retry_delay(backoff, 20), RETRY_BACKOFF_MAX);
+        assert_eq!(retry_delay(backoff, 100), RETRY_BACKOFF_MAX);
+    }
+}
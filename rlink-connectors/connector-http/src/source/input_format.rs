@@ -0,0 +1,173 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::Server;
+use rlink::channel::named_channel;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::metrics::Tag;
+use tokio::sync::oneshot;
+
+use crate::source::server::{handle, WebhookContext};
+use crate::source::stream::HttpRecordStream;
+
+const BUFFER_SIZE_DEFAULT: usize = 1000;
+const MAX_BODY_SIZE_DEFAULT: usize = 1024 * 1024;
+
+/// Receive the webhooks by an HTTP server, the JSON object or the array of JSON objects `POST`ed
+/// to the `path` are emitted as the records of the `schema`, the object fields are matched by the
+/// schema field names.
+///
+/// Each task listens on the port of the `address` plus its task number. The requests are
+/// buffered by at most `buffer_size`, and the later ones are rejected with
+/// `429 Too Many Requests` until the stream catches up. The accepted records are not
+/// checkpointed, so the ones buffered when the task fails are lost.
+#[derive(NamedFunction)]
+pub struct HttpInputFormat {
+    address: String,
+    path: String,
+    schema: Schema,
+    parallelism: u16,
+    buffer_size: usize,
+    max_body_size: usize,
+
+    receiver: Option<ChannelReceiver<Vec<Record>>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl HttpInputFormat {
+    /// Listen on the `address` such as `0.0.0.0:8080`, and accept the requests of the `path`
+    pub fn new(address: &str, path: &str, schema: Schema) -> Self {
+        HttpInputFormat {
+            address: address.to_string(),
+            path: path.to_string(),
+            schema,
+            parallelism: 1,
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            max_body_size: MAX_BODY_SIZE_DEFAULT,
+            receiver: None,
+            shutdown: None,
+        }
+    }
+
+    /// The tasks listen on the consecutive ports from the port of the `address`
+    pub fn parallelism(mut self, parallelism: u16) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// The max number of the requests accepted but not emitted
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// The larger request bodies are rejected with `413 Payload Too Large`
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    fn bind_addr(&self, task_number: u16) -> anyhow::Result<SocketAddr> {
+        let mut bind_addr: SocketAddr = self
+            .address
+            .parse()
+            .map_err(|e| anyhow!("invalid address {}. {}", self.address, e))?;
+        let port = bind_addr
+            .port()
+            .checked_add(task_number)
+            .ok_or(anyhow!("the port of task {} overflows", task_number))?;
+        bind_addr.set_port(port);
+        Ok(bind_addr)
+    }
+}
+
+#[async_trait]
+impl InputFormat for HttpInputFormat {
+    async fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        let bind_addr = self.bind_addr(context.task_id.task_number())?;
+
+        let mut tags = context.task_id.to_tags();
+        tags.push(Tag::new("path", self.path.as_str()));
+        let (sender, receiver) = named_channel("HttpSource_Handover", tags, self.buffer_size);
+
+        let webhook_context = Arc::new(WebhookContext {
+            path: self.path.clone(),
+            schema: self.schema.clone(),
+            max_body_size: self.max_body_size,
+            sender,
+        });
+        let make_service = make_service_fn(move |_conn| {
+            let webhook_context = webhook_context.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| handle(req, webhook_context.clone())))
+            }
+        });
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let server = Server::try_bind(&bind_addr)
+            .map_err(|e| core::Error::from(format!("bind {} error. {}", bind_addr, e)))?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                shutdown_receiver.await.ok();
+            });
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                error!("http source server error. {}", e);
+            }
+        });
+        info!("http source listen on {}{}", bind_addr, self.path);
+
+        self.receiver = Some(receiver);
+        self.shutdown = Some(shutdown_sender);
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let receiver = self.receiver.take().unwrap();
+        Box::pin(HttpRecordStream::new(receiver))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for HttpInputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
+
+/// One split per task, each task listens on its own port
+impl InputSplitSource for HttpInputFormat {}
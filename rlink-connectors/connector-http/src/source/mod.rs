@@ -0,0 +1,3 @@
+pub mod input_format;
+pub mod server;
+pub mod stream;
@@ -0,0 +1,183 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use hyper::body::HttpBody;
+use hyper::http::header;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use rlink::channel::sender::ChannelSender;
+use rlink::channel::TrySendError;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use serde_json::Value;
+
+use crate::record::to_record;
+
+/// The seconds for the client to wait before retrying a rejected request
+const RETRY_AFTER_SECONDS: &str = "1";
+
+pub(crate) struct WebhookContext {
+    pub path: String,
+    pub schema: Schema,
+    pub max_body_size: usize,
+    pub sender: ChannelSender<Vec<Record>>,
+}
+
+/// Accept the `POST` request of a JSON object or an array of JSON objects, the objects are
+/// converted to the records of the schema and handed over to the stream as a whole, the request
+/// is rejected with `429 Too Many Requests` if the stream falls behind.
+pub(crate) async fn handle(
+    req: Request<Body>,
+    context: Arc<WebhookContext>,
+) -> Result<Response<Body>, Infallible> {
+    if req.uri().path() != context.path {
+        return Ok(response(StatusCode::NOT_FOUND, "Page not found"));
+    }
+    if req.method() != Method::POST {
+        return Ok(response(StatusCode::METHOD_NOT_ALLOWED, "POST only"));
+    }
+
+    let body = match read_body(req.into_body(), context.max_body_size).await {
+        Ok(Some(body)) => body,
+        Ok(None) => {
+            return Ok(response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("the body exceeds {} bytes", context.max_body_size).as_str(),
+            ))
+        }
+        Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string().as_str())),
+    };
+
+    let records = match to_records(&context.schema, body.as_slice()) {
+        Ok(records) => records,
+        Err(e) => return Ok(response(StatusCode::BAD_REQUEST, e.to_string().as_str())),
+    };
+
+    let response = match context.sender.try_send(records) {
+        Ok(()) => response(StatusCode::OK, ""),
+        Err(TrySendError::Full(_records)) => {
+            let mut response = response(StatusCode::TOO_MANY_REQUESTS, "the source is busy");
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                header::HeaderValue::from_static(RETRY_AFTER_SECONDS),
+            );
+            response
+        }
+        Err(TrySendError::Closed(_records)) => {
+            response(StatusCode::SERVICE_UNAVAILABLE, "the source is closed")
+        }
+    };
+    Ok(response)
+}
+
+/// Returns `None` if the body exceeds the `max_body_size`
+async fn read_body(mut body: Body, max_body_size: usize) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buffer.len() + chunk.len() > max_body_size {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(chunk.as_ref());
+    }
+    Ok(Some(buffer))
+}
+
+fn to_records(schema: &Schema, body: &[u8]) -> anyhow::Result<Vec<Record>> {
+    let value: Value =
+        serde_json::from_slice(body).map_err(|e| anyhow!("invalid json body. {}", e))?;
+    match value {
+        Value::Object(object) => Ok(vec![to_record(schema, &object)?]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::Object(object) => to_record(schema, object),
+                _ => Err(anyhow!("the array item is not a json object")),
+            })
+            .collect(),
+        _ => Err(anyhow!("the body is neither a json object nor an array")),
+    }
+}
+
+fn response(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(message.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hyper::{Body, Method, Request, StatusCode};
+    use rlink::channel::named_channel;
+    use rlink::core::data_types::{DataType, Field, Schema};
+
+    use crate::source::server::{handle, WebhookContext};
+
+    fn request(method: Method, path: &str, body: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    pub async fn handle_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+        ]);
+        let (sender, mut receiver) = named_channel("handle_test", vec![], 1);
+        let context = Arc::new(WebhookContext {
+            path: "/events".to_string(),
+            schema,
+            max_body_size: 64,
+            sender,
+        });
+
+        let status = |req| {
+            let context = context.clone();
+            async move { handle(req, context).await.unwrap().status() }
+        };
+
+        let body = r#"[{"id": 1, "name": "a"}, {"id": 2}]"#;
+        assert_eq!(
+            status(request(Method::POST, "/events", body)).await,
+            StatusCode::OK
+        );
+        // the channel is full
+        assert_eq!(
+            status(request(Method::POST, "/events", r#"{"id": 3}"#)).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(receiver.recv().await.unwrap().len(), 2);
+        assert_eq!(
+            status(request(Method::POST, "/events", r#"{"id": 3}"#)).await,
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            status(request(Method::POST, "/other", "{}")).await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(request(Method::GET, "/events", "")).await,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(request(Method::POST, "/events", r#"{"id": [1]}"#)).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(request(Method::POST, "/events", "1")).await,
+            StatusCode::BAD_REQUEST
+        );
+        let body = format!(r#"{{"name": "{}"}}"#, "a".repeat(64));
+        assert_eq!(
+            status(request(Method::POST, "/events", body.as_str())).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::{Element, Record};
+use rlink::core::function::ElementStream;
+
+/// Emit the records of the requests in the order they are accepted
+pub struct HttpRecordStream {
+    receiver: ChannelReceiver<Vec<Record>>,
+    records: VecDeque<Record>,
+}
+
+impl HttpRecordStream {
+    pub(crate) fn new(receiver: ChannelReceiver<Vec<Record>>) -> Self {
+        HttpRecordStream {
+            receiver,
+            records: VecDeque::new(),
+        }
+    }
+}
+
+impl ElementStream for HttpRecordStream {}
+
+impl Stream for HttpRecordStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(record) = self.records.pop_front() {
+                return Poll::Ready(Some(Element::Record(record)));
+            }
+
+            match self.as_mut().receiver.poll_recv(cx) {
+                Poll::Ready(Some(records)) => self.records.extend(records),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
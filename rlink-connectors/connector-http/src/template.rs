@@ -0,0 +1,173 @@
+use rlink::core::data_types::Schema;
+use rlink::core::element::BufferReader;
+use serde_json::Value;
+
+use crate::record::to_json_value;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// the column referred by the `{name}` placeholder
+    Field(String),
+}
+
+/// A string with the `{name}` placeholders of the record columns, such as
+/// `http://alert/api/{tenant}/events`, `{{` and `}}` are the escaped braces
+#[derive(Clone, Debug)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(anyhow!("unclosed `{{` in template {}", template)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(name.trim().to_string()));
+                }
+                '}' => return Err(anyhow!("unmatched `}}` in template {}", template)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Template { segments })
+    }
+
+    /// Resolve the placeholders to the field indexes of the `schema`
+    pub fn resolve(&self, schema: &Schema) -> anyhow::Result<ResolvedTemplate> {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => Ok(ResolvedSegment::Literal(literal.clone())),
+                Segment::Field(name) => schema
+                    .fields()
+                    .iter()
+                    .position(|field| field.name().eq(name))
+                    .map(ResolvedSegment::Field)
+                    .ok_or(anyhow!("column `{}` not found in the schema", name)),
+            })
+            .collect::<anyhow::Result<Vec<ResolvedSegment>>>()?;
+        Ok(ResolvedTemplate { segments })
+    }
+}
+
+#[derive(Clone, Debug)]
+enum ResolvedSegment {
+    Literal(String),
+    Field(usize),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ResolvedTemplate {
+    segments: Vec<ResolvedSegment>,
+}
+
+impl ResolvedTemplate {
+    /// Render the template with the record values, the values are percent-encoded if `encode`
+    pub fn render(
+        &self,
+        schema: &Schema,
+        reader: &BufferReader,
+        encode: bool,
+    ) -> anyhow::Result<String> {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                ResolvedSegment::Literal(literal) => rendered.push_str(literal),
+                ResolvedSegment::Field(index) => {
+                    let data_type = schema.fields()[*index].data_type();
+                    let value = match to_json_value(reader, *index, data_type)? {
+                        Value::String(v) => v,
+                        v => v.to_string(),
+                    };
+                    if encode {
+                        percent_encode(value.as_str(), &mut rendered);
+                    } else {
+                        rendered.push_str(value.as_str());
+                    }
+                }
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+/// Encode all the characters except the unreserved ones of RFC 3986
+fn percent_encode(value: &str, output: &mut String) {
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                output.push(b as char)
+            }
+            b => output.push_str(format!("%{:02X}", b).as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::template::Template;
+
+    #[test]
+    pub fn template_test() {
+        let schema = Schema::new(vec![
+            Field::new("tenant", DataType::String),
+            Field::new("id", DataType::Int64),
+        ]);
+        let mut record = Record::new();
+        let mut writer = record.as_writer(schema.as_type_ids());
+        writer.set_str("a b/c").unwrap();
+        writer.set_i64(10).unwrap();
+        let reader = record.as_reader(schema.as_type_ids());
+
+        let template = Template::parse("http://alert/{tenant}/events/{ id }?v={{1}}").unwrap();
+        let template = template.resolve(&schema).unwrap();
+        assert_eq!(
+            template.render(&schema, &reader, true).unwrap(),
+            "http://alert/a%20b%2Fc/events/10?v={1}"
+        );
+
+        let template = Template::parse("Bearer {tenant}").unwrap();
+        let template = template.resolve(&schema).unwrap();
+        assert_eq!(
+            template.render(&schema, &reader, false).unwrap(),
+            "Bearer a b/c"
+        );
+
+        assert!(Template::parse("http://alert/{tenant").is_err());
+        assert!(Template::parse("http://alert/}").is_err());
+        assert!(Template::parse("{unknown}")
+            .unwrap()
+            .resolve(&schema)
+            .is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! The message headers are stored in the `headers` field of the `kafka_message` entity, each
+//! header is encoded as `key length(u32) | key | value length(i32, -1 is null) | value` in
+//! little-endian.
+
+use rdkafka::message::{BorrowedHeaders, Header, Headers, OwnedHeaders};
+
+pub fn encode_headers(headers: &BorrowedHeaders) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for header in headers.iter() {
+        bytes.extend_from_slice(&(header.key.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(header.key.as_bytes());
+        match header.value {
+            Some(value) => {
+                bytes.extend_from_slice(&(value.len() as i32).to_le_bytes());
+                bytes.extend_from_slice(value);
+            }
+            None => bytes.extend_from_slice(&(-1i32).to_le_bytes()),
+        }
+    }
+
+    bytes
+}
+
+/// Decode the headers, return `None` if there is no header
+pub fn decode_headers(bytes: &[u8]) -> anyhow::Result<Option<OwnedHeaders>> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+        if bytes.len() < len {
+            return Err(anyhow!("truncated kafka headers"));
+        }
+        let (v, remaining) = bytes.split_at(len);
+        *bytes = remaining;
+        Ok(v)
+    }
+
+    let mut headers = OwnedHeaders::new();
+    let mut bytes = bytes;
+    while !bytes.is_empty() {
+        let key_len = take(&mut bytes, 4)?;
+        let key_len = u32::from_le_bytes([key_len[0], key_len[1], key_len[2], key_len[3]]);
+        let key = std::str::from_utf8(take(&mut bytes, key_len as usize)?)?;
+
+        let value_len = take(&mut bytes, 4)?;
+        let value_len =
+            i32::from_le_bytes([value_len[0], value_len[1], value_len[2], value_len[3]]);
+        let value = if value_len < 0 {
+            None
+        } else {
+            Some(take(&mut bytes, value_len as usize)?)
+        };
+
+        headers = headers.insert(Header { key, value });
+    }
+
+    Ok(Some(headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::message::{Header, Headers, OwnedHeaders};
+
+    use crate::headers::{decode_headers, encode_headers};
+
+    #[test]
+    pub fn headers_codec_test() {
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "trace_id",
+                value: Some("abc"),
+            })
+            .insert(Header {
+                key: "empty",
+                value: None::<&[u8]>,
+            });
+
+        let bytes = encode_headers(headers.as_borrowed());
+        let decoded_headers = decode_headers(bytes.as_slice()).unwrap().unwrap();
+        assert_eq!(decoded_headers.count(), 2);
+        assert_eq!(decoded_headers.get(0).key, "trace_id");
+        assert_eq!(decoded_headers.get(0).value, Some("abc".as_bytes()));
+        assert_eq!(decoded_headers.get(1).value, None);
+
+        assert!(decode_headers(&[]).unwrap().is_none());
+        assert!(decode_headers(&bytes[..3]).is_err());
+    }
+}
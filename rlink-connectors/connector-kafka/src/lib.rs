@@ -9,6 +9,7 @@ extern crate anyhow;
 #[macro_use]
 extern crate async_trait;
 
+pub mod headers;
 pub mod sink;
 pub mod source;
 
@@ -19,6 +20,7 @@ pub mod buffer_gen {
 pub use sink::output_format::KafkaOutputFormat;
 pub use source::input_format::KafkaInputFormat;
 
+use rdkafka::message::BorrowedHeaders;
 use rlink::core::element::Record;
 
 use crate::buffer_gen::kafka_message;
@@ -26,21 +28,37 @@ use crate::buffer_gen::kafka_message;
 pub const KAFKA: &str = "kafka";
 pub const BOOTSTRAP_SERVERS: &str = "bootstrap.servers";
 pub const GROUP_ID: &str = "group.id";
+pub const ENABLE_AUTO_COMMIT: &str = "enable.auto.commit";
 
 pub const TOPICS: &str = "topics";
+pub const TOPIC_PATTERN: &str = "topic.pattern";
+pub const PARTITION_DISCOVERY_INTERVAL_MS: &str = "partition.discovery.interval.ms";
 pub const BUFFER_SIZE: &str = "buffer.size";
+pub const TRANSACTIONAL_ID_PREFIX: &str = "transactional.id.prefix";
+pub const PARTITION_FROM_RECORD: &str = "partition.from.record";
 
 pub const OFFSET: &str = "offset";
 pub const OFFSET_TYPE: &str = "type";
 pub const OFFSET_BEGIN: &str = "begin";
 pub const OFFSET_END: &str = "end";
+pub const OFFSET_RESET: &str = "reset";
 
 pub const INPUT_FORMAT_FN_NAME_DEFAULT: &str = "KafkaInputFormat";
 pub const OUTPUT_FORMAT_FN_NAME_DEFAULT: &str = "KafkaOutputFormat";
 
+/// The partition of the record is unassigned, the producer's partitioner decides it
+pub const PARTITION_UNASSIGNED: i32 = -1;
+
 pub const SOURCE_CHANNEL_SIZE: usize = 50000;
 pub const SINK_CHANNEL_SIZE: usize = 50000;
 
+/// Build the `kafka_message` record.
+///
+/// When the record is written to the sink, the `key` is the message key, and the `topic` is used
+/// if the sink has no topic configured. The `partition` is the target partition only if the sink
+/// is built with `partition_from_record` and it's not `PARTITION_UNASSIGNED`, otherwise the
+/// producer's partitioner decides it, because the partition of a consumed record is seldom a
+/// partition of the sink topic.
 pub fn build_kafka_record(
     timestamp: i64,
     key: &[u8],
@@ -49,6 +67,23 @@ pub fn build_kafka_record(
     partition: i32,
     offset: i64,
 ) -> Result<Record, std::io::Error> {
+    build_kafka_record_with_headers(timestamp, key, payload, topic, partition, offset, None)
+}
+
+/// Build the `kafka_message` record with the message headers, see `build_kafka_record`
+#[allow(clippy::too_many_arguments)]
+pub fn build_kafka_record_with_headers(
+    timestamp: i64,
+    key: &[u8],
+    payload: &[u8],
+    topic: &str,
+    partition: i32,
+    offset: i64,
+    headers: Option<&BorrowedHeaders>,
+) -> Result<Record, std::io::Error> {
+    let headers = headers
+        .map(|headers| headers::encode_headers(headers))
+        .unwrap_or_default();
     let message = kafka_message::Entity {
         timestamp,
         key,
@@ -56,12 +91,13 @@ pub fn build_kafka_record(
         topic,
         partition,
         offset,
+        headers: headers.as_slice(),
     };
 
-    // 36 = 12(len(payload) + len(topic) + len(key)) +
+    // 40 = 16(len(payload) + len(topic) + len(key) + len(headers)) +
     //      20(len(timestamp) + len(partition) + len(offset)) +
     //      4(place_holder)
-    let capacity = payload.len() + topic.len() + key.len() + 36;
+    let capacity = payload.len() + topic.len() + key.len() + headers.len() + 40;
     let mut record = Record::with_capacity(capacity);
 
     message.to_buffer(record.as_buffer()).unwrap();
@@ -5,8 +5,8 @@ use rdkafka::ClientConfig;
 use rlink::core::properties::Properties;
 
 use crate::{
-    KafkaOutputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, KAFKA, SINK_CHANNEL_SIZE,
-    SOURCE_CHANNEL_SIZE, TOPICS,
+    KafkaOutputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, KAFKA, PARTITION_FROM_RECORD,
+    SINK_CHANNEL_SIZE, SOURCE_CHANNEL_SIZE, TOPICS, TRANSACTIONAL_ID_PREFIX,
 };
 
 #[derive(Debug)]
@@ -14,6 +14,8 @@ pub struct KafkaOutputFormatBuilder {
     conf_map: HashMap<String, String>,
     topics: Option<String>,
     buffer_size: Option<usize>,
+    transactional_id_prefix: Option<String>,
+    partition_from_record: bool,
 }
 
 impl KafkaOutputFormatBuilder {
@@ -22,6 +24,8 @@ impl KafkaOutputFormatBuilder {
             conf_map,
             topics,
             buffer_size: None,
+            transactional_id_prefix: None,
+            partition_from_record: false,
         }
     }
 
@@ -30,6 +34,27 @@ impl KafkaOutputFormatBuilder {
         self
     }
 
+    /// Write the records with kafka transactions, the transaction of a checkpoint is committed
+    /// when a later checkpoint is completed. A task writes with a pool of transactional ids
+    /// `{transactional_id_prefix}-{job_id}-{task_number}-{index}`, the prefix must be unique
+    /// across applications.
+    ///
+    /// librdkafka can't resume a transaction after restart, so the transactions not committed
+    /// are aborted when the task restarts, including the ones of the restored checkpoint.
+    ///
+    /// The consumers must read with `isolation.level=read_committed`.
+    pub fn exactly_once(mut self, transactional_id_prefix: &str) -> Self {
+        self.transactional_id_prefix = Some(transactional_id_prefix.to_string());
+        self
+    }
+
+    /// Write the record to the `partition` of the `KafkaRecord` unless it's
+    /// `PARTITION_UNASSIGNED`. By default the partition is decided by the producer's partitioner.
+    pub fn partition_from_record(mut self) -> Self {
+        self.partition_from_record = true;
+        self
+    }
+
     pub fn build(self) -> KafkaOutputFormat {
         info!("build kafka sink with: {:?}", &self);
 
@@ -40,7 +65,10 @@ impl KafkaOutputFormatBuilder {
 
         let buffer_size = self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE);
 
-        KafkaOutputFormat::new(client_config, self.topics, buffer_size)
+        let mut output_format = KafkaOutputFormat::new(client_config, self.topics, buffer_size);
+        output_format.transactional_id_prefix = self.transactional_id_prefix;
+        output_format.partition_from_record = self.partition_from_record;
+        output_format
     }
 }
 
@@ -72,7 +100,14 @@ impl TryFrom<Properties> for KafkaOutputFormatBuilder {
             .get_usize(BUFFER_SIZE)
             .unwrap_or(SINK_CHANNEL_SIZE);
 
-        let builder = KafkaOutputFormatBuilder::new(client_config, topic).buffer_size(buffer_size);
+        let mut builder =
+            KafkaOutputFormatBuilder::new(client_config, topic).buffer_size(buffer_size);
+        if let Ok(transactional_id_prefix) = properties.get_string(TRANSACTIONAL_ID_PREFIX) {
+            builder = builder.exactly_once(transactional_id_prefix.as_str());
+        }
+        if properties.get_bool(PARTITION_FROM_RECORD).unwrap_or(false) {
+            builder = builder.partition_from_record();
+        }
 
         Ok(builder)
     }
@@ -1,3 +1,4 @@
 pub mod builder;
 pub mod output_format;
 pub mod producer;
+pub mod transaction;
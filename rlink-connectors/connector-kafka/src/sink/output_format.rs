@@ -1,21 +1,39 @@
+use std::time::Duration;
+
 use rdkafka::ClientConfig;
 use rlink::channel::named_channel;
 use rlink::channel::sender::ChannelSender;
 use rlink::core;
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
-use rlink::core::element::{Element, Record};
+use rlink::core::element::Element;
 use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::core::properties::SystemProperties;
 use rlink::metrics::Tag;
+use tokio::sync::oneshot;
+
+use crate::sink::producer::{KafkaProducerThread, KafkaSinkMessage};
+use crate::sink::transaction::{
+    fence, transactional_ids, KafkaTransactionProducer, KafkaTransactionSnapshot,
+};
 
-use crate::sink::producer::KafkaProducerThread;
+/// The checkpoint interval of rlink when it's not configured
+const CHECKPOINT_INTERVAL_DEFAULT: Duration = Duration::from_secs(30);
 
 #[derive(NamedFunction)]
 pub struct KafkaOutputFormat {
     client_config: ClientConfig,
     topic: Option<String>,
+    /// write the record to its `partition` instead of the partitioner's choice
+    pub(crate) partition_from_record: bool,
 
     buffer_size: usize,
-    handover: Option<ChannelSender<Record>>,
+    handover: Option<ChannelSender<KafkaSinkMessage>>,
+    /// the producer thread has exited, all the later checkpoints are declined
+    thread_exited: bool,
+
+    /// enable the exactly-once mode with kafka transactions
+    pub(crate) transactional_id_prefix: Option<String>,
+    transaction_producer: Option<KafkaTransactionProducer>,
 }
 
 impl KafkaOutputFormat {
@@ -23,15 +41,57 @@ impl KafkaOutputFormat {
         KafkaOutputFormat {
             client_config,
             topic,
+            partition_from_record: false,
             buffer_size,
             handover: None,
+            thread_exited: false,
+            transactional_id_prefix: None,
+            transaction_producer: None,
+        }
+    }
+
+    /// wait for the acks of the records handed over before
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.thread_exited {
+            return Err(anyhow!("kafka producer thread has exited"));
         }
+
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        let message = KafkaSinkMessage::Flush(ack_sender);
+        if self.handover.as_ref().unwrap().send(message).await.is_err() {
+            self.thread_exited = true;
+            return Err(anyhow!("kafka producer thread has exited"));
+        }
+
+        ack_receiver
+            .await
+            .map_err(|_e| anyhow!("kafka producer thread has exited"))?
     }
 }
 
 #[async_trait]
 impl OutputFormat for KafkaOutputFormat {
     async fn open(&mut self, context: &Context) -> core::Result<()> {
+        if let Some(transactional_id_prefix) = &self.transactional_id_prefix {
+            let transactional_ids = transactional_ids(transactional_id_prefix, &context.task_id);
+            let checkpoint_interval = context
+                .application_properties
+                .get_checkpoint_interval()
+                .unwrap_or(CHECKPOINT_INTERVAL_DEFAULT);
+            let transaction_producer = KafkaTransactionProducer::new(
+                transactional_ids,
+                self.topic.clone(),
+                self.partition_from_record,
+                &self.client_config,
+                checkpoint_interval,
+            )?;
+            self.transaction_producer = Some(transaction_producer);
+
+            self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+                .await;
+            return Ok(());
+        }
+
         let mut tags = context.task_id.to_tags();
         tags.push(Tag::new(
             "topic",
@@ -42,9 +102,11 @@ impl OutputFormat for KafkaOutputFormat {
         self.handover = Some(sender);
 
         let topic = self.topic.clone();
+        let partition_from_record = self.partition_from_record;
         let client_config = self.client_config.clone();
         tokio::spawn(async move {
-            let mut kafka_consumer = KafkaProducerThread::new(topic, client_config, receiver);
+            let mut kafka_consumer = KafkaProducerThread::new(topic, client_config, receiver)
+                .partition_from_record(partition_from_record);
             kafka_consumer.run().await;
         });
 
@@ -52,15 +114,27 @@ impl OutputFormat for KafkaOutputFormat {
     }
 
     async fn write_element(&mut self, element: Element) {
-        self.handover
-            .as_ref()
-            .unwrap()
-            .send(element.into_record())
-            .await
-            .unwrap();
+        if let Some(transaction_producer) = self.transaction_producer.as_mut() {
+            // the records of the aborted transaction can't be recovered, fail the task to
+            // restart from the last checkpoint
+            if let Err(e) = transaction_producer.send(element.into_record()).await {
+                panic!("kafka transactional write error. {}", e);
+            }
+            return;
+        }
+
+        let message = KafkaSinkMessage::Record(element.into_record());
+        if self.handover.as_ref().unwrap().send(message).await.is_err() && !self.thread_exited {
+            // surfaced as a failure of the next checkpoint
+            error!("kafka producer thread has exited, the records are discarded");
+            self.thread_exited = true;
+        }
     }
 
     async fn close(&mut self) -> core::Result<()> {
+        if let Some(transaction_producer) = self.transaction_producer.as_mut() {
+            transaction_producer.close().await?;
+        }
         Ok(())
     }
 }
@@ -69,15 +143,74 @@ impl OutputFormat for KafkaOutputFormat {
 impl CheckpointFunction for KafkaOutputFormat {
     async fn initialize_state(
         &mut self,
-        _context: &FunctionSnapshotContext,
-        _handle: &Option<CheckpointHandle>,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
     ) {
+        let transaction_producer = match self.transaction_producer.as_ref() {
+            Some(transaction_producer) => transaction_producer,
+            None => return,
+        };
+        let handle = match handle {
+            Some(handle) if !context.checkpoint_id.is_default() && !handle.handle.is_empty() => {
+                handle
+            }
+            _ => return,
+        };
+
+        match KafkaTransactionSnapshot::from_handle(handle) {
+            Ok(snapshot) => {
+                info!(
+                    "load kafka transaction from checkpoint({:?}): {:?}",
+                    context.checkpoint_id, snapshot
+                );
+                // librdkafka can't resume the transaction of another producer, the pending
+                // transactions not committed before the failure are aborted when their ids are
+                // fenced
+                for pending in &snapshot.pending {
+                    error!(
+                        "the kafka transaction {} of checkpoint {} is aborted if it's not \
+                         committed, the records of it are lost",
+                        pending.transactional_id, pending.checkpoint_id
+                    );
+                }
+
+                // the current transactional ids have been fenced when the producers initialized
+                let transactional_ids = transaction_producer.transactional_ids();
+                for transactional_id in &snapshot.transactional_ids {
+                    if transactional_ids.contains(transactional_id) {
+                        continue;
+                    }
+                    if let Err(e) = fence(&self.client_config, transactional_id) {
+                        error!(
+                            "fence kafka transactional producer {} error. {}",
+                            transactional_id, e
+                        );
+                    }
+                }
+            }
+            Err(e) => error!("parse kafka transaction checkpoint error. {}", e),
+        }
     }
 
     async fn snapshot_state(
         &mut self,
-        _context: &FunctionSnapshotContext,
+        context: &FunctionSnapshotContext,
     ) -> Option<CheckpointHandle> {
+        if let Some(transaction_producer) = self.transaction_producer.as_mut() {
+            let pre_commit = transaction_producer
+                .pre_commit(context.checkpoint_id, context.completed_checkpoint_id)
+                .await;
+            return match pre_commit {
+                Ok(snapshot) => Some(snapshot.to_handle()),
+                // the transaction can't be committed anymore, fail the task to restart from the
+                // last checkpoint
+                Err(e) => panic!("pre-commit kafka transaction error. {}", e),
+            };
+        }
+
+        if let Err(e) = self.flush().await {
+            context.decline(format!("flush kafka producer error. {}", e).as_str());
+        }
         None
     }
 }
@@ -7,35 +7,85 @@ use rdkafka::ClientConfig;
 use rlink::channel::receiver::ChannelReceiver;
 use rlink::channel::TryRecvError;
 use rlink::core::element::Record;
+use tokio::sync::oneshot;
 
 use crate::buffer_gen::kafka_message;
+use crate::headers::decode_headers;
+use crate::PARTITION_UNASSIGNED;
+
+/// Build the message of the `kafka_message` entity, the `topic` of the sink takes precedence
+/// over the entity's, and the entity's `partition` is used only if `partition_from_record`
+pub(crate) fn to_future_record<'a>(
+    entity: &kafka_message::Entity<'a>,
+    topic: Option<&'a String>,
+    partition_from_record: bool,
+) -> anyhow::Result<FutureRecord<'a, [u8], [u8]>> {
+    let topic = match topic {
+        Some(topic) => topic.as_str(),
+        None => entity.topic,
+    };
+    if topic.is_empty() {
+        return Err(anyhow!("topic not found in `KafkaRecord`"));
+    }
+
+    let mut future_record = FutureRecord::to(topic)
+        .payload(entity.payload)
+        .timestamp(entity.timestamp)
+        .key(entity.key);
+    if partition_from_record && entity.partition != PARTITION_UNASSIGNED {
+        future_record = future_record.partition(entity.partition);
+    }
+    if let Some(headers) = decode_headers(entity.headers)? {
+        future_record = future_record.headers(headers);
+    }
+
+    Ok(future_record)
+}
+
+/// Message handed over from the `KafkaOutputFormat` to the `KafkaProducerThread`
+pub enum KafkaSinkMessage {
+    Record(Record),
+    /// wait for the acks of the produced records, sent when the sink snapshots
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+}
 
 pub struct KafkaProducerThread {
     topic: Option<String>,
+    partition_from_record: bool,
     producer: FutureProducer,
-    receiver: ChannelReceiver<Record>,
+    receiver: ChannelReceiver<KafkaSinkMessage>,
 
     drain_counter: Arc<AtomicU64>,
     discard_counter: Arc<AtomicU64>,
+
+    /// the first produce error, the discarded records are lost, so all the later flushes fail
+    produce_error: Option<String>,
 }
 
 impl KafkaProducerThread {
     pub fn new(
         topic: Option<String>,
         client_config: ClientConfig,
-        receiver: ChannelReceiver<Record>,
+        receiver: ChannelReceiver<KafkaSinkMessage>,
     ) -> Self {
         let producer: FutureProducer = client_config.create().expect("Consumer creation failed");
 
         KafkaProducerThread {
             topic,
+            partition_from_record: false,
             producer,
             receiver,
             drain_counter: Arc::new(AtomicU64::new(0)),
             discard_counter: Arc::new(AtomicU64::new(0)),
+            produce_error: None,
         }
     }
 
+    pub fn partition_from_record(mut self, partition_from_record: bool) -> Self {
+        self.partition_from_record = partition_from_record;
+        self
+    }
+
     pub async fn run(&mut self) {
         let idle_delay_10 = Duration::from_millis(10);
         let idle_delay_300 = Duration::from_millis(300);
@@ -46,58 +96,65 @@ impl KafkaProducerThread {
         loop {
             let mut future_queue = Vec::with_capacity(batch);
             let mut discard_counter = 0;
+            let mut flush_ack = None;
+            let mut disconnected = false;
             for _n in 0..batch {
                 match self.receiver.try_recv() {
-                    Ok(mut record) => {
-                        let kafka_message::Entity {
-                            timestamp,
-                            key,
-                            payload,
-                            topic,
-                            ..
-                        } = kafka_message::Entity::parse(record.as_buffer()).unwrap();
-
-                        let topic = match self.topic.as_ref() {
-                            Some(topic) => topic.as_str(),
-                            None => topic,
+                    Ok(KafkaSinkMessage::Record(mut record)) => {
+                        let entity = match kafka_message::Entity::parse(record.as_buffer()) {
+                            Ok(entity) => entity,
+                            Err(e) => {
+                                set_produce_error(
+                                    &mut self.produce_error,
+                                    format!("parse `KafkaRecord` error. {}", e),
+                                );
+                                discard_counter += 1;
+                                continue;
+                            }
                         };
-                        if topic.is_empty() {
-                            panic!("topic not found in `KafkaRecord`");
-                        }
 
-                        let future_record = FutureRecord::to(topic)
-                            .payload(payload)
-                            .timestamp(timestamp as i64)
-                            .key(key);
+                        let future_record = match to_future_record(
+                            &entity,
+                            self.topic.as_ref(),
+                            self.partition_from_record,
+                        ) {
+                            Ok(future_record) => future_record,
+                            Err(e) => {
+                                set_produce_error(&mut self.produce_error, e.to_string());
+                                discard_counter += 1;
+                                continue;
+                            }
+                        };
 
                         match self.producer.send_result(future_record) {
                             Ok(delivery_future) => future_queue.push(delivery_future),
                             Err((e, _future_record)) => {
-                                error!("send error: {}", e);
+                                set_produce_error(
+                                    &mut self.produce_error,
+                                    format!("send error: {}", e),
+                                );
                                 discard_counter += 1;
                             }
                         }
                     }
+                    Ok(KafkaSinkMessage::Flush(ack)) => {
+                        flush_ack = Some(ack);
+                        break;
+                    }
                     Err(TryRecvError::Empty) => {
                         break;
                     }
                     Err(TryRecvError::Disconnected) => {
-                        panic!("kafka recv channel disconnected");
+                        disconnected = true;
+                        break;
                     }
                 }
             }
 
-            if future_queue.len() == 0 {
-                idle_counter += 1;
-                if idle_counter < 30 {
-                    tokio::time::sleep(idle_delay_10).await;
-                } else {
-                    tokio::time::sleep(idle_delay_300).await;
-                }
-            } else {
+            let idle = future_queue.len() == 0;
+            if !idle {
                 idle_counter = 0;
 
-                // TODO data write consistency fault tolerant processing
                 if let Err(e) = self.producer.flush(Duration::from_secs(3)) {
                     error!("produce flush error: {:?}", e);
                 }
@@ -108,12 +165,18 @@ impl KafkaProducerThread {
                         Ok(result) => match result {
                             Ok((_, _)) => drain_counter += 1,
                             Err((err, _msg)) => {
-                                error!("produce error: {:?}", err);
+                                set_produce_error(
+                                    &mut self.produce_error,
+                                    format!("produce error: {:?}", err),
+                                );
                                 discard_counter += 1;
                             }
                         },
                         Err(e) => {
-                            error!("produce `Canceled` error: {}", e);
+                            set_produce_error(
+                                &mut self.produce_error,
+                                format!("produce `Canceled` error: {}", e),
+                            );
                             discard_counter += 1;
                         }
                     }
@@ -127,10 +190,42 @@ impl KafkaProducerThread {
                 self.discard_counter
                     .fetch_add(discard_counter as u64, Ordering::Relaxed);
             }
+
+            if let Some(ack) = flush_ack {
+                let flush_result = match &self.produce_error {
+                    Some(e) => Err(anyhow!("{}", e)),
+                    None => Ok(()),
+                };
+                if ack.send(flush_result).is_err() {
+                    warn!("the flush ack receiver has been dropped");
+                }
+            }
+
+            if disconnected {
+                info!("kafka recv channel disconnected, producer thread exit");
+                break;
+            }
+
+            if idle {
+                idle_counter += 1;
+                if idle_counter < 30 {
+                    tokio::time::sleep(idle_delay_10).await;
+                } else {
+                    tokio::time::sleep(idle_delay_300).await;
+                }
+            }
         }
     }
 }
 
+/// keep the first error, the later flushes fail with it
+fn set_produce_error(produce_error: &mut Option<String>, error: String) {
+    error!("{}", error);
+    if produce_error.is_none() {
+        *produce_error = Some(error);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::Ordering;
@@ -140,8 +235,9 @@ mod tests {
     use rlink::core::element::Record;
     use rlink::utils::date_time::current_timestamp_millis;
 
-    use crate::sink::producer::KafkaProducerThread;
-    use crate::{build_kafka_record, BOOTSTRAP_SERVERS};
+    use crate::buffer_gen::kafka_message;
+    use crate::sink::producer::{to_future_record, KafkaProducerThread, KafkaSinkMessage};
+    use crate::{build_kafka_record, BOOTSTRAP_SERVERS, PARTITION_UNASSIGNED};
 
     fn get_record() -> Record {
         build_kafka_record(
@@ -149,12 +245,35 @@ mod tests {
             "abc".as_bytes(),
             "bbbbbbbbbbbbbbbbbbbbbbbbbbb".as_bytes(),
             "",
-            0,
+            PARTITION_UNASSIGNED,
             0,
         )
         .unwrap()
     }
 
+    #[test]
+    pub fn to_future_record_test() {
+        let mut record = build_kafka_record(0, b"key", b"payload", "source", 3, 0).unwrap();
+        let entity = kafka_message::Entity::parse(record.as_buffer()).unwrap();
+        let topic = "sink".to_string();
+
+        // the partition of the consumed record is not a partition of the sink topic
+        let future_record = to_future_record(&entity, Some(&topic), false).unwrap();
+        assert_eq!(future_record.topic, "sink");
+        assert_eq!(future_record.partition, None);
+
+        let future_record = to_future_record(&entity, None, true).unwrap();
+        assert_eq!(future_record.topic, "source");
+        assert_eq!(future_record.partition, Some(3));
+
+        let mut record =
+            build_kafka_record(0, b"key", b"payload", "", PARTITION_UNASSIGNED, 0).unwrap();
+        let entity = kafka_message::Entity::parse(record.as_buffer()).unwrap();
+        let future_record = to_future_record(&entity, Some(&topic), true).unwrap();
+        assert_eq!(future_record.partition, None);
+        assert!(to_future_record(&entity, None, true).is_err());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     pub async fn producer2_result_test() {
         let topic = "rust-demo";
@@ -167,7 +286,10 @@ mod tests {
         tokio::spawn(async move {
             let record = get_record();
             for _n in 0..1000000 {
-                sender.send(record.clone()).await.unwrap();
+                sender
+                    .send(KafkaSinkMessage::Record(record.clone()))
+                    .await
+                    .unwrap_or_else(|_| panic!("send error"));
             }
             println!("finish");
         });
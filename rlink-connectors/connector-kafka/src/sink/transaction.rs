@@ -0,0 +1,778 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::future_producer::DeliveryFuture;
+use rdkafka::producer::{FutureProducer, Producer};
+use rdkafka::ClientConfig;
+use rlink::core::checkpoint::CheckpointHandle;
+use rlink::core::element::Record;
+use rlink::core::runtime::{CheckpointId, TaskId};
+
+use crate::buffer_gen::kafka_message;
+use crate::sink::producer::to_future_record;
+
+pub const TRANSACTIONAL_ID: &str = "transactional.id";
+pub const TRANSACTION_TIMEOUT_MS: &str = "transaction.timeout.ms";
+
+/// The number of the transactional ids of a task, a transactional id has only one open
+/// transaction at a time
+pub const TRANSACTIONAL_ID_POOL_SIZE: usize = 5;
+
+/// The timeout of the transactional operations: init, commit and abort
+const TRANSACTION_OPERATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The delay to send the record again when the queue of the producer is full
+const QUEUE_FULL_DELAY: Duration = Duration::from_millis(100);
+
+/// The max times to restart an aborted transaction or to commit a transaction again
+const MAX_TRANSACTION_RETRIES: usize = 3;
+
+/// The transactional ids are stable across restarts, so the producers of the restarted task fence
+/// the old ones and abort their lingering transactions.
+pub fn transactional_ids(transactional_id_prefix: &str, task_id: &TaskId) -> Vec<String> {
+    (0..TRANSACTIONAL_ID_POOL_SIZE)
+        .map(|index| {
+            format!(
+                "{}-{}-{}-{}",
+                transactional_id_prefix,
+                task_id.job_id().0,
+                task_id.task_number(),
+                index
+            )
+        })
+        .collect()
+}
+
+/// The `transaction.timeout.ms` of the producer. A transaction is opened after a checkpoint,
+/// flushed at the next one, and committed when a later barrier carries the completion of that
+/// checkpoint, so the default is 5 checkpoint intervals and 1 minute at least, and the configured
+/// one must cover 3 checkpoint intervals.
+pub fn transaction_timeout(
+    client_config: &ClientConfig,
+    checkpoint_interval: Duration,
+) -> anyhow::Result<Duration> {
+    match client_config.get(TRANSACTION_TIMEOUT_MS) {
+        Some(timeout_ms) => {
+            let timeout_ms = timeout_ms.parse::<u64>().map_err(|e| {
+                anyhow!(
+                    "invalid `{}`: {}. {}",
+                    TRANSACTION_TIMEOUT_MS,
+                    timeout_ms,
+                    e
+                )
+            })?;
+            let timeout = Duration::from_millis(timeout_ms);
+            if timeout < checkpoint_interval * 3 {
+                return Err(anyhow!(
+                    "`{}` {}ms is less than 3 checkpoint intervals of {}ms",
+                    TRANSACTION_TIMEOUT_MS,
+                    timeout_ms,
+                    checkpoint_interval.as_millis()
+                ));
+            }
+            Ok(timeout)
+        }
+        None => Ok(std::cmp::max(
+            checkpoint_interval * 5,
+            Duration::from_secs(60),
+        )),
+    }
+}
+
+/// Init the transactions of the producer, the broker rejects a `transaction.timeout.ms` larger
+/// than its `transaction.max.timeout.ms`
+fn init_transactions(
+    producer: &FutureProducer,
+    transaction_timeout: Duration,
+) -> anyhow::Result<()> {
+    producer
+        .init_transactions(TRANSACTION_OPERATION_TIMEOUT)
+        .map_err(|e| {
+            if e.rdkafka_error_code() == Some(RDKafkaErrorCode::InvalidTransactionTimeout) {
+                anyhow!(
+                    "`{}` {}ms is larger than the `transaction.max.timeout.ms` of the broker. {}",
+                    TRANSACTION_TIMEOUT_MS,
+                    transaction_timeout.as_millis(),
+                    e
+                )
+            } else {
+                anyhow!(e)
+            }
+        })
+}
+
+/// Fence the producers using `transactional_id` and abort their lingering transaction
+pub fn fence(client_config: &ClientConfig, transactional_id: &str) -> anyhow::Result<()> {
+    let mut client_config = client_config.clone();
+    client_config.set(TRANSACTIONAL_ID, transactional_id);
+
+    let producer: FutureProducer = client_config.create()?;
+    producer.init_transactions(TRANSACTION_OPERATION_TIMEOUT)?;
+
+    info!("fence kafka transactional producer: {}", transactional_id);
+    Ok(())
+}
+
+/// The failed deliveries and the abortable transaction errors are recovered by aborting the
+/// transaction and sending its records again
+fn requires_abort(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<KafkaError>() {
+        Some(KafkaError::Transaction(e)) => e.txn_requires_abort(),
+        Some(KafkaError::MessageProduction(_code)) => true,
+        _ => false,
+    }
+}
+
+fn is_retriable(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<KafkaError>() {
+        Some(KafkaError::Transaction(e)) => e.is_retriable(),
+        _ => false,
+    }
+}
+
+/// A transaction flushed by a checkpoint and not committed yet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingTransaction {
+    pub checkpoint_id: u64,
+    pub transactional_id: String,
+}
+
+/// The transaction state recorded in the `CheckpointHandle`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KafkaTransactionSnapshot {
+    /// the transactional ids of the task
+    pub transactional_ids: Vec<String>,
+    /// the transactions waiting for a later checkpoint to complete
+    pub pending: Vec<PendingTransaction>,
+}
+
+impl KafkaTransactionSnapshot {
+    pub fn from_handle(handle: &CheckpointHandle) -> anyhow::Result<Self> {
+        serde_json::from_str(handle.handle.as_str()).map_err(|e| anyhow!(e))
+    }
+
+    pub fn to_handle(&self) -> CheckpointHandle {
+        CheckpointHandle {
+            handle: serde_json::to_string(self).unwrap(),
+        }
+    }
+}
+
+/// The transactional operations of a producer of a transactional id
+#[async_trait]
+pub trait TransactionalProducer: Send + Sync {
+    fn transactional_id(&self) -> &str;
+
+    fn begin_transaction(&mut self) -> anyhow::Result<()>;
+
+    /// send the record in the open transaction, wait if the queue of the producer is full
+    async fn send(&mut self, record: Record) -> anyhow::Result<()>;
+
+    /// wait for the acks of the records sent in the open transaction
+    async fn flush(&mut self) -> anyhow::Result<()>;
+
+    fn commit_transaction(&mut self) -> anyhow::Result<()>;
+
+    fn abort_transaction(&mut self) -> anyhow::Result<()>;
+}
+
+struct KafkaFutureProducer {
+    transactional_id: String,
+    topic: Option<String>,
+    partition_from_record: bool,
+    producer: FutureProducer,
+    /// the in-flight records of the open transaction
+    delivery_futures: Vec<DeliveryFuture>,
+}
+
+#[async_trait]
+impl TransactionalProducer for KafkaFutureProducer {
+    fn transactional_id(&self) -> &str {
+        self.transactional_id.as_str()
+    }
+
+    fn begin_transaction(&mut self) -> anyhow::Result<()> {
+        self.producer.begin_transaction()?;
+        Ok(())
+    }
+
+    async fn send(&mut self, mut record: Record) -> anyhow::Result<()> {
+        let entity = kafka_message::Entity::parse(record.as_buffer())?;
+        let mut future_record =
+            to_future_record(&entity, self.topic.as_ref(), self.partition_from_record)?;
+        loop {
+            match self.producer.send_result(future_record) {
+                Ok(delivery_future) => {
+                    self.delivery_futures.push(delivery_future);
+                    return Ok(());
+                }
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    // wait for the in-flight records to be delivered
+                    future_record = returned;
+                    tokio::time::sleep(QUEUE_FULL_DELAY).await;
+                }
+                Err((e, _future_record)) => return Err(e.into()),
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        for delivery_future in std::mem::take(&mut self.delivery_futures) {
+            match delivery_future.await {
+                Ok(Ok((_partition, _offset))) => {}
+                Ok(Err((e, _msg))) => return Err(e.into()),
+                Err(e) => return Err(anyhow!("produce `Canceled` error: {}", e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn commit_transaction(&mut self) -> anyhow::Result<()> {
+        self.producer
+            .commit_transaction(TRANSACTION_OPERATION_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn abort_transaction(&mut self) -> anyhow::Result<()> {
+        self.delivery_futures.clear();
+        self.producer
+            .abort_transaction(TRANSACTION_OPERATION_TIMEOUT)?;
+        Ok(())
+    }
+}
+
+/// The open transaction and the records sent in it
+struct OpenTransaction {
+    producer: Box<dyn TransactionalProducer>,
+    /// the records are sent again in a new transaction if the transaction is aborted, they are
+    /// released when the transaction is flushed by a checkpoint
+    records: Vec<Record>,
+}
+
+/// Exactly-once producer of a task, write the records of a checkpoint interval in a transaction.
+///
+/// The transaction is flushed when the task snapshots a checkpoint and becomes pending, then the
+/// records after the checkpoint are written in the transaction of another transactional id of
+/// the pool. The pending transaction is committed when a later barrier carries the completion of
+/// a checkpoint after it, because the job may still recover from the checkpoint before the
+/// completed one. When all the transactional ids are pending, the open transaction carries on to
+/// the next checkpoint.
+///
+/// The transaction failed with a delivery error or an abortable error is aborted, and its
+/// records are sent again in a new transaction. The other errors fail the task.
+///
+/// Note that the transactions not committed are aborted if the task restarts, including the ones
+/// of the restored checkpoint, librdkafka can't resume a transaction in another producer.
+pub struct KafkaTransactionProducer {
+    /// the producers without transaction
+    idle: VecDeque<Box<dyn TransactionalProducer>>,
+    /// the transaction of the records after the last checkpoint
+    open: Option<OpenTransaction>,
+    /// checkpoint id -> the transaction flushed by the checkpoint
+    pending: BTreeMap<u64, Box<dyn TransactionalProducer>>,
+}
+
+impl KafkaTransactionProducer {
+    pub fn new(
+        transactional_ids: Vec<String>,
+        topic: Option<String>,
+        partition_from_record: bool,
+        client_config: &ClientConfig,
+        checkpoint_interval: Duration,
+    ) -> anyhow::Result<Self> {
+        let transaction_timeout = transaction_timeout(client_config, checkpoint_interval)?;
+
+        let mut producers: Vec<Box<dyn TransactionalProducer>> = Vec::new();
+        for transactional_id in transactional_ids {
+            let mut client_config = client_config.clone();
+            client_config.set(TRANSACTIONAL_ID, transactional_id.as_str());
+            client_config.set(
+                TRANSACTION_TIMEOUT_MS,
+                transaction_timeout.as_millis().to_string(),
+            );
+
+            let producer: FutureProducer = client_config.create()?;
+            // fence the producer of the previous run and abort its lingering transaction
+            init_transactions(&producer, transaction_timeout)?;
+            info!(
+                "kafka transactional producer initialized: {}, transaction timeout: {:?}",
+                transactional_id, transaction_timeout
+            );
+
+            producers.push(Box::new(KafkaFutureProducer {
+                transactional_id,
+                topic: topic.clone(),
+                partition_from_record,
+                producer,
+                delivery_futures: Vec::new(),
+            }));
+        }
+        Ok(Self::with_producers(producers))
+    }
+
+    /// The producers of the different transactional ids, 2 at least
+    pub fn with_producers(producers: Vec<Box<dyn TransactionalProducer>>) -> Self {
+        assert!(
+            producers.len() >= 2,
+            "kafka transactional producers are less than 2"
+        );
+        KafkaTransactionProducer {
+            idle: producers.into_iter().collect(),
+            open: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    pub fn transactional_ids(&self) -> Vec<String> {
+        self.idle
+            .iter()
+            .chain(self.open.iter().map(|open| &open.producer))
+            .chain(self.pending.values())
+            .map(|producer| producer.transactional_id().to_string())
+            .collect()
+    }
+
+    pub async fn send(&mut self, record: Record) -> anyhow::Result<()> {
+        if self.open.is_none() {
+            let mut producer = self.idle.pop_front().unwrap();
+            producer.begin_transaction()?;
+            self.open = Some(OpenTransaction {
+                producer,
+                records: Vec::new(),
+            });
+        }
+
+        let open = self.open.as_mut().unwrap();
+        open.records.push(record.clone());
+        if let Err(e) = open.producer.send(record).await {
+            self.restart_transaction(e).await?;
+        }
+        Ok(())
+    }
+
+    /// Abort the open transaction after an abortable error, and send its records again in a new
+    /// transaction until they are acked
+    async fn restart_transaction(&mut self, mut e: anyhow::Error) -> anyhow::Result<()> {
+        let open = self.open.as_mut().unwrap();
+        for _ in 0..MAX_TRANSACTION_RETRIES {
+            if !requires_abort(&e) {
+                return Err(e);
+            }
+
+            warn!(
+                "abort kafka transaction {} and send its {} records again. {}",
+                open.producer.transactional_id(),
+                open.records.len(),
+                e
+            );
+            open.producer.abort_transaction()?;
+            open.producer.begin_transaction()?;
+
+            let mut rt = Ok(());
+            for record in open.records.iter() {
+                rt = open.producer.send(record.clone()).await;
+                if rt.is_err() {
+                    break;
+                }
+            }
+            if rt.is_ok() {
+                rt = open.producer.flush().await;
+            }
+
+            match rt {
+                Ok(()) => return Ok(()),
+                Err(err) => e = err,
+            }
+        }
+
+        Err(e)
+    }
+
+    /// Commit the pending transactions before the `completed_checkpoint_id`, then flush the open
+    /// transaction of the records received before the `checkpoint_id` barrier and mark it as
+    /// pending.
+    pub async fn pre_commit(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        completed_checkpoint_id: Option<CheckpointId>,
+    ) -> anyhow::Result<KafkaTransactionSnapshot> {
+        if let Some(completed_checkpoint_id) = completed_checkpoint_id {
+            self.commit_before(completed_checkpoint_id.0)?;
+        }
+
+        let mut pending = self.pending_transactions();
+        if let Some(open) = self.open.as_mut() {
+            if let Err(e) = open.producer.flush().await {
+                self.restart_transaction(e).await?;
+            }
+
+            let open = self.open.as_mut().unwrap();
+            pending.push(PendingTransaction {
+                checkpoint_id: checkpoint_id.0,
+                transactional_id: open.producer.transactional_id().to_string(),
+            });
+            if self.idle.is_empty() {
+                warn!(
+                    "all the kafka transactional ids are pending, the transaction {} carries on to \
+                     the next checkpoint",
+                    open.producer.transactional_id()
+                );
+            } else {
+                let open = self.open.take().unwrap();
+                self.pending.insert(checkpoint_id.0, open.producer);
+            }
+        }
+
+        Ok(KafkaTransactionSnapshot {
+            transactional_ids: self.transactional_ids(),
+            pending,
+        })
+    }
+
+    fn pending_transactions(&self) -> Vec<PendingTransaction> {
+        self.pending
+            .iter()
+            .map(|(checkpoint_id, producer)| PendingTransaction {
+                checkpoint_id: *checkpoint_id,
+                transactional_id: producer.transactional_id().to_string(),
+            })
+            .collect()
+    }
+
+    /// commit the pending transactions of the checkpoints before the `checkpoint_id`
+    fn commit_before(&mut self, checkpoint_id: u64) -> anyhow::Result<()> {
+        let remaining = self.pending.split_off(&checkpoint_id);
+        let committed = std::mem::replace(&mut self.pending, remaining);
+        for (checkpoint_id, mut producer) in committed {
+            commit(producer.as_mut())?;
+            info!(
+                "kafka transaction committed, transactional_id: {}, checkpoint_id: {}",
+                producer.transactional_id(),
+                checkpoint_id
+            );
+            self.idle.push_back(producer);
+        }
+
+        Ok(())
+    }
+
+    /// commit all the records when the stream is finished, the pending transactions first
+    pub async fn close(&mut self) -> anyhow::Result<()> {
+        self.commit_before(u64::MAX)?;
+
+        if let Some(open) = self.open.as_mut() {
+            if let Err(e) = open.producer.flush().await {
+                self.restart_transaction(e).await?;
+            }
+
+            let mut open = self.open.take().unwrap();
+            commit(open.producer.as_mut())?;
+            self.idle.push_back(open.producer);
+        }
+
+        Ok(())
+    }
+}
+
+/// Commit the transaction, it's committed again after the retriable errors
+fn commit(producer: &mut dyn TransactionalProducer) -> anyhow::Result<()> {
+    let mut retries = 0;
+    loop {
+        match producer.commit_transaction() {
+            Ok(()) => return Ok(()),
+            Err(e) if retries < MAX_TRANSACTION_RETRIES && is_retriable(&e) => {
+                warn!(
+                    "commit kafka transaction {} error, retry it. {}",
+                    producer.transactional_id(),
+                    e
+                );
+                retries += 1;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "commit kafka transaction {} error. {}",
+                    producer.transactional_id(),
+                    e
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use rdkafka::ClientConfig;
+    use rlink::core::element::Record;
+    use rlink::core::runtime::CheckpointId;
+
+    use crate::buffer_gen::kafka_message;
+    use crate::sink::transaction::{
+        transaction_timeout, KafkaTransactionProducer, PendingTransaction, TransactionalProducer,
+        TRANSACTION_TIMEOUT_MS,
+    };
+    use crate::{build_kafka_record, PARTITION_UNASSIGNED};
+
+    /// Record the operations as `begin:{id}`, `send:{key}`, `flush:{id}`, `commit:{id}` and
+    /// `abort:{id}`. The next flush fails with the `flush_error`
+    struct MockProducer {
+        transactional_id: String,
+        operations: Arc<Mutex<Vec<String>>>,
+        flush_error: Arc<Mutex<Option<KafkaError>>>,
+    }
+
+    impl MockProducer {
+        fn push(&self, operation: &str) {
+            self.operations
+                .lock()
+                .unwrap()
+                .push(format!("{}:{}", operation, self.transactional_id));
+        }
+    }
+
+    #[async_trait]
+    impl TransactionalProducer for MockProducer {
+        fn transactional_id(&self) -> &str {
+            self.transactional_id.as_str()
+        }
+
+        fn begin_transaction(&mut self) -> anyhow::Result<()> {
+            self.push("begin");
+            Ok(())
+        }
+
+        async fn send(&mut self, mut record: Record) -> anyhow::Result<()> {
+            let entity = kafka_message::Entity::parse(record.as_buffer())?;
+            let key = String::from_utf8(entity.key.to_vec())?;
+            self.operations
+                .lock()
+                .unwrap()
+                .push(format!("send:{}", key));
+            Ok(())
+        }
+
+        async fn flush(&mut self) -> anyhow::Result<()> {
+            self.push("flush");
+            match self.flush_error.lock().unwrap().take() {
+                Some(e) => Err(e.into()),
+                None => Ok(()),
+            }
+        }
+
+        fn commit_transaction(&mut self) -> anyhow::Result<()> {
+            self.push("commit");
+            Ok(())
+        }
+
+        fn abort_transaction(&mut self) -> anyhow::Result<()> {
+            self.push("abort");
+            Ok(())
+        }
+    }
+
+    struct TestProducer {
+        producer: KafkaTransactionProducer,
+        operations: Arc<Mutex<Vec<String>>>,
+        flush_error: Arc<Mutex<Option<KafkaError>>>,
+    }
+
+    impl TestProducer {
+        /// the producers of the transactional ids `t0`, `t1` ...
+        fn new(pool_size: usize) -> Self {
+            let operations = Arc::new(Mutex::new(Vec::new()));
+            let flush_error = Arc::new(Mutex::new(None));
+            let producers = (0..pool_size)
+                .map(|index| {
+                    let producer: Box<dyn TransactionalProducer> = Box::new(MockProducer {
+                        transactional_id: format!("t{}", index),
+                        operations: operations.clone(),
+                        flush_error: flush_error.clone(),
+                    });
+                    producer
+                })
+                .collect();
+            TestProducer {
+                producer: KafkaTransactionProducer::with_producers(producers),
+                operations,
+                flush_error,
+            }
+        }
+
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.operations.lock().unwrap())
+        }
+    }
+
+    fn record(key: &str) -> Record {
+        build_kafka_record(0, key.as_bytes(), b"", "topic", PARTITION_UNASSIGNED, 0).unwrap()
+    }
+
+    fn pending(checkpoint_id: u64, transactional_id: &str) -> PendingTransaction {
+        PendingTransaction {
+            checkpoint_id,
+            transactional_id: transactional_id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    pub async fn transaction_commit_test() {
+        let mut test = TestProducer::new(3);
+        let producer = &mut test.producer;
+
+        // no record, no transaction
+        let snapshot = producer.pre_commit(CheckpointId(1), None).await.unwrap();
+        assert!(snapshot.pending.is_empty());
+        assert_eq!(snapshot.transactional_ids, vec!["t0", "t1", "t2"]);
+        assert!(test.take().is_empty());
+
+        let producer = &mut test.producer;
+        producer.send(record("a")).await.unwrap();
+        let snapshot = producer.pre_commit(CheckpointId(2), None).await.unwrap();
+        assert_eq!(snapshot.pending, vec![pending(2, "t0")]);
+        assert_eq!(test.take(), vec!["begin:t0", "send:a", "flush:t0"]);
+
+        // the records after the checkpoint are written in the transaction of another id
+        let producer = &mut test.producer;
+        producer.send(record("b")).await.unwrap();
+        assert_eq!(test.take(), vec!["begin:t1", "send:b"]);
+
+        // the checkpoint 2 is completed, but the job may still recover from it
+        let producer = &mut test.producer;
+        let snapshot = producer
+            .pre_commit(CheckpointId(3), Some(CheckpointId(2)))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.pending, vec![pending(2, "t0"), pending(3, "t1")]);
+        assert_eq!(test.take(), vec!["flush:t1"]);
+
+        // committed when a later checkpoint is completed
+        let producer = &mut test.producer;
+        let snapshot = producer
+            .pre_commit(CheckpointId(4), Some(CheckpointId(3)))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.pending, vec![pending(3, "t1")]);
+        assert_eq!(test.take(), vec!["commit:t0"]);
+
+        // the committed id is reused
+        let producer = &mut test.producer;
+        producer.send(record("c")).await.unwrap();
+        producer
+            .pre_commit(CheckpointId(5), Some(CheckpointId(4)))
+            .await
+            .unwrap();
+        assert_eq!(
+            test.take(),
+            vec!["begin:t2", "send:c", "commit:t1", "flush:t2"]
+        );
+        let producer = &mut test.producer;
+        producer.send(record("d")).await.unwrap();
+        assert_eq!(test.take(), vec!["begin:t0", "send:d"]);
+    }
+
+    #[tokio::test]
+    pub async fn transaction_pool_test() {
+        let mut test = TestProducer::new(2);
+
+        let producer = &mut test.producer;
+        producer.send(record("a")).await.unwrap();
+        producer.pre_commit(CheckpointId(1), None).await.unwrap();
+        producer.send(record("b")).await.unwrap();
+        test.take();
+
+        // all the ids are pending, the open transaction carries on to the next checkpoint
+        let producer = &mut test.producer;
+        let snapshot = producer.pre_commit(CheckpointId(2), None).await.unwrap();
+        assert_eq!(snapshot.pending, vec![pending(1, "t0"), pending(2, "t1")]);
+        producer.send(record("c")).await.unwrap();
+        assert_eq!(test.take(), vec!["flush:t1", "send:c"]);
+
+        let producer = &mut test.producer;
+        let snapshot = producer
+            .pre_commit(CheckpointId(3), Some(CheckpointId(2)))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.pending, vec![pending(3, "t1")]);
+        assert_eq!(test.take(), vec!["commit:t0", "flush:t1"]);
+    }
+
+    #[tokio::test]
+    pub async fn transaction_abort_test() {
+        let mut test = TestProducer::new(2);
+
+        let producer = &mut test.producer;
+        producer.send(record("a")).await.unwrap();
+        producer.send(record("b")).await.unwrap();
+        test.take();
+
+        // the failed delivery aborts the transaction and sends the records again
+        *test.flush_error.lock().unwrap() = Some(KafkaError::MessageProduction(
+            RDKafkaErrorCode::MessageTimedOut,
+        ));
+        let producer = &mut test.producer;
+        let snapshot = producer.pre_commit(CheckpointId(1), None).await.unwrap();
+        assert_eq!(snapshot.pending, vec![pending(1, "t0")]);
+        assert_eq!(
+            test.take(),
+            vec!["flush:t0", "abort:t0", "begin:t0", "send:a", "send:b", "flush:t0"]
+        );
+
+        // the other errors fail the task
+        let producer = &mut test.producer;
+        producer.send(record("c")).await.unwrap();
+        *test.flush_error.lock().unwrap() = Some(KafkaError::Canceled);
+        let producer = &mut test.producer;
+        assert!(producer.pre_commit(CheckpointId(2), None).await.is_err());
+    }
+
+    #[tokio::test]
+    pub async fn transaction_close_test() {
+        let mut test = TestProducer::new(3);
+
+        let producer = &mut test.producer;
+        producer.send(record("a")).await.unwrap();
+        producer.pre_commit(CheckpointId(1), None).await.unwrap();
+        producer.send(record("b")).await.unwrap();
+        test.take();
+
+        // the pending transaction is committed before the open one
+        let producer = &mut test.producer;
+        producer.close().await.unwrap();
+        assert_eq!(test.take(), vec!["commit:t0", "flush:t1", "commit:t1"]);
+
+        // nothing to commit
+        let producer = &mut test.producer;
+        producer.close().await.unwrap();
+        assert!(test.take().is_empty());
+    }
+
+    #[test]
+    pub fn transaction_timeout_test() {
+        let checkpoint_interval = Duration::from_secs(30);
+
+        let client_config = ClientConfig::new();
+        assert_eq!(
+            transaction_timeout(&client_config, checkpoint_interval).unwrap(),
+            Duration::from_secs(150)
+        );
+        assert_eq!(
+            transaction_timeout(&client_config, Duration::from_secs(10)).unwrap(),
+            Duration::from_secs(60)
+        );
+
+        let mut client_config = ClientConfig::new();
+        client_config.set(TRANSACTION_TIMEOUT_MS, "600000");
+        assert_eq!(
+            transaction_timeout(&client_config, checkpoint_interval).unwrap(),
+            Duration::from_secs(600)
+        );
+
+        client_config.set(TRANSACTION_TIMEOUT_MS, "80000");
+        assert!(transaction_timeout(&client_config, checkpoint_interval).is_err());
+    }
+}
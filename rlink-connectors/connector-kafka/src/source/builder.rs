@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 use rdkafka::ClientConfig;
+use regex::Regex;
 use rlink::core::element::FnSchema;
 use rlink::core::properties::{Properties, PARALLELISM};
 
@@ -12,8 +14,8 @@ use crate::source::deserializer::{
 };
 use crate::source::offset_range::OffsetRange;
 use crate::{
-    KafkaInputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, GROUP_ID, KAFKA, OFFSET, SOURCE_CHANNEL_SIZE,
-    TOPICS,
+    KafkaInputFormat, BOOTSTRAP_SERVERS, BUFFER_SIZE, GROUP_ID, KAFKA, OFFSET,
+    PARTITION_DISCOVERY_INTERVAL_MS, SOURCE_CHANNEL_SIZE, TOPICS, TOPIC_PATTERN,
 };
 
 #[derive(Debug)]
@@ -24,6 +26,8 @@ pub struct KafkaInputFormatBuilder {
     topics: Vec<String>,
     buffer_size: Option<usize>,
     offset_range: OffsetRange,
+    topic_pattern: Option<Regex>,
+    discovery_interval: Option<Duration>,
 }
 
 impl KafkaInputFormatBuilder {
@@ -35,6 +39,8 @@ impl KafkaInputFormatBuilder {
             topics,
             buffer_size: None,
             offset_range: OffsetRange::None,
+            topic_pattern: None,
+            discovery_interval: None,
         }
     }
 
@@ -53,6 +59,25 @@ impl KafkaInputFormatBuilder {
         self
     }
 
+    /// Subscribe the topics matching the regex `pattern` instead of the `topics`,
+    /// the topics created later are discovered by the partition discovery.
+    pub fn topic_pattern(mut self, pattern: &str) -> Self {
+        let pattern = Regex::new(pattern).expect("invalid kafka topic pattern");
+        self.topic_pattern = Some(pattern);
+        self
+    }
+
+    /// Periodically discover the partitions of the subscribed topics. The partitions are
+    /// assigned to the tasks by the topic and partition, and the new partitions are consumed
+    /// from the earliest.
+    ///
+    /// Note that the end offset is not supported, and the parallelism can't be changed when the
+    /// job is recovered from a checkpoint.
+    pub fn partition_discovery(mut self, interval: Duration) -> Self {
+        self.discovery_interval = Some(interval);
+        self
+    }
+
     pub fn build(
         self,
         deserializer_builder: Option<Box<dyn KafkaRecordDeserializerBuilder>>,
@@ -78,7 +103,7 @@ impl KafkaInputFormatBuilder {
             deserializer_builder
         });
 
-        KafkaInputFormat::new(
+        let mut input_format = KafkaInputFormat::new(
             client_config,
             self.topics,
             buffer_size,
@@ -86,7 +111,11 @@ impl KafkaInputFormatBuilder {
             deserializer_builder,
             self.parallelism,
             fn_name,
-        )
+        );
+        input_format.topic_pattern = self.topic_pattern;
+        input_format.discovery_interval = self.discovery_interval;
+
+        input_format
     }
 }
 
@@ -106,14 +135,27 @@ impl TryFrom<Properties> for KafkaInputFormatBuilder {
             kafka_properties.as_map().clone()
         };
 
-        let topics = properties.get_string(TOPICS)?;
-        let topics: Vec<String> = topics.trim().split(",").map(|x| x.to_string()).collect();
-        if topics.len() == 0 {
+        let topic_pattern = properties.get_string(TOPIC_PATTERN).ok();
+        let topics = match properties.get_string(TOPICS) {
+            Ok(topics) => topics.trim().split(",").map(|x| x.to_string()).collect(),
+            Err(_e) if topic_pattern.is_some() => vec![],
+            Err(e) => return Err(e),
+        };
+        if topics.len() == 0 && topic_pattern.is_none() {
             return Err(anyhow!("`topics` not found"));
         }
 
         let mut builder = KafkaInputFormatBuilder::new(client_config, topics, parallelism);
 
+        if let Some(topic_pattern) = topic_pattern {
+            Regex::new(topic_pattern.as_str())?;
+            builder = builder.topic_pattern(topic_pattern.as_str());
+        }
+
+        if let Ok(interval_ms) = properties.get_u64(PARTITION_DISCOVERY_INTERVAL_MS) {
+            builder = builder.partition_discovery(Duration::from_millis(interval_ms));
+        }
+
         builder = builder.fn_name(properties.name());
 
         if let Ok(buffer_size) = properties.get_usize(BUFFER_SIZE) {
@@ -1,33 +1,45 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
 use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer, ConsumerContext};
+use rdkafka::error::KafkaResult;
+use rdkafka::{ClientConfig, ClientContext, Offset, TopicPartitionList};
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
-use rlink::core::runtime::TaskId;
+use rlink::core::runtime::{CheckpointId, TaskId};
 
 #[derive(Debug, Clone)]
 pub struct KafkaCheckpointFunction {
-    pub(crate) state_recorder: Option<KafkaSourceStateRecorder>,
+    pub(crate) state: KafkaSourceState,
     #[allow(dead_code)]
     pub(crate) application_id: String,
     #[allow(dead_code)]
     pub(crate) task_id: TaskId,
-    topic: String,
-    partition: i32,
+
+    offset_committer: Option<KafkaOffsetCommitter>,
 }
 
 impl KafkaCheckpointFunction {
-    pub fn new(application_id: String, task_id: TaskId, topic: &str, partition: i32) -> Self {
+    pub fn new(application_id: String, task_id: TaskId) -> Self {
         KafkaCheckpointFunction {
-            state_recorder: None,
+            state: KafkaSourceState::default(),
             application_id,
             task_id,
-            topic: topic.to_string(),
-            partition,
+            offset_committer: None,
         }
     }
 
-    pub fn as_state_mut(&mut self) -> &mut KafkaSourceStateRecorder {
-        self.state_recorder.as_mut().unwrap()
+    /// commit the offsets of the completed checkpoints to the consumer group
+    pub fn with_offset_committer(mut self, client_config: &ClientConfig) -> anyhow::Result<Self> {
+        let offset_committer = KafkaOffsetCommitter::new(client_config)?;
+        self.offset_committer = Some(offset_committer);
+        Ok(self)
+    }
+
+    pub fn state(&self) -> &KafkaSourceState {
+        &self.state
     }
 }
 
@@ -38,10 +50,6 @@ impl CheckpointFunction for KafkaCheckpointFunction {
         context: &FunctionSnapshotContext,
         handle: &Option<CheckpointHandle>,
     ) {
-        self.state_recorder = Some(KafkaSourceStateRecorder::new(
-            self.topic.as_str(),
-            self.partition,
-        ));
         info!("Checkpoint initialize, context: {:?}", context);
 
         if context.checkpoint_id.is_default() || handle.is_none() {
@@ -49,9 +57,7 @@ impl CheckpointFunction for KafkaCheckpointFunction {
         }
 
         let handle = handle.as_ref().unwrap();
-
-        let state_cache = self.state_recorder.as_mut().unwrap();
-        state_cache
+        self.state
             .update_from_snapshot(handle.handle.as_str())
             .unwrap();
 
@@ -65,9 +71,17 @@ impl CheckpointFunction for KafkaCheckpointFunction {
         &mut self,
         context: &FunctionSnapshotContext,
     ) -> Option<CheckpointHandle> {
-        let handle = self.state_recorder.as_ref().unwrap().snapshot();
+        let handle = self.state.snapshot();
         debug!("Checkpoint snapshot: {:?}, context: {:?}", handle, context);
 
+        if let Some(offset_committer) = self.offset_committer.as_mut() {
+            offset_committer.commit(
+                context.checkpoint_id,
+                self.state.offsets(),
+                context.completed_checkpoint_id,
+            );
+        }
+
         Some(CheckpointHandle { handle })
     }
 }
@@ -79,6 +93,77 @@ struct OffsetSnapshot<'a> {
     offset: Option<i64>,
 }
 
+/// The consumed offsets of all the partitions assigned to a task
+#[derive(Debug, Clone, Default)]
+pub struct KafkaSourceState {
+    recorders: Arc<Mutex<BTreeMap<(String, i32), KafkaSourceStateRecorder>>>,
+}
+
+impl KafkaSourceState {
+    /// get or register the recorder of the partition
+    pub fn recorder(&self, topic: &str, partition: i32) -> KafkaSourceStateRecorder {
+        let mut recorders = self.recorders.lock().unwrap();
+        recorders
+            .entry((topic.to_string(), partition))
+            .or_insert_with(|| KafkaSourceStateRecorder::new(topic, partition))
+            .clone()
+    }
+
+    pub fn get(&self, topic: &str, partition: i32) -> Option<i64> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders
+            .get(&(topic.to_string(), partition))
+            .and_then(|recorder| recorder.get())
+    }
+
+    /// the consumed offsets of the partitions, `(topic, partition, offset)`
+    pub fn offsets(&self) -> Vec<(String, i32, i64)> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders
+            .values()
+            .filter_map(|recorder| {
+                recorder
+                    .get()
+                    .map(|offset| (recorder.topic.clone(), recorder.partition, offset))
+            })
+            .collect()
+    }
+
+    /// The snapshot of a single partition is an `OffsetSnapshot` object,
+    /// otherwise is an array of `OffsetSnapshot`
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let offset_snapshots: Vec<OffsetSnapshot> = if snapshot_handle.starts_with('[') {
+            serde_json::from_str(snapshot_handle)?
+        } else {
+            vec![serde_json::from_str(snapshot_handle)?]
+        };
+
+        for offset_snapshot in offset_snapshots {
+            let recorder = self.recorder(offset_snapshot.topic, offset_snapshot.partition);
+            recorder.update(offset_snapshot.offset.unwrap_or(i64::MIN));
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> String {
+        let recorders = self.recorders.lock().unwrap();
+        let offset_snapshots: Vec<OffsetSnapshot> = recorders
+            .values()
+            .map(|recorder| OffsetSnapshot {
+                topic: recorder.topic.as_str(),
+                partition: recorder.partition,
+                offset: recorder.get(),
+            })
+            .collect();
+
+        if offset_snapshots.len() == 1 {
+            serde_json::to_string(&offset_snapshots[0]).unwrap()
+        } else {
+            serde_json::to_string(&offset_snapshots).unwrap()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KafkaSourceStateRecorder {
     topic: String,
@@ -99,43 +184,176 @@ impl KafkaSourceStateRecorder {
         self.offset.store(offset, Ordering::Relaxed);
     }
 
-    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
-        let offset_snapshot: OffsetSnapshot = serde_json::from_str(snapshot_handle)?;
+    pub fn get(&self) -> Option<i64> {
+        let offset = self.offset.load(Ordering::Relaxed);
+        if offset == i64::MIN {
+            None
+        } else {
+            Some(offset)
+        }
+    }
+}
+
+/// Report the result of the asynchronous offset commits
+pub struct OffsetCommitContext;
 
-        if !offset_snapshot.topic.eq(self.topic.as_str())
-            || offset_snapshot.partition != self.partition
-        {
-            return Err(anyhow!("Does not belong to the checkpoint of the task"));
+impl ClientContext for OffsetCommitContext {}
+
+impl ConsumerContext for OffsetCommitContext {
+    fn commit_callback(&self, result: KafkaResult<()>, offsets: &TopicPartitionList) {
+        match result {
+            Ok(()) => debug!("commit offsets to consumer group: {:?}", offsets),
+            Err(e) => warn!(
+                "commit offsets to consumer group error, offsets: {:?}. {}",
+                offsets, e
+            ),
         }
+    }
+}
 
-        self.update(offset_snapshot.offset.unwrap_or(i64::MIN));
-        Ok(())
+/// Commit the checkpointed offsets to the consumer group, so that the lag of the group can be
+/// monitored by the kafka tools.
+///
+/// The offsets of a checkpoint are committed once the checkpoint is known to be complete, that
+/// is a later `Barrier` carries it as the completed checkpoint.
+#[derive(Clone)]
+pub struct KafkaOffsetCommitter {
+    consumer: Arc<BaseConsumer<OffsetCommitContext>>,
+    /// checkpoint_id -> the last consumed offsets, `(topic, partition, offset)`
+    pending_offsets: BTreeMap<u64, Vec<(String, i32, i64)>>,
+}
+
+impl KafkaOffsetCommitter {
+    pub fn new(client_config: &ClientConfig) -> anyhow::Result<Self> {
+        client_config
+            .get(crate::GROUP_ID)
+            .ok_or(anyhow!("`group.id` not found in kafka consumer config"))?;
+        let consumer: BaseConsumer<OffsetCommitContext> =
+            client_config.create_with_context(OffsetCommitContext)?;
+
+        Ok(KafkaOffsetCommitter {
+            consumer: Arc::new(consumer),
+            pending_offsets: BTreeMap::new(),
+        })
     }
 
-    pub fn snapshot(&self) -> String {
-        let offset = {
-            let offset = self.offset.load(Ordering::Relaxed);
-            if offset == i64::MIN {
-                None
-            } else {
-                Some(offset)
+    pub fn commit(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        offsets: Vec<(String, i32, i64)>,
+        completed_checkpoint_id: Option<CheckpointId>,
+    ) {
+        if !offsets.is_empty() {
+            self.pending_offsets.insert(checkpoint_id.0, offsets);
+        }
+
+        let offsets = match completed_checkpoint_id {
+            Some(completed_checkpoint_id) => {
+                take_completed(&mut self.pending_offsets, completed_checkpoint_id)
             }
+            None => None,
         };
 
-        serde_json::to_string(&OffsetSnapshot {
-            topic: self.topic.as_str(),
-            partition: self.partition,
-            offset,
-        })
-        .unwrap()
+        if let Some(offsets) = offsets {
+            if let Err(e) = self.commit_offsets(&offsets) {
+                warn!(
+                    "commit offsets to consumer group error, offsets: {:?}. {}",
+                    offsets, e
+                );
+            }
+        }
     }
 
-    pub fn get(&self) -> Option<i64> {
-        let offset = self.offset.load(Ordering::Relaxed);
-        if offset == i64::MIN {
-            None
-        } else {
-            Some(offset)
+    fn commit_offsets(&self, offsets: &[(String, i32, i64)]) -> anyhow::Result<()> {
+        let mut tpl = TopicPartitionList::with_capacity(offsets.len());
+        for (topic, partition, offset) in offsets {
+            // the committed offset is the next message to be consumed
+            tpl.add_partition_offset(topic.as_str(), *partition, Offset::Offset(offset + 1))?;
         }
+
+        // don't block the task on the broker, the result is reported by `OffsetCommitContext`
+        self.consumer.commit(&tpl, CommitMode::Async)?;
+        // serve the commit callbacks of the previous commits, the consumer has no assigned
+        // partition so no message is polled
+        self.consumer.poll(Duration::ZERO);
+
+        Ok(())
+    }
+}
+
+impl Debug for KafkaOffsetCommitter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaOffsetCommitter")
+            .field("pending_offsets", &self.pending_offsets)
+            .finish()
+    }
+}
+
+/// remove the pending items of the checkpoints covered by `completed_checkpoint_id`,
+/// return the item of the latest one
+fn take_completed<T>(
+    pending: &mut BTreeMap<u64, T>,
+    completed_checkpoint_id: CheckpointId,
+) -> Option<T> {
+    let mut completed = pending.split_off(&(completed_checkpoint_id.0 + 1));
+    std::mem::swap(pending, &mut completed);
+    completed
+        .into_iter()
+        .next_back()
+        .map(|(_checkpoint_id, item)| item)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rlink::core::runtime::CheckpointId;
+
+    use crate::source::checkpoint::{take_completed, KafkaSourceState};
+
+    #[test]
+    pub fn take_completed_test() {
+        let mut pending_offsets = BTreeMap::new();
+        pending_offsets.insert(1, 100);
+        pending_offsets.insert(2, 200);
+        pending_offsets.insert(3, 300);
+
+        assert_eq!(take_completed(&mut pending_offsets, CheckpointId(0)), None);
+        assert_eq!(
+            take_completed(&mut pending_offsets, CheckpointId(2)),
+            Some(200)
+        );
+        assert_eq!(pending_offsets.len(), 1);
+        assert_eq!(take_completed(&mut pending_offsets, CheckpointId(2)), None);
+        assert_eq!(
+            take_completed(&mut pending_offsets, CheckpointId(5)),
+            Some(300)
+        );
+        assert!(pending_offsets.is_empty());
+    }
+
+    #[test]
+    pub fn source_state_snapshot_test() {
+        let state = KafkaSourceState::default();
+        state.recorder("topic-0", 0).update(10);
+
+        let single_snapshot = state.snapshot();
+        assert!(single_snapshot.starts_with('{'));
+
+        state.recorder("topic-0", 1).update(20);
+        let snapshot = state.snapshot();
+
+        let restored_state = KafkaSourceState::default();
+        restored_state
+            .update_from_snapshot(snapshot.as_str())
+            .unwrap();
+        assert_eq!(restored_state.get("topic-0", 1), Some(20));
+
+        let restored_state = KafkaSourceState::default();
+        restored_state
+            .update_from_snapshot(single_snapshot.as_str())
+            .unwrap();
+        assert_eq!(restored_state.get("topic-0", 0), Some(10));
+        assert_eq!(restored_state.get("topic-0", 1), None);
     }
 }
@@ -5,14 +5,17 @@ use rlink::channel::sender::ChannelSender;
 use rlink::core::runtime::JobId;
 use rlink::utils;
 
+use crate::source::checkpoint::KafkaSourceStateRecorder;
 use crate::source::deserializer::KafkaRecordDeserializer;
 use crate::source::{empty_record, ConsumerRecord};
+use crate::ENABLE_AUTO_COMMIT;
 
 #[derive(Debug, Clone)]
 pub(crate) struct ConsumerRange {
     pub(crate) topic: String,
     pub(crate) partition: i32,
     pub(crate) begin_offset: i64,
+    /// exclusive, the partition is finished before the offset
     pub(crate) end_offset: Option<i64>,
 }
 
@@ -23,6 +26,7 @@ pub(crate) async fn create_kafka_consumer(
     consumer_ranges: ConsumerRange,
     handover: ChannelSender<ConsumerRecord>,
     deserializer: Box<dyn KafkaRecordDeserializer>,
+    state_recorder: KafkaSourceStateRecorder,
 ) {
     tokio::spawn(async move {
         let mut kafka_consumer = KafkaConsumerThread::new(
@@ -32,6 +36,7 @@ pub(crate) async fn create_kafka_consumer(
             consumer_ranges,
             handover,
             deserializer,
+            state_recorder,
         );
         match kafka_consumer.run().await {
             Ok(()) => {}
@@ -48,10 +53,10 @@ pub(crate) struct KafkaConsumerThread {
 
     client_config: ClientConfig,
     consumer_ranges: ConsumerRange,
-    with_end_consumer_ranges: bool,
 
     sender: ChannelSender<ConsumerRecord>,
     deserializer: Box<dyn KafkaRecordDeserializer>,
+    state_recorder: KafkaSourceStateRecorder,
 }
 
 impl KafkaConsumerThread {
@@ -62,35 +67,54 @@ impl KafkaConsumerThread {
         consumer_ranges: ConsumerRange,
         sender: ChannelSender<ConsumerRecord>,
         deserializer: Box<dyn KafkaRecordDeserializer>,
+        state_recorder: KafkaSourceStateRecorder,
     ) -> Self {
-        let with_end_consumer_ranges = consumer_ranges.end_offset.is_some();
         KafkaConsumerThread {
             job_id,
             task_number,
             client_config,
             consumer_ranges,
-            with_end_consumer_ranges,
             sender,
             deserializer,
+            state_recorder,
         }
     }
 
-    fn end_check(&self, topic: &str, partition: i32, offset: i64) -> bool {
-        if !self.with_end_consumer_ranges {
-            return false;
-        }
-
-        if self.consumer_ranges.partition == partition
-            && self.consumer_ranges.end_offset.unwrap() < offset
-            && self.consumer_ranges.topic.eq(topic)
-        {
-            return true;
+    /// whether the consumption reaches the end offset after the `offset` is consumed
+    fn end_check(&self, offset: i64) -> bool {
+        match self.consumer_ranges.end_offset {
+            Some(end_offset) => offset + 1 >= end_offset,
+            None => false,
         }
+    }
 
-        false
+    /// notify the stream that the partition is finished
+    async fn send_end(&self) {
+        self.sender
+            .send(ConsumerRecord::new(
+                empty_record(),
+                0,
+                self.state_recorder.clone(),
+            ))
+            .await
+            .expect("kafka consumer handover `Disconnected`");
+        info!(
+            "kafka end offset reached. topic: {}, partition: {}, job_id: {}, task_num: {}",
+            self.consumer_ranges.topic,
+            self.consumer_ranges.partition,
+            *self.job_id,
+            self.task_number
+        );
     }
 
     pub async fn run(&mut self) -> anyhow::Result<()> {
+        // the range is empty
+        let begin_offset = self.consumer_ranges.begin_offset;
+        if begin_offset >= 0 && self.end_check(begin_offset - 1) {
+            self.send_end().await;
+            return Ok(());
+        }
+
         let mut assignment = TopicPartitionList::new();
         assignment
             .add_partition_offset(
@@ -105,6 +129,11 @@ impl KafkaConsumerThread {
             .get("group.id")
             .ok_or(anyhow!("`group.id` not found in kafka consumer config"))?;
 
+        // the offsets are committed to the consumer group when the checkpoint is complete
+        if self.client_config.get(ENABLE_AUTO_COMMIT).is_none() {
+            self.client_config.set(ENABLE_AUTO_COMMIT, "false");
+        }
+
         let consumer: StreamConsumer<DefaultConsumerContext> = self.client_config.create()?;
         consumer.assign(&assignment)?;
 
@@ -124,28 +153,32 @@ impl KafkaConsumerThread {
                     let key = borrowed_message.key().unwrap_or(&utils::EMPTY_SLICE);
                     let payload = borrowed_message.payload().unwrap_or(&utils::EMPTY_SLICE);
 
-                    if self.end_check(topic, partition, offset) {
-                        self.sender
-                            .send(ConsumerRecord::new(empty_record(), 0))
-                            .await
-                            .expect("kafka consumer handover `Disconnected`");
-                        info!(
-                            "kafka end offset reached. job_id: {}, task_num: {}",
-                            *self.job_id, self.task_number
-                        );
+                    // the end offset is passed, eg: the end offset is a transaction marker
+                    if self.end_check(offset - 1) {
+                        self.send_end().await;
                         break;
                     }
 
+                    let headers = borrowed_message.headers();
                     let records = self
                         .deserializer
-                        .deserialize(timestamp, key, payload, topic, partition, offset);
+                        .deserialize(timestamp, key, payload, topic, partition, offset, headers);
 
                     for record in records {
                         self.sender
-                            .send(ConsumerRecord::new(record, offset))
+                            .send(ConsumerRecord::new(
+                                record,
+                                offset,
+                                self.state_recorder.clone(),
+                            ))
                             .await
                             .expect("kafka consumer handover `Disconnected`");
                     }
+
+                    if self.end_check(offset) {
+                        self.send_end().await;
+                        break;
+                    }
                 }
                 Err(e) => warn!(
                     "Kafka consume error. job_id: {}, task_num: {}, error: {}",
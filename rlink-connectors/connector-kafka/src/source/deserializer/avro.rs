@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::sync::Arc;
+
+use apache_avro::types::Value;
+use apache_avro::Schema as AvroSchema;
+use rdkafka::message::BorrowedHeaders;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+
+use crate::source::deserializer::{
+    write_record, FieldValue, KafkaRecordDeserializer, SchemaKafkaRecordDeserializerBuilder,
+};
+
+/// The magic byte of the schema registry wire format
+const MAGIC_BYTE: u8 = 0;
+
+/// Resolve the writer schema of the message by the schema id of the schema registry wire format
+pub trait AvroSchemaResolver: Send + Sync {
+    fn resolve(&self, schema_id: u32) -> anyhow::Result<Arc<AvroSchema>>;
+}
+
+/// A local schema registry, the schemas are registered by id instead of fetched from the
+/// schema registry service, so the ids must be the same as the ones of the producers.
+#[derive(Clone, Debug, Default)]
+pub struct LocalAvroSchemaRegistry {
+    schemas: HashMap<u32, Arc<AvroSchema>>,
+}
+
+impl LocalAvroSchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, schema_id: u32, schema: &str) -> anyhow::Result<Self> {
+        let schema = AvroSchema::parse_str(schema)?;
+        self.schemas.insert(schema_id, Arc::new(schema));
+        Ok(self)
+    }
+
+    /// Load the schemas from the `{schema_id}.avsc` files in the directory
+    pub fn load_dir<P: AsRef<Path>>(mut self, dir: P) -> anyhow::Result<Self> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|x| x.to_str()) != Some("avsc") {
+                continue;
+            }
+
+            let schema_id = path
+                .file_stem()
+                .and_then(|x| x.to_str())
+                .and_then(|x| x.parse::<u32>().ok())
+                .ok_or(anyhow!("the schema id not found in file name {:?}", path))?;
+            let schema = std::fs::read_to_string(path.as_path())?;
+            self = self.register(schema_id, schema.as_str())?;
+        }
+
+        Ok(self)
+    }
+}
+
+impl AvroSchemaResolver for LocalAvroSchemaRegistry {
+    fn resolve(&self, schema_id: u32) -> anyhow::Result<Arc<AvroSchema>> {
+        self.schemas
+            .get(&schema_id)
+            .cloned()
+            .ok_or(anyhow!("avro schema {} not found", schema_id))
+    }
+}
+
+/// Deserialize the Avro record payload of the schema registry wire format, that is a magic byte,
+/// the 4 bytes big-endian schema id and the Avro binary data. The record fields are matched by
+/// the schema field names.
+///
+/// The nested record, array and map are written as JSON string, and the message failed to
+/// deserialize is discarded.
+#[derive(Clone)]
+pub struct AvroKafkaRecordDeserializer {
+    schema: Schema,
+    resolver: Arc<dyn AvroSchemaResolver>,
+}
+
+impl AvroKafkaRecordDeserializer {
+    pub fn new(schema: Schema, resolver: Arc<dyn AvroSchemaResolver>) -> Self {
+        AvroKafkaRecordDeserializer { schema, resolver }
+    }
+
+    pub fn builder(
+        schema: Schema,
+        resolver: Arc<dyn AvroSchemaResolver>,
+    ) -> SchemaKafkaRecordDeserializerBuilder<Self> {
+        SchemaKafkaRecordDeserializerBuilder::new(Self::new(schema.clone(), resolver), schema)
+    }
+
+    fn deserialize_payload(&self, payload: &[u8]) -> anyhow::Result<Record> {
+        if payload.len() < 5 || payload[0] != MAGIC_BYTE {
+            return Err(anyhow!("unknown magic byte"));
+        }
+
+        let schema_id = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]);
+        let writer_schema = self.resolver.resolve(schema_id)?;
+
+        let mut reader = &payload[5..];
+        let value = apache_avro::from_avro_datum(writer_schema.as_ref(), &mut reader, None)?;
+        let fields = match &value {
+            Value::Record(fields) => fields,
+            _ => return Err(anyhow!("the avro value is not a record")),
+        };
+
+        let values: Vec<FieldValue> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| {
+                fields
+                    .iter()
+                    .find(|(name, _value)| name.eq(field.name()))
+                    .map(|(_name, value)| to_field_value(value))
+                    .unwrap_or(FieldValue::Null)
+            })
+            .collect();
+
+        write_record(&self.schema, values.as_slice())
+    }
+}
+
+impl KafkaRecordDeserializer for AvroKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        _headers: Option<&BorrowedHeaders>,
+    ) -> Vec<Record> {
+        match self.deserialize_payload(payload) {
+            Ok(record) => vec![record],
+            Err(e) => {
+                warn!(
+                    "discard the Avro message of topic: {}, partition: {}, offset: {}. {}",
+                    topic, partition, offset, e
+                );
+                vec![]
+            }
+        }
+    }
+}
+
+fn to_field_value(value: &Value) -> FieldValue<'_> {
+    match value {
+        Value::Null => FieldValue::Null,
+        Value::Boolean(v) => FieldValue::Bool(*v),
+        Value::Int(v) | Value::Date(v) | Value::TimeMillis(v) => FieldValue::Int(*v as i64),
+        Value::Long(v)
+        | Value::TimeMicros(v)
+        | Value::TimestampMillis(v)
+        | Value::TimestampMicros(v)
+        | Value::LocalTimestampMillis(v)
+        | Value::LocalTimestampMicros(v) => FieldValue::Int(*v),
+        Value::Float(v) => FieldValue::Float(*v as f64),
+        Value::Double(v) => FieldValue::Float(*v),
+        Value::Bytes(v) | Value::Fixed(_, v) => FieldValue::Bytes(Cow::Borrowed(v.as_slice())),
+        Value::String(v) | Value::Enum(_, v) => FieldValue::String(Cow::Borrowed(v.as_str())),
+        Value::Union(_, v) => to_field_value(v.as_ref()),
+        Value::Uuid(v) => FieldValue::String(Cow::Owned(v.to_string())),
+        Value::Decimal(v) => match Vec::<u8>::try_from(v) {
+            Ok(v) => FieldValue::Bytes(Cow::Owned(v)),
+            Err(_e) => FieldValue::Null,
+        },
+        Value::Duration(_) => FieldValue::Null,
+        Value::Array(_) | Value::Map(_) | Value::Record(_) => {
+            match serde_json::Value::try_from(value.clone()) {
+                Ok(v) => FieldValue::String(Cow::Owned(v.to_string())),
+                Err(_e) => FieldValue::Null,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apache_avro::types::Record as AvroRecord;
+    use rlink::core::data_types::{DataType, Field, Schema};
+
+    use crate::source::deserializer::avro::{AvroKafkaRecordDeserializer, LocalAvroSchemaRegistry};
+    use crate::source::deserializer::KafkaRecordDeserializer;
+
+    const USER_SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "user",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "name", "type": ["null", "string"]}
+        ]
+    }"#;
+
+    #[test]
+    pub fn avro_deserialize_test() {
+        let registry = LocalAvroSchemaRegistry::new()
+            .register(7, USER_SCHEMA)
+            .unwrap();
+
+        let avro_schema = apache_avro::Schema::parse_str(USER_SCHEMA).unwrap();
+        let mut avro_record = AvroRecord::new(&avro_schema).unwrap();
+        avro_record.put("id", 10i64);
+        avro_record.put("name", Some("rlink".to_string()));
+        let datum = apache_avro::to_avro_datum(&avro_schema, avro_record).unwrap();
+
+        let mut payload = vec![0u8];
+        payload.extend_from_slice(&7u32.to_be_bytes());
+        payload.extend_from_slice(datum.as_slice());
+
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::String),
+            Field::new("id", DataType::Int64),
+        ]);
+        let mut deserializer = AvroKafkaRecordDeserializer::new(schema.clone(), Arc::new(registry));
+
+        let mut records = deserializer.deserialize(0, &[], payload.as_slice(), "t", 0, 0, None);
+        assert_eq!(records.len(), 1);
+
+        let reader = records[0].as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_str(0).unwrap(), "rlink");
+        assert_eq!(reader.get_i64(1).unwrap(), 10);
+
+        // unknown schema id
+        payload[4] = 8;
+        let records = deserializer.deserialize(0, &[], payload.as_slice(), "t", 0, 0, None);
+        assert!(records.is_empty());
+    }
+}
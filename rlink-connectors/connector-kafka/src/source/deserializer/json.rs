@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+
+use rdkafka::message::BorrowedHeaders;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use serde_json::Value;
+
+use crate::source::deserializer::{
+    write_record, FieldValue, KafkaRecordDeserializer, SchemaKafkaRecordDeserializerBuilder,
+};
+
+/// Deserialize the JSON object payload to a `Record` of the `schema`, the object fields are
+/// matched by the schema field names. A JSON array payload produces a `Record` per element.
+///
+/// The nested object and array are written as JSON string, and the message failed to
+/// deserialize is discarded.
+#[derive(Clone, Debug)]
+pub struct JsonKafkaRecordDeserializer {
+    schema: Schema,
+}
+
+impl JsonKafkaRecordDeserializer {
+    pub fn new(schema: Schema) -> Self {
+        JsonKafkaRecordDeserializer { schema }
+    }
+
+    pub fn builder(schema: Schema) -> SchemaKafkaRecordDeserializerBuilder<Self> {
+        SchemaKafkaRecordDeserializerBuilder::new(Self::new(schema.clone()), schema)
+    }
+
+    fn to_record(&self, value: &Value) -> anyhow::Result<Record> {
+        let object = value
+            .as_object()
+            .ok_or(anyhow!("the JSON value is not an object"))?;
+
+        let values: Vec<FieldValue> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| match object.get(field.name()) {
+                Some(value) => to_field_value(value),
+                None => FieldValue::Null,
+            })
+            .collect();
+
+        write_record(&self.schema, values.as_slice())
+    }
+
+    fn deserialize_payload(&self, payload: &[u8]) -> anyhow::Result<Vec<Record>> {
+        let value: Value = serde_json::from_slice(payload)?;
+        match &value {
+            Value::Array(values) => values.iter().map(|x| self.to_record(x)).collect(),
+            _ => self.to_record(&value).map(|record| vec![record]),
+        }
+    }
+}
+
+impl KafkaRecordDeserializer for JsonKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        _headers: Option<&BorrowedHeaders>,
+    ) -> Vec<Record> {
+        match self.deserialize_payload(payload) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!(
+                    "discard the JSON message of topic: {}, partition: {}, offset: {}. {}",
+                    topic, partition, offset, e
+                );
+                vec![]
+            }
+        }
+    }
+}
+
+fn to_field_value(value: &Value) -> FieldValue<'_> {
+    match value {
+        Value::Null => FieldValue::Null,
+        Value::Bool(v) => FieldValue::Bool(*v),
+        Value::Number(v) => {
+            if let Some(v) = v.as_i64() {
+                FieldValue::Int(v)
+            } else if let Some(v) = v.as_u64() {
+                FieldValue::UInt(v)
+            } else {
+                FieldValue::Float(v.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(v) => FieldValue::String(Cow::Borrowed(v.as_str())),
+        Value::Array(_) | Value::Object(_) => FieldValue::String(Cow::Owned(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+
+    use crate::source::deserializer::json::JsonKafkaRecordDeserializer;
+    use crate::source::deserializer::KafkaRecordDeserializer;
+
+    #[test]
+    pub fn json_deserialize_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UInt64),
+            Field::new("name", DataType::String),
+            Field::new("tags", DataType::String),
+        ]);
+        let mut deserializer = JsonKafkaRecordDeserializer::new(schema.clone());
+
+        let payload = r#"[{"id": 1, "name": "a", "tags": ["x"]}, {"id": 2}]"#;
+        let mut records = deserializer.deserialize(0, &[], payload.as_bytes(), "t", 0, 0, None);
+        assert_eq!(records.len(), 2);
+
+        let reader = records[0].as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_u64(0).unwrap(), 1);
+        assert_eq!(reader.get_str(1).unwrap(), "a");
+        assert_eq!(reader.get_str(2).unwrap(), r#"["x"]"#);
+
+        let reader = records[1].as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_u64(0).unwrap(), 2);
+        assert_eq!(reader.get_str(1).unwrap(), "");
+
+        let records = deserializer.deserialize(0, &[], b"{", "t", 0, 0, None);
+        assert!(records.is_empty());
+    }
+}
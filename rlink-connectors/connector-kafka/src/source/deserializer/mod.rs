@@ -0,0 +1,268 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+use rdkafka::message::BorrowedHeaders;
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{FnSchema, Record};
+
+use crate::build_kafka_record_with_headers;
+
+pub mod avro;
+pub mod json;
+pub mod protobuf;
+
+pub use avro::{AvroKafkaRecordDeserializer, AvroSchemaResolver, LocalAvroSchemaRegistry};
+pub use json::JsonKafkaRecordDeserializer;
+pub use protobuf::ProtobufKafkaRecordDeserializer;
+
+pub trait KafkaRecordDeserializer: Sync + Send {
+    #[allow(clippy::too_many_arguments)]
+    fn deserialize(
+        &mut self,
+        timestamp: i64,
+        key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        headers: Option<&BorrowedHeaders>,
+    ) -> Vec<Record>;
+}
+
+pub trait KafkaRecordDeserializerBuilder: Send + Sync {
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer>;
+    fn schema(&self) -> FnSchema;
+}
+
+/// Wrap the message in the `kafka_message` entity
+#[derive(Default)]
+pub struct DefaultKafkaRecordDeserializer {}
+
+impl KafkaRecordDeserializer for DefaultKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        timestamp: i64,
+        key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        headers: Option<&BorrowedHeaders>,
+    ) -> Vec<Record> {
+        let record = build_kafka_record_with_headers(
+            timestamp, key, payload, topic, partition, offset, headers,
+        )
+        .expect("kafka message writer to Record error");
+        vec![record]
+    }
+}
+
+pub struct DefaultKafkaRecordDeserializerBuilder<T>
+where
+    T: Default + KafkaRecordDeserializer + 'static,
+{
+    a: PhantomData<T>,
+    schema: FnSchema,
+}
+
+impl<T> DefaultKafkaRecordDeserializerBuilder<T>
+where
+    T: Default + KafkaRecordDeserializer + 'static,
+{
+    pub fn new(schema: FnSchema) -> Self {
+        DefaultKafkaRecordDeserializerBuilder {
+            a: PhantomData,
+            schema,
+        }
+    }
+}
+
+impl<T> KafkaRecordDeserializerBuilder for DefaultKafkaRecordDeserializerBuilder<T>
+where
+    T: Default + KafkaRecordDeserializer + 'static,
+{
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer> {
+        let t: Box<dyn KafkaRecordDeserializer> = Box::new(T::default());
+        t
+    }
+
+    fn schema(&self) -> FnSchema {
+        self.schema.clone()
+    }
+}
+
+/// Build the deserializers mapping the payload onto a `Schema` by cloning the prototype,
+/// see `JsonKafkaRecordDeserializer`, `AvroKafkaRecordDeserializer` and
+/// `ProtobufKafkaRecordDeserializer`
+pub struct SchemaKafkaRecordDeserializerBuilder<T>
+where
+    T: Clone + KafkaRecordDeserializer + 'static,
+{
+    deserializer: T,
+    schema: Schema,
+}
+
+impl<T> SchemaKafkaRecordDeserializerBuilder<T>
+where
+    T: Clone + KafkaRecordDeserializer + 'static,
+{
+    pub fn new(deserializer: T, schema: Schema) -> Self {
+        SchemaKafkaRecordDeserializerBuilder {
+            deserializer,
+            schema,
+        }
+    }
+}
+
+impl<T> KafkaRecordDeserializerBuilder for SchemaKafkaRecordDeserializerBuilder<T>
+where
+    T: Clone + KafkaRecordDeserializer + 'static,
+{
+    fn build(&self) -> Box<dyn KafkaRecordDeserializer> {
+        Box::new(self.deserializer.clone())
+    }
+
+    fn schema(&self) -> FnSchema {
+        FnSchema::Single(self.schema.clone())
+    }
+}
+
+/// The field value decoded from the payload, it's converted to the `DataType` of the schema
+/// field when written to the `Record`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FieldValue<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(Cow<'a, str>),
+    Bytes(Cow<'a, [u8]>),
+}
+
+impl<'a> FieldValue<'a> {
+    fn to_i64(&self) -> anyhow::Result<i64> {
+        match self {
+            Self::Null => Ok(0),
+            Self::Bool(v) => Ok(*v as i64),
+            Self::Int(v) => Ok(*v),
+            Self::UInt(v) => Ok(*v as i64),
+            Self::Float(v) => Ok(*v as i64),
+            Self::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+            Self::Bytes(_) => Err(anyhow!("bytes can't be converted to integer")),
+        }
+    }
+
+    fn to_u64(&self) -> anyhow::Result<u64> {
+        match self {
+            Self::UInt(v) => Ok(*v),
+            Self::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+            _ => self.to_i64().map(|v| v as u64),
+        }
+    }
+
+    fn to_f64(&self) -> anyhow::Result<f64> {
+        match self {
+            Self::Float(v) => Ok(*v),
+            Self::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+            Self::UInt(v) => Ok(*v as f64),
+            _ => self.to_i64().map(|v| v as f64),
+        }
+    }
+
+    fn to_bool(&self) -> anyhow::Result<bool> {
+        match self {
+            Self::Bool(v) => Ok(*v),
+            Self::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+            _ => self.to_i64().map(|v| v != 0),
+        }
+    }
+
+    fn to_str(&self) -> anyhow::Result<Cow<'_, str>> {
+        match self {
+            Self::Null => Ok(Cow::Borrowed("")),
+            Self::Bool(v) => Ok(Cow::Owned(v.to_string())),
+            Self::Int(v) => Ok(Cow::Owned(v.to_string())),
+            Self::UInt(v) => Ok(Cow::Owned(v.to_string())),
+            Self::Float(v) => Ok(Cow::Owned(v.to_string())),
+            Self::String(v) => Ok(Cow::Borrowed(v.as_ref())),
+            Self::Bytes(v) => std::str::from_utf8(v.as_ref())
+                .map(Cow::Borrowed)
+                .map_err(|e| anyhow!(e)),
+        }
+    }
+
+    fn to_bytes(&self) -> anyhow::Result<Cow<'_, [u8]>> {
+        match self {
+            Self::Bytes(v) => Ok(Cow::Borrowed(v.as_ref())),
+            _ => self.to_str().map(|v| match v {
+                Cow::Borrowed(v) => Cow::Borrowed(v.as_bytes()),
+                Cow::Owned(v) => Cow::Owned(v.into_bytes()),
+            }),
+        }
+    }
+}
+
+/// Write the values to a `Record` with the `schema`, the values are in the order of the schema
+/// fields, and the missing or null value is written as the default value of the field type.
+pub(crate) fn write_record(schema: &Schema, values: &[FieldValue]) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+
+    for (index, field) in schema.fields().iter().enumerate() {
+        let value = values.get(index).unwrap_or(&FieldValue::Null);
+        let rt = match field.data_type() {
+            DataType::Boolean => writer.set_bool(value.to_bool()?),
+            DataType::Int8 => writer.set_i8(value.to_i64()? as i8),
+            DataType::UInt8 => writer.set_u8(value.to_u64()? as u8),
+            DataType::Int16 => writer.set_i16(value.to_i64()? as i16),
+            DataType::UInt16 => writer.set_u16(value.to_u64()? as u16),
+            DataType::Int32 => writer.set_i32(value.to_i64()? as i32),
+            DataType::UInt32 => writer.set_u32(value.to_u64()? as u32),
+            DataType::Int64 => writer.set_i64(value.to_i64()?),
+            DataType::UInt64 => writer.set_u64(value.to_u64()?),
+            DataType::Float32 => writer.set_f32(value.to_f64()? as f32),
+            DataType::Float64 => writer.set_f64(value.to_f64()?),
+            DataType::Binary => writer.set_binary(value.to_bytes()?.as_ref()),
+            DataType::String => writer.set_str(value.to_str()?.as_ref()),
+        };
+        rt.map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+    }
+
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use rlink::core::data_types::{DataType, Field, Schema};
+
+    use crate::source::deserializer::{write_record, FieldValue};
+
+    #[test]
+    pub fn write_record_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+            Field::new("score", DataType::Float64),
+            Field::new("valid", DataType::Boolean),
+        ]);
+
+        let values = vec![
+            FieldValue::String(Cow::Borrowed("10")),
+            FieldValue::String(Cow::Borrowed("rlink")),
+            FieldValue::Int(3),
+        ];
+        let mut record = write_record(&schema, values.as_slice()).unwrap();
+
+        let reader = record.as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_i64(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "rlink");
+        assert_eq!(reader.get_f64(2).unwrap(), 3.0);
+        assert!(!reader.get_bool(3).unwrap());
+
+        let values = vec![FieldValue::Bytes(Cow::Borrowed(b"x"))];
+        assert!(write_record(&schema, values.as_slice()).is_err());
+    }
+}
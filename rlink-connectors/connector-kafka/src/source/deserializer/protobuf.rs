@@ -0,0 +1,215 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use rdkafka::message::BorrowedHeaders;
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::Record;
+
+use crate::source::deserializer::{
+    write_record, FieldValue, KafkaRecordDeserializer, SchemaKafkaRecordDeserializerBuilder,
+};
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_FIXED64: u64 = 1;
+const WIRE_TYPE_LENGTH_DELIMITED: u64 = 2;
+const WIRE_TYPE_FIXED32: u64 = 5;
+
+/// Deserialize the Protobuf message payload without the generated code, the message fields are
+/// matched by the field numbers, `field_numbers[i]` is the field number of the `i`th schema field.
+///
+/// The field is decoded by the `DataType` of the schema field: the integer types are decoded as
+/// `int32/int64/uint32/uint64/bool` of varint or `fixed32/fixed64/sfixed32/sfixed64`, the float
+/// types as `float/double`, and `String/Binary` as `string/bytes` or the serialized embedded
+/// message. The `sint32/sint64` zigzag encoding and the packed repeated fields are not supported,
+/// the last value wins for the repeated fields. The message failed to deserialize is discarded.
+#[derive(Clone, Debug)]
+pub struct ProtobufKafkaRecordDeserializer {
+    schema: Schema,
+    /// field number -> index of the schema field
+    field_indexes: HashMap<u64, usize>,
+}
+
+impl ProtobufKafkaRecordDeserializer {
+    pub fn new(schema: Schema, field_numbers: Vec<u32>) -> Self {
+        assert_eq!(
+            schema.fields().len(),
+            field_numbers.len(),
+            "the field numbers are not matched with the schema fields"
+        );
+
+        let field_indexes = field_numbers
+            .into_iter()
+            .enumerate()
+            .map(|(index, field_number)| (field_number as u64, index))
+            .collect();
+        ProtobufKafkaRecordDeserializer {
+            schema,
+            field_indexes,
+        }
+    }
+
+    pub fn builder(
+        schema: Schema,
+        field_numbers: Vec<u32>,
+    ) -> SchemaKafkaRecordDeserializerBuilder<Self> {
+        SchemaKafkaRecordDeserializerBuilder::new(Self::new(schema.clone(), field_numbers), schema)
+    }
+
+    fn deserialize_payload(&self, payload: &[u8]) -> anyhow::Result<Record> {
+        let mut values = vec![FieldValue::Null; self.schema.fields().len()];
+
+        let mut buf = payload;
+        while !buf.is_empty() {
+            let key = decode_varint(&mut buf)?;
+            let field_number = key >> 3;
+            let wire_type = key & 0x7;
+
+            let index = self.field_indexes.get(&field_number).copied();
+            let data_type = index.map(|index| self.schema.field(index).data_type());
+
+            let value = match wire_type {
+                WIRE_TYPE_VARINT => {
+                    let v = decode_varint(&mut buf)?;
+                    match data_type {
+                        Some(DataType::UInt32) | Some(DataType::UInt64) => FieldValue::UInt(v),
+                        _ => FieldValue::Int(v as i64),
+                    }
+                }
+                WIRE_TYPE_FIXED64 => {
+                    let bytes = take(&mut buf, 8)?;
+                    let v = u64::from_le_bytes([
+                        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6],
+                        bytes[7],
+                    ]);
+                    match data_type {
+                        Some(DataType::Float32) | Some(DataType::Float64) => {
+                            FieldValue::Float(f64::from_bits(v))
+                        }
+                        Some(DataType::UInt64) | Some(DataType::UInt32) => FieldValue::UInt(v),
+                        _ => FieldValue::Int(v as i64),
+                    }
+                }
+                WIRE_TYPE_FIXED32 => {
+                    let bytes = take(&mut buf, 4)?;
+                    let v = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    match data_type {
+                        Some(DataType::Float32) | Some(DataType::Float64) => {
+                            FieldValue::Float(f32::from_bits(v) as f64)
+                        }
+                        Some(DataType::UInt64) | Some(DataType::UInt32) => {
+                            FieldValue::UInt(v as u64)
+                        }
+                        _ => FieldValue::Int(v as i32 as i64),
+                    }
+                }
+                WIRE_TYPE_LENGTH_DELIMITED => {
+                    let len = decode_varint(&mut buf)? as usize;
+                    let bytes = take(&mut buf, len)?;
+                    match data_type {
+                        Some(DataType::String) => FieldValue::String(Cow::Borrowed(
+                            std::str::from_utf8(bytes).map_err(|e| anyhow!(e))?,
+                        )),
+                        _ => FieldValue::Bytes(Cow::Borrowed(bytes)),
+                    }
+                }
+                _ => return Err(anyhow!("unsupported wire type {}", wire_type)),
+            };
+
+            if let Some(index) = index {
+                values[index] = value;
+            }
+        }
+
+        write_record(&self.schema, values.as_slice())
+    }
+}
+
+impl KafkaRecordDeserializer for ProtobufKafkaRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        _headers: Option<&BorrowedHeaders>,
+    ) -> Vec<Record> {
+        match self.deserialize_payload(payload) {
+            Ok(record) => vec![record],
+            Err(e) => {
+                warn!(
+                    "discard the Protobuf message of topic: {}, partition: {}, offset: {}. {}",
+                    topic, partition, offset, e
+                );
+                vec![]
+            }
+        }
+    }
+}
+
+fn decode_varint(buf: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let b = *buf.get(i).ok_or(anyhow!("truncated varint"))?;
+        value |= ((b & 0x7f) as u64) << (i * 7);
+        if b < 0x80 {
+            *buf = &buf[i + 1..];
+            return Ok(value);
+        }
+    }
+
+    Err(anyhow!("invalid varint"))
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> anyhow::Result<&'a [u8]> {
+    if buf.len() < len {
+        return Err(anyhow!("truncated field"));
+    }
+
+    let (bytes, remaining) = buf.split_at(len);
+    *buf = remaining;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+
+    use crate::source::deserializer::protobuf::ProtobufKafkaRecordDeserializer;
+    use crate::source::deserializer::KafkaRecordDeserializer;
+
+    #[test]
+    pub fn protobuf_deserialize_test() {
+        // message { int64 id = 1; string name = 2; double score = 3; int32 delta = 5; }
+        let mut payload = vec![0x08, 0x96, 0x01, 0x12, 0x05];
+        payload.extend_from_slice(b"rlink");
+        payload.push(0x19);
+        payload.extend_from_slice(&1.5f64.to_le_bytes());
+        // unknown field 4
+        payload.extend_from_slice(&[0x20, 0x01]);
+        payload.push(0x28);
+        payload.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]);
+
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+            Field::new("score", DataType::Float64),
+            Field::new("delta", DataType::Int32),
+        ]);
+        let mut deserializer =
+            ProtobufKafkaRecordDeserializer::new(schema.clone(), vec![1, 2, 3, 5]);
+
+        let mut records = deserializer.deserialize(0, &[], payload.as_slice(), "t", 0, 0, None);
+        assert_eq!(records.len(), 1);
+
+        let reader = records[0].as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_i64(0).unwrap(), 150);
+        assert_eq!(reader.get_str(1).unwrap(), "rlink");
+        assert_eq!(reader.get_f64(2).unwrap(), 1.5);
+        assert_eq!(reader.get_i32(3).unwrap(), -1);
+
+        let records = deserializer.deserialize(0, &[], &payload[..6], "t", 0, 0, None);
+        assert!(records.is_empty());
+    }
+}
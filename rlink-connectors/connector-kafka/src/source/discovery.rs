@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rdkafka::consumer::{BaseConsumer, Consumer, DefaultConsumerContext};
+use rdkafka::{ClientConfig, Offset};
+use regex::Regex;
+use rlink::channel::sender::ChannelSender;
+use rlink::core::runtime::TaskId;
+
+use crate::source::checkpoint::KafkaSourceState;
+use crate::source::consumer::{create_kafka_consumer, ConsumerRange};
+use crate::source::deserializer::KafkaRecordDeserializerBuilder;
+use crate::source::ConsumerRecord;
+
+/// The topics subscribed by the source, a fixed topic list or a regex pattern
+#[derive(Debug, Clone)]
+pub enum TopicSubscription {
+    Topics(Vec<String>),
+    Pattern(Regex),
+}
+
+impl TopicSubscription {
+    fn matches(&self, topic: &str) -> bool {
+        match self {
+            TopicSubscription::Topics(topics) => topics.iter().any(|x| x.eq(topic)),
+            TopicSubscription::Pattern(pattern) => pattern.is_match(topic),
+        }
+    }
+}
+
+/// The task owning the partition, it is only decided by the topic name, the partition and the
+/// number of tasks, so every task gets the same answer without coordination.
+pub fn owner_task_number(topic: &str, partition: i32, num_tasks: u16) -> u16 {
+    // FNV-1a, stable across processes and versions
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in topic.as_bytes() {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    let partition_hash = hash.wrapping_add(partition as u64);
+    (partition_hash % num_tasks as u64) as u16
+}
+
+/// Discover the partitions of the subscribed topics that are owned by the task
+pub(crate) struct KafkaPartitionDiscoverer {
+    subscription: TopicSubscription,
+    task_id: TaskId,
+    consumer: BaseConsumer<DefaultConsumerContext>,
+    discovered_partitions: HashSet<(String, i32)>,
+}
+
+impl KafkaPartitionDiscoverer {
+    pub fn new(
+        client_config: &ClientConfig,
+        subscription: TopicSubscription,
+        task_id: TaskId,
+    ) -> anyhow::Result<Self> {
+        let consumer: BaseConsumer<DefaultConsumerContext> = client_config
+            .create()
+            .map_err(|e| anyhow!("Consumer creation failed. {}", e))?;
+
+        Ok(KafkaPartitionDiscoverer {
+            subscription,
+            task_id,
+            consumer,
+            discovered_partitions: HashSet::new(),
+        })
+    }
+
+    /// return the owned partitions that are not discovered before
+    pub fn discover(&mut self) -> anyhow::Result<Vec<(String, i32)>> {
+        let timeout = Duration::from_secs(3);
+
+        let mut partitions = Vec::new();
+        match &self.subscription {
+            TopicSubscription::Topics(topics) => {
+                for topic in topics {
+                    let metadata = self
+                        .consumer
+                        .fetch_metadata(Some(topic.as_str()), timeout)
+                        .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+                    for metadata_topic in metadata.topics() {
+                        for partition in metadata_topic.partitions() {
+                            partitions.push((metadata_topic.name().to_string(), partition.id()));
+                        }
+                    }
+                }
+            }
+            TopicSubscription::Pattern(_) => {
+                let metadata = self
+                    .consumer
+                    .fetch_metadata(None, timeout)
+                    .map_err(|e| anyhow!("Failed to fetch metadata. {}", e))?;
+                for metadata_topic in metadata.topics() {
+                    if !self.subscription.matches(metadata_topic.name()) {
+                        continue;
+                    }
+                    for partition in metadata_topic.partitions() {
+                        partitions.push((metadata_topic.name().to_string(), partition.id()));
+                    }
+                }
+            }
+        }
+
+        let task_number = self.task_id.task_number();
+        let num_tasks = self.task_id.num_tasks();
+        let new_partitions: Vec<(String, i32)> = partitions
+            .into_iter()
+            .filter(|(topic, partition)| {
+                owner_task_number(topic.as_str(), *partition, num_tasks) == task_number
+            })
+            .filter(|topic_partition| self.discovered_partitions.insert(topic_partition.clone()))
+            .collect();
+
+        Ok(new_partitions)
+    }
+
+    /// Periodically discover the new partitions and consume them from the checkpointed offset,
+    /// or from the earliest if the partition has never been consumed.
+    pub fn start_discovery(
+        mut self,
+        interval: Duration,
+        client_config: ClientConfig,
+        handover: ChannelSender<ConsumerRecord>,
+        deserializer_builder: Arc<dyn KafkaRecordDeserializerBuilder>,
+        state: KafkaSourceState,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let new_partitions = match self.discover() {
+                    Ok(new_partitions) => new_partitions,
+                    Err(e) => {
+                        warn!("kafka partition discovery error. {}", e);
+                        continue;
+                    }
+                };
+
+                for (topic, partition) in new_partitions {
+                    let begin_offset = state
+                        .get(topic.as_str(), partition)
+                        .unwrap_or(Offset::Beginning.to_raw().unwrap());
+                    info!(
+                        "kafka partition discovered, topic: {}, partition: {}, begin offset: {}",
+                        topic, partition, begin_offset
+                    );
+
+                    let state_recorder = state.recorder(topic.as_str(), partition);
+                    let consumer_range = ConsumerRange {
+                        topic,
+                        partition,
+                        begin_offset,
+                        end_offset: None,
+                    };
+                    create_kafka_consumer(
+                        self.task_id.job_id(),
+                        self.task_id.task_number(),
+                        client_config.clone(),
+                        consumer_range,
+                        handover.clone(),
+                        deserializer_builder.build(),
+                        state_recorder,
+                    )
+                    .await;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::discovery::owner_task_number;
+
+    #[test]
+    pub fn owner_task_number_test() {
+        let num_tasks = 3;
+        let mut counts = vec![0; num_tasks as usize];
+        for partition in 0..30 {
+            let owner = owner_task_number("topic-0", partition, num_tasks);
+            assert_eq!(owner, owner_task_number("topic-0", partition, num_tasks));
+            counts[owner as usize] += 1;
+        }
+
+        // the consecutive partitions of a topic are spread evenly
+        assert_eq!(counts, vec![10, 10, 10]);
+    }
+}
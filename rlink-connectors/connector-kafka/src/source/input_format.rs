@@ -1,9 +1,12 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use rdkafka::consumer::{BaseConsumer, Consumer, DefaultConsumerContext};
 use rdkafka::error::KafkaResult;
 use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use regex::Regex;
 use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
 use rlink::core;
 use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
 use rlink::core::element::FnSchema;
@@ -17,29 +20,44 @@ use rlink::metrics::Tag;
 use crate::source::checkpoint::KafkaCheckpointFunction;
 use crate::source::consumer::{create_kafka_consumer, ConsumerRange};
 use crate::source::deserializer::KafkaRecordDeserializerBuilder;
+use crate::source::discovery::{KafkaPartitionDiscoverer, TopicSubscription};
 use crate::source::offset_range::{OffsetRange, PartitionOffset};
 use crate::source::stream::KafkaRecordStream;
+use crate::source::ConsumerRecord;
 
 /// Depending on whether the task has `InputSplit`, and whether the client needs to be created
 const CREATE_KAFKA_CONNECTION: &'static str = "create_kafka_connection";
 
+const DISCOVERY_INTERVAL_DEFAULT: Duration = Duration::from_secs(30);
+/// The interval to retry when the consumers fail to start, eg: the broker is unreachable
+const START_CONSUMER_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
 pub struct KafkaInputFormat {
     name: String,
     parallelism: u16,
 
     client_config: ClientConfig,
     topics: Vec<String>,
+    /// subscribe the topics matching the pattern instead of `topics`
+    pub(crate) topic_pattern: Option<Regex>,
+    /// periodically discover the new partitions, the partitions are assigned to the tasks by
+    /// `owner_task_number` instead of one `InputSplit` per partition
+    pub(crate) discovery_interval: Option<Duration>,
 
     task_id: TaskId,
     task_topic: String,
     task_partition: i32,
+    /// the task has no partition if the parallelism is greater than the number of partitions
+    create_connection: bool,
+    /// keep the idle task's stream open until the job is stopped
+    idle_handover: Option<ChannelSender<ConsumerRecord>>,
 
     buffer_size: usize,
     offset_range: OffsetRange,
 
     tags: Vec<Tag>,
 
-    deserializer_builder: Box<dyn KafkaRecordDeserializerBuilder>,
+    deserializer_builder: Arc<dyn KafkaRecordDeserializerBuilder>,
     schema: FnSchema,
 
     checkpoint: Option<KafkaCheckpointFunction>,
@@ -61,24 +79,40 @@ impl KafkaInputFormat {
             parallelism,
             client_config,
             topics,
+            topic_pattern: None,
+            discovery_interval: None,
             task_id: Default::default(),
             task_topic: "".to_string(),
             task_partition: 0,
+            create_connection: true,
+            idle_handover: None,
             buffer_size,
             offset_range,
             checkpoint: None,
-            deserializer_builder,
+            deserializer_builder: Arc::from(deserializer_builder),
             schema,
             tags: vec![],
         }
     }
 
+    /// whether the partitions are discovered by the tasks
+    fn is_dynamic(&self) -> bool {
+        self.topic_pattern.is_some() || self.discovery_interval.is_some()
+    }
+
+    fn topic_subscription(&self) -> TopicSubscription {
+        match &self.topic_pattern {
+            Some(topic_pattern) => TopicSubscription::Pattern(topic_pattern.clone()),
+            None => TopicSubscription::Topics(self.topics.clone()),
+        }
+    }
+
     fn consumer_ranges(&mut self, topic: String, partition: i32) -> KafkaResult<ConsumerRange> {
         let (begin_partition, end_partition) = match &self.offset_range {
             OffsetRange::None => {
-                let state = self.checkpoint.as_mut().unwrap().as_state_mut();
+                let state = self.checkpoint.as_ref().unwrap().state();
                 let begin_offset = state
-                    .get()
+                    .get(topic.as_str(), partition)
                     .map(|offset| PartitionOffset { partition, offset });
                 (begin_offset, None)
             }
@@ -86,19 +120,55 @@ impl KafkaInputFormat {
                 begin_offset,
                 end_offset,
             } => {
-                let begin_partitions = {
-                    let partition_offsets = begin_offset.get(topic.as_str()).unwrap();
-                    partition_offsets.get(partition as usize).map(|p| p.clone())
-                };
-                let end_partitions = if let Some(end_offset) = end_offset {
-                    let partition_offsets = end_offset.get(topic.as_str()).unwrap();
-                    partition_offsets.get(partition as usize).map(|p| p.clone())
-                } else {
-                    None
-                };
+                // the topics matching the pattern may be absent in the offsets
+                let begin_partitions = begin_offset
+                    .get(topic.as_str())
+                    .and_then(|partition_offsets| partition_offsets.get(partition as usize))
+                    .map(|p| p.clone());
+                // the end offset is inclusive
+                let end_partitions = end_offset
+                    .as_ref()
+                    .and_then(|end_offset| end_offset.get(topic.as_str()))
+                    .and_then(|partition_offsets| partition_offsets.get(partition as usize))
+                    .map(|p| PartitionOffset::new(partition, p.offset + 1));
 
                 (begin_partitions, end_partitions)
             }
+            OffsetRange::GroupCommitted { reset } => {
+                let state = self.checkpoint.as_ref().unwrap().state();
+                let begin_offset = match state.get(topic.as_str(), partition) {
+                    Some(offset) => offset,
+                    None => {
+                        let consumer: BaseConsumer<DefaultConsumerContext> =
+                            self.client_config.create()?;
+
+                        let mut partition_list = TopicPartitionList::with_capacity(1);
+                        partition_list.add_partition(topic.as_str(), partition);
+                        let tpl =
+                            consumer.committed_offsets(partition_list, Duration::from_secs(3))?;
+
+                        let committed_offset = tpl
+                            .find_partition(topic.as_str(), partition)
+                            .map(|elem| elem.offset());
+                        match committed_offset {
+                            Some(Offset::Offset(offset)) => offset,
+                            _ => reset.to_offset().to_raw().unwrap(),
+                        }
+                    }
+                };
+                info!(
+                    "group committed offset of topic: {}, partition: {} is {}",
+                    topic, partition, begin_offset
+                );
+
+                (
+                    Some(PartitionOffset {
+                        partition,
+                        offset: begin_offset,
+                    }),
+                    None,
+                )
+            }
             OffsetRange::Timestamp {
                 begin_timestamp,
                 end_timestamp,
@@ -141,9 +211,23 @@ impl KafkaInputFormat {
                     }
                     None => None,
                 };
+                // the messages at or after the end timestamp are excluded, consume to the latest
+                // message if there is no message after the end timestamp
                 let end_partition = match end_timestamp {
                     Some(timestamp) => {
-                        offsets_for_times(&consumer, topic.as_str(), partition, *timestamp)?
+                        match offsets_for_times(&consumer, topic.as_str(), partition, *timestamp)? {
+                            Some(partition_offset) if partition_offset.offset >= 0 => {
+                                Some(partition_offset)
+                            }
+                            _ => {
+                                let (_low, high) = consumer.fetch_watermarks(
+                                    topic.as_str(),
+                                    partition,
+                                    Duration::from_secs(3),
+                                )?;
+                                Some(PartitionOffset::new(partition, high))
+                            }
+                        }
                     }
                     None => None,
                 };
@@ -152,15 +236,102 @@ impl KafkaInputFormat {
             }
         };
 
+        // the recovered task resumes from the checkpoint within the bounded range
+        let begin_offset = match self
+            .checkpoint
+            .as_ref()
+            .unwrap()
+            .state()
+            .get(&topic, partition)
+        {
+            Some(offset) if self.offset_range.is_bounded() => offset + 1,
+            _ => begin_partition
+                .map(|x| x.offset)
+                .unwrap_or(Offset::End.to_raw().unwrap()),
+        };
+
         Ok(ConsumerRange {
             topic,
             partition,
-            begin_offset: begin_partition
-                .map(|x| x.offset)
-                .unwrap_or(Offset::End.to_raw().unwrap()),
+            begin_offset,
             end_offset: end_partition.map(|x| x.offset),
         })
     }
+
+    async fn start_consumer(
+        &mut self,
+        topic: String,
+        partition: i32,
+        handover: ChannelSender<ConsumerRecord>,
+    ) -> KafkaResult<()> {
+        let consumer_ranges = self.consumer_ranges(topic, partition)?;
+        self.create_consumer(consumer_ranges, handover).await;
+
+        Ok(())
+    }
+
+    async fn create_consumer(
+        &self,
+        consumer_ranges: ConsumerRange,
+        handover: ChannelSender<ConsumerRecord>,
+    ) {
+        let state_recorder = self
+            .checkpoint
+            .as_ref()
+            .unwrap()
+            .state()
+            .recorder(consumer_ranges.topic.as_str(), consumer_ranges.partition);
+        create_kafka_consumer(
+            self.task_id.job_id(),
+            self.task_id.task_number(),
+            self.client_config.clone(),
+            consumer_ranges,
+            handover,
+            self.deserializer_builder.build(),
+            state_recorder,
+        )
+        .await;
+    }
+
+    /// Consume the owned partitions with the `offset_range`, then start the discovery of the new
+    /// partitions, which are consumed from the earliest. No consumer is started on error, so
+    /// it can be retried.
+    async fn start_dynamic_consumers(
+        &mut self,
+        handover: ChannelSender<ConsumerRecord>,
+    ) -> anyhow::Result<()> {
+        let mut discoverer = KafkaPartitionDiscoverer::new(
+            &self.client_config,
+            self.topic_subscription(),
+            self.task_id,
+        )?;
+
+        let partitions = discoverer.discover()?;
+        info!(
+            "kafka partitions assigned to task({}): {:?}",
+            self.task_id.task_number(),
+            partitions
+        );
+        let mut consumer_ranges = Vec::with_capacity(partitions.len());
+        for (topic, partition) in partitions {
+            consumer_ranges.push(self.consumer_ranges(topic, partition)?);
+        }
+        for consumer_range in consumer_ranges {
+            self.create_consumer(consumer_range, handover.clone()).await;
+        }
+
+        let state = self.checkpoint.as_ref().unwrap().state().clone();
+        discoverer.start_discovery(
+            self.discovery_interval
+                .unwrap_or(DISCOVERY_INTERVAL_DEFAULT),
+            self.client_config.clone(),
+            handover,
+            self.deserializer_builder.clone(),
+            state,
+        );
+
+        Ok(())
+    }
 }
 
 impl NamedFunction for KafkaInputFormat {
@@ -175,22 +346,40 @@ impl InputFormat for KafkaInputFormat {
         info!("kafka source open");
 
         self.task_id = context.task_id.clone();
-        self.task_topic = input_split.properties().get_string("topic").unwrap();
-        self.task_partition = input_split.properties().get_i32("partition").unwrap();
-
-        let kafka_checkpoint = KafkaCheckpointFunction::new(
-            context.application_id.clone(),
-            context.task_id,
-            self.task_topic.as_str(),
-            self.task_partition,
-        );
+
+        let kafka_checkpoint =
+            KafkaCheckpointFunction::new(context.application_id.clone(), context.task_id)
+                .with_offset_committer(&self.client_config)?;
         self.checkpoint = Some(kafka_checkpoint);
 
         self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
             .await;
 
-        self.tags.push(Tag::new("topic", self.task_topic.as_str()));
-        self.tags.push(Tag::new("partition", self.task_partition));
+        if self.is_dynamic() {
+            if self.offset_range.is_bounded() {
+                return Err(core::Error::from(
+                    "the end offset is not supported with the partition discovery",
+                ));
+            }
+
+            let topics = match &self.topic_pattern {
+                Some(topic_pattern) => topic_pattern.as_str().to_string(),
+                None => self.topics.join(","),
+            };
+            self.tags.push(Tag::new("topic", topics));
+            self.tags
+                .push(Tag::new("task_number", self.task_id.task_number()));
+        } else {
+            self.task_topic = input_split.properties().get_string("topic").unwrap();
+            self.task_partition = input_split.properties().get_i32("partition").unwrap();
+            self.create_connection = input_split
+                .properties()
+                .get_bool(CREATE_KAFKA_CONNECTION)
+                .unwrap_or(true);
+
+            self.tags.push(Tag::new("topic", self.task_topic.as_str()));
+            self.tags.push(Tag::new("partition", self.task_partition));
+        }
 
         info!("start with consumer and operator mode");
 
@@ -201,22 +390,36 @@ impl InputFormat for KafkaInputFormat {
         let (sender, receiver) =
             named_channel("KafkaSource_Handover", self.tags.clone(), self.buffer_size);
 
-        let client_config = self.client_config.clone();
-        let consumer_ranges = self
-            .consumer_ranges(self.task_topic.to_string(), self.task_partition)
-            .unwrap();
-        create_kafka_consumer(
-            self.task_id.job_id(),
-            self.task_id.task_number(),
-            client_config,
-            consumer_ranges,
-            sender,
-            self.deserializer_builder.build(),
-        )
-        .await;
+        if self.is_dynamic() {
+            while let Err(e) = self.start_dynamic_consumers(sender.clone()).await {
+                error!(
+                    "start kafka consumers error, retry after {:?}. {}",
+                    START_CONSUMER_RETRY_INTERVAL, e
+                );
+                tokio::time::sleep(START_CONSUMER_RETRY_INTERVAL).await;
+            }
+        } else if !self.create_connection {
+            info!("kafka source task has no partition to consume");
+            // the stream of the bounded source ends when the handover is dropped
+            if !self.offset_range.is_bounded() {
+                self.idle_handover = Some(sender);
+            }
+        } else {
+            let topic = self.task_topic.to_string();
+            let partition = self.task_partition;
+            while let Err(e) = self
+                .start_consumer(topic.clone(), partition, sender.clone())
+                .await
+            {
+                error!(
+                    "start kafka consumer error, topic: {}, partition: {}, retry after {:?}. {}",
+                    topic, partition, START_CONSUMER_RETRY_INTERVAL, e
+                );
+                tokio::time::sleep(START_CONSUMER_RETRY_INTERVAL).await;
+            }
+        }
 
-        let state_recorder = self.checkpoint.as_mut().unwrap().as_state_mut().clone();
-        Box::pin(KafkaRecordStream::new(receiver, state_recorder))
+        Box::pin(KafkaRecordStream::new(receiver))
     }
 
     async fn close(&mut self) -> core::Result<()> {
@@ -260,6 +463,14 @@ impl CheckpointFunction for KafkaInputFormat {
 
 impl InputSplitSource for KafkaInputFormat {
     fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        // the partitions are discovered and assigned by the tasks
+        if self.is_dynamic() {
+            let input_splits = (0..min_num_splits)
+                .map(|index| InputSplit::new(index, Properties::new()))
+                .collect();
+            return Ok(input_splits);
+        }
+
         let timeout = Duration::from_secs(3);
 
         info!("kafka config {:?}", self.client_config);
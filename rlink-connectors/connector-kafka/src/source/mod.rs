@@ -1,7 +1,10 @@
+use crate::source::checkpoint::KafkaSourceStateRecorder;
+
 pub mod builder;
 pub mod checkpoint;
 pub mod consumer;
 pub mod deserializer;
+pub mod discovery;
 pub mod input_format;
 pub mod offset_range;
 pub mod stream;
@@ -20,10 +23,20 @@ pub(crate) fn is_empty_record(record: &mut rlink::core::element::Record) -> bool
 pub(crate) struct ConsumerRecord {
     record: rlink::core::element::Record,
     offset: i64,
+    /// the state of the record's partition, updated when the record is emitted
+    state_recorder: KafkaSourceStateRecorder,
 }
 
 impl ConsumerRecord {
-    pub fn new(record: rlink::core::element::Record, offset: i64) -> Self {
-        ConsumerRecord { record, offset }
+    pub fn new(
+        record: rlink::core::element::Record,
+        offset: i64,
+        state_recorder: KafkaSourceStateRecorder,
+    ) -> Self {
+        ConsumerRecord {
+            record,
+            offset,
+            state_recorder,
+        }
     }
 }
@@ -1,11 +1,13 @@
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
+use rdkafka::Offset;
 use rlink::core::properties::Properties;
 
-use crate::{OFFSET_BEGIN, OFFSET_END, OFFSET_TYPE};
+use crate::{OFFSET_BEGIN, OFFSET_END, OFFSET_RESET, OFFSET_TYPE};
 
 #[derive(Clone, Debug)]
 pub struct PartitionOffset {
@@ -26,17 +28,77 @@ impl PartitionOffset {
     }
 }
 
+/// Where to start when the consumer group has no committed offset
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OffsetReset {
+    Earliest,
+    Latest,
+}
+
+impl OffsetReset {
+    pub fn to_offset(&self) -> Offset {
+        match self {
+            Self::Earliest => Offset::Beginning,
+            Self::Latest => Offset::End,
+        }
+    }
+}
+
+impl FromStr for OffsetReset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "earliest" => Ok(Self::Earliest),
+            "latest" => Ok(Self::Latest),
+            _ => Err(anyhow!("unknown offset reset {}", s)),
+        }
+    }
+}
+
+impl Display for OffsetReset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Earliest => write!(f, "earliest"),
+            Self::Latest => write!(f, "latest"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum OffsetRange {
     None,
+    /// Consume from the `begin_offset` of the partitions. With the `end_offset`, the source is
+    /// bounded: the partition is finished after the end offset(inclusive) is consumed, and the
+    /// job exits when all the partitions are finished.
     Direct {
         begin_offset: HashMap<String, Vec<PartitionOffset>>,
         end_offset: Option<HashMap<String, Vec<PartitionOffset>>>,
     },
+    /// Consume from the first message at or after the `begin_timestamp` of the topics. With the
+    /// `end_timestamp`, the source is bounded: the messages at or after the end timestamp are
+    /// excluded, or the messages up to the latest one when the range is resolved.
     Timestamp {
         begin_timestamp: HashMap<String, u64>,
         end_timestamp: Option<HashMap<String, u64>>,
     },
+    /// Start from the offsets committed by the consumer group,
+    /// or from `reset` if the group has no committed offset of the partition.
+    /// The offsets in the checkpoint take precedence when the task is recovered.
+    GroupCommitted {
+        reset: OffsetReset,
+    },
+}
+
+impl OffsetRange {
+    /// whether the consumption stops at an end offset
+    pub fn is_bounded(&self) -> bool {
+        match self {
+            Self::Direct { end_offset, .. } => end_offset.is_some(),
+            Self::Timestamp { end_timestamp, .. } => end_timestamp.is_some(),
+            _ => false,
+        }
+    }
 }
 
 impl Into<Properties> for OffsetRange {
@@ -91,6 +153,10 @@ impl Into<Properties> for OffsetRange {
                     add_offset(OFFSET_END, end_timestamp, properties.borrow_mut());
                 }
             }
+            Self::GroupCommitted { reset } => {
+                properties.set_str(OFFSET_TYPE, "group_committed");
+                properties.set_string(OFFSET_RESET.to_string(), reset.to_string());
+            }
         }
 
         properties
@@ -171,6 +237,13 @@ impl TryFrom<Properties> for OffsetRange {
                     end_timestamp,
                 })
             }
+            "group_committed" => {
+                let reset = match properties.get_string(OFFSET_RESET) {
+                    Ok(reset) => OffsetReset::from_str(reset.as_str())?,
+                    Err(_e) => OffsetReset::Latest,
+                };
+                Ok(Self::GroupCommitted { reset })
+            }
             "" => Ok(Self::None),
             _ => Err(anyhow!("unknown offset type {}", offset_type)),
         }
@@ -184,7 +257,7 @@ mod tests {
 
     use rlink::core::properties::Properties;
 
-    use crate::source::offset_range::{OffsetRange, PartitionOffset};
+    use crate::source::offset_range::{OffsetRange, OffsetReset, PartitionOffset};
 
     #[test]
     pub fn properties_convert_test() {
@@ -235,7 +308,40 @@ mod tests {
         }
 
         let offset_range2 = OffsetRange::try_from(properties).unwrap();
+        assert!(offset_range2.is_bounded());
 
         println!("{:?}", offset_range2)
     }
+
+    #[test]
+    pub fn group_committed_properties_convert_test() {
+        let offset_range = OffsetRange::GroupCommitted {
+            reset: OffsetReset::Earliest,
+        };
+
+        let properties: Properties = offset_range.into();
+        match OffsetRange::try_from(properties).unwrap() {
+            OffsetRange::GroupCommitted { reset } => assert_eq!(reset, OffsetReset::Earliest),
+            offset_range => panic!("unexpected offset range {:?}", offset_range),
+        }
+    }
+
+    #[test]
+    pub fn bounded_test() {
+        let mut begin_timestamp = HashMap::new();
+        begin_timestamp.insert("topic-0".to_string(), 1000);
+
+        let offset_range = OffsetRange::Timestamp {
+            begin_timestamp: begin_timestamp.clone(),
+            end_timestamp: None,
+        };
+        assert!(!offset_range.is_bounded());
+
+        let offset_range = OffsetRange::Timestamp {
+            begin_timestamp: begin_timestamp.clone(),
+            end_timestamp: Some(begin_timestamp),
+        };
+        assert!(offset_range.is_bounded());
+        assert!(!OffsetRange::None.is_bounded());
+    }
 }
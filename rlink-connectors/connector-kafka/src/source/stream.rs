@@ -7,25 +7,17 @@ use std::task::{Context, Poll};
 use rlink::core::element::Element;
 use rlink::core::function::ElementStream;
 
-use crate::source::checkpoint::KafkaSourceStateRecorder;
 use crate::source::{is_empty_record, ConsumerRecord};
 
 /// Simulate a Kafka consumption stream as an iterator.
-/// only support one topic and one partition
+/// the records of all the partitions assigned to the task are merged in the stream
 pub struct KafkaRecordStream {
     receiver: ChannelReceiver<ConsumerRecord>,
-    state_recorder: KafkaSourceStateRecorder,
 }
 
 impl KafkaRecordStream {
-    pub(crate) fn new(
-        receiver: ChannelReceiver<ConsumerRecord>,
-        state_recorder: KafkaSourceStateRecorder,
-    ) -> Self {
-        KafkaRecordStream {
-            receiver,
-            state_recorder,
-        }
+    pub(crate) fn new(receiver: ChannelReceiver<ConsumerRecord>) -> Self {
+        KafkaRecordStream { receiver }
     }
 }
 
@@ -42,7 +34,9 @@ impl Stream for KafkaRecordStream {
                         return Poll::Ready(None);
                     }
 
-                    self.state_recorder.update(consumer_record.offset);
+                    consumer_record
+                        .state_recorder
+                        .update(consumer_record.offset);
 
                     Poll::Ready(Some(Element::Record(consumer_record.record)))
                 }
@@ -0,0 +1,14 @@
+use serbuffer_gen::{Codegen, DataType::*, SchemaBuilder};
+
+fn main() {
+    Codegen::out_dir("buffer_gen")
+        .schema(
+            SchemaBuilder::new("NatsMessage")
+                .field("timestamp", I64)
+                .field("subject", STRING)
+                .field("payload", BINARY)
+                .field("sequence", U64),
+        )
+        .gen()
+        .expect("buffer gen error");
+}
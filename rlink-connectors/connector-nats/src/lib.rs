@@ -0,0 +1,68 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod sink;
+pub mod source;
+
+pub mod buffer_gen {
+    include!(concat!(env!("OUT_DIR"), "/buffer_gen/mod.rs"));
+}
+
+pub use sink::output_format::NatsOutputFormat;
+pub use source::input_format::NatsInputFormat;
+
+use rlink::core::element::Record;
+
+use crate::buffer_gen::nats_message;
+
+pub const NATS: &str = "nats";
+pub const SERVERS: &str = "servers";
+
+pub const STREAM: &str = "stream";
+pub const SUBJECTS: &str = "subjects";
+pub const DURABLE_NAME: &str = "durable.name";
+pub const DELIVER_POLICY: &str = "deliver.policy";
+pub const BUFFER_SIZE: &str = "buffer.size";
+
+pub const INPUT_FORMAT_FN_NAME_DEFAULT: &str = "NatsInputFormat";
+pub const OUTPUT_FORMAT_FN_NAME_DEFAULT: &str = "NatsOutputFormat";
+
+pub const SOURCE_CHANNEL_SIZE: usize = 50000;
+pub const SINK_CHANNEL_SIZE: usize = 50000;
+
+/// Build the `nats_message` record.
+///
+/// The `timestamp` is the time the message is stored in the stream, and the `sequence` is the
+/// stream sequence of the message. When the record is written to the sink, the `subject` is used
+/// if the sink has no subject configured.
+pub fn build_nats_record(
+    timestamp: i64,
+    subject: &str,
+    payload: &[u8],
+    sequence: u64,
+) -> Result<Record, std::io::Error> {
+    let message = nats_message::Entity {
+        timestamp,
+        subject,
+        payload,
+        sequence,
+    };
+
+    // 28 = 8(len(subject) + len(payload)) +
+    //      16(len(timestamp) + len(sequence)) +
+    //      4(place_holder)
+    let capacity = subject.len() + payload.len() + 28;
+    let mut record = Record::with_capacity(capacity);
+
+    message.to_buffer(record.as_buffer()).unwrap();
+
+    Ok(record)
+}
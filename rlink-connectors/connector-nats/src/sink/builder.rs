@@ -0,0 +1,61 @@
+use std::convert::TryFrom;
+
+use rlink::core::properties::Properties;
+
+use crate::source::builder::split_list;
+use crate::{NatsOutputFormat, BUFFER_SIZE, NATS, SERVERS, SINK_CHANNEL_SIZE, SUBJECTS};
+
+#[derive(Debug)]
+pub struct NatsOutputFormatBuilder {
+    servers: Vec<String>,
+    subject: Option<String>,
+    buffer_size: Option<usize>,
+}
+
+impl NatsOutputFormatBuilder {
+    pub fn new(servers: Vec<String>, subject: Option<String>) -> Self {
+        NatsOutputFormatBuilder {
+            servers,
+            subject,
+            buffer_size: None,
+        }
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> NatsOutputFormat {
+        info!("build nats sink with: {:?}", &self);
+
+        let buffer_size = self.buffer_size.unwrap_or(SINK_CHANNEL_SIZE);
+        NatsOutputFormat::new(self.servers, self.subject, buffer_size)
+    }
+}
+
+impl TryFrom<Properties> for NatsOutputFormatBuilder {
+    type Error = anyhow::Error;
+
+    fn try_from(properties: Properties) -> Result<Self, Self::Error> {
+        let nats_properties = properties.to_sub_properties(NATS);
+        let servers = split_list(nats_properties.get_string(SERVERS)?.as_str());
+
+        let subject = match properties.get_string(SUBJECTS) {
+            Ok(subjects) => {
+                let subjects = split_list(subjects.as_str());
+                if subjects.len() != 1 {
+                    return Err(anyhow!("only one subject support in nats sink"));
+                }
+                Some(subjects[0].clone())
+            }
+            Err(_e) => None,
+        };
+
+        let buffer_size = properties
+            .get_usize(BUFFER_SIZE)
+            .unwrap_or(SINK_CHANNEL_SIZE);
+
+        Ok(NatsOutputFormatBuilder::new(servers, subject).buffer_size(buffer_size))
+    }
+}
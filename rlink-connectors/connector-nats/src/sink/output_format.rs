@@ -0,0 +1,112 @@
+use async_nats::jetstream;
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::Element;
+use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::metrics::Tag;
+use tokio::sync::oneshot;
+
+use crate::sink::producer::{NatsProducerThread, NatsSinkMessage};
+
+/// Publish the `nats_message` records to the JetStream streams, the records before a checkpoint
+/// are acked by the streams before the checkpoint completes, so they are written at least once.
+#[derive(NamedFunction)]
+pub struct NatsOutputFormat {
+    servers: Vec<String>,
+    subject: Option<String>,
+
+    buffer_size: usize,
+    handover: Option<ChannelSender<NatsSinkMessage>>,
+    /// the producer thread has exited, all the later checkpoints are declined
+    thread_exited: bool,
+}
+
+impl NatsOutputFormat {
+    pub fn new(servers: Vec<String>, subject: Option<String>, buffer_size: usize) -> Self {
+        NatsOutputFormat {
+            servers,
+            subject,
+            buffer_size,
+            handover: None,
+            thread_exited: false,
+        }
+    }
+
+    /// wait for the acks of the records handed over before
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.thread_exited {
+            return Err(anyhow!("nats producer thread has exited"));
+        }
+
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        let message = NatsSinkMessage::Flush(ack_sender);
+        if self.handover.as_ref().unwrap().send(message).await.is_err() {
+            self.thread_exited = true;
+            return Err(anyhow!("nats producer thread has exited"));
+        }
+
+        ack_receiver
+            .await
+            .map_err(|_e| anyhow!("nats producer thread has exited"))?
+    }
+}
+
+#[async_trait]
+impl OutputFormat for NatsOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        let client = async_nats::connect(&self.servers)
+            .await
+            .map_err(|e| core::Error::from(format!("connect nats error. {}", e)))?;
+        let jetstream = jetstream::new(client);
+
+        let mut tags = context.task_id.to_tags();
+        tags.push(Tag::new("subject", self.subject.as_deref().unwrap_or("")));
+
+        let (sender, receiver) = named_channel(self.name(), tags, self.buffer_size);
+        self.handover = Some(sender);
+
+        let subject = self.subject.clone();
+        tokio::spawn(async move {
+            let mut nats_producer = NatsProducerThread::new(subject, jetstream, receiver);
+            nats_producer.run().await;
+        });
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let message = NatsSinkMessage::Record(element.into_record());
+        if self.handover.as_ref().unwrap().send(message).await.is_err() && !self.thread_exited {
+            // surfaced as a failure of the next checkpoint
+            error!("nats producer thread has exited, the records are discarded");
+            self.thread_exited = true;
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for NatsOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        if let Err(e) = self.flush().await {
+            context.decline(format!("flush nats producer error. {}", e).as_str());
+        }
+        None
+    }
+}
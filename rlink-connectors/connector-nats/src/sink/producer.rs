@@ -0,0 +1,153 @@
+use async_nats::jetstream;
+use async_nats::jetstream::context::PublishAckFuture;
+use bytes::Bytes;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::Record;
+use tokio::sync::oneshot;
+
+use crate::buffer_gen::nats_message;
+
+/// The max number of the messages published but not acked by the streams
+const MAX_PENDING_MESSAGES: usize = 3000;
+
+/// The subject and payload of the `nats_message` entity, the `subject` of the sink takes
+/// precedence over the entity's.
+pub(crate) fn to_publish_message(
+    entity: &nats_message::Entity,
+    subject: Option<&String>,
+) -> anyhow::Result<(String, Bytes)> {
+    let subject = match subject {
+        Some(subject) => subject.as_str(),
+        None => entity.subject,
+    };
+    if subject.is_empty() {
+        return Err(anyhow!("subject not found in `NatsRecord`"));
+    }
+
+    Ok((subject.to_string(), Bytes::copy_from_slice(entity.payload)))
+}
+
+/// Message handed over from the `NatsOutputFormat` to the `NatsProducerThread`
+pub enum NatsSinkMessage {
+    Record(Record),
+    /// wait for the acks of the published records, sent when the sink snapshots
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+}
+
+pub struct NatsProducerThread {
+    subject: Option<String>,
+    jetstream: jetstream::Context,
+    receiver: ChannelReceiver<NatsSinkMessage>,
+
+    pending_futures: Vec<PublishAckFuture>,
+    /// the first publish error, the discarded records are lost, so all the later flushes fail
+    produce_error: Option<String>,
+}
+
+impl NatsProducerThread {
+    pub fn new(
+        subject: Option<String>,
+        jetstream: jetstream::Context,
+        receiver: ChannelReceiver<NatsSinkMessage>,
+    ) -> Self {
+        NatsProducerThread {
+            subject,
+            jetstream,
+            receiver,
+            pending_futures: Vec::with_capacity(MAX_PENDING_MESSAGES),
+            produce_error: None,
+        }
+    }
+
+    pub async fn run(&mut self) {
+        while let Some(message) = self.receiver.recv().await {
+            match message {
+                NatsSinkMessage::Record(record) => {
+                    self.send(record).await;
+                    if self.pending_futures.len() >= MAX_PENDING_MESSAGES {
+                        self.drain().await;
+                    }
+                }
+                NatsSinkMessage::Flush(ack) => {
+                    self.drain().await;
+
+                    let flush_result = match &self.produce_error {
+                        Some(e) => Err(anyhow!("{}", e)),
+                        None => Ok(()),
+                    };
+                    if ack.send(flush_result).is_err() {
+                        warn!("the flush ack receiver has been dropped");
+                    }
+                }
+            }
+        }
+
+        self.drain().await;
+        info!("nats recv channel disconnected, producer thread exit");
+    }
+
+    async fn send(&mut self, mut record: Record) {
+        let entity = match nats_message::Entity::parse(record.as_buffer()) {
+            Ok(entity) => entity,
+            Err(e) => {
+                self.set_produce_error(format!("parse `NatsRecord` error. {}", e));
+                return;
+            }
+        };
+
+        let (subject, payload) = match to_publish_message(&entity, self.subject.as_ref()) {
+            Ok(message) => message,
+            Err(e) => {
+                self.set_produce_error(e.to_string());
+                return;
+            }
+        };
+
+        match self.jetstream.publish(subject, payload).await {
+            Ok(future) => self.pending_futures.push(future),
+            Err(e) => self.set_produce_error(format!("publish error: {}", e)),
+        }
+    }
+
+    /// wait for the acks of the published messages
+    async fn drain(&mut self) {
+        for future in std::mem::take(&mut self.pending_futures) {
+            if let Err(e) = future.await {
+                self.set_produce_error(format!("publish ack error: {}", e));
+            }
+        }
+    }
+
+    /// keep the first error, the later flushes fail with it
+    fn set_produce_error(&mut self, error: String) {
+        error!("{}", error);
+        if self.produce_error.is_none() {
+            self.produce_error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer_gen::nats_message;
+    use crate::build_nats_record;
+    use crate::sink::producer::to_publish_message;
+
+    #[test]
+    pub fn to_publish_message_test() {
+        let mut record = build_nats_record(1000, "orders.created", b"payload", 1).unwrap();
+        let entity = nats_message::Entity::parse(record.as_buffer()).unwrap();
+
+        let (subject, payload) = to_publish_message(&entity, None).unwrap();
+        assert_eq!(subject, "orders.created");
+        assert_eq!(payload.as_ref(), b"payload");
+
+        let sink_subject = "orders.copied".to_string();
+        let (subject, _payload) = to_publish_message(&entity, Some(&sink_subject)).unwrap();
+        assert_eq!(subject, "orders.copied");
+
+        let mut record = build_nats_record(0, "", b"payload", 0).unwrap();
+        let entity = nats_message::Entity::parse(record.as_buffer()).unwrap();
+        assert!(to_publish_message(&entity, None).is_err());
+    }
+}
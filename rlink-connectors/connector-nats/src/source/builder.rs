@@ -0,0 +1,151 @@
+use std::convert::TryFrom;
+
+use async_nats::jetstream::consumer::DeliverPolicy;
+use rlink::core::element::FnSchema;
+use rlink::core::properties::{Properties, PARALLELISM};
+
+use crate::buffer_gen::nats_message;
+use crate::source::deserializer::{
+    DefaultNatsRecordDeserializer, DefaultNatsRecordDeserializerBuilder,
+    NatsRecordDeserializerBuilder,
+};
+use crate::{
+    NatsInputFormat, BUFFER_SIZE, DELIVER_POLICY, DURABLE_NAME, INPUT_FORMAT_FN_NAME_DEFAULT, NATS,
+    SERVERS, SOURCE_CHANNEL_SIZE, STREAM, SUBJECTS,
+};
+
+#[derive(Debug)]
+pub struct NatsInputFormatBuilder {
+    fn_name: Option<String>,
+    parallelism: u16,
+    servers: Vec<String>,
+    stream: String,
+    subjects: Vec<String>,
+    durable_name: String,
+    buffer_size: Option<usize>,
+    deliver_policy: DeliverPolicy,
+}
+
+impl NatsInputFormatBuilder {
+    pub fn new(servers: Vec<String>, stream: &str, durable_name: &str, parallelism: u16) -> Self {
+        NatsInputFormatBuilder {
+            fn_name: None,
+            parallelism,
+            servers,
+            stream: stream.to_string(),
+            subjects: vec![],
+            durable_name: durable_name.to_string(),
+            buffer_size: None,
+            deliver_policy: DeliverPolicy::New,
+        }
+    }
+
+    pub fn fn_name(mut self, name: &str) -> Self {
+        self.fn_name = Some(name.to_string());
+        self
+    }
+
+    /// Consume the subjects of the stream by a consumer each, the whole stream by default
+    pub fn subjects(mut self, subjects: Vec<String>) -> Self {
+        self.subjects = subjects;
+        self
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Where to start when the consumers are created by the job, the new messages by default
+    pub fn deliver_policy(mut self, deliver_policy: DeliverPolicy) -> Self {
+        self.deliver_policy = deliver_policy;
+        self
+    }
+
+    pub fn build(
+        self,
+        deserializer_builder: Option<Box<dyn NatsRecordDeserializerBuilder>>,
+    ) -> NatsInputFormat {
+        info!("build nats source with: {:?}", &self);
+
+        let fn_name = self
+            .fn_name
+            .unwrap_or(INPUT_FORMAT_FN_NAME_DEFAULT.to_string());
+        let buffer_size = self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE);
+
+        let deserializer_builder = deserializer_builder.unwrap_or_else(|| {
+            let deserializer_builder: Box<dyn NatsRecordDeserializerBuilder> =
+                Box::new(DefaultNatsRecordDeserializerBuilder::<
+                    DefaultNatsRecordDeserializer,
+                >::new(FnSchema::from(
+                    &nats_message::FIELD_METADATA,
+                )));
+
+            deserializer_builder
+        });
+
+        NatsInputFormat::new(
+            self.servers,
+            self.stream,
+            self.subjects,
+            self.durable_name,
+            self.deliver_policy,
+            buffer_size,
+            deserializer_builder,
+            self.parallelism,
+            fn_name,
+        )
+    }
+}
+
+impl TryFrom<Properties> for NatsInputFormatBuilder {
+    type Error = anyhow::Error;
+
+    fn try_from(properties: Properties) -> Result<Self, Self::Error> {
+        let parallelism = properties.get_u16(PARALLELISM)?;
+
+        let nats_properties = properties.to_sub_properties(NATS);
+        let servers = split_list(nats_properties.get_string(SERVERS)?.as_str());
+
+        let stream = properties.get_string(STREAM)?;
+        let durable_name = properties.get_string(DURABLE_NAME)?;
+
+        let mut builder = NatsInputFormatBuilder::new(
+            servers,
+            stream.as_str(),
+            durable_name.as_str(),
+            parallelism,
+        );
+
+        builder = builder.fn_name(properties.name());
+
+        if let Ok(subjects) = properties.get_string(SUBJECTS) {
+            builder = builder.subjects(split_list(subjects.as_str()));
+        }
+
+        if let Ok(buffer_size) = properties.get_usize(BUFFER_SIZE) {
+            builder = builder.buffer_size(buffer_size);
+        }
+
+        if let Ok(deliver_policy) = properties.get_string(DELIVER_POLICY) {
+            let deliver_policy = match deliver_policy.as_str() {
+                "all" => DeliverPolicy::All,
+                "last" => DeliverPolicy::Last,
+                "new" => DeliverPolicy::New,
+                _ => return Err(anyhow!("unknown deliver policy {}", deliver_policy)),
+            };
+            builder = builder.deliver_policy(deliver_policy);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// split the comma separated list, the empty items are skipped
+pub(crate) fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect()
+}
@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use async_nats::{Client, Subject};
+use bytes::Bytes;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::runtime::CheckpointId;
+
+#[derive(Debug, Clone, Default)]
+pub struct NatsCheckpointFunction {
+    pub(crate) state: NatsSourceState,
+    ack_committer: NatsAckCommitter,
+    pub(crate) restore_error: Option<String>,
+}
+
+impl NatsCheckpointFunction {
+    pub fn state(&self) -> &NatsSourceState {
+        &self.state
+    }
+
+    /// ack the messages by the `client` once the checkpoints are complete
+    pub(crate) fn with_client(&mut self, client: Client) {
+        self.ack_committer.client = Some(client);
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for NatsCheckpointFunction {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        info!("Checkpoint initialize, context: {:?}", context);
+
+        let handle = match handle {
+            Some(handle) if !context.checkpoint_id.is_default() => handle,
+            _ => return,
+        };
+
+        match self.state.update_from_snapshot(handle.handle.as_str()) {
+            Ok(()) => info!(
+                "load state value from checkpoint({:?}): {:?}",
+                context.checkpoint_id, handle.handle
+            ),
+            Err(e) => self.restore_error = Some(e.to_string()),
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        let handle = self.state.snapshot();
+        debug!("Checkpoint snapshot: {:?}, context: {:?}", handle, context);
+
+        self.ack_committer
+            .commit(
+                context.checkpoint_id,
+                self.state.ack_positions(),
+                context.completed_checkpoint_id,
+            )
+            .await;
+
+        Some(CheckpointHandle { handle })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SequenceSnapshot {
+    consumer: String,
+    sequence: Option<u64>,
+}
+
+/// The stream sequences of the last emitted messages of all the consumers assigned to a task
+#[derive(Debug, Clone, Default)]
+pub struct NatsSourceState {
+    recorders: Arc<Mutex<BTreeMap<String, NatsSourceStateRecorder>>>,
+}
+
+impl NatsSourceState {
+    /// get or register the recorder of the consumer
+    pub fn recorder(&self, consumer: &str) -> NatsSourceStateRecorder {
+        let mut recorders = self.recorders.lock().unwrap();
+        recorders
+            .entry(consumer.to_string())
+            .or_insert_with(|| NatsSourceStateRecorder::new(consumer))
+            .clone()
+    }
+
+    pub fn get(&self, consumer: &str) -> Option<u64> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders.get(consumer).and_then(|recorder| recorder.get())
+    }
+
+    /// the last emitted messages to ack, `(consumer, sequence, reply)`, the restored sequences
+    /// have no reply subjects to ack
+    pub(crate) fn ack_positions(&self) -> Vec<(String, u64, Subject)> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders
+            .values()
+            .filter_map(|recorder| {
+                recorder
+                    .ack_position()
+                    .map(|(sequence, reply)| (recorder.consumer.clone(), sequence, reply))
+            })
+            .collect()
+    }
+
+    /// The snapshot is an array of `SequenceSnapshot`
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let snapshots: Vec<SequenceSnapshot> = serde_json::from_str(snapshot_handle)?;
+        for snapshot in snapshots {
+            let recorder = self.recorder(snapshot.consumer.as_str());
+            if let Some(sequence) = snapshot.sequence {
+                recorder.update(sequence, None);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> String {
+        let recorders = self.recorders.lock().unwrap();
+        let snapshots: Vec<SequenceSnapshot> = recorders
+            .values()
+            .map(|recorder| SequenceSnapshot {
+                consumer: recorder.consumer.clone(),
+                sequence: recorder.get(),
+            })
+            .collect();
+        serde_json::to_string(&snapshots).unwrap()
+    }
+}
+
+/// The stream sequence and the reply subject of a message
+type Position = (u64, Option<Subject>);
+
+#[derive(Debug, Clone)]
+pub struct NatsSourceStateRecorder {
+    consumer: String,
+    /// the position of the last emitted message
+    position: Arc<Mutex<Option<Position>>>,
+}
+
+impl NatsSourceStateRecorder {
+    pub fn new(consumer: &str) -> Self {
+        NatsSourceStateRecorder {
+            consumer: consumer.to_string(),
+            position: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn update(&self, sequence: u64, reply: Option<Subject>) {
+        *self.position.lock().unwrap() = Some((sequence, reply));
+    }
+
+    pub fn get(&self) -> Option<u64> {
+        self.position
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(sequence, _reply)| *sequence)
+    }
+
+    fn ack_position(&self) -> Option<(u64, Subject)> {
+        match self.position.lock().unwrap().as_ref() {
+            Some((sequence, Some(reply))) => Some((*sequence, reply.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Ack the checkpointed messages to the consumers with the `All` ack policy, which acks all the
+/// messages before, so that the consumers are advanced and their pending messages can be
+/// monitored by the nats tools.
+///
+/// The messages of a checkpoint are acked once the checkpoint is known to be complete, that is
+/// a later `Barrier` carries it as the completed checkpoint.
+#[derive(Debug, Clone, Default)]
+struct NatsAckCommitter {
+    client: Option<Client>,
+    /// checkpoint_id -> the last emitted messages, `(consumer, sequence, reply)`
+    pending_acks: BTreeMap<u64, Vec<(String, u64, Subject)>>,
+}
+
+impl NatsAckCommitter {
+    async fn commit(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        ack_positions: Vec<(String, u64, Subject)>,
+        completed_checkpoint_id: Option<CheckpointId>,
+    ) {
+        if !ack_positions.is_empty() {
+            self.pending_acks.insert(checkpoint_id.0, ack_positions);
+        }
+
+        let ack_positions = match completed_checkpoint_id {
+            Some(completed_checkpoint_id) => {
+                take_completed(&mut self.pending_acks, completed_checkpoint_id)
+            }
+            None => None,
+        };
+
+        let client = match &self.client {
+            Some(client) => client,
+            None => return,
+        };
+        for (consumer, sequence, reply) in ack_positions.unwrap_or_default() {
+            // the messages left pending are re-delivered after the ack wait
+            if let Err(e) = client.publish(reply, Bytes::from_static(b"+ACK")).await {
+                warn!(
+                    "ack the message {} of nats consumer {} error. {}",
+                    sequence, consumer, e
+                );
+            }
+        }
+    }
+}
+
+/// remove the pending items of the checkpoints covered by `completed_checkpoint_id`,
+/// return the item of the latest one
+fn take_completed<T>(
+    pending: &mut BTreeMap<u64, T>,
+    completed_checkpoint_id: CheckpointId,
+) -> Option<T> {
+    let mut completed = pending.split_off(&(completed_checkpoint_id.0 + 1));
+    std::mem::swap(pending, &mut completed);
+    completed
+        .into_iter()
+        .next_back()
+        .map(|(_checkpoint_id, item)| item)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use async_nats::Subject;
+    use rlink::core::runtime::CheckpointId;
+
+    use crate::source::checkpoint::{take_completed, NatsSourceState};
+
+    #[test]
+    pub fn take_completed_test() {
+        let mut pending_acks = BTreeMap::new();
+        pending_acks.insert(1, 100);
+        pending_acks.insert(2, 200);
+        pending_acks.insert(3, 300);
+
+        assert_eq!(take_completed(&mut pending_acks, CheckpointId(0)), None);
+        assert_eq!(
+            take_completed(&mut pending_acks, CheckpointId(2)),
+            Some(200)
+        );
+        assert_eq!(pending_acks.len(), 1);
+    }
+
+    #[test]
+    pub fn source_state_snapshot_test() {
+        let state = NatsSourceState::default();
+        state.recorder("orders-0").update(
+            10,
+            Some(Subject::from("$JS.ACK.orders.orders-0.1.10.10.0.0")),
+        );
+        state.recorder("orders-1");
+        assert_eq!(state.ack_positions().len(), 1);
+
+        let snapshot = state.snapshot();
+        let restored_state = NatsSourceState::default();
+        restored_state
+            .update_from_snapshot(snapshot.as_str())
+            .unwrap();
+        assert_eq!(restored_state.get("orders-0"), Some(10));
+        assert_eq!(restored_state.get("orders-1"), None);
+
+        // the restored messages are acked by the restored consumers
+        assert!(restored_state.ack_positions().is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use async_nats::jetstream;
+use async_nats::jetstream::consumer::{pull, AckPolicy, DeliverPolicy, PullConsumer};
+use futures::StreamExt;
+use rlink::channel::sender::ChannelSender;
+
+use crate::source::checkpoint::NatsSourceStateRecorder;
+use crate::source::deserializer::NatsRecordDeserializer;
+use crate::source::ConsumerRecord;
+
+/// The messages not acked are re-delivered after the wait, they are acked after the checkpoints
+/// complete, so the wait is longer than the checkpoint interval
+const ACK_WAIT: Duration = Duration::from_secs(600);
+
+pub(crate) struct NatsConsumerThread {
+    jetstream: jetstream::Context,
+    stream: String,
+    /// the filter subject of the consumer, empty to consume the whole stream
+    subject: String,
+    durable_name: String,
+    deliver_policy: DeliverPolicy,
+
+    sender: ChannelSender<ConsumerRecord>,
+    deserializer: Box<dyn NatsRecordDeserializer>,
+    state_recorder: NatsSourceStateRecorder,
+}
+
+impl NatsConsumerThread {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        jetstream: jetstream::Context,
+        stream: String,
+        subject: String,
+        durable_name: String,
+        deliver_policy: DeliverPolicy,
+        sender: ChannelSender<ConsumerRecord>,
+        deserializer: Box<dyn NatsRecordDeserializer>,
+        state_recorder: NatsSourceStateRecorder,
+    ) -> Self {
+        NatsConsumerThread {
+            jetstream,
+            stream,
+            subject,
+            durable_name,
+            deliver_policy,
+            sender,
+            deserializer,
+            state_recorder,
+        }
+    }
+
+    /// Consume until the stream is closed, the consumer is re-created after the last emitted
+    /// message when an error occurs
+    pub async fn run(mut self) {
+        loop {
+            match self.consume().await {
+                Ok(()) => {
+                    info!(
+                        "nats source of consumer {} is closed, the consumer exits",
+                        self.durable_name
+                    );
+                    return;
+                }
+                Err(e) => {
+                    error!("consume nats consumer {} error. {}", self.durable_name, e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    async fn create_consumer(&self, last_sequence: Option<u64>) -> anyhow::Result<PullConsumer> {
+        let stream = self
+            .jetstream
+            .get_stream(self.stream.as_str())
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        let deliver_policy = match last_sequence {
+            Some(sequence) => DeliverPolicy::ByStartSequence {
+                start_sequence: sequence + 1,
+            },
+            None => self.deliver_policy,
+        };
+        let config = pull::Config {
+            durable_name: Some(self.durable_name.clone()),
+            filter_subject: self.subject.clone(),
+            deliver_policy,
+            ack_policy: AckPolicy::All,
+            ack_wait: ACK_WAIT,
+            max_ack_pending: -1,
+            ..Default::default()
+        };
+
+        match last_sequence {
+            Some(_) => {
+                // the emitted messages may be ahead of or behind the acked position of the
+                // durable consumer, whose start position can't be updated, so it is re-created
+                if let Err(e) = stream.delete_consumer(self.durable_name.as_str()).await {
+                    warn!("delete nats consumer {} error. {}", self.durable_name, e);
+                }
+                stream.create_consumer(config).await.map_err(|e| anyhow!(e))
+            }
+            None => stream
+                .get_or_create_consumer(self.durable_name.as_str(), config)
+                .await
+                .map_err(|e| anyhow!(e)),
+        }
+    }
+
+    async fn consume(&mut self) -> anyhow::Result<()> {
+        let last_sequence = self.state_recorder.get();
+        let consumer = self.create_consumer(last_sequence).await?;
+        info!(
+            "create nats consumer success. stream: {}, subject: {}, consumer: {}, resume after: {:?}",
+            self.stream, self.subject, self.durable_name, last_sequence
+        );
+
+        let mut messages = consumer.messages().await.map_err(|e| anyhow!(e))?;
+        // the messages not acked in time are re-delivered
+        let mut last_sequence = last_sequence.unwrap_or_default();
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|e| anyhow!(e))?;
+            let (timestamp, sequence) = {
+                let info = message.info().map_err(|e| anyhow!(e))?;
+                let timestamp = info.published.unix_timestamp_nanos() / 1_000_000;
+                (timestamp as i64, info.stream_sequence)
+            };
+            if sequence <= last_sequence {
+                continue;
+            }
+            last_sequence = sequence;
+
+            if !self.emit(message, timestamp, sequence).await {
+                return Ok(());
+            }
+        }
+
+        Err(anyhow!("nats consumer stream is closed"))
+    }
+
+    /// Returns false if the stream is closed
+    async fn emit(&mut self, message: jetstream::Message, timestamp: i64, sequence: u64) -> bool {
+        let records = self.deserializer.deserialize(
+            timestamp,
+            message.subject.as_str(),
+            message.payload.as_ref(),
+            sequence,
+        );
+        for record in records {
+            let consumer_record = ConsumerRecord::new(
+                record,
+                sequence,
+                message.reply.clone(),
+                self.state_recorder.clone(),
+            );
+            if self.sender.send(consumer_record).await.is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,226 @@
+use std::marker::PhantomData;
+
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{FnSchema, Record};
+use serde_json::Value;
+
+use crate::build_nats_record;
+
+pub mod json;
+
+pub use json::JsonNatsRecordDeserializer;
+
+pub trait NatsRecordDeserializer: Sync + Send {
+    fn deserialize(
+        &mut self,
+        timestamp: i64,
+        subject: &str,
+        payload: &[u8],
+        sequence: u64,
+    ) -> Vec<Record>;
+}
+
+pub trait NatsRecordDeserializerBuilder: Send + Sync {
+    fn build(&self) -> Box<dyn NatsRecordDeserializer>;
+    fn schema(&self) -> FnSchema;
+}
+
+/// Wrap the message in the `nats_message` entity
+#[derive(Default)]
+pub struct DefaultNatsRecordDeserializer {}
+
+impl NatsRecordDeserializer for DefaultNatsRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        timestamp: i64,
+        subject: &str,
+        payload: &[u8],
+        sequence: u64,
+    ) -> Vec<Record> {
+        let record = build_nats_record(timestamp, subject, payload, sequence)
+            .expect("nats message writer to Record error");
+        vec![record]
+    }
+}
+
+pub struct DefaultNatsRecordDeserializerBuilder<T>
+where
+    T: Default + NatsRecordDeserializer + 'static,
+{
+    a: PhantomData<T>,
+    schema: FnSchema,
+}
+
+impl<T> DefaultNatsRecordDeserializerBuilder<T>
+where
+    T: Default + NatsRecordDeserializer + 'static,
+{
+    pub fn new(schema: FnSchema) -> Self {
+        DefaultNatsRecordDeserializerBuilder {
+            a: PhantomData,
+            schema,
+        }
+    }
+}
+
+impl<T> NatsRecordDeserializerBuilder for DefaultNatsRecordDeserializerBuilder<T>
+where
+    T: Default + NatsRecordDeserializer + 'static,
+{
+    fn build(&self) -> Box<dyn NatsRecordDeserializer> {
+        let t: Box<dyn NatsRecordDeserializer> = Box::new(T::default());
+        t
+    }
+
+    fn schema(&self) -> FnSchema {
+        self.schema.clone()
+    }
+}
+
+/// Build the deserializers mapping the payload onto a `Schema` by cloning the prototype,
+/// see `JsonNatsRecordDeserializer`
+pub struct SchemaNatsRecordDeserializerBuilder<T>
+where
+    T: Clone + NatsRecordDeserializer + 'static,
+{
+    deserializer: T,
+    schema: Schema,
+}
+
+impl<T> SchemaNatsRecordDeserializerBuilder<T>
+where
+    T: Clone + NatsRecordDeserializer + 'static,
+{
+    pub fn new(deserializer: T, schema: Schema) -> Self {
+        SchemaNatsRecordDeserializerBuilder {
+            deserializer,
+            schema,
+        }
+    }
+}
+
+impl<T> NatsRecordDeserializerBuilder for SchemaNatsRecordDeserializerBuilder<T>
+where
+    T: Clone + NatsRecordDeserializer + 'static,
+{
+    fn build(&self) -> Box<dyn NatsRecordDeserializer> {
+        Box::new(self.deserializer.clone())
+    }
+
+    fn schema(&self) -> FnSchema {
+        FnSchema::Single(self.schema.clone())
+    }
+}
+
+/// Write the JSON values to a `Record` with the `schema`, the values are in the order of the
+/// schema fields, and the missing or null value is written as the default value of the field
+/// type. The nested object and array are written as JSON string.
+pub(crate) fn write_record(schema: &Schema, values: &[Option<&Value>]) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+
+    for (index, field) in schema.fields().iter().enumerate() {
+        let value = values.get(index).copied().flatten().unwrap_or(&Value::Null);
+        let rt = match field.data_type() {
+            DataType::Boolean => writer.set_bool(to_bool(value)?),
+            DataType::Int8 => writer.set_i8(to_i64(value)? as i8),
+            DataType::UInt8 => writer.set_u8(to_u64(value)? as u8),
+            DataType::Int16 => writer.set_i16(to_i64(value)? as i16),
+            DataType::UInt16 => writer.set_u16(to_u64(value)? as u16),
+            DataType::Int32 => writer.set_i32(to_i64(value)? as i32),
+            DataType::UInt32 => writer.set_u32(to_u64(value)? as u32),
+            DataType::Int64 => writer.set_i64(to_i64(value)?),
+            DataType::UInt64 => writer.set_u64(to_u64(value)?),
+            DataType::Float32 => writer.set_f32(to_f64(value)? as f32),
+            DataType::Float64 => writer.set_f64(to_f64(value)?),
+            DataType::Binary => writer.set_binary(to_string(value).as_bytes()),
+            DataType::String => writer.set_str(to_string(value).as_str()),
+        };
+        rt.map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+    }
+
+    Ok(record)
+}
+
+fn to_i64(value: &Value) -> anyhow::Result<i64> {
+    match value {
+        Value::Null => Ok(0),
+        Value::Bool(v) => Ok(*v as i64),
+        Value::Number(v) => v
+            .as_i64()
+            .or_else(|| v.as_f64().map(|v| v as i64))
+            .ok_or(anyhow!("{} can't be converted to integer", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => Err(anyhow!("{} can't be converted to integer", value)),
+    }
+}
+
+fn to_u64(value: &Value) -> anyhow::Result<u64> {
+    match value {
+        Value::Number(v) if v.is_u64() => Ok(v.as_u64().unwrap()),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as u64),
+    }
+}
+
+fn to_f64(value: &Value) -> anyhow::Result<f64> {
+    match value {
+        Value::Number(v) => v
+            .as_f64()
+            .ok_or(anyhow!("{} can't be converted to float", v)),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v as f64),
+    }
+}
+
+fn to_bool(value: &Value) -> anyhow::Result<bool> {
+    match value {
+        Value::Bool(v) => Ok(*v),
+        Value::String(v) => v.trim().parse().map_err(|e| anyhow!("{}: {}", e, v)),
+        _ => to_i64(value).map(|v| v != 0),
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(v) => v.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use serde_json::json;
+
+    use crate::source::deserializer::write_record;
+
+    #[test]
+    pub fn write_record_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+            Field::new("score", DataType::Float64),
+            Field::new("valid", DataType::Boolean),
+            Field::new("tags", DataType::String),
+        ]);
+
+        let id = json!("10");
+        let name = json!("rlink");
+        let score = json!(3);
+        let tags = json!(["a", "b"]);
+        let values = vec![Some(&id), Some(&name), Some(&score), None, Some(&tags)];
+        let mut record = write_record(&schema, values.as_slice()).unwrap();
+
+        let reader = record.as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_i64(0).unwrap(), 10);
+        assert_eq!(reader.get_str(1).unwrap(), "rlink");
+        assert_eq!(reader.get_f64(2).unwrap(), 3.0);
+        assert!(!reader.get_bool(3).unwrap());
+        assert_eq!(reader.get_str(4).unwrap(), r#"["a","b"]"#);
+
+        let values = vec![Some(&tags)];
+        assert!(write_record(&schema, values.as_slice()).is_err());
+    }
+}
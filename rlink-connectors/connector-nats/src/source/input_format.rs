@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use async_nats::jetstream;
+use async_nats::jetstream::consumer::DeliverPolicy;
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::FnSchema;
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, NamedFunction, SendableElementStream,
+};
+use rlink::core::properties::Properties;
+use rlink::metrics::Tag;
+
+use crate::source::checkpoint::NatsCheckpointFunction;
+use crate::source::consumer::NatsConsumerThread;
+use crate::source::deserializer::NatsRecordDeserializerBuilder;
+use crate::source::stream::NatsRecordStream;
+use crate::source::ConsumerRecord;
+
+/// Depending on whether the task has `InputSplit`, and whether the client needs to be created
+const CREATE_NATS_CONNECTION: &str = "create_nats_connection";
+
+/// Consume the stream with a durable pull consumer per filter subject, or a single one of the
+/// whole stream if there is no subject, each consumer is run by a task. The stream sequence of
+/// the last emitted message of each consumer is checkpointed, the recovered task re-creates the
+/// consumer after the checkpointed sequence, and the messages of the completed checkpoints are
+/// acked.
+pub struct NatsInputFormat {
+    name: String,
+    parallelism: u16,
+
+    servers: Vec<String>,
+    stream: String,
+    subjects: Vec<String>,
+    durable_name: String,
+    deliver_policy: DeliverPolicy,
+
+    task_subject: String,
+    task_durable_name: String,
+    /// the task has no consumer if the parallelism is greater than the number of subjects
+    create_connection: bool,
+    /// keep the idle task's stream open until the job is stopped
+    idle_handover: Option<ChannelSender<ConsumerRecord>>,
+
+    buffer_size: usize,
+
+    tags: Vec<Tag>,
+
+    deserializer_builder: Arc<dyn NatsRecordDeserializerBuilder>,
+    schema: FnSchema,
+
+    jetstream: Option<jetstream::Context>,
+    checkpoint: Option<NatsCheckpointFunction>,
+}
+
+impl NatsInputFormat {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        servers: Vec<String>,
+        stream: String,
+        subjects: Vec<String>,
+        durable_name: String,
+        deliver_policy: DeliverPolicy,
+        buffer_size: usize,
+        deserializer_builder: Box<dyn NatsRecordDeserializerBuilder>,
+        parallelism: u16,
+        fn_name: String,
+    ) -> Self {
+        let schema = deserializer_builder.schema();
+        NatsInputFormat {
+            name: fn_name,
+            parallelism,
+            servers,
+            stream,
+            subjects,
+            durable_name,
+            deliver_policy,
+            task_subject: "".to_string(),
+            task_durable_name: "".to_string(),
+            create_connection: true,
+            idle_handover: None,
+            buffer_size,
+            tags: vec![],
+            deserializer_builder: Arc::from(deserializer_builder),
+            schema,
+            jetstream: None,
+            checkpoint: None,
+        }
+    }
+
+    fn start_consumer(&mut self, handover: ChannelSender<ConsumerRecord>) {
+        let checkpoint = self.checkpoint.as_ref().unwrap();
+        let state_recorder = checkpoint.state().recorder(self.task_durable_name.as_str());
+
+        let consumer = NatsConsumerThread::new(
+            self.jetstream.clone().unwrap(),
+            self.stream.clone(),
+            self.task_subject.clone(),
+            self.task_durable_name.clone(),
+            self.deliver_policy,
+            handover,
+            self.deserializer_builder.build(),
+            state_recorder,
+        );
+        tokio::spawn(consumer.run());
+    }
+}
+
+impl NamedFunction for NatsInputFormat {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+#[async_trait]
+impl InputFormat for NatsInputFormat {
+    async fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
+        info!("nats source open");
+
+        self.checkpoint = Some(NatsCheckpointFunction::default());
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.checkpoint.as_ref().unwrap().restore_error {
+            return Err(core::Error::from(format!(
+                "restore nats source error. {}",
+                e
+            )));
+        }
+
+        self.task_subject = input_split.properties().get_string("subject")?;
+        self.task_durable_name = input_split.properties().get_string("durable_name")?;
+        self.create_connection = input_split
+            .properties()
+            .get_bool(CREATE_NATS_CONNECTION)
+            .unwrap_or(true);
+        self.tags.push(Tag::new("stream", self.stream.as_str()));
+        self.tags
+            .push(Tag::new("subject", self.task_subject.as_str()));
+
+        if self.create_connection {
+            let client = async_nats::connect(&self.servers)
+                .await
+                .map_err(|e| core::Error::from(format!("connect nats error. {}", e)))?;
+            self.checkpoint
+                .as_mut()
+                .unwrap()
+                .with_client(client.clone());
+            self.jetstream = Some(jetstream::new(client));
+        }
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let (sender, receiver) =
+            named_channel("NatsSource_Handover", self.tags.clone(), self.buffer_size);
+
+        if self.create_connection {
+            self.start_consumer(sender);
+        } else {
+            info!("nats source task has no subject to consume");
+            self.idle_handover = Some(sender);
+        }
+
+        Box::pin(NatsRecordStream::new(receiver))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        self.schema.clone()
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for NatsInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        self.checkpoint
+            .as_mut()
+            .unwrap()
+            .initialize_state(context, handle)
+            .await;
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        match self.checkpoint.as_mut() {
+            Some(checkpoint) => checkpoint.snapshot_state(context).await,
+            None => None,
+        }
+    }
+}
+
+impl InputSplitSource for NatsInputFormat {
+    /// One split per subject, the extra splits of the idle tasks are copies of the subjects'
+    /// ones without the connection
+    fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        let subjects = if self.subjects.is_empty() {
+            vec!["".to_string()]
+        } else {
+            self.subjects.clone()
+        };
+        if subjects.len() > min_num_splits as usize {
+            return Err(core::Error::from(format!(
+                "the parallelism {} is less than the number of nats subjects {}",
+                min_num_splits,
+                subjects.len()
+            )));
+        }
+
+        let mut input_splits = Vec::with_capacity(min_num_splits as usize);
+        for index in 0..min_num_splits {
+            let subject_index = index as usize % subjects.len();
+
+            let mut properties = Properties::new();
+            properties.set_str("subject", subjects[subject_index].as_str());
+            properties.set_str(
+                "durable_name",
+                format!("{}-{}", self.durable_name, subject_index).as_str(),
+            );
+            properties.set_bool(CREATE_NATS_CONNECTION, (index as usize) < subjects.len());
+            input_splits.push(InputSplit::new(index, properties));
+        }
+
+        Ok(input_splits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_nats::jetstream::consumer::DeliverPolicy;
+    use rlink::core::element::FnSchema;
+    use rlink::core::function::InputSplitSource;
+
+    use crate::buffer_gen::nats_message;
+    use crate::source::deserializer::{
+        DefaultNatsRecordDeserializer, DefaultNatsRecordDeserializerBuilder,
+    };
+    use crate::NatsInputFormat;
+
+    fn input_format(subjects: Vec<String>) -> NatsInputFormat {
+        NatsInputFormat::new(
+            vec!["nats://localhost:4222".to_string()],
+            "orders".to_string(),
+            subjects,
+            "rlink".to_string(),
+            DeliverPolicy::All,
+            10,
+            Box::new(DefaultNatsRecordDeserializerBuilder::<
+                DefaultNatsRecordDeserializer,
+            >::new(FnSchema::from(
+                &nats_message::FIELD_METADATA,
+            ))),
+            3,
+            "NatsInputFormat".to_string(),
+        )
+    }
+
+    #[test]
+    pub fn create_input_splits_test() {
+        let subjects = vec!["orders.created".to_string(), "orders.paid".to_string()];
+        let splits = input_format(subjects).create_input_splits(3).unwrap();
+        assert_eq!(splits.len(), 3);
+
+        let properties = splits[1].properties();
+        assert_eq!(properties.get_string("subject").unwrap(), "orders.paid");
+        assert_eq!(properties.get_string("durable_name").unwrap(), "rlink-1");
+
+        // the idle task shares the first consumer's split without the connection
+        let properties = splits[2].properties();
+        assert_eq!(properties.get_string("durable_name").unwrap(), "rlink-0");
+        assert!(!properties.get_bool("create_nats_connection").unwrap());
+
+        // the whole stream is consumed if there is no subject
+        let splits = input_format(vec![]).create_input_splits(1).unwrap();
+        assert_eq!(splits[0].properties().get_string("subject").unwrap(), "");
+
+        assert!(input_format(vec!["a".to_string(), "b".to_string()])
+            .create_input_splits(1)
+            .is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use async_nats::Subject;
+
+use crate::source::checkpoint::NatsSourceStateRecorder;
+
+pub mod builder;
+pub mod checkpoint;
+pub mod consumer;
+pub mod deserializer;
+pub mod input_format;
+pub mod stream;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ConsumerRecord {
+    record: rlink::core::element::Record,
+    /// the stream sequence of the message
+    sequence: u64,
+    /// the subject to ack the message
+    reply: Option<Subject>,
+    /// the state of the record's consumer, updated when the record is emitted
+    state_recorder: NatsSourceStateRecorder,
+}
+
+impl ConsumerRecord {
+    pub fn new(
+        record: rlink::core::element::Record,
+        sequence: u64,
+        reply: Option<Subject>,
+        state_recorder: NatsSourceStateRecorder,
+    ) -> Self {
+        ConsumerRecord {
+            record,
+            sequence,
+            reply,
+            state_recorder,
+        }
+    }
+}
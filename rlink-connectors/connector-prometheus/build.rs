@@ -0,0 +1,14 @@
+use serbuffer_gen::{Codegen, DataType::*, SchemaBuilder};
+
+fn main() {
+    Codegen::out_dir("buffer_gen")
+        .schema(
+            SchemaBuilder::new("PrometheusSample")
+                .field("timestamp", I64)
+                .field("name", STRING)
+                .field("labels", STRING)
+                .field("value", F64),
+        )
+        .gen()
+        .expect("buffer gen error");
+}
@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod proto;
+pub mod sink;
+pub mod source;
+
+pub mod buffer_gen {
+    include!(concat!(env!("OUT_DIR"), "/buffer_gen/mod.rs"));
+}
+
+pub use sink::PrometheusRemoteWriteOutputFormat;
+pub use source::input_format::PrometheusScrapeInputFormat;
+
+use rlink::core::element::Record;
+
+use crate::buffer_gen::prometheus_sample;
+
+/// Build the `prometheus_sample` record.
+///
+/// The `labels` is a JSON object of the label names and values, excluding the metric `name`.
+pub fn build_prometheus_sample_record(
+    timestamp: i64,
+    name: &str,
+    labels: &str,
+    value: f64,
+) -> Result<Record, std::io::Error> {
+    let sample = prometheus_sample::Entity {
+        timestamp,
+        name,
+        labels,
+        value,
+    };
+
+    // 28 = 8(len(name) + len(labels)) +
+    //      16(len(timestamp) + len(value)) +
+    //      4(place_holder)
+    let capacity = name.len() + labels.len() + 28;
+    let mut record = Record::with_capacity(capacity);
+
+    sample.to_buffer(record.as_buffer()).unwrap();
+
+    Ok(record)
+}
@@ -0,0 +1,35 @@
+//! The messages of the Prometheus remote write protocol, `prometheus.WriteRequest` of
+//! `prompb/remote.proto`, the metadata and exemplars are not supported.
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimeSeries {
+    /// sorted by the label names
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    /// sorted by the timestamps
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Label {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Sample {
+    #[prost(double, tag = "1")]
+    pub value: f64,
+    /// milliseconds since the epoch
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+}
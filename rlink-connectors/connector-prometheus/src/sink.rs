@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use hyper::{Body, Client, Request, StatusCode};
+use prost::Message;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{BufferReader, Element, FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+use rlink::core::window::TWindow;
+use rlink::utils::date_time::current_timestamp_millis;
+
+use crate::proto::{Label, Sample, TimeSeries, WriteRequest};
+
+const BATCH_SIZE_DEFAULT: usize = 1000;
+const MAX_RETRIES_DEFAULT: usize = 3;
+const RETRY_BACKOFF_DEFAULT: Duration = Duration::from_millis(500);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+const TIMEOUT_DEFAULT: Duration = Duration::from_secs(30);
+
+const METRIC_NAME_LABEL: &str = "__name__";
+
+/// The columns resolved to the field indexes
+struct ResolvedColumns {
+    /// `(index, label name)`
+    labels: Vec<(usize, String)>,
+    /// `(index, metric name)`
+    values: Vec<(usize, String)>,
+    timestamp: Option<usize>,
+}
+
+/// The samples of the series, grouped by the labels
+#[derive(Default)]
+struct SeriesBatch {
+    series: HashMap<Vec<(String, String)>, Vec<Sample>>,
+    samples: usize,
+}
+
+impl SeriesBatch {
+    fn append(&mut self, labels: Vec<(String, String)>, sample: Sample) {
+        self.series.entry(labels).or_default().push(sample);
+        self.samples += 1;
+    }
+
+    fn take_write_request(&mut self) -> WriteRequest {
+        self.samples = 0;
+        let timeseries = self
+            .series
+            .drain()
+            .map(|(mut labels, mut samples)| {
+                labels.sort();
+                samples.sort_by_key(|sample| sample.timestamp);
+                TimeSeries {
+                    labels: labels
+                        .into_iter()
+                        .map(|(name, value)| Label { name, value })
+                        .collect(),
+                    samples,
+                }
+            })
+            .collect();
+        WriteRequest { timeseries }
+    }
+}
+
+/// Write the rows as the samples of Prometheus by the remote write protocol, to Prometheus or
+/// the compatible storages such as VictoriaMetrics, Thanos and Cortex.
+///
+/// Each of the `value_columns` is a metric of the same name with the `metric_prefix`, and the
+/// `label_columns` are the labels of the same names. The sample timestamp is the `timestamp_column`
+/// if set, otherwise the end of the window of the record, such as the output of
+/// `window().reduce()`, or the current time if the record is not of a window.
+///
+/// The samples are sent in snappy-compressed protobuf batches of at most `batch_size` samples,
+/// and on every checkpoint, so the samples before the checkpoint are written at least once. The
+/// requests failed with connection errors, `429` and `5xx` are retried with exponential backoff
+/// until written, the later records and checkpoints wait for them. The ones rejected with other
+/// `4xx`, such as the out of order samples replayed after recovery, are dropped as Prometheus
+/// does. The records failed to convert to the samples are discarded, and fail the next
+/// checkpoint. Only the `http` urls are supported.
+#[derive(NamedFunction)]
+pub struct PrometheusRemoteWriteOutputFormat {
+    url: String,
+    label_columns: Vec<String>,
+    value_columns: Vec<String>,
+    metric_prefix: String,
+    static_labels: Vec<(String, String)>,
+    timestamp_column: Option<String>,
+    headers: Vec<(String, String)>,
+    batch_size: usize,
+    max_retries: usize,
+    retry_backoff: Duration,
+    timeout: Duration,
+
+    schema: Schema,
+    columns: Option<ResolvedColumns>,
+    header_values: Vec<(HeaderName, HeaderValue)>,
+    client: Option<Client<HttpConnector>>,
+    batch: SeriesBatch,
+    /// the records failed to convert since the last checkpoint, they are discarded and the next
+    /// checkpoint is declined with the first error
+    discarded: usize,
+    discard_error: Option<String>,
+}
+
+impl PrometheusRemoteWriteOutputFormat {
+    /// Write to the remote write `url`, such as `http://prometheus:9090/api/v1/write`
+    pub fn new(url: &str, label_columns: Vec<String>, value_columns: Vec<String>) -> Self {
+        PrometheusRemoteWriteOutputFormat {
+            url: url.to_string(),
+            label_columns,
+            value_columns,
+            metric_prefix: "".to_string(),
+            static_labels: vec![],
+            timestamp_column: None,
+            headers: vec![],
+            batch_size: BATCH_SIZE_DEFAULT,
+            max_retries: MAX_RETRIES_DEFAULT,
+            retry_backoff: RETRY_BACKOFF_DEFAULT,
+            timeout: TIMEOUT_DEFAULT,
+            schema: Schema::empty(),
+            columns: None,
+            header_values: vec![],
+            client: None,
+            batch: SeriesBatch::default(),
+            discarded: 0,
+            discard_error: None,
+        }
+    }
+
+    /// Prepend the prefix to the metric names
+    pub fn metric_prefix(mut self, metric_prefix: &str) -> Self {
+        self.metric_prefix = metric_prefix.to_string();
+        self
+    }
+
+    /// Add the label to all the series, such as `job`
+    pub fn static_label(mut self, name: &str, value: &str) -> Self {
+        self.static_labels
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// The milliseconds column of the sample timestamps, instead of the window end
+    pub fn timestamp_column(mut self, timestamp_column: &str) -> Self {
+        self.timestamp_column = Some(timestamp_column.to_string());
+        self
+    }
+
+    /// Add a header to the requests, such as `Authorization`
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// The max number of the samples of a request
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Retry the failed requests `max_retries` times, the delay begins with `backoff` and
+    /// doubles after each retry up to 60s. The request is retried again after the retries fail,
+    /// the later records and checkpoints wait for it
+    pub fn retry(mut self, max_retries: usize, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// The timeout of each request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn resolve(&self) -> anyhow::Result<ResolvedColumns> {
+        let index = |name: &str| {
+            self.schema
+                .fields()
+                .iter()
+                .position(|field| field.name().eq(name))
+                .ok_or(anyhow!("column `{}` not found in the schema", name))
+        };
+
+        let mut labels = Vec::with_capacity(self.label_columns.len());
+        for column in &self.label_columns {
+            if !is_valid_name(column.as_str(), false) {
+                return Err(anyhow!("invalid label name `{}`", column));
+            }
+            labels.push((index(column)?, column.clone()));
+        }
+
+        let mut values = Vec::with_capacity(self.value_columns.len());
+        for column in &self.value_columns {
+            let value = index(column)?;
+            if let DataType::Binary | DataType::String = self.schema.fields()[value].data_type() {
+                return Err(anyhow!("the value column `{}` is not a number", column));
+            }
+            let metric_name = format!("{}{}", self.metric_prefix, column);
+            if !is_valid_name(metric_name.as_str(), true) {
+                return Err(anyhow!("invalid metric name `{}`", metric_name));
+            }
+            values.push((value, metric_name));
+        }
+
+        let timestamp = match &self.timestamp_column {
+            Some(column) => {
+                let timestamp = index(column)?;
+                match self.schema.fields()[timestamp].data_type() {
+                    DataType::Int64 | DataType::UInt64 => Some(timestamp),
+                    _ => return Err(anyhow!("the timestamp column `{}` is not i64", column)),
+                }
+            }
+            None => None,
+        };
+
+        Ok(ResolvedColumns {
+            labels,
+            values,
+            timestamp,
+        })
+    }
+
+    /// Add the samples of the value columns of the record to the batch
+    fn append(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let window = record.trigger_window();
+        let columns = self.columns.as_ref().unwrap();
+        let fields = self.schema.fields();
+        let reader = record.as_reader(self.schema.as_type_ids());
+
+        let timestamp = match columns.timestamp {
+            Some(index) => match fields[index].data_type() {
+                DataType::UInt64 => reader.get_u64(index)? as i64,
+                _ => reader.get_i64(index)?,
+            },
+            None => match window {
+                Some(window) => window.max_timestamp() as i64,
+                None => current_timestamp_millis() as i64,
+            },
+        };
+
+        let mut labels = self.static_labels.clone();
+        for (index, name) in &columns.labels {
+            let value = to_label_value(&reader, *index, fields[*index].data_type())?;
+            labels.push((name.clone(), value));
+        }
+
+        for (index, metric_name) in &columns.values {
+            let value = to_sample_value(&reader, *index, fields[*index].data_type())?;
+            let mut labels = labels.clone();
+            labels.push((METRIC_NAME_LABEL.to_string(), metric_name.clone()));
+            self.batch.append(labels, Sample { value, timestamp });
+        }
+
+        Ok(())
+    }
+
+    /// Send the batch, it's retried with backoff until written or rejected
+    async fn flush(&mut self) {
+        if self.batch.samples == 0 {
+            return;
+        }
+
+        let samples = self.batch.samples;
+        let write_request = self.batch.take_write_request();
+        let body = match encode(&write_request) {
+            Ok(body) => body,
+            Err(e) => {
+                self.discard(samples, e);
+                return;
+            }
+        };
+
+        let mut retries = 0;
+        loop {
+            let e = match self.send(body.clone()).await {
+                Ok(()) => return,
+                Err(RemoteWriteError::Rejected(e)) => {
+                    warn!("drop {} samples rejected by the storage. {}", samples, e);
+                    return;
+                }
+                Err(RemoteWriteError::Retryable(e)) => e,
+            };
+
+            let delay = std::cmp::min(
+                self.retry_backoff
+                    .saturating_mul(2u32.saturating_pow(retries as u32)),
+                RETRY_BACKOFF_MAX,
+            );
+            if retries < self.max_retries {
+                warn!(
+                    "remote write error, retry {} after {:?}. {}",
+                    retries + 1,
+                    delay,
+                    e
+                );
+            } else {
+                error!(
+                    "remote write error after {} retries, retry {} samples again after {:?}. {}",
+                    self.max_retries, samples, delay, e
+                );
+            }
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+
+    async fn send(&self, body: Vec<u8>) -> Result<(), RemoteWriteError> {
+        let mut builder = Request::post(self.url.as_str())
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .header(CONTENT_ENCODING, "snappy")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0");
+        for (name, value) in &self.header_values {
+            builder = builder.header(name, value);
+        }
+        let req = builder
+            .body(Body::from(body))
+            .map_err(|e| RemoteWriteError::Retryable(anyhow!(e)))?;
+
+        let client = self.client.as_ref().unwrap();
+        let response = match tokio::time::timeout(self.timeout, client.request(req)).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(RemoteWriteError::Retryable(anyhow!(e))),
+            Err(_e) => {
+                return Err(RemoteWriteError::Retryable(anyhow!(
+                    "request {} timeout",
+                    self.url
+                )))
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap_or_default();
+        let e = anyhow!(
+            "remote write failed with {}. {}",
+            status,
+            String::from_utf8_lossy(body.as_ref())
+        );
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            Err(RemoteWriteError::Retryable(e))
+        } else {
+            Err(RemoteWriteError::Rejected(e))
+        }
+    }
+
+    fn discard(&mut self, records: usize, e: anyhow::Error) {
+        // surfaced as a failure of the next checkpoint
+        error!(
+            "remote write error, {} records are discarded. {}",
+            records, e
+        );
+        self.discarded += records;
+        if self.discard_error.is_none() {
+            self.discard_error = Some(e.to_string());
+        }
+    }
+
+    /// The error of the records discarded since the last call
+    fn take_discard_error(&mut self) -> Option<String> {
+        let discarded = std::mem::replace(&mut self.discarded, 0);
+        self.discard_error.take().map(|e| {
+            format!(
+                "{} records are discarded, the first error: {}",
+                discarded, e
+            )
+        })
+    }
+}
+
+enum RemoteWriteError {
+    /// the connection failures, timeouts, `429` and `5xx`
+    Retryable(anyhow::Error),
+    /// the other `4xx`, the samples are invalid or out of order
+    Rejected(anyhow::Error),
+}
+
+#[async_trait]
+impl OutputFormat for PrometheusRemoteWriteOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        // the client has no TLS
+        if !self.url.to_ascii_lowercase().starts_with("http://") {
+            return Err(core::Error::from(format!(
+                "only the `http://` urls are supported, but got {}",
+                self.url
+            )));
+        }
+
+        self.schema = context.input_schema.first().clone();
+        self.columns = Some(self.resolve()?);
+
+        for (name, _value) in &self.static_labels {
+            if !is_valid_name(name.as_str(), false) {
+                return Err(core::Error::from(format!("invalid label name `{}`", name)));
+            }
+        }
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| core::Error::from(format!("invalid header {}. {}", name, e)))?;
+            let value = HeaderValue::from_str(value.as_str())
+                .map_err(|e| core::Error::from(format!("invalid header {}. {}", name, e)))?;
+            self.header_values.push((name, value));
+        }
+
+        self.client = Some(Client::new());
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let mut record = element.into_record();
+        if let Err(e) = self.append(&mut record) {
+            self.discard(1, e);
+            return;
+        }
+
+        if self.batch.samples >= self.batch_size {
+            self.flush().await;
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush().await;
+        match self.take_discard_error() {
+            Some(e) => Err(core::Error::from(format!("remote write error. {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for PrometheusRemoteWriteOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        self.flush().await;
+        if let Some(e) = self.take_discard_error() {
+            context.decline(format!("remote write error. {}", e).as_str());
+        }
+        None
+    }
+}
+
+/// The protobuf of the request compressed by the snappy block format
+fn encode(write_request: &WriteRequest) -> anyhow::Result<Vec<u8>> {
+    let buf = write_request.encode_to_vec();
+    snap::raw::Encoder::new()
+        .compress_vec(buf.as_slice())
+        .map_err(|e| anyhow!("snappy compress error. {}", e))
+}
+
+/// The metric names match `[a-zA-Z_:][a-zA-Z0-9_:]*`, and the label names match
+/// `[a-zA-Z_][a-zA-Z0-9_]*`
+fn is_valid_name(name: &str, metric: bool) -> bool {
+    let valid_char = |c: char| c.is_ascii_alphabetic() || c == '_' || (metric && c == ':');
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if valid_char(c) => chars.all(|c| valid_char(c) || c.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+fn to_label_value(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> anyhow::Result<String> {
+    let value = match data_type {
+        DataType::Boolean => reader.get_bool(index)?.to_string(),
+        DataType::Int8 => reader.get_i8(index)?.to_string(),
+        DataType::UInt8 => reader.get_u8(index)?.to_string(),
+        DataType::Int16 => reader.get_i16(index)?.to_string(),
+        DataType::UInt16 => reader.get_u16(index)?.to_string(),
+        DataType::Int32 => reader.get_i32(index)?.to_string(),
+        DataType::UInt32 => reader.get_u32(index)?.to_string(),
+        DataType::Int64 => reader.get_i64(index)?.to_string(),
+        DataType::UInt64 => reader.get_u64(index)?.to_string(),
+        DataType::Float32 => reader.get_f32(index)?.to_string(),
+        DataType::Float64 => reader.get_f64(index)?.to_string(),
+        DataType::Binary => String::from_utf8_lossy(reader.get_binary(index)?).to_string(),
+        DataType::String => reader.get_str(index)?.to_string(),
+    };
+    Ok(value)
+}
+
+fn to_sample_value(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> anyhow::Result<f64> {
+    let value = match data_type {
+        DataType::Boolean => reader.get_bool(index)? as u8 as f64,
+        DataType::Int8 => reader.get_i8(index)? as f64,
+        DataType::UInt8 => reader.get_u8(index)? as f64,
+        DataType::Int16 => reader.get_i16(index)? as f64,
+        DataType::UInt16 => reader.get_u16(index)? as f64,
+        DataType::Int32 => reader.get_i32(index)? as f64,
+        DataType::UInt32 => reader.get_u32(index)? as f64,
+        DataType::Int64 => reader.get_i64(index)? as f64,
+        DataType::UInt64 => reader.get_u64(index)? as f64,
+        DataType::Float32 => reader.get_f32(index)? as f64,
+        DataType::Float64 => reader.get_f64(index)?,
+        DataType::Binary | DataType::String => return Err(anyhow!("the value is not a number")),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+    use rlink::core::window::{TimeWindow, Window};
+
+    use crate::proto::{Label, WriteRequest};
+    use crate::sink::{encode, is_valid_name, PrometheusRemoteWriteOutputFormat};
+
+    #[test]
+    pub fn write_request_test() {
+        let mut output_format = PrometheusRemoteWriteOutputFormat::new(
+            "http://localhost:9090/api/v1/write",
+            vec!["host".to_string()],
+            vec!["count".to_string(), "latency".to_string()],
+        )
+        .metric_prefix("http_")
+        .static_label("job", "rlink");
+        output_format.schema = Schema::new(vec![
+            Field::new("host", DataType::String),
+            Field::new("count", DataType::Int64),
+            Field::new("latency", DataType::Float64),
+        ]);
+        output_format.columns = Some(output_format.resolve().unwrap());
+
+        for (end, count) in [(2000, 2), (1000, 1)] {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(output_format.schema.as_type_ids());
+            writer.set_str("a").unwrap();
+            writer.set_i64(count).unwrap();
+            writer.set_f64(0.5).unwrap();
+            record.set_window_trigger(Window::TimeWindow(TimeWindow::new(end - 1000, end)));
+            output_format.append(&mut record).unwrap();
+        }
+        assert_eq!(output_format.batch.samples, 4);
+
+        let body = encode(&output_format.batch.take_write_request()).unwrap();
+        let buf = snap::raw::Decoder::new()
+            .decompress_vec(body.as_slice())
+            .unwrap();
+        let mut write_request = WriteRequest::decode(buf.as_slice()).unwrap();
+        write_request
+            .timeseries
+            .sort_by(|a, b| a.labels[0].value.cmp(&b.labels[0].value));
+        assert_eq!(write_request.timeseries.len(), 2);
+
+        let series = &write_request.timeseries[0];
+        let label = |name: &str, value: &str| Label {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(
+            series.labels,
+            vec![
+                label("__name__", "http_count"),
+                label("host", "a"),
+                label("job", "rlink")
+            ]
+        );
+        let samples: Vec<(i64, f64)> = series
+            .samples
+            .iter()
+            .map(|sample| (sample.timestamp, sample.value))
+            .collect();
+        assert_eq!(samples, vec![(1000, 1.0), (2000, 2.0)]);
+        assert_eq!(output_format.batch.samples, 0);
+
+        output_format.value_columns = vec!["host".to_string()];
+        assert!(output_format.resolve().is_err());
+    }
+
+    #[test]
+    pub fn valid_name_test() {
+        assert!(is_valid_name("http_requests:rate5m", true));
+        assert!(!is_valid_name("http_requests:rate5m", false));
+        assert!(!is_valid_name("5xx", false));
+        assert!(!is_valid_name("", false));
+    }
+}
@@ -0,0 +1,172 @@
+//! The parser of the Prometheus text exposition format, the `# HELP` and `# TYPE` lines are
+//! skipped, so the histograms and summaries are parsed as the plain series of their buckets,
+//! sums and counts.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ExpositionSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+    /// milliseconds since the epoch, the scrape time is used if absent
+    pub timestamp: Option<i64>,
+}
+
+pub(crate) fn parse(text: &str) -> anyhow::Result<Vec<ExpositionSample>> {
+    let mut samples = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let sample =
+            parse_line(line).map_err(|e| anyhow!("line {}: {}. {}", line_number + 1, line, e))?;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+fn parse_line(line: &str) -> anyhow::Result<ExpositionSample> {
+    let mut chars = line.chars().peekable();
+
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| *c != '{' && !c.is_whitespace()) {
+        name.push(c);
+    }
+    if name.is_empty() {
+        return Err(anyhow!("metric name not found"));
+    }
+
+    let labels = if chars.next_if_eq(&'{').is_some() {
+        parse_labels(&mut chars)?
+    } else {
+        vec![]
+    };
+
+    let rest: String = chars.collect();
+    let mut parts = rest.split_whitespace();
+    let value = match parts.next() {
+        Some(value) => parse_value(value)?,
+        None => return Err(anyhow!("value not found")),
+    };
+    let timestamp = match parts.next() {
+        Some(timestamp) => Some(timestamp.parse()?),
+        None => None,
+    };
+
+    Ok(ExpositionSample {
+        name,
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+/// Parse the `name="value",...}` after the `{`
+fn parse_labels(chars: &mut Peekable<Chars>) -> anyhow::Result<Vec<(String, String)>> {
+    let mut labels = Vec::new();
+    loop {
+        skip_whitespace(chars);
+        if chars.next_if_eq(&'}').is_some() {
+            return Ok(labels);
+        }
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            name.push(c);
+        }
+        skip_whitespace(chars);
+        if chars.next() != Some('=') {
+            return Err(anyhow!("`=` not found after label {}", name));
+        }
+        skip_whitespace(chars);
+        if chars.next() != Some('"') {
+            return Err(anyhow!("the value of label {} is not quoted", name));
+        }
+
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(c) => value.push(c),
+                    None => return Err(anyhow!("unclosed value of label {}", name)),
+                },
+                Some(c) => value.push(c),
+                None => return Err(anyhow!("unclosed value of label {}", name)),
+            }
+        }
+        labels.push((name, value));
+
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some('}') => return Ok(labels),
+            _ => return Err(anyhow!("unclosed labels")),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_value(value: &str) -> anyhow::Result<f64> {
+    match value {
+        "+Inf" | "Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        value => value
+            .parse()
+            .map_err(|e| anyhow!("invalid value {}. {}", value, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::exposition::parse;
+
+    #[test]
+    pub fn parse_test() {
+        let text = r#"
+# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method="post",code="200"} 1027 1395066363000
+http_requests_total{ method = "post" , code="400", } 3
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\""} 1.458255915e9
+http_request_duration_seconds_bucket{le="+Inf"} +Inf
+metric_without_labels 12.47
+"#;
+        let samples = parse(text).unwrap();
+        assert_eq!(samples.len(), 5);
+
+        assert_eq!(samples[0].name, "http_requests_total");
+        assert_eq!(
+            samples[0].labels,
+            vec![
+                ("method".to_string(), "post".to_string()),
+                ("code".to_string(), "200".to_string())
+            ]
+        );
+        assert_eq!(samples[0].value, 1027.0);
+        assert_eq!(samples[0].timestamp, Some(1395066363000));
+
+        assert_eq!(samples[1].labels[1].1, "400");
+        assert_eq!(samples[1].timestamp, None);
+
+        assert_eq!(samples[2].labels[0].1, r#"C:\DIR\FILE.TXT"#);
+        assert_eq!(samples[2].labels[1].1, "Cannot find file:\n\"FILE.TXT\"");
+        assert_eq!(samples[2].value, 1.458255915e9);
+
+        assert!(samples[3].value.is_infinite());
+        assert!(samples[4].labels.is_empty());
+
+        assert!(parse(r#"m{a="1" 1"#).is_err());
+        assert!(parse("m").is_err());
+        assert!(parse("m abc").is_err());
+    }
+}
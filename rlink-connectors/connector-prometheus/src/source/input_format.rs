@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use hyper::client::HttpConnector;
+use hyper::{Client, Uri};
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::core::properties::Properties;
+use rlink::metrics::Tag;
+use rlink::utils::date_time::current_timestamp_millis;
+use serde_json::{Map, Value};
+
+use crate::buffer_gen::prometheus_sample;
+use crate::build_prometheus_sample_record;
+use crate::source::exposition::{parse, ExpositionSample};
+use crate::source::stream::PrometheusSampleStream;
+
+const BUFFER_SIZE_DEFAULT: usize = 10000;
+const TIMEOUT_DEFAULT: Duration = Duration::from_secs(10);
+
+/// The label of the target, added to all the samples of the target
+const INSTANCE_LABEL: &str = "instance";
+/// The metric of the scrape health, `1` if the scrape succeeds, otherwise `0`
+const UP_METRIC: &str = "up";
+
+/// Scrape the metrics of the targets in the Prometheus text exposition format every `interval`,
+/// and emit the samples as the `prometheus_sample` records, the `labels` is a JSON object of the
+/// sample labels and the `instance` label of the target's address.
+///
+/// The targets are distributed among the tasks. Like Prometheus, an `up` sample is emitted for
+/// every scrape of a target. The scrapes are not checkpointed, the counters and gauges are read
+/// again after recovery.
+#[derive(NamedFunction)]
+pub struct PrometheusScrapeInputFormat {
+    targets: Vec<String>,
+    interval: Duration,
+    timeout: Duration,
+    parallelism: u16,
+    buffer_size: usize,
+
+    task_targets: Vec<String>,
+    tags: Vec<Tag>,
+    /// keep the idle task's stream open until the job is stopped
+    idle_handover: Option<ChannelSender<Record>>,
+}
+
+impl PrometheusScrapeInputFormat {
+    /// Scrape the `targets`, such as `http://node-exporter:9100/metrics`
+    pub fn new(targets: Vec<String>, interval: Duration) -> Self {
+        PrometheusScrapeInputFormat {
+            targets,
+            interval,
+            timeout: TIMEOUT_DEFAULT,
+            parallelism: 1,
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            task_targets: vec![],
+            tags: vec![],
+            idle_handover: None,
+        }
+    }
+
+    pub fn parallelism(mut self, parallelism: u16) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// The timeout of each scrape
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+}
+
+#[async_trait]
+impl InputFormat for PrometheusScrapeInputFormat {
+    async fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
+        let targets = input_split.properties().get_string("targets")?;
+        self.task_targets = targets
+            .split(',')
+            .filter(|target| !target.is_empty())
+            .map(|target| target.to_string())
+            .collect();
+
+        self.tags = context.task_id.to_tags();
+        info!("prometheus scrape source targets {:?}", self.task_targets);
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let (sender, receiver) = named_channel(
+            "PrometheusSource_Handover",
+            self.tags.clone(),
+            self.buffer_size,
+        );
+
+        if self.task_targets.is_empty() {
+            info!("prometheus scrape source task has no target to scrape");
+            self.idle_handover = Some(sender);
+        } else {
+            let scraper = Scraper {
+                client: Client::new(),
+                targets: self.task_targets.clone(),
+                interval: self.interval,
+                timeout: self.timeout,
+                sender,
+            };
+            tokio::spawn(scraper.run());
+        }
+
+        Box::pin(PrometheusSampleStream::new(receiver))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&prometheus_sample::FIELD_METADATA)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for PrometheusScrapeInputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
+
+impl InputSplitSource for PrometheusScrapeInputFormat {
+    /// The targets are assigned to the splits round-robin
+    fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        let mut input_splits = Vec::with_capacity(min_num_splits as usize);
+        for index in 0..min_num_splits {
+            let targets: Vec<&str> = self
+                .targets
+                .iter()
+                .enumerate()
+                .filter(|(i, _target)| i % min_num_splits as usize == index as usize)
+                .map(|(_i, target)| target.as_str())
+                .collect();
+
+            let mut properties = Properties::new();
+            properties.set_str("targets", targets.join(",").as_str());
+            input_splits.push(InputSplit::new(index, properties));
+        }
+        Ok(input_splits)
+    }
+}
+
+struct Scraper {
+    client: Client<HttpConnector>,
+    targets: Vec<String>,
+    interval: Duration,
+    timeout: Duration,
+    sender: ChannelSender<Record>,
+}
+
+impl Scraper {
+    async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            interval.tick().await;
+
+            for target in &self.targets {
+                let timestamp = current_timestamp_millis() as i64;
+                let instance = instance(target.as_str());
+                let (samples, up) = match self.scrape(target.as_str()).await {
+                    Ok(samples) => (samples, 1.0),
+                    Err(e) => {
+                        warn!("scrape {} error. {}", target, e);
+                        (vec![], 0.0)
+                    }
+                };
+
+                let up = ExpositionSample {
+                    name: UP_METRIC.to_string(),
+                    labels: vec![],
+                    value: up,
+                    timestamp: None,
+                };
+                for sample in samples.iter().chain(std::iter::once(&up)) {
+                    let record = to_record(sample, instance.as_str(), timestamp);
+                    if self.sender.send(record).await.is_err() {
+                        info!("prometheus scrape source is closed, the scraper exits");
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn scrape(&self, target: &str) -> anyhow::Result<Vec<ExpositionSample>> {
+        let uri: Uri = target.parse()?;
+        let response = tokio::time::timeout(self.timeout, self.client.get(uri))
+            .await
+            .map_err(|_e| anyhow!("timeout"))??;
+        if !response.status().is_success() {
+            return Err(anyhow!("response status {}", response.status()));
+        }
+
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        parse(std::str::from_utf8(body.as_ref())?)
+    }
+}
+
+/// The address of the target, or the target itself if it's not a url
+fn instance(target: &str) -> String {
+    target
+        .parse::<Uri>()
+        .ok()
+        .and_then(|uri| uri.authority().map(|authority| authority.to_string()))
+        .unwrap_or_else(|| target.to_string())
+}
+
+fn to_record(sample: &ExpositionSample, instance: &str, scrape_timestamp: i64) -> Record {
+    let mut labels = Map::with_capacity(sample.labels.len() + 1);
+    for (name, value) in &sample.labels {
+        labels.insert(name.clone(), Value::from(value.as_str()));
+    }
+    labels
+        .entry(INSTANCE_LABEL)
+        .or_insert_with(|| Value::from(instance));
+
+    build_prometheus_sample_record(
+        sample.timestamp.unwrap_or(scrape_timestamp),
+        sample.name.as_str(),
+        Value::Object(labels).to_string().as_str(),
+        sample.value,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rlink::core::function::InputSplitSource;
+    use serde_json::json;
+
+    use crate::buffer_gen::prometheus_sample;
+    use crate::source::exposition::ExpositionSample;
+    use crate::source::input_format::{instance, to_record};
+    use crate::PrometheusScrapeInputFormat;
+
+    #[test]
+    pub fn to_record_test() {
+        assert_eq!(instance("http://node:9100/metrics"), "node:9100");
+
+        let sample = ExpositionSample {
+            name: "http_requests_total".to_string(),
+            labels: vec![("method".to_string(), "post".to_string())],
+            value: 3.0,
+            timestamp: None,
+        };
+        let mut record = to_record(&sample, "node:9100", 1000);
+        let entity = prometheus_sample::Entity::parse(record.as_buffer()).unwrap();
+        assert_eq!(entity.timestamp, 1000);
+        assert_eq!(entity.name, "http_requests_total");
+        let labels: serde_json::Value = serde_json::from_str(entity.labels).unwrap();
+        assert_eq!(labels, json!({"instance": "node:9100", "method": "post"}));
+        assert_eq!(entity.value, 3.0);
+    }
+
+    #[test]
+    pub fn create_input_splits_test() {
+        let targets = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let input_format = PrometheusScrapeInputFormat::new(targets, Duration::from_secs(15));
+        let splits = input_format.create_input_splits(2).unwrap();
+        assert_eq!(splits[0].properties().get_string("targets").unwrap(), "a,c");
+        assert_eq!(splits[1].properties().get_string("targets").unwrap(), "b");
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod exposition;
+pub mod input_format;
+pub mod stream;
@@ -0,0 +1,32 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::{Element, Record};
+use rlink::core::function::ElementStream;
+
+/// Emit the samples of the scrapes as the `prometheus_sample` records
+pub struct PrometheusSampleStream {
+    receiver: ChannelReceiver<Record>,
+}
+
+impl PrometheusSampleStream {
+    pub(crate) fn new(receiver: ChannelReceiver<Record>) -> Self {
+        PrometheusSampleStream { receiver }
+    }
+}
+
+impl ElementStream for PrometheusSampleStream {}
+
+impl Stream for PrometheusSampleStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.as_mut().receiver.poll_recv(cx) {
+            Poll::Ready(Some(record)) => Poll::Ready(Some(Element::Record(record))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
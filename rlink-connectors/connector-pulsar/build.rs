@@ -0,0 +1,15 @@
+use serbuffer_gen::{Codegen, DataType::*, SchemaBuilder};
+
+fn main() {
+    Codegen::out_dir("buffer_gen")
+        .schema(
+            SchemaBuilder::new("PulsarMessage")
+                .field("timestamp", I64)
+                .field("key", BINARY)
+                .field("payload", BINARY)
+                .field("topic", STRING)
+                .field("message_id", STRING),
+        )
+        .gen()
+        .expect("buffer gen error");
+}
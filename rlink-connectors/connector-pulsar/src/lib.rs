@@ -0,0 +1,71 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod message_id;
+pub mod sink;
+pub mod source;
+
+pub mod buffer_gen {
+    include!(concat!(env!("OUT_DIR"), "/buffer_gen/mod.rs"));
+}
+
+pub use message_id::PulsarMessageId;
+pub use sink::output_format::PulsarOutputFormat;
+pub use source::input_format::PulsarInputFormat;
+
+use rlink::core::element::Record;
+
+use crate::buffer_gen::pulsar_message;
+
+pub const PULSAR: &str = "pulsar";
+pub const SERVICE_URL: &str = "service.url";
+
+pub const TOPICS: &str = "topics";
+pub const SUBSCRIPTION: &str = "subscription";
+pub const INITIAL_POSITION: &str = "initial.position";
+pub const BUFFER_SIZE: &str = "buffer.size";
+
+pub const INPUT_FORMAT_FN_NAME_DEFAULT: &str = "PulsarInputFormat";
+pub const OUTPUT_FORMAT_FN_NAME_DEFAULT: &str = "PulsarOutputFormat";
+
+pub const SOURCE_CHANNEL_SIZE: usize = 50000;
+pub const SINK_CHANNEL_SIZE: usize = 50000;
+
+/// Build the `pulsar_message` record.
+///
+/// The `timestamp` is the event time of the message, or the publish time if the event time is
+/// absent. When the record is written to the sink, the `key` is the partition key, the
+/// `timestamp` is the event time, and the `topic` is used if the sink has no topic configured.
+pub fn build_pulsar_record(
+    timestamp: i64,
+    key: &[u8],
+    payload: &[u8],
+    topic: &str,
+    message_id: &str,
+) -> Result<Record, std::io::Error> {
+    let message = pulsar_message::Entity {
+        timestamp,
+        key,
+        payload,
+        topic,
+        message_id,
+    };
+
+    // 28 = 16(len(payload) + len(topic) + len(key) + len(message_id)) +
+    //      8(len(timestamp)) +
+    //      4(place_holder)
+    let capacity = payload.len() + topic.len() + key.len() + message_id.len() + 28;
+    let mut record = Record::with_capacity(capacity);
+
+    message.to_buffer(record.as_buffer()).unwrap();
+
+    Ok(record)
+}
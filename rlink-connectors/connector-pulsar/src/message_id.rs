@@ -0,0 +1,99 @@
+//! The message id is stored in the `message_id` field of the `pulsar_message` entity and in the
+//! checkpoints as a `{ledger_id}:{entry_id}:{batch_index}` string.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use pulsar::message::proto::MessageIdData;
+
+/// The position of a message in a topic partition, ordered as the messages are delivered
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PulsarMessageId {
+    pub ledger_id: u64,
+    pub entry_id: u64,
+    /// the index of the message in the batch entry, `-1` if the entry is not batched
+    pub batch_index: i32,
+}
+
+impl PulsarMessageId {
+    pub fn new(ledger_id: u64, entry_id: u64, batch_index: i32) -> Self {
+        PulsarMessageId {
+            ledger_id,
+            entry_id,
+            batch_index,
+        }
+    }
+
+    pub fn to_message_id_data(&self) -> MessageIdData {
+        MessageIdData {
+            ledger_id: self.ledger_id,
+            entry_id: self.entry_id,
+            batch_index: if self.batch_index >= 0 {
+                Some(self.batch_index)
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&MessageIdData> for PulsarMessageId {
+    fn from(id: &MessageIdData) -> Self {
+        PulsarMessageId {
+            ledger_id: id.ledger_id,
+            entry_id: id.entry_id,
+            batch_index: id.batch_index.unwrap_or(-1),
+        }
+    }
+}
+
+impl Display for PulsarMessageId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.ledger_id, self.entry_id, self.batch_index
+        )
+    }
+}
+
+impl FromStr for PulsarMessageId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() != 3 {
+            return Err(anyhow!("invalid pulsar message id {}", s));
+        }
+
+        Ok(PulsarMessageId {
+            ledger_id: parts[0].parse()?,
+            entry_id: parts[1].parse()?,
+            batch_index: parts[2].parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::PulsarMessageId;
+
+    #[test]
+    pub fn message_id_test() {
+        let id = PulsarMessageId::new(10, 2, -1);
+        assert_eq!(id.to_string(), "10:2:-1");
+        assert_eq!(PulsarMessageId::from_str("10:2:-1").unwrap(), id);
+        assert!(PulsarMessageId::from_str("10:2").is_err());
+
+        // the messages of a batch entry are after the entry's previous ones
+        assert!(PulsarMessageId::new(10, 2, 0) > id);
+        assert!(PulsarMessageId::new(10, 2, 1) < PulsarMessageId::new(10, 3, -1));
+        assert!(PulsarMessageId::new(11, 0, -1) > PulsarMessageId::new(10, 3, 5));
+
+        let data = PulsarMessageId::new(10, 2, 3).to_message_id_data();
+        assert_eq!(PulsarMessageId::from(&data), PulsarMessageId::new(10, 2, 3));
+    }
+}
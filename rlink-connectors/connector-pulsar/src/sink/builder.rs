@@ -0,0 +1,60 @@
+use std::convert::TryFrom;
+
+use rlink::core::properties::Properties;
+
+use crate::{PulsarOutputFormat, BUFFER_SIZE, PULSAR, SERVICE_URL, SINK_CHANNEL_SIZE, TOPICS};
+
+#[derive(Debug)]
+pub struct PulsarOutputFormatBuilder {
+    service_url: String,
+    topic: Option<String>,
+    buffer_size: Option<usize>,
+}
+
+impl PulsarOutputFormatBuilder {
+    pub fn new(service_url: &str, topic: Option<String>) -> Self {
+        PulsarOutputFormatBuilder {
+            service_url: service_url.to_string(),
+            topic,
+            buffer_size: None,
+        }
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> PulsarOutputFormat {
+        info!("build pulsar sink with: {:?}", &self);
+
+        let buffer_size = self.buffer_size.unwrap_or(SINK_CHANNEL_SIZE);
+        PulsarOutputFormat::new(self.service_url, self.topic, buffer_size)
+    }
+}
+
+impl TryFrom<Properties> for PulsarOutputFormatBuilder {
+    type Error = anyhow::Error;
+
+    fn try_from(properties: Properties) -> Result<Self, Self::Error> {
+        let pulsar_properties = properties.to_sub_properties(PULSAR);
+        let service_url = pulsar_properties.get_string(SERVICE_URL)?;
+
+        let topic = match properties.get_string(TOPICS) {
+            Ok(topics) => {
+                let topics: Vec<&str> = topics.trim().split(',').collect();
+                if topics.len() != 1 {
+                    return Err(anyhow!("only one topic support in pulsar sink"));
+                }
+                Some(topics[0].to_string())
+            }
+            Err(_e) => None,
+        };
+
+        let buffer_size = properties
+            .get_usize(BUFFER_SIZE)
+            .unwrap_or(SINK_CHANNEL_SIZE);
+
+        Ok(PulsarOutputFormatBuilder::new(service_url.as_str(), topic).buffer_size(buffer_size))
+    }
+}
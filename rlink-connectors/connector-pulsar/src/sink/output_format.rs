@@ -0,0 +1,116 @@
+use pulsar::{Pulsar, TokioExecutor};
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::Element;
+use rlink::core::function::{Context, NamedFunction, OutputFormat};
+use rlink::metrics::Tag;
+use tokio::sync::oneshot;
+
+use crate::sink::producer::{PulsarProducerThread, PulsarSinkMessage};
+
+/// Write the `pulsar_message` records to the topics, the records before a checkpoint are
+/// acked by the brokers before the checkpoint completes, so they are written at least once.
+#[derive(NamedFunction)]
+pub struct PulsarOutputFormat {
+    service_url: String,
+    topic: Option<String>,
+
+    buffer_size: usize,
+    handover: Option<ChannelSender<PulsarSinkMessage>>,
+    /// the producer thread has exited, all the later checkpoints are declined
+    thread_exited: bool,
+}
+
+impl PulsarOutputFormat {
+    pub fn new(service_url: String, topic: Option<String>, buffer_size: usize) -> Self {
+        PulsarOutputFormat {
+            service_url,
+            topic,
+            buffer_size,
+            handover: None,
+            thread_exited: false,
+        }
+    }
+
+    /// wait for the acks of the records handed over before
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.thread_exited {
+            return Err(anyhow!("pulsar producer thread has exited"));
+        }
+
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        let message = PulsarSinkMessage::Flush(ack_sender);
+        if self.handover.as_ref().unwrap().send(message).await.is_err() {
+            self.thread_exited = true;
+            return Err(anyhow!("pulsar producer thread has exited"));
+        }
+
+        ack_receiver
+            .await
+            .map_err(|_e| anyhow!("pulsar producer thread has exited"))?
+    }
+}
+
+#[async_trait]
+impl OutputFormat for PulsarOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        let pulsar = Pulsar::builder(self.service_url.as_str(), TokioExecutor)
+            .build()
+            .await
+            .map_err(|e| core::Error::from(format!("connect pulsar error. {}", e)))?;
+        let producer = pulsar
+            .producer()
+            .with_name(format!("{}-{}", self.name(), context.task_id.task_number()))
+            .build_multi_topic();
+
+        let mut tags = context.task_id.to_tags();
+        tags.push(Tag::new("topic", self.topic.as_deref().unwrap_or("")));
+
+        let (sender, receiver) = named_channel(self.name(), tags, self.buffer_size);
+        self.handover = Some(sender);
+
+        let topic = self.topic.clone();
+        tokio::spawn(async move {
+            let mut pulsar_producer = PulsarProducerThread::new(topic, producer, receiver);
+            pulsar_producer.run().await;
+        });
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let message = PulsarSinkMessage::Record(element.into_record());
+        if self.handover.as_ref().unwrap().send(message).await.is_err() && !self.thread_exited {
+            // surfaced as a failure of the next checkpoint
+            error!("pulsar producer thread has exited, the records are discarded");
+            self.thread_exited = true;
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for PulsarOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        if let Err(e) = self.flush().await {
+            context.decline(format!("flush pulsar producer error. {}", e).as_str());
+        }
+        None
+    }
+}
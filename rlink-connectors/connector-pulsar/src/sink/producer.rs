@@ -0,0 +1,177 @@
+use pulsar::producer::{Message, SendFuture};
+use pulsar::{MultiTopicProducer, TokioExecutor};
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::Record;
+use tokio::sync::oneshot;
+
+use crate::buffer_gen::pulsar_message;
+
+/// The max number of the messages sent but not acked by the broker
+const MAX_PENDING_MESSAGES: usize = 3000;
+
+/// Build the message of the `pulsar_message` entity, the `topic` of the sink takes precedence
+/// over the entity's. The key is the partition key and the positive timestamp is the event time.
+pub(crate) fn to_producer_message(
+    entity: &pulsar_message::Entity,
+    topic: Option<&String>,
+) -> anyhow::Result<(String, Message)> {
+    let topic = match topic {
+        Some(topic) => topic.as_str(),
+        None => entity.topic,
+    };
+    if topic.is_empty() {
+        return Err(anyhow!("topic not found in `PulsarRecord`"));
+    }
+
+    let partition_key = if entity.key.is_empty() {
+        None
+    } else {
+        let key = std::str::from_utf8(entity.key)
+            .map_err(|e| anyhow!("the key of `PulsarRecord` is not UTF-8. {}", e))?;
+        Some(key.to_string())
+    };
+    let event_time = if entity.timestamp > 0 {
+        Some(entity.timestamp as u64)
+    } else {
+        None
+    };
+
+    let message = Message {
+        payload: entity.payload.to_vec(),
+        partition_key,
+        event_time,
+        ..Default::default()
+    };
+    Ok((topic.to_string(), message))
+}
+
+/// Message handed over from the `PulsarOutputFormat` to the `PulsarProducerThread`
+pub enum PulsarSinkMessage {
+    Record(Record),
+    /// wait for the acks of the produced records, sent when the sink snapshots
+    Flush(oneshot::Sender<anyhow::Result<()>>),
+}
+
+pub struct PulsarProducerThread {
+    topic: Option<String>,
+    producer: MultiTopicProducer<TokioExecutor>,
+    receiver: ChannelReceiver<PulsarSinkMessage>,
+
+    pending_futures: Vec<SendFuture>,
+    /// the first produce error, the discarded records are lost, so all the later flushes fail
+    produce_error: Option<String>,
+}
+
+impl PulsarProducerThread {
+    pub fn new(
+        topic: Option<String>,
+        producer: MultiTopicProducer<TokioExecutor>,
+        receiver: ChannelReceiver<PulsarSinkMessage>,
+    ) -> Self {
+        PulsarProducerThread {
+            topic,
+            producer,
+            receiver,
+            pending_futures: Vec::with_capacity(MAX_PENDING_MESSAGES),
+            produce_error: None,
+        }
+    }
+
+    pub async fn run(&mut self) {
+        while let Some(message) = self.receiver.recv().await {
+            match message {
+                PulsarSinkMessage::Record(record) => {
+                    self.send(record).await;
+                    if self.pending_futures.len() >= MAX_PENDING_MESSAGES {
+                        self.drain().await;
+                    }
+                }
+                PulsarSinkMessage::Flush(ack) => {
+                    self.drain().await;
+
+                    let flush_result = match &self.produce_error {
+                        Some(e) => Err(anyhow!("{}", e)),
+                        None => Ok(()),
+                    };
+                    if ack.send(flush_result).is_err() {
+                        warn!("the flush ack receiver has been dropped");
+                    }
+                }
+            }
+        }
+
+        self.drain().await;
+        info!("pulsar recv channel disconnected, producer thread exit");
+    }
+
+    async fn send(&mut self, mut record: Record) {
+        let entity = match pulsar_message::Entity::parse(record.as_buffer()) {
+            Ok(entity) => entity,
+            Err(e) => {
+                self.set_produce_error(format!("parse `PulsarRecord` error. {}", e));
+                return;
+            }
+        };
+
+        let (topic, message) = match to_producer_message(&entity, self.topic.as_ref()) {
+            Ok(message) => message,
+            Err(e) => {
+                self.set_produce_error(e.to_string());
+                return;
+            }
+        };
+
+        match self.producer.send_non_blocking(topic, message).await {
+            Ok(future) => self.pending_futures.push(future),
+            Err(e) => self.set_produce_error(format!("send error: {}", e)),
+        }
+    }
+
+    /// wait for the acks of the sent messages
+    async fn drain(&mut self) {
+        for future in std::mem::take(&mut self.pending_futures) {
+            if let Err(e) = future.await {
+                self.set_produce_error(format!("produce error: {}", e));
+            }
+        }
+    }
+
+    /// keep the first error, the later flushes fail with it
+    fn set_produce_error(&mut self, error: String) {
+        error!("{}", error);
+        if self.produce_error.is_none() {
+            self.produce_error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer_gen::pulsar_message;
+    use crate::build_pulsar_record;
+    use crate::sink::producer::to_producer_message;
+
+    #[test]
+    pub fn to_producer_message_test() {
+        let mut record = build_pulsar_record(1000, b"k", b"payload", "t-record", "").unwrap();
+        let entity = pulsar_message::Entity::parse(record.as_buffer()).unwrap();
+
+        let (topic, message) = to_producer_message(&entity, None).unwrap();
+        assert_eq!(topic, "t-record");
+        assert_eq!(message.payload, b"payload".to_vec());
+        assert_eq!(message.partition_key, Some("k".to_string()));
+        assert_eq!(message.event_time, Some(1000));
+
+        let sink_topic = "t-sink".to_string();
+        let (topic, _message) = to_producer_message(&entity, Some(&sink_topic)).unwrap();
+        assert_eq!(topic, "t-sink");
+
+        let mut record = build_pulsar_record(0, b"", b"payload", "", "").unwrap();
+        let entity = pulsar_message::Entity::parse(record.as_buffer()).unwrap();
+        assert!(to_producer_message(&entity, None).is_err());
+
+        let (_topic, message) = to_producer_message(&entity, Some(&sink_topic)).unwrap();
+        assert_eq!(message.partition_key, None);
+        assert_eq!(message.event_time, None);
+    }
+}
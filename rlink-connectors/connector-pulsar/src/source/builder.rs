@@ -0,0 +1,138 @@
+use std::convert::TryFrom;
+
+use pulsar::consumer::InitialPosition;
+use rlink::core::element::FnSchema;
+use rlink::core::properties::{Properties, PARALLELISM};
+
+use crate::buffer_gen::pulsar_message;
+use crate::source::deserializer::{
+    DefaultPulsarRecordDeserializer, DefaultPulsarRecordDeserializerBuilder,
+    PulsarRecordDeserializerBuilder,
+};
+use crate::{
+    PulsarInputFormat, BUFFER_SIZE, INITIAL_POSITION, INPUT_FORMAT_FN_NAME_DEFAULT, PULSAR,
+    SERVICE_URL, SOURCE_CHANNEL_SIZE, SUBSCRIPTION, TOPICS,
+};
+
+#[derive(Debug)]
+pub struct PulsarInputFormatBuilder {
+    fn_name: Option<String>,
+    parallelism: u16,
+    service_url: String,
+    topics: Vec<String>,
+    subscription: String,
+    buffer_size: Option<usize>,
+    initial_position: InitialPosition,
+}
+
+impl PulsarInputFormatBuilder {
+    pub fn new(
+        service_url: &str,
+        topics: Vec<String>,
+        subscription: &str,
+        parallelism: u16,
+    ) -> Self {
+        PulsarInputFormatBuilder {
+            fn_name: None,
+            parallelism,
+            service_url: service_url.to_string(),
+            topics,
+            subscription: subscription.to_string(),
+            buffer_size: None,
+            initial_position: InitialPosition::Latest,
+        }
+    }
+
+    pub fn fn_name(mut self, name: &str) -> Self {
+        self.fn_name = Some(name.to_string());
+        self
+    }
+
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Where to start when the subscription is created by the job, the latest by default
+    pub fn initial_position(mut self, initial_position: InitialPosition) -> Self {
+        self.initial_position = initial_position;
+        self
+    }
+
+    pub fn build(
+        self,
+        deserializer_builder: Option<Box<dyn PulsarRecordDeserializerBuilder>>,
+    ) -> PulsarInputFormat {
+        info!("build pulsar source with: {:?}", &self);
+
+        let fn_name = self
+            .fn_name
+            .unwrap_or(INPUT_FORMAT_FN_NAME_DEFAULT.to_string());
+        let buffer_size = self.buffer_size.unwrap_or(SOURCE_CHANNEL_SIZE);
+
+        let deserializer_builder = deserializer_builder.unwrap_or_else(|| {
+            let deserializer_builder: Box<dyn PulsarRecordDeserializerBuilder> =
+                Box::new(DefaultPulsarRecordDeserializerBuilder::<
+                    DefaultPulsarRecordDeserializer,
+                >::new(FnSchema::from(
+                    &pulsar_message::FIELD_METADATA,
+                )));
+
+            deserializer_builder
+        });
+
+        PulsarInputFormat::new(
+            self.service_url,
+            self.topics,
+            self.subscription,
+            self.initial_position,
+            buffer_size,
+            deserializer_builder,
+            self.parallelism,
+            fn_name,
+        )
+    }
+}
+
+impl TryFrom<Properties> for PulsarInputFormatBuilder {
+    type Error = anyhow::Error;
+
+    fn try_from(properties: Properties) -> Result<Self, Self::Error> {
+        let parallelism = properties.get_u16(PARALLELISM)?;
+
+        let pulsar_properties = properties.to_sub_properties(PULSAR);
+        let service_url = pulsar_properties.get_string(SERVICE_URL)?;
+
+        let topics: Vec<String> = properties
+            .get_string(TOPICS)?
+            .trim()
+            .split(',')
+            .map(|x| x.to_string())
+            .collect();
+        let subscription = properties.get_string(SUBSCRIPTION)?;
+
+        let mut builder = PulsarInputFormatBuilder::new(
+            service_url.as_str(),
+            topics,
+            subscription.as_str(),
+            parallelism,
+        );
+
+        builder = builder.fn_name(properties.name());
+
+        if let Ok(buffer_size) = properties.get_usize(BUFFER_SIZE) {
+            builder = builder.buffer_size(buffer_size);
+        }
+
+        if let Ok(initial_position) = properties.get_string(INITIAL_POSITION) {
+            let initial_position = match initial_position.as_str() {
+                "earliest" => InitialPosition::Earliest,
+                "latest" => InitialPosition::Latest,
+                _ => return Err(anyhow!("unknown initial position {}", initial_position)),
+            };
+            builder = builder.initial_position(initial_position);
+        }
+
+        Ok(builder)
+    }
+}
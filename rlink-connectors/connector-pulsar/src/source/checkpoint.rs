@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::runtime::CheckpointId;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::message_id::PulsarMessageId;
+
+#[derive(Debug, Clone, Default)]
+pub struct PulsarCheckpointFunction {
+    pub(crate) state: PulsarSourceState,
+    ack_committer: PulsarAckCommitter,
+    pub(crate) restore_error: Option<String>,
+}
+
+impl PulsarCheckpointFunction {
+    pub fn state(&self) -> &PulsarSourceState {
+        &self.state
+    }
+
+    /// ack the messages of the topic by the `ack_sender` once the checkpoints are complete
+    pub(crate) fn register_acker(
+        &mut self,
+        topic: &str,
+        ack_sender: UnboundedSender<PulsarMessageId>,
+    ) {
+        self.ack_committer
+            .ack_senders
+            .insert(topic.to_string(), ack_sender);
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for PulsarCheckpointFunction {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        info!("Checkpoint initialize, context: {:?}", context);
+
+        let handle = match handle {
+            Some(handle) if !context.checkpoint_id.is_default() => handle,
+            _ => return,
+        };
+
+        match self.state.update_from_snapshot(handle.handle.as_str()) {
+            Ok(()) => info!(
+                "load state value from checkpoint({:?}): {:?}",
+                context.checkpoint_id, handle.handle
+            ),
+            Err(e) => self.restore_error = Some(e.to_string()),
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        let handle = self.state.snapshot();
+        debug!("Checkpoint snapshot: {:?}, context: {:?}", handle, context);
+
+        self.ack_committer.commit(
+            context.checkpoint_id,
+            self.state.message_ids(),
+            context.completed_checkpoint_id,
+        );
+
+        Some(CheckpointHandle { handle })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageIdSnapshot {
+    topic: String,
+    message_id: Option<String>,
+}
+
+/// The last emitted message ids of all the topic partitions assigned to a task
+#[derive(Debug, Clone, Default)]
+pub struct PulsarSourceState {
+    recorders: Arc<Mutex<BTreeMap<String, PulsarSourceStateRecorder>>>,
+}
+
+impl PulsarSourceState {
+    /// get or register the recorder of the topic partition
+    pub fn recorder(&self, topic: &str) -> PulsarSourceStateRecorder {
+        let mut recorders = self.recorders.lock().unwrap();
+        recorders
+            .entry(topic.to_string())
+            .or_insert_with(|| PulsarSourceStateRecorder::new(topic))
+            .clone()
+    }
+
+    pub fn get(&self, topic: &str) -> Option<PulsarMessageId> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders.get(topic).and_then(|recorder| recorder.get())
+    }
+
+    /// the last emitted message ids of the topic partitions, `(topic, message_id)`
+    pub fn message_ids(&self) -> Vec<(String, PulsarMessageId)> {
+        let recorders = self.recorders.lock().unwrap();
+        recorders
+            .values()
+            .filter_map(|recorder| {
+                recorder
+                    .get()
+                    .map(|message_id| (recorder.topic.clone(), message_id))
+            })
+            .collect()
+    }
+
+    /// The snapshot is an array of `MessageIdSnapshot`
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let snapshots: Vec<MessageIdSnapshot> = serde_json::from_str(snapshot_handle)?;
+        for snapshot in snapshots {
+            let recorder = self.recorder(snapshot.topic.as_str());
+            if let Some(message_id) = snapshot.message_id {
+                recorder.update(PulsarMessageId::from_str(message_id.as_str())?);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> String {
+        let recorders = self.recorders.lock().unwrap();
+        let snapshots: Vec<MessageIdSnapshot> = recorders
+            .values()
+            .map(|recorder| MessageIdSnapshot {
+                topic: recorder.topic.clone(),
+                message_id: recorder.get().map(|message_id| message_id.to_string()),
+            })
+            .collect();
+        serde_json::to_string(&snapshots).unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PulsarSourceStateRecorder {
+    topic: String,
+    message_id: Arc<Mutex<Option<PulsarMessageId>>>,
+}
+
+impl PulsarSourceStateRecorder {
+    pub fn new(topic: &str) -> Self {
+        PulsarSourceStateRecorder {
+            topic: topic.to_string(),
+            message_id: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn update(&self, message_id: PulsarMessageId) {
+        *self.message_id.lock().unwrap() = Some(message_id);
+    }
+
+    pub fn get(&self) -> Option<PulsarMessageId> {
+        *self.message_id.lock().unwrap()
+    }
+}
+
+/// Cumulatively ack the checkpointed message ids to the subscription, so that the backlog of
+/// the subscription is released and can be monitored by the pulsar tools.
+///
+/// The message ids of a checkpoint are acked once the checkpoint is known to be complete, that
+/// is a later `Barrier` carries it as the completed checkpoint. The acks are sent to the
+/// consumers of the topic partitions, which own the subscriptions.
+#[derive(Debug, Clone, Default)]
+struct PulsarAckCommitter {
+    ack_senders: HashMap<String, UnboundedSender<PulsarMessageId>>,
+    /// checkpoint_id -> the last emitted message ids, `(topic, message_id)`
+    pending_acks: BTreeMap<u64, Vec<(String, PulsarMessageId)>>,
+}
+
+impl PulsarAckCommitter {
+    fn commit(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        message_ids: Vec<(String, PulsarMessageId)>,
+        completed_checkpoint_id: Option<CheckpointId>,
+    ) {
+        if !message_ids.is_empty() {
+            self.pending_acks.insert(checkpoint_id.0, message_ids);
+        }
+
+        let message_ids = match completed_checkpoint_id {
+            Some(completed_checkpoint_id) => {
+                take_completed(&mut self.pending_acks, completed_checkpoint_id)
+            }
+            None => None,
+        };
+
+        for (topic, message_id) in message_ids.unwrap_or_default() {
+            let sent = self
+                .ack_senders
+                .get(topic.as_str())
+                .map(|ack_sender| ack_sender.send(message_id).is_ok())
+                .unwrap_or_default();
+            // acked by a later checkpoint or by the restored consumer
+            if !sent {
+                warn!(
+                    "the consumer of topic {} has exited, ack {} is skipped",
+                    topic, message_id
+                );
+            }
+        }
+    }
+}
+
+/// remove the pending items of the checkpoints covered by `completed_checkpoint_id`,
+/// return the item of the latest one
+fn take_completed<T>(
+    pending: &mut BTreeMap<u64, T>,
+    completed_checkpoint_id: CheckpointId,
+) -> Option<T> {
+    let mut completed = pending.split_off(&(completed_checkpoint_id.0 + 1));
+    std::mem::swap(pending, &mut completed);
+    completed
+        .into_iter()
+        .next_back()
+        .map(|(_checkpoint_id, item)| item)
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::runtime::CheckpointId;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::message_id::PulsarMessageId;
+    use crate::source::checkpoint::{PulsarAckCommitter, PulsarSourceState};
+
+    #[test]
+    pub fn source_state_snapshot_test() {
+        let state = PulsarSourceState::default();
+        state
+            .recorder("topic-partition-0")
+            .update(PulsarMessageId::new(1, 10, -1));
+        state.recorder("topic-partition-1");
+
+        let snapshot = state.snapshot();
+        let restored_state = PulsarSourceState::default();
+        restored_state
+            .update_from_snapshot(snapshot.as_str())
+            .unwrap();
+        assert_eq!(
+            restored_state.get("topic-partition-0"),
+            Some(PulsarMessageId::new(1, 10, -1))
+        );
+        assert_eq!(restored_state.get("topic-partition-1"), None);
+        assert_eq!(restored_state.message_ids().len(), 1);
+    }
+
+    #[test]
+    pub fn ack_committer_test() {
+        let (ack_sender, mut ack_receiver) = unbounded_channel();
+        let mut committer = PulsarAckCommitter::default();
+        committer.ack_senders.insert("t".to_string(), ack_sender);
+
+        let message_ids = |entry_id| vec![("t".to_string(), PulsarMessageId::new(1, entry_id, -1))];
+        committer.commit(CheckpointId(1), message_ids(10), None);
+        committer.commit(CheckpointId(2), message_ids(20), Some(CheckpointId(0)));
+        assert!(ack_receiver.try_recv().is_err());
+
+        // the latest completed checkpoint is acked
+        committer.commit(CheckpointId(3), message_ids(30), Some(CheckpointId(2)));
+        assert_eq!(
+            ack_receiver.try_recv().unwrap(),
+            PulsarMessageId::new(1, 20, -1)
+        );
+        assert!(ack_receiver.try_recv().is_err());
+        assert_eq!(committer.pending_acks.len(), 1);
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use pulsar::consumer::{InitialPosition, Message};
+use pulsar::{Consumer, ConsumerOptions, Pulsar, SubType, TokioExecutor};
+use rlink::channel::sender::ChannelSender;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::message_id::PulsarMessageId;
+use crate::source::checkpoint::PulsarSourceStateRecorder;
+use crate::source::deserializer::PulsarRecordDeserializer;
+use crate::source::ConsumerRecord;
+
+pub(crate) struct PulsarConsumerThread {
+    pulsar: Pulsar<TokioExecutor>,
+    topic: String,
+    subscription: String,
+    consumer_name: String,
+    initial_position: InitialPosition,
+
+    sender: ChannelSender<ConsumerRecord>,
+    ack_receiver: UnboundedReceiver<PulsarMessageId>,
+    deserializer: Box<dyn PulsarRecordDeserializer>,
+    state_recorder: PulsarSourceStateRecorder,
+}
+
+impl PulsarConsumerThread {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        pulsar: Pulsar<TokioExecutor>,
+        topic: String,
+        subscription: String,
+        consumer_name: String,
+        initial_position: InitialPosition,
+        sender: ChannelSender<ConsumerRecord>,
+        ack_receiver: UnboundedReceiver<PulsarMessageId>,
+        deserializer: Box<dyn PulsarRecordDeserializer>,
+        state_recorder: PulsarSourceStateRecorder,
+    ) -> Self {
+        PulsarConsumerThread {
+            pulsar,
+            topic,
+            subscription,
+            consumer_name,
+            initial_position,
+            sender,
+            ack_receiver,
+            deserializer,
+            state_recorder,
+        }
+    }
+
+    /// Consume until the stream is closed, the consumer is re-created after the last emitted
+    /// message when an error occurs
+    pub async fn run(mut self) {
+        loop {
+            match self.consume().await {
+                Ok(()) => {
+                    info!(
+                        "pulsar source of topic {} is closed, the consumer exits",
+                        self.topic
+                    );
+                    return;
+                }
+                Err(e) => {
+                    error!("consume pulsar topic {} error. {}", self.topic, e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    async fn consume(&mut self) -> anyhow::Result<()> {
+        let options =
+            ConsumerOptions::default().with_initial_position(self.initial_position.clone());
+        // a single active consumer of the partition, the zombie consumer of the restarted task
+        // is kept as the standby one
+        let mut consumer: Consumer<Vec<u8>, TokioExecutor> = self
+            .pulsar
+            .consumer()
+            .with_topic(self.topic.as_str())
+            .with_subscription(self.subscription.as_str())
+            .with_subscription_type(SubType::Failover)
+            .with_consumer_name(self.consumer_name.as_str())
+            .with_options(options)
+            .build()
+            .await?;
+
+        // the emitted messages may be ahead of or behind the acked position of the subscription
+        let last_message_id = self.state_recorder.get();
+        if let Some(message_id) = last_message_id {
+            consumer
+                .seek(
+                    None,
+                    Some(message_id.to_message_id_data()),
+                    None,
+                    self.pulsar.clone(),
+                )
+                .await?;
+        }
+        info!(
+            "create pulsar consumer success. topic: {}, subscription: {}, consumer: {}, resume after: {:?}",
+            self.topic, self.subscription, self.consumer_name, last_message_id
+        );
+
+        loop {
+            tokio::select! {
+                message = consumer.next() => match message {
+                    Some(Ok(message)) => {
+                        let message_id = PulsarMessageId::from(message.message_id());
+                        // the message at the seek position is re-delivered
+                        if matches!(last_message_id, Some(last) if message_id <= last) {
+                            continue;
+                        }
+                        if !self.emit(&message, message_id).await {
+                            return Ok(());
+                        }
+                    }
+                    Some(Err(e)) => return Err(anyhow!(e)),
+                    None => return Err(anyhow!("pulsar consumer stream is closed")),
+                },
+                message_id = self.ack_receiver.recv() => match message_id {
+                    Some(message_id) => {
+                        let rt = consumer
+                            .cumulative_ack_with_id(self.topic.as_str(), message_id.to_message_id_data())
+                            .await;
+                        // acked by a later checkpoint
+                        if let Err(e) = rt {
+                            warn!("ack pulsar message {} of topic {} error. {}", message_id, self.topic, e);
+                        }
+                    }
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    /// Returns false if the stream is closed
+    async fn emit(&mut self, message: &Message<Vec<u8>>, message_id: PulsarMessageId) -> bool {
+        let metadata = message.metadata();
+        let timestamp = metadata.event_time.unwrap_or(metadata.publish_time) as i64;
+        let key = metadata
+            .partition_key
+            .as_ref()
+            .map(|key| key.as_bytes())
+            .unwrap_or_default();
+
+        let records = self.deserializer.deserialize(
+            timestamp,
+            key,
+            message.payload.data.as_slice(),
+            message.topic.as_str(),
+            &message_id,
+        );
+        for record in records {
+            let consumer_record =
+                ConsumerRecord::new(record, message_id, self.state_recorder.clone());
+            if self.sender.send(consumer_record).await.is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,73 @@
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use serde_json::Value;
+
+use crate::message_id::PulsarMessageId;
+use crate::source::deserializer::{
+    write_record, PulsarRecordDeserializer, SchemaPulsarRecordDeserializerBuilder,
+};
+
+/// Deserialize the JSON object payload to a `Record` of the `schema`, the object fields are
+/// matched by the schema field names. A JSON array payload produces a `Record` per element.
+///
+/// The nested object and array are written as JSON string, and the message failed to
+/// deserialize is discarded.
+#[derive(Clone, Debug)]
+pub struct JsonPulsarRecordDeserializer {
+    schema: Schema,
+}
+
+impl JsonPulsarRecordDeserializer {
+    pub fn new(schema: Schema) -> Self {
+        JsonPulsarRecordDeserializer { schema }
+    }
+
+    pub fn builder(schema: Schema) -> SchemaPulsarRecordDeserializerBuilder<Self> {
+        SchemaPulsarRecordDeserializerBuilder::new(Self::new(schema.clone()), schema)
+    }
+
+    fn to_record(&self, value: &Value) -> anyhow::Result<Record> {
+        let object = value
+            .as_object()
+            .ok_or(anyhow!("the JSON value is not an object"))?;
+
+        let values: Vec<Option<&Value>> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| object.get(field.name()))
+            .collect();
+
+        write_record(&self.schema, values.as_slice())
+    }
+
+    fn deserialize_payload(&self, payload: &[u8]) -> anyhow::Result<Vec<Record>> {
+        let value: Value = serde_json::from_slice(payload)?;
+        match &value {
+            Value::Array(values) => values.iter().map(|x| self.to_record(x)).collect(),
+            _ => self.to_record(&value).map(|record| vec![record]),
+        }
+    }
+}
+
+impl PulsarRecordDeserializer for JsonPulsarRecordDeserializer {
+    fn deserialize(
+        &mut self,
+        _timestamp: i64,
+        _key: &[u8],
+        payload: &[u8],
+        topic: &str,
+        message_id: &PulsarMessageId,
+    ) -> Vec<Record> {
+        match self.deserialize_payload(payload) {
+            Ok(records) => records,
+            Err(e) => {
+                warn!(
+                    "discard the JSON message of topic: {}, message id: {}. {}",
+                    topic, message_id, e
+                );
+                vec![]
+            }
+        }
+    }
+}
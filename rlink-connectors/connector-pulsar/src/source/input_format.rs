@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use pulsar::consumer::InitialPosition;
+use pulsar::{Pulsar, TokioExecutor};
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::FnSchema;
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, NamedFunction, SendableElementStream,
+};
+use rlink::core::properties::Properties;
+use rlink::core::runtime::TaskId;
+use rlink::metrics::Tag;
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::source::checkpoint::PulsarCheckpointFunction;
+use crate::source::consumer::PulsarConsumerThread;
+use crate::source::deserializer::PulsarRecordDeserializerBuilder;
+use crate::source::stream::PulsarRecordStream;
+use crate::source::ConsumerRecord;
+
+/// Depending on whether the task has `InputSplit`, and whether the client needs to be created
+const CREATE_PULSAR_CONNECTION: &str = "create_pulsar_connection";
+
+/// Consume the topic partitions with a `Failover` subscription, each partition is consumed by a
+/// task. The last emitted message id of each partition is checkpointed, the recovered task seeks
+/// the subscription to the checkpointed message, and the messages of the completed checkpoints
+/// are cumulatively acked.
+pub struct PulsarInputFormat {
+    name: String,
+    parallelism: u16,
+
+    service_url: String,
+    topics: Vec<String>,
+    subscription: String,
+    initial_position: InitialPosition,
+
+    task_id: TaskId,
+    task_topic: String,
+    /// the task has no partition if the parallelism is greater than the number of partitions
+    create_connection: bool,
+    /// keep the idle task's stream open until the job is stopped
+    idle_handover: Option<ChannelSender<ConsumerRecord>>,
+
+    buffer_size: usize,
+
+    tags: Vec<Tag>,
+
+    deserializer_builder: Arc<dyn PulsarRecordDeserializerBuilder>,
+    schema: FnSchema,
+
+    pulsar: Option<Pulsar<TokioExecutor>>,
+    checkpoint: Option<PulsarCheckpointFunction>,
+}
+
+impl PulsarInputFormat {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        service_url: String,
+        topics: Vec<String>,
+        subscription: String,
+        initial_position: InitialPosition,
+        buffer_size: usize,
+        deserializer_builder: Box<dyn PulsarRecordDeserializerBuilder>,
+        parallelism: u16,
+        fn_name: String,
+    ) -> Self {
+        let schema = deserializer_builder.schema();
+        PulsarInputFormat {
+            name: fn_name,
+            parallelism,
+            service_url,
+            topics,
+            subscription,
+            initial_position,
+            task_id: Default::default(),
+            task_topic: "".to_string(),
+            create_connection: true,
+            idle_handover: None,
+            buffer_size,
+            tags: vec![],
+            deserializer_builder: Arc::from(deserializer_builder),
+            schema,
+            pulsar: None,
+            checkpoint: None,
+        }
+    }
+
+    fn start_consumer(&mut self, handover: ChannelSender<ConsumerRecord>) {
+        let checkpoint = self.checkpoint.as_mut().unwrap();
+        let state_recorder = checkpoint.state().recorder(self.task_topic.as_str());
+
+        let (ack_sender, ack_receiver) = unbounded_channel();
+        checkpoint.register_acker(self.task_topic.as_str(), ack_sender);
+
+        let consumer = PulsarConsumerThread::new(
+            self.pulsar.clone().unwrap(),
+            self.task_topic.clone(),
+            self.subscription.clone(),
+            format!("{}-{}", self.subscription, self.task_id.task_number()),
+            self.initial_position.clone(),
+            handover,
+            ack_receiver,
+            self.deserializer_builder.build(),
+            state_recorder,
+        );
+        tokio::spawn(consumer.run());
+    }
+}
+
+impl NamedFunction for PulsarInputFormat {
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+#[async_trait]
+impl InputFormat for PulsarInputFormat {
+    async fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
+        info!("pulsar source open");
+
+        self.task_id = context.task_id;
+        self.checkpoint = Some(PulsarCheckpointFunction::default());
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.checkpoint.as_ref().unwrap().restore_error {
+            return Err(core::Error::from(format!(
+                "restore pulsar source error. {}",
+                e
+            )));
+        }
+
+        self.task_topic = input_split.properties().get_string("topic")?;
+        self.create_connection = input_split
+            .properties()
+            .get_bool(CREATE_PULSAR_CONNECTION)
+            .unwrap_or(true);
+        self.tags.push(Tag::new("topic", self.task_topic.as_str()));
+
+        if self.create_connection {
+            let pulsar = Pulsar::builder(self.service_url.as_str(), TokioExecutor)
+                .build()
+                .await
+                .map_err(|e| core::Error::from(format!("connect pulsar error. {}", e)))?;
+            self.pulsar = Some(pulsar);
+        }
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let (sender, receiver) =
+            named_channel("PulsarSource_Handover", self.tags.clone(), self.buffer_size);
+
+        if self.create_connection {
+            self.start_consumer(sender);
+        } else {
+            info!("pulsar source task has no partition to consume");
+            self.idle_handover = Some(sender);
+        }
+
+        Box::pin(PulsarRecordStream::new(receiver))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        self.schema.clone()
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for PulsarInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        self.checkpoint
+            .as_mut()
+            .unwrap()
+            .initialize_state(context, handle)
+            .await;
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        match self.checkpoint.as_mut() {
+            Some(checkpoint) => checkpoint.snapshot_state(context).await,
+            None => None,
+        }
+    }
+}
+
+impl InputSplitSource for PulsarInputFormat {
+    /// One split per topic partition, the extra splits of the idle tasks are copies of the
+    /// partitions' ones without the connection
+    fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        let partitions = lookup_partitions(self.service_url.as_str(), self.topics.clone())?;
+        info!("pulsar topic partitions {:?}", partitions);
+
+        if partitions.is_empty() {
+            return Err(core::Error::from("no pulsar topic to consume"));
+        }
+        if partitions.len() > min_num_splits as usize {
+            return Err(core::Error::from(format!(
+                "the parallelism {} is less than the number of pulsar topic partitions {}",
+                min_num_splits,
+                partitions.len()
+            )));
+        }
+
+        let mut input_splits = Vec::with_capacity(min_num_splits as usize);
+        for index in 0..min_num_splits {
+            let partition = &partitions[index as usize % partitions.len()];
+
+            let mut properties = Properties::new();
+            properties.set_str("topic", partition.as_str());
+            properties.set_bool(
+                CREATE_PULSAR_CONNECTION,
+                (index as usize) < partitions.len(),
+            );
+            input_splits.push(InputSplit::new(index, properties));
+        }
+
+        Ok(input_splits)
+    }
+}
+
+/// The partition topics of the `topics`, a non-partitioned topic is a partition itself. The
+/// splits are created out of the job runtime, so the lookups run on a temporary one.
+fn lookup_partitions(service_url: &str, topics: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let service_url = service_url.to_string();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        runtime.block_on(async move {
+            let pulsar = Pulsar::builder(service_url, TokioExecutor).build().await?;
+
+            let mut partitions = Vec::new();
+            for topic in topics {
+                let topic_partitions = pulsar.lookup_partitioned_topic(topic.as_str()).await?;
+                partitions.extend(topic_partitions.into_iter().map(|(partition, _)| partition));
+            }
+            Ok(partitions)
+        })
+    })
+    .join()
+    .map_err(|_e| anyhow!("lookup pulsar topic partitions panicked"))?
+}
@@ -0,0 +1,31 @@
+use crate::message_id::PulsarMessageId;
+use crate::source::checkpoint::PulsarSourceStateRecorder;
+
+pub mod builder;
+pub mod checkpoint;
+pub mod consumer;
+pub mod deserializer;
+pub mod input_format;
+pub mod stream;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ConsumerRecord {
+    record: rlink::core::element::Record,
+    message_id: PulsarMessageId,
+    /// the state of the record's topic partition, updated when the record is emitted
+    state_recorder: PulsarSourceStateRecorder,
+}
+
+impl ConsumerRecord {
+    pub fn new(
+        record: rlink::core::element::Record,
+        message_id: PulsarMessageId,
+        state_recorder: PulsarSourceStateRecorder,
+    ) -> Self {
+        ConsumerRecord {
+            record,
+            message_id,
+            state_recorder,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::Element;
+use rlink::core::function::ElementStream;
+
+use crate::source::ConsumerRecord;
+
+/// Simulate a Pulsar consumption stream as an iterator.
+pub struct PulsarRecordStream {
+    receiver: ChannelReceiver<ConsumerRecord>,
+}
+
+impl PulsarRecordStream {
+    pub(crate) fn new(receiver: ChannelReceiver<ConsumerRecord>) -> Self {
+        PulsarRecordStream { receiver }
+    }
+}
+
+impl ElementStream for PulsarRecordStream {}
+
+impl Stream for PulsarRecordStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.as_mut().receiver.poll_recv(cx) {
+            Poll::Ready(Some(consumer_record)) => {
+                consumer_record
+                    .state_recorder
+                    .update(consumer_record.message_id);
+
+                Poll::Ready(Some(Element::Record(consumer_record.record)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
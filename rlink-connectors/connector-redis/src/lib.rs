@@ -0,0 +1,19 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod lookup;
+pub(crate) mod record;
+pub mod sink;
+pub mod source;
+
+pub use lookup::{RedisLookup, RedisLookupCommand};
+pub use sink::{RedisCommand, RedisOutputFormat};
+pub use source::input_format::RedisStreamInputFormat;
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use redis::aio::ConnectionManager;
+use rlink::core;
+use rlink::core::data_types::Schema;
+use rlink::core::element::Record;
+use rlink::core::function::{Context, LookupSource};
+
+use crate::record::{to_record, to_redis_arg};
+
+/// The command to look up the value of a key
+#[derive(Clone, Debug)]
+pub enum RedisLookupCommand {
+    /// `GET {key}`, the value is the only field of the value `Schema`
+    Get,
+    /// `HGETALL {key}`, the hash fields are matched by the value `Schema` field names, the empty
+    /// hash is an absent key
+    HGetAll,
+}
+
+/// Look up the dimension data in Redis by the `DataStream::lookup_join`. The Redis key is the
+/// `key_prefix` followed by the values of the key columns, of the `key_schema`, joined by `:`.
+/// The keys of a batch are looked up by a pipeline, and the values are cached by the
+/// `LookupJoinOptions`.
+#[derive(NamedFunction)]
+pub struct RedisLookup {
+    url: String,
+    command: RedisLookupCommand,
+    key_schema: Schema,
+    value_schema: Schema,
+    key_prefix: String,
+
+    connection: Option<ConnectionManager>,
+}
+
+impl RedisLookup {
+    pub fn new(
+        url: &str,
+        command: RedisLookupCommand,
+        key_schema: Schema,
+        value_schema: Schema,
+    ) -> Self {
+        RedisLookup {
+            url: url.to_string(),
+            command,
+            key_schema,
+            value_schema,
+            key_prefix: String::new(),
+            connection: None,
+        }
+    }
+
+    /// The prefix of the Redis keys, such as `user:`
+    pub fn key_prefix(mut self, key_prefix: &str) -> Self {
+        self.key_prefix = key_prefix.to_string();
+        self
+    }
+
+    fn redis_key(&self, key: &mut Record) -> anyhow::Result<Vec<u8>> {
+        let mut redis_key = self.key_prefix.as_bytes().to_vec();
+        let reader = key.as_reader(self.key_schema.as_type_ids());
+        for (index, field) in self.key_schema.fields().iter().enumerate() {
+            if index > 0 {
+                redis_key.push(b':');
+            }
+            redis_key.extend(to_redis_arg(&reader, index, field.data_type())?);
+        }
+        Ok(redis_key)
+    }
+
+    fn to_value(&self, value: redis::Value) -> anyhow::Result<Option<Record>> {
+        let values: HashMap<String, redis::Value> = match self.command {
+            RedisLookupCommand::Get => match value {
+                redis::Value::Nil => return Ok(None),
+                value => {
+                    let field = &self.value_schema.fields()[0];
+                    HashMap::from([(field.name().to_string(), value)])
+                }
+            },
+            RedisLookupCommand::HGetAll => redis::from_redis_value(&value)?,
+        };
+        if values.is_empty() {
+            return Ok(None);
+        }
+
+        to_record(&self.value_schema, &values).map(Some)
+    }
+}
+
+#[async_trait]
+impl LookupSource for RedisLookup {
+    async fn open(&mut self, _context: &Context) -> core::Result<()> {
+        if let RedisLookupCommand::Get = self.command {
+            if self.value_schema.fields().len() != 1 {
+                return Err(core::Error::from(
+                    "the value schema of `GET` must have one field",
+                ));
+            }
+        }
+
+        let client = redis::Client::open(self.url.as_str())
+            .map_err(|e| core::Error::from(format!("open redis client error. {}", e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| core::Error::from(format!("connect redis error. {}", e)))?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    async fn lookup(&mut self, keys: &[Record]) -> core::Result<Vec<Option<Record>>> {
+        let mut pipeline = redis::pipe();
+        for key in keys {
+            let redis_key = self.redis_key(&mut key.clone())?;
+            let command = match self.command {
+                RedisLookupCommand::Get => "GET",
+                RedisLookupCommand::HGetAll => "HGETALL",
+            };
+            pipeline.cmd(command).arg(redis_key);
+        }
+
+        let connection = self
+            .connection
+            .as_mut()
+            .ok_or(core::Error::from("redis lookup is not opened"))?;
+        let values: Vec<redis::Value> = pipeline
+            .query_async(connection)
+            .await
+            .map_err(|e| core::Error::from(format!("lookup redis error. {}", e)))?;
+
+        let mut records = Vec::with_capacity(values.len());
+        for value in values {
+            records.push(self.to_value(value)?);
+        }
+        Ok(records)
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self) -> Schema {
+        self.value_schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::lookup::{RedisLookup, RedisLookupCommand};
+
+    #[test]
+    pub fn redis_lookup_test() {
+        let key_schema = Schema::new(vec![
+            Field::new("city", DataType::String),
+            Field::new("user_id", DataType::Int64),
+        ]);
+        let value_schema = Schema::new(vec![
+            Field::new("name", DataType::String),
+            Field::new("age", DataType::Int32),
+        ]);
+        let lookup = RedisLookup::new(
+            "redis://127.0.0.1/",
+            RedisLookupCommand::HGetAll,
+            key_schema.clone(),
+            value_schema.clone(),
+        )
+        .key_prefix("user:");
+
+        let mut key = Record::new();
+        let mut writer = key.as_writer(key_schema.as_type_ids());
+        writer.set_str("bj").unwrap();
+        writer.set_i64(7).unwrap();
+        assert_eq!(lookup.redis_key(&mut key).unwrap(), b"user:bj:7".to_vec());
+
+        // the empty hash is absent
+        let hash = |fields: Vec<(&str, &str)>| {
+            redis::Value::Map(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| {
+                        (
+                            redis::Value::BulkString(field.as_bytes().to_vec()),
+                            redis::Value::BulkString(value.as_bytes().to_vec()),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        assert!(lookup.to_value(hash(vec![])).unwrap().is_none());
+
+        let mut value = lookup
+            .to_value(hash(vec![("age", "30"), ("name", "a"), ("other", "x")]))
+            .unwrap()
+            .unwrap();
+        let reader = value.as_reader(value_schema.as_type_ids());
+        assert_eq!(reader.get_str(0).unwrap(), "a");
+        assert_eq!(reader.get_i32(1).unwrap(), 30);
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{BufferReader, BufferWriter, Record};
+
+/// Read the field as the Redis argument, the numbers and booleans are formatted as strings
+pub(crate) fn to_redis_arg(
+    reader: &BufferReader,
+    index: usize,
+    data_type: &DataType,
+) -> std::io::Result<Vec<u8>> {
+    let value = match data_type {
+        DataType::Boolean => reader.get_bool(index)?.to_string(),
+        DataType::Int8 => reader.get_i8(index)?.to_string(),
+        DataType::UInt8 => reader.get_u8(index)?.to_string(),
+        DataType::Int16 => reader.get_i16(index)?.to_string(),
+        DataType::UInt16 => reader.get_u16(index)?.to_string(),
+        DataType::Int32 => reader.get_i32(index)?.to_string(),
+        DataType::UInt32 => reader.get_u32(index)?.to_string(),
+        DataType::Int64 => reader.get_i64(index)?.to_string(),
+        DataType::UInt64 => reader.get_u64(index)?.to_string(),
+        DataType::Float32 => reader.get_f32(index)?.to_string(),
+        DataType::Float64 => reader.get_f64(index)?.to_string(),
+        DataType::Binary => return Ok(reader.get_binary(index)?.to_vec()),
+        DataType::String => return Ok(reader.get_str(index)?.as_bytes().to_vec()),
+    };
+    Ok(value.into_bytes())
+}
+
+/// Write the Redis field values to a `Record` of the `schema`, the values are matched by the
+/// schema field names and parsed to the field types, the missing value is written as the
+/// default value of the field type.
+pub(crate) fn to_record(
+    schema: &Schema,
+    values: &HashMap<String, redis::Value>,
+) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+    for field in schema.fields() {
+        let value: Vec<u8> = match values.get(field.name()) {
+            Some(value) => redis::from_redis_value(value)?,
+            None => Vec::new(),
+        };
+
+        write_field(&mut writer, field.data_type(), value)
+            .map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+    }
+
+    Ok(record)
+}
+
+fn write_field(
+    writer: &mut BufferWriter,
+    data_type: &DataType,
+    value: Vec<u8>,
+) -> anyhow::Result<()> {
+    if let DataType::Binary = data_type {
+        writer.set_binary(value.as_slice())?;
+        return Ok(());
+    }
+
+    let value = String::from_utf8(value)?;
+    if let DataType::String = data_type {
+        writer.set_str(value.as_str())?;
+        return Ok(());
+    }
+
+    let value = match value.trim() {
+        "" => "0",
+        v => v,
+    };
+    match data_type {
+        DataType::Boolean => writer.set_bool(value == "true" || value == "1")?,
+        DataType::Int8 => writer.set_i8(value.parse()?)?,
+        DataType::UInt8 => writer.set_u8(value.parse()?)?,
+        DataType::Int16 => writer.set_i16(value.parse()?)?,
+        DataType::UInt16 => writer.set_u16(value.parse()?)?,
+        DataType::Int32 => writer.set_i32(value.parse()?)?,
+        DataType::UInt32 => writer.set_u32(value.parse()?)?,
+        DataType::Int64 => writer.set_i64(value.parse()?)?,
+        DataType::UInt64 => writer.set_u64(value.parse()?)?,
+        DataType::Float32 => writer.set_f32(value.parse()?)?,
+        DataType::Float64 => writer.set_f64(value.parse()?)?,
+        DataType::Binary | DataType::String => {}
+    }
+    Ok(())
+}
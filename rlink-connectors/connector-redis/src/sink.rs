@@ -0,0 +1,352 @@
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use redis::{Cmd, Pipeline};
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::{DataType, Schema};
+use rlink::core::element::{BufferReader, Element, FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+
+use crate::record::to_redis_arg;
+
+const BATCH_SIZE_DEFAULT: usize = 1000;
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// The command to write a record, the keys and values are the columns of the input `Schema`
+/// referred by the field names
+#[derive(Clone, Debug)]
+pub enum RedisCommand {
+    /// `SET {key} {value}`
+    Set { key: String, value: String },
+    /// `HSET {key} {field} {value} ...`, all the columns except the `key` are written as the hash
+    /// fields of the same names
+    HSet { key: String },
+    /// `INCRBY {key} {value}`, the `value` must be an integer column
+    IncrBy { key: String, value: String },
+    /// `XADD {stream} [MAXLEN ~ {max_len}] * {field} {value} ...`, all the columns are written as
+    /// the entry fields of the same names, the `stream` is the key of the stream instead of a
+    /// column
+    XAdd {
+        stream: String,
+        max_len: Option<usize>,
+    },
+}
+
+/// The `RedisCommand` with the columns resolved to the field indexes
+enum ResolvedCommand {
+    Set {
+        key: usize,
+        value: usize,
+    },
+    HSet {
+        key: usize,
+    },
+    IncrBy {
+        key: usize,
+        value: usize,
+    },
+    XAdd {
+        stream: String,
+        max_len: Option<usize>,
+    },
+}
+
+impl RedisCommand {
+    fn resolve(&self, schema: &Schema) -> anyhow::Result<ResolvedCommand> {
+        let index = |name: &str| {
+            schema
+                .fields()
+                .iter()
+                .position(|field| field.name().eq(name))
+                .ok_or(anyhow!("column `{}` not found in the schema", name))
+        };
+
+        let command = match self {
+            Self::Set { key, value } => ResolvedCommand::Set {
+                key: index(key)?,
+                value: index(value)?,
+            },
+            Self::HSet { key } => ResolvedCommand::HSet { key: index(key)? },
+            Self::IncrBy { key, value } => {
+                let value = index(value)?;
+                let field = &schema.fields()[value];
+                match field.data_type() {
+                    DataType::Boolean
+                    | DataType::Float32
+                    | DataType::Float64
+                    | DataType::Binary
+                    | DataType::String => {
+                        return Err(anyhow!(
+                            "the INCRBY column `{}` is not an integer",
+                            field.name()
+                        ))
+                    }
+                    _ => {}
+                }
+                ResolvedCommand::IncrBy {
+                    key: index(key)?,
+                    value,
+                }
+            }
+            Self::XAdd { stream, max_len } => ResolvedCommand::XAdd {
+                stream: stream.clone(),
+                max_len: *max_len,
+            },
+        };
+        Ok(command)
+    }
+}
+
+/// Write the records to Redis by the `RedisCommand`, the commands are sent in a pipeline for
+/// every `batch_size` records and on every checkpoint, so the records before the checkpoint are
+/// written at least once. The failed pipeline is retried with backoff until written, the later
+/// records and checkpoints wait for it. The records failed to convert to the commands are
+/// discarded, and fail the next checkpoint.
+///
+/// With `ttl`, the keys are expired after the duration since the last write.
+#[derive(NamedFunction)]
+pub struct RedisOutputFormat {
+    url: String,
+    command: RedisCommand,
+    key_prefix: String,
+    ttl: Option<Duration>,
+    batch_size: usize,
+
+    schema: Schema,
+    resolved_command: Option<ResolvedCommand>,
+    connection: Option<ConnectionManager>,
+    pipeline: Pipeline,
+    pipeline_size: usize,
+    /// the records failed to convert since the last checkpoint, they are discarded and the next
+    /// checkpoint is declined with the first error
+    discarded: usize,
+    discard_error: Option<String>,
+}
+
+impl RedisOutputFormat {
+    pub fn new(url: &str, command: RedisCommand) -> Self {
+        RedisOutputFormat {
+            url: url.to_string(),
+            command,
+            key_prefix: "".to_string(),
+            ttl: None,
+            batch_size: BATCH_SIZE_DEFAULT,
+            schema: Schema::empty(),
+            resolved_command: None,
+            connection: None,
+            pipeline: redis::pipe(),
+            pipeline_size: 0,
+            discarded: 0,
+            discard_error: None,
+        }
+    }
+
+    /// Prepend the prefix to the keys of `SET`, `HSET` and `INCRBY`
+    pub fn key_prefix(mut self, key_prefix: &str) -> Self {
+        self.key_prefix = key_prefix.to_string();
+        self
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn key(&self, reader: &BufferReader, index: usize) -> anyhow::Result<Vec<u8>> {
+        let mut key = self.key_prefix.as_bytes().to_vec();
+        key.extend(to_redis_arg(
+            reader,
+            index,
+            self.schema.fields()[index].data_type(),
+        )?);
+        Ok(key)
+    }
+
+    fn append(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        let fields = self.schema.fields();
+
+        let (key, cmd) = match self.resolved_command.as_ref().unwrap() {
+            ResolvedCommand::Set { key, value } => {
+                let key = self.key(&reader, *key)?;
+                let mut cmd = Cmd::new();
+                cmd.arg("SET").arg(key.as_slice()).arg(to_redis_arg(
+                    &reader,
+                    *value,
+                    fields[*value].data_type(),
+                )?);
+                if let Some(ttl) = self.ttl {
+                    cmd.arg("PX").arg(ttl.as_millis() as u64);
+                }
+                // the ttl is set by the command
+                (None, cmd)
+            }
+            ResolvedCommand::HSet { key } => {
+                let key_index = *key;
+                let key = self.key(&reader, key_index)?;
+                let mut cmd = Cmd::new();
+                cmd.arg("HSET").arg(key.as_slice());
+                for (index, field) in fields.iter().enumerate() {
+                    if index != key_index {
+                        cmd.arg(field.name())
+                            .arg(to_redis_arg(&reader, index, field.data_type())?);
+                    }
+                }
+                (Some(key), cmd)
+            }
+            ResolvedCommand::IncrBy { key, value } => {
+                let key = self.key(&reader, *key)?;
+                let mut cmd = Cmd::new();
+                cmd.arg("INCRBY").arg(key.as_slice()).arg(to_redis_arg(
+                    &reader,
+                    *value,
+                    fields[*value].data_type(),
+                )?);
+                (Some(key), cmd)
+            }
+            ResolvedCommand::XAdd { stream, max_len } => {
+                let stream = stream.as_bytes().to_vec();
+                let mut cmd = Cmd::new();
+                cmd.arg("XADD").arg(stream.as_slice());
+                if let Some(max_len) = max_len {
+                    cmd.arg("MAXLEN").arg("~").arg(*max_len);
+                }
+                cmd.arg("*");
+                for (index, field) in fields.iter().enumerate() {
+                    cmd.arg(field.name())
+                        .arg(to_redis_arg(&reader, index, field.data_type())?);
+                }
+                (Some(stream), cmd)
+            }
+        };
+
+        self.pipeline.add_command(cmd).ignore();
+        if let (Some(key), Some(ttl)) = (key, self.ttl) {
+            self.pipeline
+                .cmd("PEXPIRE")
+                .arg(key)
+                .arg(ttl.as_millis() as u64)
+                .ignore();
+        }
+        self.pipeline_size += 1;
+
+        Ok(())
+    }
+
+    /// Send the pipeline, it's kept and retried with backoff until written
+    async fn flush(&mut self) {
+        if self.pipeline_size == 0 {
+            return;
+        }
+
+        let mut backoff = RETRY_BACKOFF;
+        loop {
+            let rt = self
+                .pipeline
+                .query_async::<()>(self.connection.as_mut().unwrap())
+                .await;
+            match rt {
+                Ok(()) => break,
+                Err(e) => {
+                    error!(
+                        "write redis error, retry the {} records after {:?}. {}",
+                        self.pipeline_size, backoff, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
+                }
+            }
+        }
+
+        self.pipeline = redis::pipe();
+        self.pipeline_size = 0;
+    }
+
+    /// The error of the records discarded since the last call
+    fn take_discard_error(&mut self) -> Option<String> {
+        let discarded = std::mem::replace(&mut self.discarded, 0);
+        self.discard_error.take().map(|e| {
+            format!(
+                "{} records are discarded, the first error: {}",
+                discarded, e
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl OutputFormat for RedisOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        self.schema = context.input_schema.first().clone();
+        self.resolved_command = Some(self.command.resolve(&self.schema)?);
+
+        let client = redis::Client::open(self.url.as_str())
+            .map_err(|e| core::Error::from(format!("open redis client error. {}", e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| core::Error::from(format!("connect redis error. {}", e)))?;
+        self.connection = Some(connection);
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let mut record = element.into_record();
+        if let Err(e) = self.append(&mut record) {
+            // surfaced as a failure of the next checkpoint
+            error!(
+                "convert the record to redis command error, discard it. {}",
+                e
+            );
+            self.discarded += 1;
+            if self.discard_error.is_none() {
+                self.discard_error = Some(e.to_string());
+            }
+            return;
+        }
+
+        if self.pipeline_size >= self.batch_size {
+            self.flush().await;
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush().await;
+        match self.take_discard_error() {
+            Some(e) => Err(core::Error::from(format!("write redis error. {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for RedisOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        self.flush().await;
+        if let Some(e) = self.take_discard_error() {
+            context.decline(format!("write redis error. {}", e).as_str());
+        }
+        None
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// The id of a stream entry, `{milliseconds}-{sequence}`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamEntryId {
+    pub millis: u64,
+    pub sequence: u64,
+}
+
+impl FromStr for StreamEntryId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (millis, sequence) = s.split_once('-').unwrap_or((s, "0"));
+        Ok(StreamEntryId {
+            millis: millis.parse()?,
+            sequence: sequence.parse()?,
+        })
+    }
+}
+
+impl Display for StreamEntryId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.millis, self.sequence)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StreamSnapshot {
+    last_id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct StreamStateInner {
+    last_id: Option<StreamEntryId>,
+    /// the ids of the entries emitted after the last checkpoint
+    emitted: Vec<String>,
+}
+
+/// The last emitted entry id of the task's consumer, and the entry ids to ack
+#[derive(Debug, Clone, Default)]
+pub struct RedisStreamState {
+    inner: Arc<Mutex<StreamStateInner>>,
+}
+
+impl RedisStreamState {
+    pub fn last_id(&self) -> Option<StreamEntryId> {
+        self.inner.lock().unwrap().last_id
+    }
+
+    pub(crate) fn emit(&self, id: String) {
+        let mut inner = self.inner.lock().unwrap();
+        match StreamEntryId::from_str(id.as_str()) {
+            Ok(entry_id) => inner.last_id = Some(entry_id),
+            Err(e) => warn!("unknown stream entry id {}. {}", id, e),
+        }
+        inner.emitted.push(id);
+    }
+
+    /// The snapshot of the last id, and the ids emitted since the last snapshot
+    pub(crate) fn snapshot(&self) -> (String, Vec<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        let snapshot = StreamSnapshot {
+            last_id: inner.last_id.map(|x| x.to_string()),
+        };
+        let emitted = std::mem::take(&mut inner.emitted);
+        (serde_json::to_string(&snapshot).unwrap(), emitted)
+    }
+
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let snapshot: StreamSnapshot = serde_json::from_str(snapshot_handle)?;
+        let last_id = match snapshot.last_id {
+            Some(last_id) => Some(StreamEntryId::from_str(last_id.as_str())?),
+            None => None,
+        };
+        self.inner.lock().unwrap().last_id = last_id;
+        Ok(())
+    }
+}
+
+/// The entry ids emitted before the checkpoints, acked when the checkpoints are completed
+#[derive(Debug, Default)]
+pub(crate) struct PendingAcks {
+    pending: BTreeMap<u64, Vec<String>>,
+}
+
+impl PendingAcks {
+    pub fn add(&mut self, checkpoint_id: u64, ids: Vec<String>) {
+        if !ids.is_empty() {
+            self.pending.entry(checkpoint_id).or_default().extend(ids);
+        }
+    }
+
+    /// Take the ids of the checkpoints covered by the `completed_checkpoint_id`
+    pub fn complete(&mut self, completed_checkpoint_id: u64) -> Vec<String> {
+        let remaining = self.pending.split_off(&(completed_checkpoint_id + 1));
+        let completed = std::mem::replace(&mut self.pending, remaining);
+        completed.into_values().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::source::checkpoint::{PendingAcks, RedisStreamState, StreamEntryId};
+
+    #[test]
+    pub fn stream_state_test() {
+        let a = StreamEntryId::from_str("1700000000000-2").unwrap();
+        let b = StreamEntryId::from_str("1700000000000-10").unwrap();
+        assert!(a < b);
+        assert_eq!(b.to_string(), "1700000000000-10");
+
+        let state = RedisStreamState::default();
+        state.emit("1700000000000-2".to_string());
+        state.emit("1700000000000-10".to_string());
+        let (handle, emitted) = state.snapshot();
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(state.snapshot().1.len(), 0);
+
+        let restored = RedisStreamState::default();
+        restored.update_from_snapshot(handle.as_str()).unwrap();
+        assert_eq!(restored.last_id(), Some(b));
+
+        let mut pending_acks = PendingAcks::default();
+        pending_acks.add(1, emitted);
+        pending_acks.add(2, vec!["1700000000001-0".to_string()]);
+        assert_eq!(pending_acks.complete(1).len(), 2);
+        assert_eq!(
+            pending_acks.complete(2),
+            vec!["1700000000001-0".to_string()]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, RedisResult};
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::FnSchema;
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::metrics::Tag;
+
+use crate::record::to_record;
+use crate::source::checkpoint::{PendingAcks, RedisStreamState, StreamEntryId};
+use crate::source::stream::RedisRecordStream;
+use crate::source::StreamRecord;
+
+const BATCH_SIZE_DEFAULT: usize = 1000;
+const BUFFER_SIZE_DEFAULT: usize = 10000;
+/// The max time of a `XREADGROUP` blocking for the new entries
+const BLOCK_MILLIS: usize = 1000;
+
+/// Read the entries of a Redis Stream by the consumer group, each task reads as the consumer
+/// `{consumer_prefix}-{task_number}`, so the entries are distributed among the tasks by Redis.
+/// The entry fields are matched by the schema field names.
+///
+/// The last emitted entry id is checkpointed, and the entries are acked when the checkpoints are
+/// completed. The recovered task re-emits the pending entries delivered to its consumer after the
+/// checkpointed id, and acks the ones before. The consumer names must be kept across the
+/// restarts, so the parallelism should not be changed, otherwise the pending entries of the
+/// removed consumers are left to be claimed manually.
+#[derive(NamedFunction)]
+pub struct RedisStreamInputFormat {
+    url: String,
+    stream: String,
+    group: String,
+    consumer_prefix: String,
+    schema: Schema,
+    parallelism: u16,
+    /// the id the group is created at, `$` for the new entries, `0` for all the entries
+    group_start_id: String,
+    batch_size: usize,
+    buffer_size: usize,
+
+    client: Option<redis::Client>,
+    connection: Option<ConnectionManager>,
+    consumer: String,
+    tags: Vec<Tag>,
+    state: RedisStreamState,
+    pending_acks: PendingAcks,
+    restore_error: Option<String>,
+}
+
+impl RedisStreamInputFormat {
+    pub fn new(url: &str, stream: &str, group: &str, schema: Schema, parallelism: u16) -> Self {
+        RedisStreamInputFormat {
+            url: url.to_string(),
+            stream: stream.to_string(),
+            group: group.to_string(),
+            consumer_prefix: "consumer".to_string(),
+            schema,
+            parallelism,
+            group_start_id: "$".to_string(),
+            batch_size: BATCH_SIZE_DEFAULT,
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            client: None,
+            connection: None,
+            consumer: "".to_string(),
+            tags: vec![],
+            state: RedisStreamState::default(),
+            pending_acks: PendingAcks::default(),
+            restore_error: None,
+        }
+    }
+
+    pub fn consumer_prefix(mut self, consumer_prefix: &str) -> Self {
+        self.consumer_prefix = consumer_prefix.to_string();
+        self
+    }
+
+    /// Read all the entries of the stream if the group is created by the job, instead of the
+    /// entries added after
+    pub fn from_beginning(mut self) -> Self {
+        self.group_start_id = "0".to_string();
+        self
+    }
+
+    /// The max number of the entries read by a `XREADGROUP`
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    async fn create_group(&mut self) -> RedisResult<()> {
+        let rt: RedisResult<()> = redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(self.stream.as_str())
+            .arg(self.group.as_str())
+            .arg(self.group_start_id.as_str())
+            .arg("MKSTREAM")
+            .query_async(self.connection.as_mut().unwrap())
+            .await;
+        match rt {
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            rt => rt,
+        }
+    }
+}
+
+#[async_trait]
+impl InputFormat for RedisStreamInputFormat {
+    async fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.restore_error {
+            return Err(core::Error::from(format!(
+                "restore redis stream source error. {}",
+                e
+            )));
+        }
+
+        let client = redis::Client::open(self.url.as_str())
+            .map_err(|e| core::Error::from(format!("open redis client error. {}", e)))?;
+        let connection = ConnectionManager::new(client.clone())
+            .await
+            .map_err(|e| core::Error::from(format!("connect redis error. {}", e)))?;
+        self.client = Some(client);
+        self.connection = Some(connection);
+
+        self.create_group()
+            .await
+            .map_err(|e| core::Error::from(format!("create consumer group error. {}", e)))?;
+
+        self.consumer = format!("{}-{}", self.consumer_prefix, context.task_id.task_number());
+        self.tags.push(Tag::new("stream", self.stream.as_str()));
+        self.tags.push(Tag::new("consumer", self.consumer.as_str()));
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let (sender, receiver) =
+            named_channel("RedisSource_Handover", self.tags.clone(), self.buffer_size);
+
+        // the blocking reads hold the connection, so the reader has its own
+        let connection = ConnectionManager::new(self.client.clone().unwrap())
+            .await
+            .unwrap();
+        let reader = RedisStreamReader {
+            connection,
+            stream: self.stream.clone(),
+            group: self.group.clone(),
+            consumer: self.consumer.clone(),
+            schema: self.schema.clone(),
+            batch_size: self.batch_size,
+            restored_last_id: self.state.last_id(),
+            sender,
+        };
+        tokio::spawn(reader.run());
+
+        Box::pin(RedisRecordStream::new(receiver, self.state.clone()))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for RedisStreamInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            match self.state.update_from_snapshot(handle.handle.as_str()) {
+                Ok(()) => info!(
+                    "load redis stream state from checkpoint({:?}): {}",
+                    context.checkpoint_id, handle.handle
+                ),
+                Err(e) => self.restore_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        let (handle, emitted) = self.state.snapshot();
+        self.pending_acks.add(context.checkpoint_id.0, emitted);
+
+        if let Some(completed_checkpoint_id) = context.completed_checkpoint_id {
+            let ids = self.pending_acks.complete(completed_checkpoint_id.0);
+            if !ids.is_empty() {
+                let rt: RedisResult<usize> = self
+                    .connection
+                    .as_mut()
+                    .unwrap()
+                    .xack(self.stream.as_str(), self.group.as_str(), &ids)
+                    .await;
+                // the entries left pending are acked when recovering
+                if let Err(e) = rt {
+                    warn!("ack {} redis stream entries error. {}", ids.len(), e);
+                }
+            }
+        }
+
+        Some(CheckpointHandle { handle })
+    }
+}
+
+/// One split per task, the entries are distributed among the consumers of the tasks by Redis
+impl InputSplitSource for RedisStreamInputFormat {}
+
+struct RedisStreamReader {
+    connection: ConnectionManager,
+    stream: String,
+    group: String,
+    consumer: String,
+    schema: Schema,
+    batch_size: usize,
+    restored_last_id: Option<StreamEntryId>,
+    sender: ChannelSender<StreamRecord>,
+}
+
+impl RedisStreamReader {
+    async fn run(mut self) {
+        loop {
+            match self.recover_pending().await {
+                Ok(()) => break,
+                Err(e) => {
+                    error!("read the pending redis stream entries error. {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+
+        loop {
+            let entries = match self.read(">", true).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    error!("read redis stream error. {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                if !self.send(entry).await {
+                    info!("redis stream is closed, the reader exits");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Re-emit the entries delivered to the consumer but not emitted before the restored
+    /// checkpoint, and ack the ones emitted before
+    async fn recover_pending(&mut self) -> RedisResult<()> {
+        let mut cursor = "0".to_string();
+        let mut ack_ids = Vec::new();
+        loop {
+            let entries = self.read(cursor.as_str(), false).await?;
+            if entries.is_empty() {
+                break;
+            }
+
+            for entry in entries {
+                cursor = entry.id.clone();
+                let emitted = match (self.restored_last_id, StreamEntryId::from_str(&entry.id)) {
+                    (Some(last_id), Ok(id)) => id <= last_id,
+                    _ => false,
+                };
+                if emitted {
+                    ack_ids.push(entry.id);
+                } else if !self.send(entry).await {
+                    return Ok(());
+                }
+            }
+        }
+
+        if !ack_ids.is_empty() {
+            info!(
+                "ack {} redis stream entries emitted before the checkpoint",
+                ack_ids.len()
+            );
+            self.connection
+                .xack::<_, _, _, usize>(self.stream.as_str(), self.group.as_str(), &ack_ids)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn read(&mut self, id: &str, block: bool) -> RedisResult<Vec<StreamId>> {
+        let mut options = StreamReadOptions::default()
+            .group(self.group.as_str(), self.consumer.as_str())
+            .count(self.batch_size);
+        if block {
+            options = options.block(BLOCK_MILLIS);
+        }
+
+        let reply: Option<StreamReadReply> = self
+            .connection
+            .xread_options(&[self.stream.as_str()], &[id], &options)
+            .await?;
+        let entries = reply
+            .map(|reply| reply.keys.into_iter().flat_map(|key| key.ids).collect())
+            .unwrap_or_default();
+        Ok(entries)
+    }
+
+    /// Returns false if the stream is closed
+    async fn send(&self, entry: StreamId) -> bool {
+        let record = match to_record(&self.schema, &entry.map) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!("discard the redis stream entry {}. {}", entry.id, e);
+                None
+            }
+        };
+
+        let stream_record = StreamRecord {
+            id: entry.id,
+            record,
+        };
+        self.sender.send(stream_record).await.is_ok()
+    }
+}
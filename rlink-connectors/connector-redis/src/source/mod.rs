@@ -0,0 +1,10 @@
+pub mod checkpoint;
+pub mod input_format;
+pub mod stream;
+
+/// The entry message from the stream reader to the stream, the `record` is `None` if the entry
+/// failed to decode, it's acked without emitting
+pub(crate) struct StreamRecord {
+    id: String,
+    record: Option<rlink::core::element::Record>,
+}
@@ -0,0 +1,44 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::Element;
+use rlink::core::function::ElementStream;
+
+use crate::source::checkpoint::RedisStreamState;
+use crate::source::StreamRecord;
+
+/// The records of the entries delivered to the task's consumer, the last id is updated when the
+/// records are emitted
+pub struct RedisRecordStream {
+    receiver: ChannelReceiver<StreamRecord>,
+    state: RedisStreamState,
+}
+
+impl RedisRecordStream {
+    pub(crate) fn new(receiver: ChannelReceiver<StreamRecord>, state: RedisStreamState) -> Self {
+        RedisRecordStream { receiver, state }
+    }
+}
+
+impl ElementStream for RedisRecordStream {}
+
+impl Stream for RedisRecordStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.as_mut().receiver.poll_recv(cx) {
+                Poll::Ready(Some(stream_record)) => {
+                    self.state.emit(stream_record.id);
+                    if let Some(record) = stream_record.record {
+                        return Poll::Ready(Some(Element::Record(record)));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
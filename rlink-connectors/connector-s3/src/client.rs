@@ -0,0 +1,139 @@
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::Client;
+
+/// The connection of an S3-compatible storage, such as AWS S3 or MinIO
+#[derive(Clone, Debug)]
+pub struct S3Config {
+    region: String,
+    endpoint: Option<String>,
+    /// the default credential chain of AWS is used if absent
+    credentials: Option<Credentials>,
+    force_path_style: bool,
+}
+
+impl S3Config {
+    pub fn new(region: &str) -> Self {
+        S3Config {
+            region: region.to_string(),
+            endpoint: None,
+            credentials: None,
+            force_path_style: false,
+        }
+    }
+
+    /// The endpoint of the S3-compatible storage, such as `http://localhost:9000` of MinIO, the
+    /// path-style addressing is enabled too
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self.force_path_style = true;
+        self
+    }
+
+    pub fn credentials(mut self, access_key_id: &str, secret_access_key: &str) -> Self {
+        self.credentials = Some(Credentials::new(
+            access_key_id,
+            secret_access_key,
+            None,
+            None,
+            "rlink",
+        ));
+        self
+    }
+
+    /// Address the bucket by the path `{endpoint}/{bucket}` instead of the virtual host
+    pub fn force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+
+    pub async fn create_client(&self) -> Client {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(self.region.clone()));
+        if let Some(credentials) = &self.credentials {
+            loader = loader.credentials_provider(credentials.clone());
+        }
+        let sdk_config = loader.load().await;
+
+        let mut builder =
+            aws_sdk_s3::config::Builder::from(&sdk_config).force_path_style(self.force_path_style);
+        if let Some(endpoint) = &self.endpoint {
+            builder = builder.endpoint_url(endpoint.as_str());
+        }
+        Client::from_conf(builder.build())
+    }
+}
+
+/// The location of the objects, `s3://{bucket}/{key}`. The key is a prefix for the sources and
+/// the sinks, the objects are listed and written under it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct S3Url {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl S3Url {
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let path = url
+            .strip_prefix("s3://")
+            .ok_or(anyhow!("the url {} is not started with `s3://`", url))?;
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        if bucket.is_empty() {
+            return Err(anyhow!("the bucket of url {} is empty", url));
+        }
+
+        Ok(S3Url {
+            bucket: bucket.to_string(),
+            key: key.trim_matches('/').to_string(),
+        })
+    }
+
+    /// The key of the `path` under the key of the url, the empty segments are skipped
+    pub fn join(&self, path: &[&str]) -> String {
+        std::iter::once(self.key.as_str())
+            .chain(path.iter().copied())
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<&str>>()
+            .join("/")
+    }
+
+    /// The prefix to list the objects under the key of the url
+    pub(crate) fn list_prefix(&self) -> String {
+        if self.key.is_empty() {
+            "".to_string()
+        } else {
+            format!("{}/", self.key)
+        }
+    }
+}
+
+/// The SDK errors are displayed with the causes, such as the error code of the service
+pub(crate) fn s3_error<E: std::error::Error>(e: E) -> anyhow::Error {
+    anyhow!("{}", DisplayErrorContext(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::S3Url;
+
+    #[test]
+    pub fn s3_url_test() {
+        let url = S3Url::parse("s3://archive/events/").unwrap();
+        assert_eq!(url.bucket, "archive");
+        assert_eq!(url.key, "events");
+        assert_eq!(url.list_prefix(), "events/");
+        assert_eq!(
+            url.join(&["", "part-0-1.json"]),
+            "events/part-0-1.json".to_string()
+        );
+
+        let url = S3Url::parse("s3://archive").unwrap();
+        assert_eq!(url.key, "");
+        assert_eq!(url.list_prefix(), "");
+        assert_eq!(url.join(&["dt=1", "part-0-1.json"]), "dt=1/part-0-1.json");
+
+        assert!(S3Url::parse("archive/events").is_err());
+        assert!(S3Url::parse("s3:///events").is_err());
+    }
+}
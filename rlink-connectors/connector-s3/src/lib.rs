@@ -0,0 +1,18 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod client;
+pub mod sink;
+pub mod source;
+
+pub use client::{S3Config, S3Url};
+pub use sink::output_format::S3OutputFormat;
+pub use source::input_format::S3InputFormat;
@@ -0,0 +1,3 @@
+pub mod output_format;
+pub(crate) mod storage;
+pub(crate) mod upload;
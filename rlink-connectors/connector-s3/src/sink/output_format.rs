@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::element::{Element, FnSchema};
+use rlink::core::function::{Context, OutputFormat};
+use rlink_connector_files::sink::buckets::{Buckets, FileSinkSnapshot};
+use rlink_connector_files::{BucketAssigner, FileFormat, RollingPolicy};
+
+use crate::client::{S3Config, S3Url};
+use crate::sink::storage::S3PartStorage;
+use crate::sink::upload::MIN_PART_SIZE;
+
+const PART_SIZE_DEFAULT: u64 = 16 * 1024 * 1024;
+
+/// Write the records to the objects in the bucket prefixes of the `url`, such as
+/// `s3://archive/events`, encoded by the `FileFormat` with the input `Schema`.
+///
+/// The part files are written to the local staging files, and uploaded as the multipart uploads
+/// of `{prefix}-{task_number}-{counter}.{ext}` by the parts of `part_size` while written, the
+/// rest is uploaded when rolled by the `RollingPolicy` and on every checkpoint. The uploads are
+/// completed only when a later checkpoint is completed, the job may still recover from the
+/// checkpoint before the latest completed one, so the objects are visible exactly once. The
+/// uncompleted uploads of the task are aborted when recovering.
+#[derive(NamedFunction)]
+pub struct S3OutputFormat {
+    config: S3Config,
+    url: String,
+    format: FileFormat,
+    bucket_assigner: BucketAssigner,
+    rolling_policy: RollingPolicy,
+    part_prefix: String,
+    part_size: u64,
+    staging_dir: PathBuf,
+
+    buckets: Option<Buckets<S3PartStorage>>,
+    /// the first write error, the records are lost, so all the later checkpoints are declined
+    write_error: Option<String>,
+}
+
+impl S3OutputFormat {
+    pub fn new(config: S3Config, url: &str, format: FileFormat) -> Self {
+        S3OutputFormat {
+            config,
+            url: url.to_string(),
+            format,
+            bucket_assigner: BucketAssigner::Base,
+            rolling_policy: RollingPolicy::default(),
+            part_prefix: "part".to_string(),
+            part_size: PART_SIZE_DEFAULT,
+            staging_dir: std::env::temp_dir(),
+            buckets: None,
+            write_error: None,
+        }
+    }
+
+    pub fn bucket_assigner(mut self, bucket_assigner: BucketAssigner) -> Self {
+        self.bucket_assigner = bucket_assigner;
+        self
+    }
+
+    pub fn rolling_policy(mut self, rolling_policy: RollingPolicy) -> Self {
+        self.rolling_policy = rolling_policy;
+        self
+    }
+
+    pub fn part_prefix(mut self, part_prefix: &str) -> Self {
+        self.part_prefix = part_prefix.to_string();
+        self
+    }
+
+    /// The size of the upload parts, 5MB at least
+    pub fn part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size.max(MIN_PART_SIZE);
+        self
+    }
+
+    /// The local directory of the part files before uploaded, the system temporary directory
+    /// by default
+    pub fn staging_dir(mut self, staging_dir: &str) -> Self {
+        self.staging_dir = PathBuf::from(staging_dir);
+        self
+    }
+}
+
+#[async_trait]
+impl OutputFormat for S3OutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        let url = S3Url::parse(self.url.as_str())?;
+        let storage = S3PartStorage::new(
+            self.config.create_client().await,
+            url,
+            self.part_size,
+            self.staging_dir.clone(),
+        );
+        self.buckets = Some(Buckets::new(
+            storage,
+            self.format.clone(),
+            self.bucket_assigner.clone(),
+            self.rolling_policy,
+            self.part_prefix.clone(),
+            context.task_id.task_number(),
+            context.input_schema.first().clone(),
+        ));
+
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.write_error {
+            return Err(core::Error::from(format!("restore s3 sink error. {}", e)));
+        }
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        if self.write_error.is_some() {
+            return;
+        }
+
+        let mut record = element.into_record();
+        if let Err(e) = self.buckets.as_mut().unwrap().write(&mut record).await {
+            // surfaced as a failure of the next checkpoint
+            error!("write s3 error, the later records are discarded. {}", e);
+            self.write_error = Some(e.to_string());
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        if let Some(e) = &self.write_error {
+            return Err(core::Error::from(format!("write s3 error. {}", e)));
+        }
+
+        self.buckets.as_mut().unwrap().close().await?;
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for S3OutputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        let snapshot = match handle {
+            Some(handle) => match FileSinkSnapshot::from_handle(handle) {
+                Ok(snapshot) => {
+                    info!(
+                        "load s3 sink from checkpoint({:?}): {:?}",
+                        context.checkpoint_id, snapshot
+                    );
+                    Some(snapshot)
+                }
+                Err(e) => {
+                    self.write_error = Some(format!("parse s3 sink checkpoint error. {}", e));
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        if let Err(e) = self.buckets.as_mut().unwrap().restore(snapshot).await {
+            self.write_error = Some(e.to_string());
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        if let Some(e) = &self.write_error {
+            context.decline(format!("write s3 error. {}", e).as_str());
+            return None;
+        }
+
+        let snapshot = self
+            .buckets
+            .as_mut()
+            .unwrap()
+            .snapshot(
+                context.checkpoint_id.0,
+                context.completed_checkpoint_id.map(|x| x.0),
+            )
+            .await;
+        match snapshot {
+            Ok(snapshot) => Some(snapshot.to_handle()),
+            Err(e) => {
+                context.decline(format!("snapshot s3 sink error. {}", e).as_str());
+                self.write_error = Some(e.to_string());
+                None
+            }
+        }
+    }
+}
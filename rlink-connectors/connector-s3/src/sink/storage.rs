@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use aws_sdk_s3::Client;
+use rlink_connector_files::sink::storage::PartStorage;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::client::S3Url;
+use crate::sink::upload::{abort_uploads, object_exists, PendingUpload};
+
+/// A part file written to the local staging file, and uploaded part by part while written
+pub(crate) struct StagingPart {
+    key: String,
+    staging_path: PathBuf,
+    /// the upload is created by the first part
+    upload: Option<PendingUpload>,
+    /// the size of the staging file uploaded
+    uploaded: u64,
+}
+
+/// The part files uploaded as the objects in the bucket prefixes of the `url` by the multipart
+/// uploads, which are completed when committed
+pub(crate) struct S3PartStorage {
+    client: Client,
+    url: S3Url,
+    part_size: u64,
+    staging_dir: PathBuf,
+}
+
+impl S3PartStorage {
+    pub fn new(client: Client, url: S3Url, part_size: u64, staging_dir: PathBuf) -> Self {
+        S3PartStorage {
+            client,
+            url,
+            part_size,
+            staging_dir,
+        }
+    }
+
+    /// Upload the staging file after the uploaded size as the parts of `part_size`, the
+    /// remaining data less than `part_size` is uploaded as the last part only if `last`
+    async fn upload_parts(&self, part: &mut StagingPart, last: bool) -> anyhow::Result<()> {
+        let bucket = self.url.bucket.as_str();
+        let file_size = tokio::fs::metadata(part.staging_path.as_path())
+            .await?
+            .len();
+        if file_size - part.uploaded < self.part_size && !last {
+            return Ok(());
+        }
+
+        let mut reader = tokio::fs::File::open(part.staging_path.as_path()).await?;
+        reader.seek(SeekFrom::Start(part.uploaded)).await?;
+        loop {
+            let remaining = file_size - part.uploaded;
+            // a multipart upload has one part at least, even if the file is empty
+            let len = if remaining >= self.part_size {
+                self.part_size
+            } else if last && (remaining > 0 || part.upload.is_none()) {
+                remaining
+            } else {
+                return Ok(());
+            };
+
+            let mut buf = vec![0; len as usize];
+            reader.read_exact(buf.as_mut_slice()).await?;
+
+            if part.upload.is_none() {
+                let upload = PendingUpload::create(&self.client, bucket, part.key.as_str()).await?;
+                part.upload = Some(upload);
+            }
+            let upload = part.upload.as_mut().unwrap();
+            upload.upload_part(&self.client, bucket, buf).await?;
+            part.uploaded += len;
+        }
+    }
+}
+
+#[async_trait]
+impl PartStorage for S3PartStorage {
+    type InProgress = StagingPart;
+    type Pending = PendingUpload;
+
+    async fn create(
+        &self,
+        bucket_id: &str,
+        name: &str,
+    ) -> anyhow::Result<Option<(StagingPart, File)>> {
+        let key = self.url.join(&[bucket_id, name]);
+        if object_exists(&self.client, self.url.bucket.as_str(), key.as_str()).await? {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(self.staging_dir.as_path())?;
+        let staging_path = self.staging_dir.join(format!(
+            ".{}-{}-{}",
+            self.url.bucket,
+            std::process::id(),
+            name
+        ));
+        let file = File::create(staging_path.as_path())
+            .map_err(|e| anyhow!("create staging file {:?} error. {}", staging_path, e))?;
+
+        let part = StagingPart {
+            key,
+            staging_path,
+            upload: None,
+            uploaded: 0,
+        };
+        Ok(Some((part, file)))
+    }
+
+    /// Upload the full parts of the staging file written, the buffered records are not in the
+    /// staging file yet, so the file size is checked only if the written `size` is enough
+    async fn written(&self, part: &mut StagingPart, size: u64) -> anyhow::Result<()> {
+        if size.saturating_sub(part.uploaded) < self.part_size {
+            return Ok(());
+        }
+        self.upload_parts(part, false).await
+    }
+
+    /// Upload the rest of the staging file, it's removed after uploaded
+    async fn finish(&self, mut part: StagingPart) -> anyhow::Result<PendingUpload> {
+        self.upload_parts(&mut part, true).await?;
+        std::fs::remove_file(part.staging_path.as_path())?;
+        Ok(part.upload.unwrap())
+    }
+
+    async fn commit(&self, part: &PendingUpload) -> anyhow::Result<()> {
+        part.commit(&self.client, self.url.bucket.as_str()).await
+    }
+
+    /// Abort the uncompleted uploads
+    async fn clean_up(&self, name_prefix: &str) -> anyhow::Result<()> {
+        abort_uploads(
+            &self.client,
+            self.url.bucket.as_str(),
+            self.url.list_prefix().as_str(),
+            name_prefix,
+        )
+        .await
+    }
+}
@@ -0,0 +1,179 @@
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+
+use crate::client::s3_error;
+
+/// The min size of the parts except the last one, limited by S3
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct UploadedPart {
+    part_number: i32,
+    e_tag: String,
+}
+
+/// A multipart upload of a part file, the object is visible only after the upload is completed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PendingUpload {
+    pub key: String,
+    upload_id: String,
+    parts: Vec<UploadedPart>,
+}
+
+impl PendingUpload {
+    /// Create a new multipart upload of the `key` without any part
+    pub async fn create(client: &Client, bucket: &str, key: &str) -> anyhow::Result<Self> {
+        let upload_id = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(s3_error)?
+            .upload_id
+            .ok_or(anyhow!("the upload id of {} is not returned", key))?;
+
+        Ok(PendingUpload {
+            key: key.to_string(),
+            upload_id,
+            parts: Vec::new(),
+        })
+    }
+
+    /// Upload the `buf` as the next part, all the parts except the last one must be
+    /// `MIN_PART_SIZE` at least
+    pub async fn upload_part(
+        &mut self,
+        client: &Client,
+        bucket: &str,
+        buf: Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let part_number = self.parts.len() as i32 + 1;
+        let e_tag = client
+            .upload_part()
+            .bucket(bucket)
+            .key(self.key.as_str())
+            .upload_id(self.upload_id.as_str())
+            .part_number(part_number)
+            .body(ByteStream::from(buf))
+            .send()
+            .await
+            .map_err(s3_error)?
+            .e_tag
+            .ok_or(anyhow!(
+                "the etag of {} part {} is not returned",
+                self.key,
+                part_number
+            ))?;
+        self.parts.push(UploadedPart { part_number, e_tag });
+        Ok(())
+    }
+
+    /// Complete the upload to make the object visible, it's idempotent for the replayed commits
+    /// after recovery
+    pub async fn commit(&self, client: &Client, bucket: &str) -> anyhow::Result<()> {
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| {
+                CompletedPart::builder()
+                    .part_number(part.part_number)
+                    .e_tag(part.e_tag.as_str())
+                    .build()
+            })
+            .collect();
+        let rt = client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(self.key.as_str())
+            .upload_id(self.upload_id.as_str())
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await;
+
+        match rt {
+            Ok(_) => {
+                info!("commit object {}", self.key);
+                Ok(())
+            }
+            Err(e) if e.code() == Some("NoSuchUpload") => {
+                if object_exists(client, bucket, self.key.as_str()).await? {
+                    Ok(())
+                } else {
+                    Err(anyhow!("the upload of object {} is lost", self.key))
+                }
+            }
+            Err(e) => Err(s3_error(e)),
+        }
+    }
+}
+
+pub(crate) async fn object_exists(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+) -> anyhow::Result<bool> {
+    match client.head_object().bucket(bucket).key(key).send().await {
+        Ok(_) => Ok(true),
+        Err(e) => match e.into_service_error() {
+            e if e.is_not_found() => Ok(false),
+            e => Err(s3_error(e)),
+        },
+    }
+}
+
+/// Abort the uncompleted multipart uploads under the `prefix` whose object names start with the
+/// `name_prefix`
+pub(crate) async fn abort_uploads(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    name_prefix: &str,
+) -> anyhow::Result<()> {
+    let mut key_marker = None;
+    let mut upload_id_marker = None;
+    loop {
+        let output = client
+            .list_multipart_uploads()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_key_marker(key_marker)
+            .set_upload_id_marker(upload_id_marker)
+            .send()
+            .await
+            .map_err(s3_error)?;
+
+        for upload in output.uploads() {
+            let (key, upload_id) = match (upload.key(), upload.upload_id()) {
+                (Some(key), Some(upload_id)) => (key, upload_id),
+                _ => continue,
+            };
+            let name = key.rsplit('/').next().unwrap_or(key);
+            if !name.starts_with(name_prefix) {
+                continue;
+            }
+
+            info!("abort the uncommitted upload of object {}", key);
+            client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .send()
+                .await
+                .map_err(s3_error)?;
+        }
+
+        if output.is_truncated() != Some(true) {
+            return Ok(());
+        }
+        key_marker = output.next_key_marker;
+        upload_id_marker = output.next_upload_id_marker;
+    }
+}
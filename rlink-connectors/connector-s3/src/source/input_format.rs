@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::FnSchema;
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::core::properties::Properties;
+use rlink::metrics::Tag;
+use rlink_connector_files::source::fetcher::fetched_file_stream;
+use rlink_connector_files::source::split::assign_files;
+use rlink_connector_files::source::state::FileSourceState;
+use rlink_connector_files::FileFormat;
+use tokio::runtime::Handle;
+
+use crate::client::{S3Config, S3Url};
+use crate::source::reader::{list_objects, S3FileFetcher};
+
+const BUFFER_SIZE_DEFAULT: usize = 10000;
+
+/// Read the records of the objects under the `url`, such as `s3://archive/events`, decoded by
+/// the `FileFormat` with the `Schema` like the `FileInputFormat`.
+///
+/// The objects are listed when the splits are created, and assigned to the splits by the hash of
+/// the keys, so an object is always read by the same task across the restarts. Each object is
+/// downloaded to the staging directory before read, and the read offset of each object is
+/// checkpointed, so the recovered task resumes from the offsets. The objects deleted after listed
+/// are skipped, and the stream ends when all the objects of the split are read.
+#[derive(NamedFunction)]
+pub struct S3InputFormat {
+    config: S3Config,
+    url: String,
+    format: FileFormat,
+    schema: Schema,
+    parallelism: u16,
+    staging_dir: PathBuf,
+    buffer_size: usize,
+
+    fetcher: Option<S3FileFetcher>,
+    split_keys: Vec<String>,
+    state: FileSourceState,
+    restore_error: Option<String>,
+}
+
+impl S3InputFormat {
+    pub fn new(
+        config: S3Config,
+        url: &str,
+        format: FileFormat,
+        schema: Schema,
+        parallelism: u16,
+    ) -> Self {
+        S3InputFormat {
+            config,
+            url: url.to_string(),
+            format,
+            schema,
+            parallelism,
+            staging_dir: std::env::temp_dir(),
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            fetcher: None,
+            split_keys: vec![],
+            state: FileSourceState::default(),
+            restore_error: None,
+        }
+    }
+
+    /// The local directory of the downloaded objects, the system temporary directory by default
+    pub fn staging_dir(mut self, staging_dir: &str) -> Self {
+        self.staging_dir = PathBuf::from(staging_dir);
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+}
+
+#[async_trait]
+impl InputFormat for S3InputFormat {
+    async fn open(&mut self, input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.restore_error {
+            return Err(core::Error::from(format!("restore s3 source error. {}", e)));
+        }
+
+        let url = S3Url::parse(self.url.as_str())?;
+        let keys = input_split.properties().get_string("keys")?;
+        let keys: Vec<String> = serde_json::from_str(keys.as_str())
+            .map_err(|e| core::Error::from(format!("parse the split keys error. {}", e)))?;
+        info!("s3 source split keys {:?}", keys);
+
+        self.fetcher = Some(S3FileFetcher {
+            handle: Handle::current(),
+            client: self.config.create_client().await,
+            bucket: url.bucket,
+            staging_dir: self.staging_dir.clone(),
+            task_number: context.task_id.task_number(),
+        });
+        self.split_keys = keys;
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let fetcher = self.fetcher.take().unwrap();
+        let tags = vec![
+            Tag::new("url", self.url.as_str()),
+            Tag::new("task_number", fetcher.task_number),
+        ];
+        fetched_file_stream(
+            Box::new(fetcher),
+            std::mem::take(&mut self.split_keys),
+            self.format.clone(),
+            self.schema.clone(),
+            self.state.clone(),
+            tags,
+            self.buffer_size,
+        )
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for S3InputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            match self.state.update_from_snapshot(handle.handle.as_str()) {
+                Ok(()) => info!(
+                    "load s3 source state from checkpoint({:?}): {}",
+                    context.checkpoint_id, handle.handle
+                ),
+                Err(e) => self.restore_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        Some(CheckpointHandle {
+            handle: self.state.snapshot(),
+        })
+    }
+}
+
+impl InputSplitSource for S3InputFormat {
+    /// The objects are listed and assigned to the splits by the hash of the keys, the keys of each split are
+    /// in the `keys` property as a JSON array
+    fn create_input_splits(&self, min_num_splits: u16) -> core::Result<Vec<InputSplit>> {
+        let url = S3Url::parse(self.url.as_str())?;
+        let keys = lookup_objects(self.config.clone(), url)?;
+        info!("s3 source objects {:?}", keys);
+
+        let input_splits = assign_files(keys.as_slice(), min_num_splits)
+            .into_iter()
+            .enumerate()
+            .map(|(index, keys)| {
+                let mut properties = Properties::new();
+                properties.set_str("keys", serde_json::to_string(&keys).unwrap().as_str());
+                InputSplit::new(index as u16, properties)
+            })
+            .collect();
+        Ok(input_splits)
+    }
+}
+
+/// The splits are created out of the job runtime, so the objects are listed on a temporary one
+fn lookup_objects(config: S3Config, url: S3Url) -> anyhow::Result<Vec<String>> {
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        runtime.block_on(async move {
+            let client = config.create_client().await;
+            list_objects(&client, url.bucket.as_str(), url.list_prefix().as_str()).await
+        })
+    })
+    .join()
+    .map_err(|_e| anyhow!("list s3 objects panicked"))?
+}
@@ -0,0 +1,2 @@
+pub mod input_format;
+pub(crate) mod reader;
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use aws_sdk_s3::Client;
+use rlink_connector_files::source::fetcher::FileFetcher;
+use tokio::runtime::Handle;
+
+use crate::client::s3_error;
+
+/// List the objects under the `prefix`, the hidden objects, whose names start with `.` or `_`,
+/// and the directory markers are skipped
+pub(crate) async fn list_objects(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+) -> anyhow::Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut continuation_token = None;
+    loop {
+        let output = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(prefix)
+            .set_continuation_token(continuation_token)
+            .send()
+            .await
+            .map_err(s3_error)?;
+
+        for object in output.contents() {
+            let key = match object.key() {
+                Some(key) => key,
+                None => continue,
+            };
+            let name = key.rsplit('/').next().unwrap_or(key);
+            if name.is_empty() || name.starts_with('.') || name.starts_with('_') {
+                continue;
+            }
+            keys.push(key.to_string());
+        }
+
+        match output.next_continuation_token {
+            Some(token) if output.is_truncated() == Some(true) => continuation_token = Some(token),
+            _ => break,
+        }
+    }
+
+    keys.sort();
+    Ok(keys)
+}
+
+/// Download the objects to the staging directory to read, the `.gz` objects are decompressed
+/// by the reader. The downloaded file is removed after read.
+pub(crate) struct S3FileFetcher {
+    pub handle: Handle,
+    pub client: Client,
+    pub bucket: String,
+    pub staging_dir: PathBuf,
+    pub task_number: u16,
+}
+
+impl FileFetcher for S3FileFetcher {
+    fn fetch(&self, key: &str) -> anyhow::Result<Option<PathBuf>> {
+        // keep the object name, the extension tells whether it's compressed
+        let name = key.rsplit('/').next().unwrap_or(key);
+        let staging_path = self.staging_dir.join(format!(
+            ".{}-{}-{}-{}",
+            self.bucket,
+            std::process::id(),
+            self.task_number,
+            name
+        ));
+
+        let found = self.handle.block_on(download(
+            &self.client,
+            self.bucket.as_str(),
+            key,
+            staging_path.as_path(),
+        ))?;
+        Ok(if found { Some(staging_path) } else { None })
+    }
+
+    fn release(&self, local_path: &Path) -> anyhow::Result<()> {
+        std::fs::remove_file(local_path)?;
+        Ok(())
+    }
+}
+
+/// Download the object to the `path`, returns false if the object is not found
+async fn download(client: &Client, bucket: &str, key: &str, path: &Path) -> anyhow::Result<bool> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let output = match client.get_object().bucket(bucket).key(key).send().await {
+        Ok(output) => output,
+        Err(e) => match e.into_service_error() {
+            e if e.is_no_such_key() => return Ok(false),
+            e => return Err(s3_error(e)),
+        },
+    };
+    let mut reader = output.body.into_async_read();
+    let mut file = tokio::fs::File::create(path).await?;
+    tokio::io::copy_buf(&mut reader, &mut file).await?;
+    file.sync_all().await?;
+    Ok(true)
+}
@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+/// The position in the binlog files
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BinlogPosition {
+    pub filename: String,
+    pub position: u64,
+}
+
+impl BinlogPosition {
+    pub fn new(filename: &str, position: u64) -> Self {
+        BinlogPosition {
+            filename: filename.to_string(),
+            position,
+        }
+    }
+}
+
+/// The binlog position to resume from, it's the end of the last transaction whose rows are all
+/// emitted, so the rows of the transaction in progress are re-emitted after recovery
+#[derive(Debug, Clone, Default)]
+pub struct BinlogState {
+    position: Arc<Mutex<Option<BinlogPosition>>>,
+}
+
+impl BinlogState {
+    pub fn get(&self) -> Option<BinlogPosition> {
+        self.position.lock().unwrap().clone()
+    }
+
+    pub(crate) fn update(&self, position: BinlogPosition) {
+        *self.position.lock().unwrap() = Some(position);
+    }
+
+    pub fn snapshot(&self) -> Option<String> {
+        self.get()
+            .map(|position| serde_json::to_string(&position).unwrap())
+    }
+
+    pub fn update_from_snapshot(&self, snapshot_handle: &str) -> anyhow::Result<()> {
+        let position: BinlogPosition = serde_json::from_str(snapshot_handle)?;
+        self.update(position);
+        Ok(())
+    }
+}
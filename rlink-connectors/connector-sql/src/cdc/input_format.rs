@@ -0,0 +1,375 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use mysql_async::binlog::events::{EventData, RowsEventData};
+use mysql_async::binlog::row::BinlogRow;
+use mysql_async::prelude::Queryable;
+use mysql_async::{BinlogRequest, Conn, Opts, Row};
+use rlink::channel::named_channel;
+use rlink::channel::sender::ChannelSender;
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::element::{FnSchema, Record};
+use rlink::core::function::{
+    Context, InputFormat, InputSplit, InputSplitSource, SendableElementStream,
+};
+use rlink::metrics::Tag;
+
+use crate::cdc::checkpoint::{BinlogPosition, BinlogState};
+use crate::cdc::stream::CdcRecordStream;
+use crate::cdc::value::write_value;
+use crate::cdc::{CdcRecord, RowOp};
+
+const BUFFER_SIZE_DEFAULT: usize = 10000;
+
+/// Read the changed rows of a MySQL table from the binlog, which must be in the `ROW` format with
+/// the `FULL` row image. The output schema is the op-type column, `insert`, `update` or
+/// `delete`, followed by the fields of the `schema`, which are matched by the table column names
+/// when opening. The updated rows are the after images, and the deleted rows are the before
+/// images.
+///
+/// The reader is registered as a replica with the `server_id`, which must be unique among the
+/// replicas of the server. It starts from the checkpointed binlog position, or the
+/// `start_position`, or the current position of the server, and the position is checkpointed at
+/// the transaction boundaries, so the rows are emitted at least once.
+#[derive(NamedFunction)]
+pub struct MysqlCdcInputFormat {
+    url: String,
+    database: String,
+    table: String,
+    schema: Schema,
+    server_id: u32,
+    op_column: String,
+    start_position: Option<BinlogPosition>,
+    buffer_size: usize,
+
+    /// the table column index of each field of the `schema`
+    column_indexes: Vec<usize>,
+    begin_position: Option<BinlogPosition>,
+    state: BinlogState,
+    restore_error: Option<String>,
+}
+
+impl MysqlCdcInputFormat {
+    pub fn new(url: &str, database: &str, table: &str, schema: Schema, server_id: u32) -> Self {
+        MysqlCdcInputFormat {
+            url: url.to_string(),
+            database: database.to_string(),
+            table: table.to_string(),
+            schema,
+            server_id,
+            op_column: "op".to_string(),
+            start_position: None,
+            buffer_size: BUFFER_SIZE_DEFAULT,
+            column_indexes: vec![],
+            begin_position: None,
+            state: BinlogState::default(),
+            restore_error: None,
+        }
+    }
+
+    pub fn op_column(mut self, op_column: &str) -> Self {
+        self.op_column = op_column.to_string();
+        self
+    }
+
+    /// Start from the position instead of the current position if there is no checkpoint
+    pub fn start_position(mut self, filename: &str, position: u64) -> Self {
+        self.start_position = Some(BinlogPosition::new(filename, position));
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    fn output_schema(&self) -> Schema {
+        let mut fields = vec![Field::new(self.op_column.as_str(), DataType::String)];
+        fields.extend_from_slice(self.schema.fields());
+        Schema::new(fields)
+    }
+
+    async fn prepare(&mut self) -> anyhow::Result<()> {
+        let mut conn = Conn::new(Opts::from_url(self.url.as_str())?).await?;
+
+        let columns: Vec<String> = conn
+            .exec(
+                "SELECT COLUMN_NAME FROM information_schema.COLUMNS \
+                 WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+                (self.database.as_str(), self.table.as_str()),
+            )
+            .await?;
+        if columns.is_empty() {
+            return Err(anyhow!("table {}.{} not found", self.database, self.table));
+        }
+        self.column_indexes = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| {
+                columns
+                    .iter()
+                    .position(|column| column.eq_ignore_ascii_case(field.name()))
+                    .ok_or(anyhow!("column `{}` not found in the table", field.name()))
+            })
+            .collect::<anyhow::Result<Vec<usize>>>()?;
+
+        self.begin_position = match (self.state.get(), &self.start_position) {
+            (Some(position), _) => Some(position),
+            (None, Some(position)) => Some(position.clone()),
+            (None, None) => Some(current_position(&mut conn).await?),
+        };
+
+        conn.disconnect().await?;
+        Ok(())
+    }
+}
+
+/// The current binlog position of the server
+async fn current_position(conn: &mut Conn) -> anyhow::Result<BinlogPosition> {
+    // renamed in MySQL 8.4
+    let row: Option<Row> = match conn.query_first("SHOW MASTER STATUS").await {
+        Ok(row) => row,
+        Err(_e) => conn.query_first("SHOW BINARY LOG STATUS").await?,
+    };
+    let row = row.ok_or(anyhow!("the binlog is disabled"))?;
+
+    let filename: String = row.get(0).ok_or(anyhow!("binlog file not found"))?;
+    let position: u64 = row.get(1).ok_or(anyhow!("binlog position not found"))?;
+    Ok(BinlogPosition { filename, position })
+}
+
+#[async_trait]
+impl InputFormat for MysqlCdcInputFormat {
+    async fn open(&mut self, _input_split: InputSplit, context: &Context) -> core::Result<()> {
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+        if let Some(e) = &self.restore_error {
+            return Err(core::Error::from(format!("restore mysql cdc error. {}", e)));
+        }
+
+        self.prepare()
+            .await
+            .map_err(|e| core::Error::from(format!("prepare mysql cdc error. {}", e)))?;
+        info!(
+            "mysql cdc of {}.{} begins at {:?}",
+            self.database, self.table, self.begin_position
+        );
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let tags = vec![Tag::new(
+            "table",
+            format!("{}.{}", self.database, self.table),
+        )];
+        let (sender, receiver) = named_channel("MysqlCdc_Handover", tags, self.buffer_size);
+
+        let reader = BinlogReader {
+            url: self.url.clone(),
+            server_id: self.server_id,
+            database: self.database.clone(),
+            table: self.table.clone(),
+            schema: self.schema.clone(),
+            output_schema: self.output_schema(),
+            column_indexes: self.column_indexes.clone(),
+            position: self.begin_position.clone().unwrap(),
+            sender,
+        };
+        tokio::spawn(reader.run());
+
+        Box::pin(CdcRecordStream::new(receiver, self.state.clone()))
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.output_schema())
+    }
+
+    /// The binlog is read by a single task
+    fn parallelism(&self) -> u16 {
+        1
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for MysqlCdcInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            match self.state.update_from_snapshot(handle.handle.as_str()) {
+                Ok(()) => info!(
+                    "load binlog position from checkpoint({:?}): {}",
+                    context.checkpoint_id, handle.handle
+                ),
+                Err(e) => self.restore_error = Some(e.to_string()),
+            }
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        // nothing is emitted yet, resume from the begin position
+        let handle = self.state.snapshot().or_else(|| {
+            self.begin_position
+                .as_ref()
+                .map(|position| serde_json::to_string(position).unwrap())
+        })?;
+        Some(CheckpointHandle { handle })
+    }
+}
+
+impl InputSplitSource for MysqlCdcInputFormat {}
+
+struct BinlogReader {
+    url: String,
+    server_id: u32,
+    database: String,
+    table: String,
+    schema: Schema,
+    output_schema: Schema,
+    column_indexes: Vec<usize>,
+    /// the end of the last transaction read
+    position: BinlogPosition,
+    sender: ChannelSender<CdcRecord>,
+}
+
+impl BinlogReader {
+    async fn run(mut self) {
+        loop {
+            match self.read().await {
+                Ok(()) => {
+                    info!("mysql cdc stream is closed, the reader exits");
+                    return;
+                }
+                Err(e) => {
+                    // the rows of the transaction in progress are re-read
+                    error!("read binlog at {:?} error. {}", self.position, e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    /// Read the binlog from the `position` until the stream is closed
+    async fn read(&mut self) -> anyhow::Result<()> {
+        let conn = Conn::new(Opts::from_url(self.url.as_str())?).await?;
+        let request = BinlogRequest::new(self.server_id)
+            .with_filename(self.position.filename.as_bytes().to_vec())
+            .with_pos(self.position.position);
+        let mut binlog_stream = conn.get_binlog_stream(request).await?;
+
+        let mut filename = self.position.filename.clone();
+        while let Some(event) = binlog_stream.next().await {
+            let event = event?;
+            let log_pos = event.header().log_pos() as u64;
+            let event_data = match event.read_data()? {
+                Some(event_data) => event_data,
+                None => continue,
+            };
+
+            let rows_event = match event_data {
+                EventData::RotateEvent(rotate_event) => {
+                    // rotated between the transactions
+                    filename = rotate_event.name().to_string();
+                    self.position = BinlogPosition::new(filename.as_str(), rotate_event.position());
+                    if !self.send(None).await {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                EventData::XidEvent(_) => {
+                    self.position = BinlogPosition::new(filename.as_str(), log_pos);
+                    if !self.send(None).await {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                // the DDL, or the commit of the non-transactional tables
+                EventData::QueryEvent(query_event) if query_event.query() != "BEGIN" => {
+                    self.position = BinlogPosition::new(filename.as_str(), log_pos);
+                    if !self.send(None).await {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                EventData::RowsEvent(rows_event) => rows_event,
+                _ => continue,
+            };
+
+            let table_map_event = match binlog_stream.get_tme(rows_event.table_id()) {
+                Some(table_map_event) => table_map_event,
+                None => continue,
+            };
+            if table_map_event.database_name() != self.database
+                || table_map_event.table_name() != self.table
+            {
+                continue;
+            }
+
+            let op = match &rows_event {
+                RowsEventData::WriteRowsEventV1(_) | RowsEventData::WriteRowsEvent(_) => {
+                    RowOp::Insert
+                }
+                RowsEventData::DeleteRowsEventV1(_) | RowsEventData::DeleteRowsEvent(_) => {
+                    RowOp::Delete
+                }
+                _ => RowOp::Update,
+            };
+
+            let mut records = Vec::new();
+            for row in rows_event.rows(table_map_event) {
+                let (before, after) = row?;
+                let row = match op {
+                    RowOp::Delete => before,
+                    _ => after,
+                };
+                if let Some(row) = row {
+                    match self.to_record(op, row) {
+                        Ok(record) => records.push(record),
+                        Err(e) => warn!("discard the {} row of {}. {}", op.as_str(), self.table, e),
+                    }
+                }
+            }
+
+            for record in records {
+                if !self.send(Some(record)).await {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(anyhow!("binlog stream is closed by the server"))
+    }
+
+    fn to_record(&self, op: RowOp, mut row: BinlogRow) -> anyhow::Result<Record> {
+        let mut record = Record::new();
+        let mut writer = record.as_writer(self.output_schema.as_type_ids());
+        writer.set_str(op.as_str())?;
+        for (field, column_index) in self.schema.fields().iter().zip(&self.column_indexes) {
+            write_value(&mut writer, field.data_type(), row.take(*column_index))
+                .map_err(|e| anyhow!("write field `{}` error. {}", field.name(), e))?;
+        }
+        Ok(record)
+    }
+
+    /// Returns false if the stream is closed
+    async fn send(&self, record: Option<Record>) -> bool {
+        let cdc_record = CdcRecord {
+            position: self.position.clone(),
+            record,
+        };
+        self.sender.send(cdc_record).await.is_ok()
+    }
+}
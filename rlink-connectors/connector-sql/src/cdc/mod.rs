@@ -0,0 +1,32 @@
+use crate::cdc::checkpoint::BinlogPosition;
+
+pub mod checkpoint;
+pub mod input_format;
+pub mod stream;
+pub(crate) mod value;
+
+/// The message from the binlog reader to the stream, the `record` is `None` for the end of a
+/// transaction
+pub(crate) struct CdcRecord {
+    /// the position to resume from if the record is the last emitted
+    position: BinlogPosition,
+    record: Option<rlink::core::element::Record>,
+}
+
+/// The change type of a row, written as the op-type column
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl RowOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use rlink::channel::receiver::ChannelReceiver;
+use rlink::core::element::Element;
+use rlink::core::function::ElementStream;
+
+use crate::cdc::checkpoint::BinlogState;
+use crate::cdc::CdcRecord;
+
+/// The changed rows of the binlog, the binlog position is updated when the rows are emitted
+pub struct CdcRecordStream {
+    receiver: ChannelReceiver<CdcRecord>,
+    state: BinlogState,
+}
+
+impl CdcRecordStream {
+    pub(crate) fn new(receiver: ChannelReceiver<CdcRecord>, state: BinlogState) -> Self {
+        CdcRecordStream { receiver, state }
+    }
+}
+
+impl ElementStream for CdcRecordStream {}
+
+impl Stream for CdcRecordStream {
+    type Item = Element;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.as_mut().receiver.poll_recv(cx) {
+                Poll::Ready(Some(cdc_record)) => {
+                    self.state.update(cdc_record.position);
+                    if let Some(record) = cdc_record.record {
+                        return Poll::Ready(Some(Element::Record(record)));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
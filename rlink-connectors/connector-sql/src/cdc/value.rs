@@ -0,0 +1,170 @@
+use chrono::NaiveDate;
+use mysql_async::binlog::value::BinlogValue;
+use mysql_async::Value;
+use rlink::core::data_types::DataType;
+use rlink::core::element::BufferWriter;
+
+/// Write the binlog value to the field, the absent and NULL values are written as the default
+/// value of the field type.
+///
+/// The integers are casted to the field types, so the unsigned columns, which are signed in the
+/// binlog, are recovered by the unsigned fields. The `DATE`, `DATETIME` and `TIMESTAMP` values
+/// are the milliseconds since the epoch in the integer fields, and the `YYYY-MM-DD hh:mm:ss`
+/// strings in the string fields. The `DECIMAL` values are parsed in the numeric fields.
+pub(crate) fn write_value(
+    writer: &mut BufferWriter,
+    data_type: &DataType,
+    value: Option<BinlogValue>,
+) -> anyhow::Result<()> {
+    let value = match value {
+        Some(value) => Value::try_from(value).map_err(|e| anyhow!("{}", e))?,
+        None => Value::NULL,
+    };
+
+    match data_type {
+        DataType::Boolean => writer.set_bool(to_i64(&value)? != 0)?,
+        DataType::Int8 => writer.set_i8(to_i64(&value)? as i8)?,
+        DataType::UInt8 => writer.set_u8(to_i64(&value)? as u8)?,
+        DataType::Int16 => writer.set_i16(to_i64(&value)? as i16)?,
+        DataType::UInt16 => writer.set_u16(to_i64(&value)? as u16)?,
+        DataType::Int32 => writer.set_i32(to_i64(&value)? as i32)?,
+        DataType::UInt32 => writer.set_u32(to_i64(&value)? as u32)?,
+        DataType::Int64 => writer.set_i64(to_i64(&value)?)?,
+        DataType::UInt64 => writer.set_u64(to_i64(&value)? as u64)?,
+        DataType::Float32 => writer.set_f32(to_f64(&value)? as f32)?,
+        DataType::Float64 => writer.set_f64(to_f64(&value)?)?,
+        DataType::Binary => match value {
+            Value::Bytes(v) => writer.set_binary(v.as_slice())?,
+            value => writer.set_binary(to_string(&value).as_bytes())?,
+        },
+        DataType::String => writer.set_str(to_string(&value).as_str())?,
+    }
+
+    Ok(())
+}
+
+fn to_i64(value: &Value) -> anyhow::Result<i64> {
+    let v = match value {
+        Value::NULL => 0,
+        Value::Int(v) => *v,
+        Value::UInt(v) => *v as i64,
+        Value::Float(v) => *v as i64,
+        Value::Double(v) => *v as i64,
+        Value::Bytes(v) => {
+            let v = std::str::from_utf8(v.as_slice())?.trim();
+            match v.parse::<i64>() {
+                Ok(v) => v,
+                // DECIMAL
+                Err(_e) => v.parse::<f64>()? as i64,
+            }
+        }
+        Value::Date(year, month, day, hour, minute, second, micros) => {
+            NaiveDate::from_ymd_opt(*year as i32, *month as u32, *day as u32)
+                .and_then(|date| {
+                    date.and_hms_micro_opt(*hour as u32, *minute as u32, *second as u32, *micros)
+                })
+                .map(|date_time| date_time.and_utc().timestamp_millis())
+                // zero date
+                .unwrap_or_default()
+        }
+        Value::Time(negative, days, hours, minutes, seconds, micros) => {
+            let millis = (((*days as i64 * 24 + *hours as i64) * 60 + *minutes as i64) * 60
+                + *seconds as i64)
+                * 1000
+                + *micros as i64 / 1000;
+            if *negative {
+                -millis
+            } else {
+                millis
+            }
+        }
+    };
+    Ok(v)
+}
+
+fn to_f64(value: &Value) -> anyhow::Result<f64> {
+    match value {
+        Value::Float(v) => Ok(*v as f64),
+        Value::Double(v) => Ok(*v),
+        Value::Bytes(v) => Ok(std::str::from_utf8(v.as_slice())?.trim().parse()?),
+        value => to_i64(value).map(|v| v as f64),
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::NULL => "".to_string(),
+        Value::Bytes(v) => String::from_utf8_lossy(v.as_slice()).to_string(),
+        Value::Int(v) => v.to_string(),
+        Value::UInt(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Double(v) => v.to_string(),
+        Value::Date(year, month, day, hour, minute, second, micros) => {
+            let mut s = format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                year, month, day, hour, minute, second
+            );
+            if *micros > 0 {
+                s.push_str(format!(".{:06}", micros).as_str());
+            }
+            s
+        }
+        Value::Time(negative, days, hours, minutes, seconds, micros) => {
+            let hours = *days * 24 + *hours as u32;
+            let mut s = format!(
+                "{}{:02}:{:02}:{:02}",
+                if *negative { "-" } else { "" },
+                hours,
+                minutes,
+                seconds
+            );
+            if *micros > 0 {
+                s.push_str(format!(".{:06}", micros).as_str());
+            }
+            s
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mysql_async::binlog::value::BinlogValue;
+    use mysql_async::Value;
+    use rlink::core::data_types::{DataType, Field, Schema};
+    use rlink::core::element::Record;
+
+    use crate::cdc::value::write_value;
+
+    #[test]
+    pub fn write_value_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UInt32),
+            Field::new("amount", DataType::Float64),
+            Field::new("created", DataType::Int64),
+            Field::new("updated", DataType::String),
+            Field::new("name", DataType::String),
+        ]);
+        let values = vec![
+            // INT UNSIGNED 4294967295 in binlog
+            Some(BinlogValue::Value(Value::Int(-1))),
+            // DECIMAL
+            Some(BinlogValue::Value(Value::Bytes(b"12.50".to_vec()))),
+            Some(BinlogValue::Value(Value::Date(2024, 1, 2, 3, 4, 5, 0))),
+            Some(BinlogValue::Value(Value::Date(2024, 1, 2, 3, 4, 5, 120))),
+            Some(BinlogValue::Value(Value::NULL)),
+        ];
+
+        let mut record = Record::new();
+        let mut writer = record.as_writer(schema.as_type_ids());
+        for (field, value) in schema.fields().iter().zip(values) {
+            write_value(&mut writer, field.data_type(), value).unwrap();
+        }
+
+        let reader = record.as_reader(schema.as_type_ids());
+        assert_eq!(reader.get_u32(0).unwrap(), u32::MAX);
+        assert_eq!(reader.get_f64(1).unwrap(), 12.5);
+        assert_eq!(reader.get_i64(2).unwrap(), 1704164645000);
+        assert_eq!(reader.get_str(3).unwrap(), "2024-01-02 03:04:05.000120");
+        assert_eq!(reader.get_str(4).unwrap(), "");
+    }
+}
@@ -0,0 +1,18 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate rlink_derive;
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate async_trait;
+
+pub mod cdc;
+pub mod dialect;
+pub mod sink;
+
+pub use cdc::input_format::MysqlCdcInputFormat;
+pub use dialect::SqlDialect;
+pub use sink::upsert::SqlUpsertOutputFormat;
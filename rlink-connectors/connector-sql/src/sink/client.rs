@@ -0,0 +1,136 @@
+use mysql_async::prelude::Queryable;
+use rlink::core::data_types::DataType;
+use rlink::core::element::BufferReader;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::NoTls;
+
+use crate::dialect::SqlDialect;
+
+/// The value of a column, read from the record by the field type
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SqlValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl SqlValue {
+    pub fn read(
+        reader: &BufferReader,
+        index: usize,
+        data_type: &DataType,
+    ) -> std::io::Result<SqlValue> {
+        let value = match data_type {
+            DataType::Boolean => SqlValue::Bool(reader.get_bool(index)?),
+            DataType::Int8 => SqlValue::Int(reader.get_i8(index)? as i64),
+            DataType::UInt8 => SqlValue::Int(reader.get_u8(index)? as i64),
+            DataType::Int16 => SqlValue::Int(reader.get_i16(index)? as i64),
+            DataType::UInt16 => SqlValue::Int(reader.get_u16(index)? as i64),
+            DataType::Int32 => SqlValue::Int(reader.get_i32(index)? as i64),
+            DataType::UInt32 => SqlValue::Int(reader.get_u32(index)? as i64),
+            DataType::Int64 => SqlValue::Int(reader.get_i64(index)?),
+            DataType::UInt64 => SqlValue::UInt(reader.get_u64(index)?),
+            DataType::Float32 => SqlValue::Float(reader.get_f32(index)?),
+            DataType::Float64 => SqlValue::Double(reader.get_f64(index)?),
+            DataType::Binary => SqlValue::Bytes(reader.get_binary(index)?.to_vec()),
+            DataType::String => SqlValue::Text(reader.get_str(index)?.to_string()),
+        };
+        Ok(value)
+    }
+
+    fn to_mysql(&self) -> mysql_async::Value {
+        match self {
+            Self::Bool(v) => mysql_async::Value::Int(*v as i64),
+            Self::Int(v) => mysql_async::Value::Int(*v),
+            Self::UInt(v) => mysql_async::Value::UInt(*v),
+            Self::Float(v) => mysql_async::Value::Float(*v),
+            Self::Double(v) => mysql_async::Value::Double(*v),
+            Self::Text(v) => mysql_async::Value::Bytes(v.as_bytes().to_vec()),
+            Self::Bytes(v) => mysql_async::Value::Bytes(v.clone()),
+        }
+    }
+
+    /// The PostgreSQL parameter, the `UInt64` greater than `i64::MAX` is overflowed
+    fn to_postgres(&self) -> (Type, Box<dyn ToSql + Sync + Send>) {
+        match self {
+            Self::Bool(v) => (Type::BOOL, Box::new(*v)),
+            Self::Int(v) => (Type::INT8, Box::new(*v)),
+            Self::UInt(v) => (Type::INT8, Box::new(*v as i64)),
+            Self::Float(v) => (Type::FLOAT4, Box::new(*v)),
+            Self::Double(v) => (Type::FLOAT8, Box::new(*v)),
+            Self::Text(v) => (Type::TEXT, Box::new(v.clone())),
+            Self::Bytes(v) => (Type::BYTEA, Box::new(v.clone())),
+        }
+    }
+}
+
+pub(crate) enum SqlClient {
+    MySql(mysql_async::Pool),
+    Postgres(tokio_postgres::Client),
+}
+
+impl SqlClient {
+    pub async fn connect(dialect: SqlDialect, url: &str) -> anyhow::Result<Self> {
+        match dialect {
+            SqlDialect::MySql => {
+                let pool = mysql_async::Pool::from_url(url)?;
+                // check the connection when opening
+                pool.get_conn().await?;
+                Ok(SqlClient::MySql(pool))
+            }
+            SqlDialect::Postgres => {
+                let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        error!("postgres connection error. {}", e);
+                    }
+                });
+                Ok(SqlClient::Postgres(client))
+            }
+        }
+    }
+
+    /// The PostgreSQL connection is closed after an error, and must be connected again
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Self::MySql(_pool) => false,
+            Self::Postgres(client) => client.is_closed(),
+        }
+    }
+
+    /// Execute the statement with the values of the rows. The PostgreSQL parameters are typed
+    /// by the values, and assigned to the columns by the assignment casts, such as `int8` to
+    /// `int4`.
+    pub async fn execute(&mut self, sql: &str, rows: &[Vec<SqlValue>]) -> anyhow::Result<()> {
+        match self {
+            Self::MySql(pool) => {
+                let params: Vec<mysql_async::Value> =
+                    rows.iter().flatten().map(|x| x.to_mysql()).collect();
+                let mut conn = pool.get_conn().await?;
+                conn.exec_drop(sql, params).await?;
+            }
+            Self::Postgres(client) => {
+                let (types, params): (Vec<Type>, Vec<Box<dyn ToSql + Sync + Send>>) =
+                    rows.iter().flatten().map(|x| x.to_postgres()).unzip();
+                let statement = client.prepare_typed(sql, types.as_slice()).await?;
+                let params: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|x| x.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+                client.execute(&statement, params.as_slice()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        if let Self::MySql(pool) = self {
+            pool.disconnect().await?;
+        }
+        Ok(())
+    }
+}
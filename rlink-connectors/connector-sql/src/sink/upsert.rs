@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use rlink::core;
+use rlink::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use rlink::core::data_types::Schema;
+use rlink::core::element::{Element, FnSchema, Record};
+use rlink::core::function::{Context, OutputFormat};
+
+use crate::dialect::SqlDialect;
+use crate::sink::client::{SqlClient, SqlValue};
+
+const BATCH_SIZE_DEFAULT: usize = 1000;
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Upsert the records to the `table` of MySQL or PostgreSQL, the columns are the fields of the
+/// input `Schema`, and the rows of the existing `key_columns` are updated. The `table` is not
+/// quoted, so it can be qualified by the database or schema name.
+///
+/// The records are buffered and written in a statement for every `batch_size` records and on
+/// every checkpoint, only the last record of the same keys in a batch is written. The records
+/// before the checkpoint are written at least once, and the replayed records after recovery
+/// are idempotent by the keys. The rows failed to write are kept and retried with backoff until
+/// written, the later records and checkpoints wait for them. The records failed to convert to
+/// the rows are discarded, and fail the next checkpoint.
+#[derive(NamedFunction)]
+pub struct SqlUpsertOutputFormat {
+    url: String,
+    table: String,
+    key_columns: Vec<String>,
+    batch_size: usize,
+
+    dialect: Option<SqlDialect>,
+    schema: Schema,
+    key_indexes: Vec<usize>,
+    client: Option<SqlClient>,
+    buffer: Vec<Vec<SqlValue>>,
+    /// the records failed to convert since the last checkpoint, they are discarded and the next
+    /// checkpoint is declined with the first error
+    discarded: usize,
+    discard_error: Option<String>,
+}
+
+impl SqlUpsertOutputFormat {
+    pub fn new(url: &str, table: &str, key_columns: Vec<&str>) -> Self {
+        SqlUpsertOutputFormat {
+            url: url.to_string(),
+            table: table.to_string(),
+            key_columns: key_columns.into_iter().map(|x| x.to_string()).collect(),
+            batch_size: BATCH_SIZE_DEFAULT,
+            dialect: None,
+            schema: Schema::empty(),
+            key_indexes: vec![],
+            client: None,
+            buffer: vec![],
+            discarded: 0,
+            discard_error: None,
+        }
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    fn append(&mut self, record: &mut Record) -> anyhow::Result<()> {
+        let reader = record.as_reader(self.schema.as_type_ids());
+        let mut row = Vec::with_capacity(self.schema.fields().len());
+        for (index, field) in self.schema.fields().iter().enumerate() {
+            row.push(SqlValue::read(&reader, index, field.data_type())?);
+        }
+        self.buffer.push(row);
+        Ok(())
+    }
+
+    /// Keep the last row of the same keys, the statement fails if a row is affected twice
+    fn deduplicate(&self, rows: Vec<Vec<SqlValue>>) -> Vec<Vec<SqlValue>> {
+        let mut last_rows = HashMap::with_capacity(rows.len());
+        for (index, row) in rows.iter().enumerate() {
+            let key: Vec<&SqlValue> = self.key_indexes.iter().map(|x| &row[*x]).collect();
+            last_rows.insert(format!("{:?}", key), index);
+        }
+
+        if last_rows.len() == rows.len() {
+            return rows;
+        }
+        let last_indexes: HashSet<usize> = last_rows.into_values().collect();
+        rows.into_iter()
+            .enumerate()
+            .filter(|(index, _row)| last_indexes.contains(index))
+            .map(|(_index, row)| row)
+            .collect()
+    }
+
+    /// Write the buffered rows, the rows not written are kept and retried with backoff until
+    /// written, the upserts are idempotent by the keys
+    async fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let buffer = std::mem::take(&mut self.buffer);
+        self.buffer = self.deduplicate(buffer);
+
+        let dialect = self.dialect.unwrap();
+        let columns: Vec<&str> = self.schema.fields().iter().map(|x| x.name()).collect();
+        let key_columns: Vec<&str> = self.key_columns.iter().map(|x| x.as_str()).collect();
+        let max_rows = std::cmp::max(dialect.max_parameters() / columns.len(), 1);
+        let mut backoff = RETRY_BACKOFF;
+        while !self.buffer.is_empty() {
+            let len = std::cmp::min(self.buffer.len(), max_rows);
+            let sql = dialect.upsert_statement(
+                self.table.as_str(),
+                columns.as_slice(),
+                key_columns.as_slice(),
+                len,
+            );
+            let rt = self
+                .client
+                .as_mut()
+                .unwrap()
+                .execute(sql.as_str(), &self.buffer[..len])
+                .await;
+            match rt {
+                Ok(()) => {
+                    self.buffer.drain(..len);
+                    backoff = RETRY_BACKOFF;
+                }
+                Err(e) => {
+                    error!(
+                        "upsert {} error, retry the {} rows after {:?}. {}",
+                        self.table,
+                        self.buffer.len(),
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, RETRY_BACKOFF_MAX);
+
+                    if self.client.as_ref().unwrap().is_closed() {
+                        match SqlClient::connect(dialect, self.url.as_str()).await {
+                            Ok(client) => self.client = Some(client),
+                            Err(e) => error!("reconnect database error. {}", e),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The error of the records discarded since the last call
+    fn take_discard_error(&mut self) -> Option<String> {
+        let discarded = std::mem::replace(&mut self.discarded, 0);
+        self.discard_error.take().map(|e| {
+            format!(
+                "{} records are discarded, the first error: {}",
+                discarded, e
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl OutputFormat for SqlUpsertOutputFormat {
+    async fn open(&mut self, context: &Context) -> core::Result<()> {
+        let dialect = SqlDialect::from_url(self.url.as_str())?;
+        self.dialect = Some(dialect);
+
+        self.schema = context.input_schema.first().clone();
+        if self.key_columns.is_empty() {
+            return Err(core::Error::from("the key columns of upsert are empty"));
+        }
+        for key_column in &self.key_columns {
+            let index = self
+                .schema
+                .index_of(key_column.as_str())
+                .ok_or(core::Error::from(format!(
+                    "key column `{}` not found in the schema",
+                    key_column
+                )))?;
+            self.key_indexes.push(index);
+        }
+
+        let client = SqlClient::connect(dialect, self.url.as_str())
+            .await
+            .map_err(|e| core::Error::from(format!("connect database error. {}", e)))?;
+        self.client = Some(client);
+
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let mut record = element.into_record();
+        if let Err(e) = self.append(&mut record) {
+            // surfaced as a failure of the next checkpoint
+            error!(
+                "convert the record to {} row error, discard it. {}",
+                self.table, e
+            );
+            self.discarded += 1;
+            if self.discard_error.is_none() {
+                self.discard_error = Some(e.to_string());
+            }
+            return;
+        }
+
+        if self.buffer.len() >= self.batch_size {
+            self.flush().await;
+        }
+    }
+
+    async fn close(&mut self) -> core::Result<()> {
+        self.flush().await;
+        if let Some(client) = self.client.take() {
+            client.close().await?;
+        }
+        match self.take_discard_error() {
+            Some(e) => Err(core::Error::from(format!("upsert error. {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for SqlUpsertOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        self.flush().await;
+        if let Some(e) = self.take_discard_error() {
+            context.decline(format!("upsert error. {}", e).as_str());
+        }
+        None
+    }
+}
@@ -7,6 +7,8 @@ use syn::DeriveInput;
 
 use proc_macro::TokenStream;
 
+mod record;
+
 #[proc_macro_derive(NamedFunction)]
 pub fn derive_named_function(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
@@ -79,6 +81,23 @@ pub fn derive_function(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Generate `rlink::core::element::RlinkRecord` for a struct with named fields, and the
+/// associated constants: the index of every field (named `INDEX_` and the upper case field name),
+/// `FIELD_NAME` and `FIELD_TYPE`.
+///
+/// Supported field types: `bool`, `i8`, `u8`, `i16`, `u16`, `i32`, `u32`, `i64`, `u64`, `f32`,
+/// `f64`, `String` and `Vec<u8>`.
+#[proc_macro_derive(RlinkRecord)]
+pub fn derive_rlink_record(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match record::expand_rlink_record(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 // #[proc_macro_attribute]
 // #[cfg(not(test))]
 // pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
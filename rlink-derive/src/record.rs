@@ -0,0 +1,235 @@
+use proc_macro2::TokenStream;
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Fields, GenericArgument, PathArguments, Type};
+
+/// How a field is written to and read from the `Buffer`
+enum Accessor {
+    /// `bool` and numeric types, copied by value
+    Value,
+    /// `String`, written as `&str`
+    Str,
+    /// `Vec<u8>`, written as `&[u8]`
+    Binary,
+}
+
+/// Resolve the `DataType` variant and the `Buffer` accessor suffix of a supported field type
+fn field_type(ty: &Type) -> Option<(&'static str, &'static str, Accessor)> {
+    let segment = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path.path.segments.last()?,
+        _ => return None,
+    };
+
+    let field_type = match segment.ident.to_string().as_str() {
+        "bool" => ("Boolean", "bool", Accessor::Value),
+        "i8" => ("Int8", "i8", Accessor::Value),
+        "u8" => ("UInt8", "u8", Accessor::Value),
+        "i16" => ("Int16", "i16", Accessor::Value),
+        "u16" => ("UInt16", "u16", Accessor::Value),
+        "i32" => ("Int32", "i32", Accessor::Value),
+        "u32" => ("UInt32", "u32", Accessor::Value),
+        "i64" => ("Int64", "i64", Accessor::Value),
+        "u64" => ("UInt64", "u64", Accessor::Value),
+        "f32" => ("Float32", "f32", Accessor::Value),
+        "f64" => ("Float64", "f64", Accessor::Value),
+        "String" => ("String", "str", Accessor::Str),
+        "Vec" => {
+            let is_bytes = match &segment.arguments {
+                PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                    match args.args.first() {
+                        Some(GenericArgument::Type(Type::Path(p))) => p.path.is_ident("u8"),
+                        _ => false,
+                    }
+                }
+                _ => false,
+            };
+            if !is_bytes {
+                return None;
+            }
+            ("Binary", "binary", Accessor::Binary)
+        }
+        _ => return None,
+    };
+
+    Some(field_type)
+}
+
+pub(crate) fn expand_rlink_record(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (im, ty, wh) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                let msg = "`RlinkRecord` only support struct with named fields";
+                return Err(syn::Error::new_spanned(&input.ident, msg));
+            }
+        },
+        _ => {
+            let msg = "`RlinkRecord` only support struct";
+            return Err(syn::Error::new_spanned(&input.ident, msg));
+        }
+    };
+
+    let field_len = fields.len();
+    let mut index_consts = Vec::with_capacity(field_len);
+    let mut field_names = Vec::with_capacity(field_len);
+    let mut data_types = Vec::with_capacity(field_len);
+    let mut setters = Vec::with_capacity(field_len);
+    let mut getters = Vec::with_capacity(field_len);
+
+    for (index, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().unwrap();
+        let (data_type, suffix, accessor) = field_type(&field.ty).ok_or_else(|| {
+            let msg = "unsupported field type, expect one of bool, i8, u8, i16, u16, i32, u32, \
+                       i64, u64, f32, f64, String and Vec<u8>";
+            syn::Error::new_spanned(&field.ty, msg)
+        })?;
+
+        let field_name = ident.unraw().to_string();
+        let index_const = format_ident!("INDEX_{}", field_name.to_uppercase());
+        if index_consts.iter().any(|(c, _)| c == &index_const) {
+            let msg = format!(
+                "the field `{}` conflicts with another field on the index constant `{}`",
+                field_name, index_const
+            );
+            return Err(syn::Error::new_spanned(ident, msg));
+        }
+        let data_type = format_ident!("{}", data_type);
+        let set_fn = format_ident!("set_{}", suffix);
+        let get_fn = format_ident!("get_{}", suffix);
+
+        index_consts.push((index_const, index));
+        data_types.push(quote! {
+            rlink::core::data_types::DataType::#data_type
+        });
+        setters.push(match accessor {
+            Accessor::Value => quote! { writer.#set_fn(self.#ident).unwrap(); },
+            Accessor::Str | Accessor::Binary => quote! { writer.#set_fn(&self.#ident).unwrap(); },
+        });
+        let get_value = quote! { reader.#get_fn(#index).map_err(rlink::core::Error::wrap)? };
+        getters.push(match accessor {
+            Accessor::Value => quote! { #ident: #get_value },
+            Accessor::Str => quote! { #ident: #get_value.to_string() },
+            Accessor::Binary => quote! { #ident: #get_value.to_vec() },
+        });
+        field_names.push(field_name);
+    }
+
+    let index_consts = index_consts.iter().map(|(index_const, index)| {
+        quote! {
+            pub const #index_const: usize = #index;
+        }
+    });
+
+    let expanded = quote! {
+        impl #im #name #ty #wh {
+            #(#index_consts)*
+
+            pub const FIELD_NAME: [&'static str; #field_len] = [#(#field_names),*];
+            pub const FIELD_TYPE: [u8; #field_len] = [#(#data_types.id()),*];
+        }
+
+        impl #im rlink::core::element::RlinkRecord for #name #ty #wh {
+            fn schema() -> rlink::core::data_types::Schema {
+                rlink::core::data_types::Schema::new(vec![
+                    #(rlink::core::data_types::Field::new(#field_names, #data_types)),*
+                ])
+            }
+
+            fn to_record(&self) -> rlink::core::element::Record {
+                let mut record = rlink::core::element::Record::new();
+                let mut writer = record.as_writer(&Self::FIELD_TYPE);
+                #(#setters)*
+                record
+            }
+
+            fn from_record(
+                record: &mut rlink::core::element::Record,
+            ) -> rlink::core::Result<Self> {
+                let reader = record.as_reader(&Self::FIELD_TYPE);
+                Ok(Self {
+                    #(#getters),*
+                })
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::DeriveInput;
+
+    use crate::record::expand_rlink_record;
+
+    fn expand(input: DeriveInput) -> syn::Result<String> {
+        expand_rlink_record(&input).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    pub fn expand_rlink_record_test() {
+        let expanded = expand(parse_quote! {
+            struct Entity {
+                field_name: String,
+                field_type: u8,
+                r#type: i64,
+                data: Vec<u8>,
+            }
+        })
+        .unwrap();
+
+        assert!(expanded.contains("pub const INDEX_FIELD_NAME : usize = 0usize ;"));
+        assert!(expanded.contains("pub const INDEX_FIELD_TYPE : usize = 1usize ;"));
+        assert!(expanded.contains("pub const INDEX_TYPE : usize = 2usize ;"));
+        assert!(expanded.contains("pub const INDEX_DATA : usize = 3usize ;"));
+        assert!(expanded.contains(
+            "pub const FIELD_NAME : [& 'static str ; 4usize] = \
+             [\"field_name\" , \"field_type\" , \"type\" , \"data\"] ;"
+        ));
+        assert!(expanded.contains("writer . set_str (& self . field_name) . unwrap () ;"));
+        assert!(expanded.contains("writer . set_u8 (self . field_type) . unwrap () ;"));
+        assert!(expanded.contains("writer . set_binary (& self . data) . unwrap () ;"));
+    }
+
+    #[test]
+    pub fn expand_rlink_record_error_test() {
+        let err = expand(parse_quote! {
+            struct Entity {
+                name: String,
+                NAME: String,
+            }
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the field `NAME` conflicts with another field on the index constant `INDEX_NAME`"
+        );
+
+        let err = expand(parse_quote! {
+            struct Entity {
+                names: Vec<String>,
+            }
+        })
+        .unwrap_err();
+        assert!(err.to_string().starts_with("unsupported field type"));
+
+        let err = expand(parse_quote! {
+            struct Entity(String);
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`RlinkRecord` only support struct with named fields"
+        );
+
+        let err = expand(parse_quote! {
+            enum Entity {
+                A,
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "`RlinkRecord` only support struct");
+    }
+}
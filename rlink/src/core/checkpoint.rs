@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
@@ -12,7 +13,9 @@ pub struct FunctionSnapshotContext {
     pub checkpoint_id: CheckpointId,
     pub completed_checkpoint_id: Option<CheckpointId>,
 
-    pub(crate) task_context: Arc<WorkerTaskContext>,
+    /// `None` if the function runs out of a worker, such as in the `testing` harnesses
+    pub(crate) task_context: Option<Arc<WorkerTaskContext>>,
+    declined: Arc<AtomicBool>,
 }
 
 impl FunctionSnapshotContext {
@@ -21,7 +24,7 @@ impl FunctionSnapshotContext {
         task_id: TaskId,
         checkpoint_id: CheckpointId,
         completed_checkpoint_id: Option<CheckpointId>,
-        task_context: Arc<WorkerTaskContext>,
+        task_context: Option<Arc<WorkerTaskContext>>,
     ) -> Self {
         FunctionSnapshotContext {
             operator_id,
@@ -29,11 +32,34 @@ impl FunctionSnapshotContext {
             checkpoint_id,
             completed_checkpoint_id,
             task_context,
+            declined: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Decline the checkpoint, eg: a sink fails to flush the buffered data.
+    /// The `Checkpoint` of the task is not reported, so the checkpoint never completes.
+    pub fn decline(&self, reason: &str) {
+        error!(
+            "{:?} decline checkpoint_id={:?}. {}",
+            self.operator_id, self.checkpoint_id, reason
+        );
+        self.declined.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_declined(&self) -> bool {
+        self.declined.load(Ordering::Relaxed)
+    }
+
     pub(crate) fn report(&self, ck: Checkpoint) -> Option<Checkpoint> {
-        self.task_context.checkpoint_publish().report(ck)
+        if self.is_declined() {
+            warn!("skip report declined checkpoint. {:?}", ck);
+            return None;
+        }
+
+        match &self.task_context {
+            Some(task_context) => task_context.checkpoint_publish().report(ck),
+            None => Some(ck),
+        }
     }
 }
 
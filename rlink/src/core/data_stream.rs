@@ -1,15 +1,20 @@
 use std::fmt::Debug;
 use std::rc::Rc;
 
+use crate::core::element::{FnSchema, Record, RlinkRecord};
 use crate::core::env::StreamManager;
 use crate::core::function::{
     CoProcessFunction, FilterFunction, FlatMapFunction, InputFormat, KeySelectorFunction,
-    OutputFormat, ReduceFunction,
+    LookupSource, OutputFormat, ReduceFunction,
 };
 use crate::core::operator::{FunctionCreator, StreamOperator};
 use crate::core::runtime::OperatorId;
 use crate::core::watermark::WatermarkStrategy;
 use crate::core::window::WindowAssigner;
+use crate::functions::filter::{FnFilterFunction, TypedFilterFunction};
+use crate::functions::flat_map::{FnFlatMapFunction, FnMapFunction, TypedMapFunction};
+use crate::functions::key_selector::FnKeySelector;
+use crate::functions::lookup::{LookupJoinFunction, LookupJoinOptions};
 use crate::functions::system::window_base_reduce::WindowBaseReduceFunction;
 
 /// A DataStream represents a stream of elements of the same type. A DataStream can be transformed
@@ -23,6 +28,50 @@ pub trait TDataStream {
     where
         F: FilterFunction + 'static;
 
+    /// Map every record to another with a closure of typed records, see `RlinkRecord`
+    fn typed_map<I, O, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        O: RlinkRecord + 'static,
+        F: Fn(I) -> O + Send + Sync + 'static;
+
+    /// Filter the records with a predicate of typed records, see `RlinkRecord`
+    fn typed_filter<I, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        F: Fn(&I) -> bool + Send + Sync + 'static;
+
+    /// Map every record to another with a closure, `schema` is the output schema
+    fn map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static;
+
+    /// Map every record to zero or more records with a closure, `schema` is the output schema
+    fn flat_map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Vec<Record> + Send + Sync + 'static;
+
+    /// Filter the records with a closure
+    fn filter_fn<F>(self, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> bool + Send + Sync + 'static;
+
+    /// Select the key of every record with a closure, `key_schema` is the schema of the key
+    fn key_by_fn<F>(self, key_schema: FnSchema, f: F) -> KeyedStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static;
+
+    /// Join every record with the value looked up by the `key_columns` in the `LookupSource`,
+    /// the fields of the value are appended to the record, see `LookupJoinOptions`
+    fn lookup_join<L>(
+        self,
+        lookup_source: L,
+        key_columns: &[&str],
+        options: LookupJoinOptions,
+    ) -> DataStream
+    where
+        L: LookupSource + 'static;
+
     fn key_by<F>(self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static;
@@ -68,6 +117,16 @@ pub trait TWindowedStream {
     fn reduce<F>(self, reduce: F) -> DataStream
     where
         F: ReduceFunction + 'static;
+
+    /// Same as `reduce`, but the records are pre-aggregated per key and window inside the
+    /// upstream task before shuffle. At most `max_buffer_size` partial values are buffered,
+    /// the buffer is flushed to the reduce task when it's full or on watermark.
+    ///
+    /// The same `ReduceFunction` is used in both phases, the final phase merges the partial
+    /// values with `ReduceFunction::merge`.
+    fn reduce_with_combiner<F>(self, reduce: F, max_buffer_size: usize) -> DataStream
+    where
+        F: ReduceFunction + 'static;
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -125,6 +184,64 @@ impl TDataStream for DataStream {
         self.data_stream.filter(filter)
     }
 
+    fn typed_map<I, O, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        O: RlinkRecord + 'static,
+        F: Fn(I) -> O + Send + Sync + 'static,
+    {
+        self.data_stream.typed_map(f)
+    }
+
+    fn typed_filter<I, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.data_stream.typed_filter(f)
+    }
+
+    fn map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static,
+    {
+        self.data_stream.map_fn(schema, f)
+    }
+
+    fn flat_map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Vec<Record> + Send + Sync + 'static,
+    {
+        self.data_stream.flat_map_fn(schema, f)
+    }
+
+    fn filter_fn<F>(self, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> bool + Send + Sync + 'static,
+    {
+        self.data_stream.filter_fn(f)
+    }
+
+    fn key_by_fn<F>(self, key_schema: FnSchema, f: F) -> KeyedStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static,
+    {
+        self.data_stream.key_by_fn(key_schema, f)
+    }
+
+    fn lookup_join<L>(
+        self,
+        lookup_source: L,
+        key_columns: &[&str],
+        options: LookupJoinOptions,
+    ) -> DataStream
+    where
+        L: LookupSource + 'static,
+    {
+        self.data_stream
+            .lookup_join(lookup_source, key_columns, options)
+    }
+
     fn key_by<F>(self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static,
@@ -232,6 +349,14 @@ impl TWindowedStream for WindowedStream {
     {
         self.windowed_stream.reduce(reduce)
     }
+
+    fn reduce_with_combiner<F>(self, reduce: F, max_buffer_size: usize) -> DataStream
+    where
+        F: ReduceFunction + 'static,
+    {
+        self.windowed_stream
+            .reduce_with_combiner(reduce, max_buffer_size)
+    }
 }
 
 #[derive(Debug)]
@@ -315,6 +440,63 @@ impl TDataStream for StreamBuilder {
         DataStream::new(self)
     }
 
+    fn typed_map<I, O, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        O: RlinkRecord + 'static,
+        F: Fn(I) -> O + Send + Sync + 'static,
+    {
+        self.flat_map(TypedMapFunction::new(f))
+    }
+
+    fn typed_filter<I, F>(self, f: F) -> DataStream
+    where
+        I: RlinkRecord + 'static,
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.filter(TypedFilterFunction::new(f))
+    }
+
+    fn map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static,
+    {
+        self.flat_map(FnMapFunction::new(schema, f))
+    }
+
+    fn flat_map_fn<F>(self, schema: FnSchema, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> Vec<Record> + Send + Sync + 'static,
+    {
+        self.flat_map(FnFlatMapFunction::new(schema, f))
+    }
+
+    fn filter_fn<F>(self, f: F) -> DataStream
+    where
+        F: Fn(&mut Record) -> bool + Send + Sync + 'static,
+    {
+        self.filter(FnFilterFunction::new(f))
+    }
+
+    fn key_by_fn<F>(self, key_schema: FnSchema, f: F) -> KeyedStream
+    where
+        F: Fn(&mut Record) -> Record + Send + Sync + 'static,
+    {
+        self.key_by(FnKeySelector::new(key_schema, f))
+    }
+
+    fn lookup_join<L>(
+        self,
+        lookup_source: L,
+        key_columns: &[&str],
+        options: LookupJoinOptions,
+    ) -> DataStream
+    where
+        L: LookupSource + 'static,
+    {
+        self.flat_map(LookupJoinFunction::new(lookup_source, key_columns, options))
+    }
+
     fn key_by<F>(mut self, key_selector: F) -> KeyedStream
     where
         F: KeySelectorFunction + 'static,
@@ -429,4 +611,23 @@ impl TWindowedStream for StreamBuilder {
 
         DataStream::new(self)
     }
+
+    fn reduce_with_combiner<F>(mut self, reduce: F, max_buffer_size: usize) -> DataStream
+    where
+        F: ReduceFunction + 'static,
+    {
+        let parallelism = reduce.parallelism();
+        let reduce_func = Box::new(reduce);
+        let base_reduce_func = Box::new(WindowBaseReduceFunction::with_combiner(
+            reduce_func,
+            max_buffer_size,
+        ));
+        let stream_reduce = StreamOperator::new_reduce(parallelism, base_reduce_func);
+
+        self.cur_operator_id = self
+            .stream_manager
+            .add_operator(stream_reduce, vec![self.cur_operator_id]);
+
+        DataStream::new(self)
+    }
 }
@@ -51,7 +51,7 @@ impl DataType {
         }
     }
 
-    pub fn id(&self) -> u8 {
+    pub const fn id(&self) -> u8 {
         match self {
             Self::Boolean => types::BOOL,
             Self::Int8 => types::I8,
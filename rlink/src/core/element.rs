@@ -212,6 +212,17 @@ impl Record {
     }
 }
 
+/// A plain struct that can be converted from/to the values of a `Record`,
+/// usually implemented by `#[derive(RlinkRecord)]` of `rlink-derive`.
+pub trait RlinkRecord: Sized {
+    /// the schema of the `Record` values
+    fn schema() -> Schema;
+
+    fn to_record(&self) -> Record;
+
+    fn from_record(record: &mut Record) -> crate::core::Result<Self>;
+}
+
 impl Partition for Record {
     fn partition(&self) -> u16 {
         self.partition_num
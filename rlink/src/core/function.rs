@@ -5,11 +5,16 @@ use std::sync::Arc;
 use futures::Stream;
 
 use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::data_types::Schema;
 use crate::core::element::{Element, FnSchema, Record};
 use crate::core::properties::Properties;
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
+use crate::core::timer::{Timer, TimerService};
+use crate::core::window::WindowAssigner;
 use crate::dag::execution_graph::{ExecutionEdge, ExecutionNode};
+use crate::functions::system::window_combine_function::WindowCombineFunction;
 use crate::runtime::worker::WorkerTaskContext;
+use crate::utils::stream::MemoryStream;
 
 /// Base class of all operators in the Rust API.
 pub trait NamedFunction {
@@ -35,6 +40,8 @@ pub struct Context {
 
     #[serde(skip)]
     pub(crate) task_context: Option<Arc<WorkerTaskContext>>,
+    #[serde(skip)]
+    pub(crate) timer_service: TimerService,
 }
 
 impl Context {
@@ -44,10 +51,16 @@ impl Context {
             self.task_id,
             self.checkpoint_id,
             self.completed_checkpoint_id,
-            self.task_context(),
+            self.task_context.clone(),
         )
     }
 
+    /// The `TimerService` of the operator, the timers are only fired for `FlatMapFunction` and
+    /// `CoProcessFunction`, see `on_timer`
+    pub fn timer_service(&self) -> TimerService {
+        self.timer_service.clone()
+    }
+
     pub(crate) fn task_context(&self) -> Arc<WorkerTaskContext> {
         self.task_context.as_ref().unwrap().clone()
     }
@@ -189,6 +202,19 @@ where
 {
     async fn open(&mut self, context: &Context) -> crate::core::Result<()>;
     async fn flat_map_element(&mut self, element: Element) -> SendableElementStream;
+
+    /// Called when a timer registered with `Context::timer_service` fires
+    async fn on_timer(&mut self, _timer: Timer) -> SendableElementStream {
+        Box::pin(MemoryStream::new(vec![]))
+    }
+
+    /// Emit the records buffered by the function, called before a barrier, a watermark or a
+    /// stream status is forwarded, so the buffered records are covered by the checkpoint and
+    /// are not late
+    async fn flush(&mut self) -> SendableElementStream {
+        Box::pin(MemoryStream::new(vec![]))
+    }
+
     async fn close(&mut self) -> crate::core::Result<()>;
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
@@ -229,6 +255,14 @@ where
 
     fn reduce(&self, value: Option<&mut Record>, record: &mut Record) -> Record;
 
+    /// Merge a partial value, produced by `reduce` in a map-side combiner, into `value`.
+    ///
+    /// The default delegates to `reduce`, which is only correct when the output of `reduce`
+    /// has the same schema as its input.
+    fn merge(&self, value: Option<&mut Record>, partial: &mut Record) -> Record {
+        self.reduce(value, partial)
+    }
+
     async fn close(&mut self) -> crate::core::Result<()>;
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
@@ -250,6 +284,17 @@ where
     async fn close(&mut self) -> crate::core::Result<()>;
 
     fn value_schema(&self, key_schema: FnSchema) -> FnSchema;
+
+    /// whether the records reaching this function are partial values of a map-side combiner
+    fn is_combined(&self) -> bool {
+        false
+    }
+
+    /// move the user `ReduceFunction` into a map-side combiner running in the upstream task
+    fn into_combiner(
+        self: Box<Self>,
+        window_assigner: Box<dyn WindowAssigner>,
+    ) -> Option<WindowCombineFunction>;
 }
 
 #[async_trait]
@@ -266,7 +311,31 @@ where
 
     async fn process_right(&mut self, stream_seq: usize, record: Record) -> SendableElementStream;
 
+    /// Called when a timer registered with `Context::timer_service` fires
+    async fn on_timer(&mut self, _timer: Timer) -> SendableElementStream {
+        Box::pin(MemoryStream::new(vec![]))
+    }
+
     async fn close(&mut self) -> crate::core::Result<()>;
 
     fn schema(&self, input_schema: FnSchema) -> FnSchema;
 }
+
+/// The external table looked up by the `DataStream::lookup_join`, such as a MySQL table, Redis or
+/// an HTTP service
+#[async_trait]
+pub trait LookupSource
+where
+    Self: NamedFunction + Send + Sync,
+{
+    async fn open(&mut self, context: &Context) -> crate::core::Result<()>;
+
+    /// Look up the values of the `keys` in batch, the keys are the records of the key columns,
+    /// and the values are in the order of the keys, `None` if the key is absent
+    async fn lookup(&mut self, keys: &[Record]) -> crate::core::Result<Vec<Option<Record>>>;
+
+    async fn close(&mut self) -> crate::core::Result<()>;
+
+    /// The schema of the looked up values, the fields are appended to the joined records
+    fn schema(&self) -> Schema;
+}
@@ -10,6 +10,7 @@ pub mod function;
 pub mod operator;
 pub mod properties;
 pub mod runtime;
+pub mod timer;
 pub mod watermark;
 pub mod window;
 
@@ -38,6 +38,14 @@ pub trait SystemProperties {
 
     fn set_pub_sub_channel_size(&mut self, channel_size: usize);
     fn get_pub_sub_channel_size(&self) -> anyhow::Result<usize>;
+
+    /// the interval of the worker heartbeat, default 10s
+    fn set_heartbeat_interval(&mut self, interval: Duration);
+    fn get_heartbeat_interval(&self) -> anyhow::Result<Duration>;
+
+    /// the worker is lost if no heartbeat in the timeout, default 50s
+    fn set_heartbeat_timeout(&mut self, timeout: Duration);
+    fn get_heartbeat_timeout(&self) -> anyhow::Result<Duration>;
 }
 
 pub trait FunctionProperties {
@@ -305,6 +313,8 @@ const SYSTEM_CHECKPOINT_INTERVAL: &str = "SYSTEM_CHECKPOINT_INTERVAL";
 const SYSTEM_CHECKPOINT_TTL: &str = "SYSTEM_CHECKPOINT_TTL";
 const SYSTEM_CLUSTER_MODE: &str = "SYSTEM_CLUSTER_MODE";
 const SYSTEM_PUB_SUB_CHANNEL_SIZE: &str = "SYSTEM_PUB_SUB_CHANNEL_SIZE";
+const SYSTEM_HEARTBEAT_INTERVAL: &str = "SYSTEM_HEARTBEAT_INTERVAL";
+const SYSTEM_HEARTBEAT_TIMEOUT: &str = "SYSTEM_HEARTBEAT_TIMEOUT";
 
 impl SystemProperties for Properties {
     fn set_application_name(&mut self, application_name: &str) {
@@ -381,6 +391,22 @@ impl SystemProperties for Properties {
     fn get_pub_sub_channel_size(&self) -> anyhow::Result<usize> {
         self.get_usize(SYSTEM_PUB_SUB_CHANNEL_SIZE)
     }
+
+    fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.set_duration(SYSTEM_HEARTBEAT_INTERVAL, interval);
+    }
+
+    fn get_heartbeat_interval(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_HEARTBEAT_INTERVAL)
+    }
+
+    fn set_heartbeat_timeout(&mut self, timeout: Duration) {
+        self.set_duration(SYSTEM_HEARTBEAT_TIMEOUT, timeout);
+    }
+
+    fn get_heartbeat_timeout(&self) -> anyhow::Result<Duration> {
+        self.get_duration(SYSTEM_HEARTBEAT_TIMEOUT)
+    }
 }
 
 impl InnerSystemProperties for Properties {
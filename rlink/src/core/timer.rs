@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use crate::core::checkpoint::CheckpointHandle;
+use crate::utils::date_time::current_timestamp_millis;
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TimeDomain {
+    /// fired when the watermark passes the timer's timestamp
+    EventTime,
+    /// fired when the wall clock passes the timer's timestamp
+    ProcessingTime,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Timer {
+    pub time_domain: TimeDomain,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Default)]
+struct TimerQueue {
+    event_time_timers: BTreeSet<u64>,
+    processing_time_timers: BTreeSet<u64>,
+    current_watermark: u64,
+    /// the processing time set by the test harness instead of the wall clock
+    manual_processing_time: Option<u64>,
+}
+
+impl TimerQueue {
+    fn current_processing_time(&self) -> u64 {
+        self.manual_processing_time
+            .unwrap_or_else(current_timestamp_millis)
+    }
+
+    fn queue_mut(&mut self, time_domain: TimeDomain) -> &mut BTreeSet<u64> {
+        match time_domain {
+            TimeDomain::EventTime => &mut self.event_time_timers,
+            TimeDomain::ProcessingTime => &mut self.processing_time_timers,
+        }
+    }
+
+    /// remove and return the timers with timestamp <= `timestamp`
+    fn pop_expired(&mut self, time_domain: TimeDomain, timestamp: u64) -> Vec<Timer> {
+        let queue = self.queue_mut(time_domain);
+        let mut timers = Vec::new();
+        while let Some(first) = queue.iter().next().copied() {
+            if first > timestamp {
+                break;
+            }
+
+            queue.remove(&first);
+            timers.push(Timer {
+                time_domain,
+                timestamp: first,
+            });
+        }
+
+        timers
+    }
+}
+
+/// Register processing-time and event-time timers of a user function, the timers are fired by
+/// `FlatMapFunction::on_timer` and `CoProcessFunction::on_timer` and are part of the operator's
+/// checkpoint.
+///
+/// A timer is identified by its `TimeDomain` and timestamp,
+/// registering the same timer more than once fires it only once.
+#[derive(Clone, Debug, Default)]
+pub struct TimerService {
+    queue: Arc<Mutex<TimerQueue>>,
+}
+
+impl TimerService {
+    pub fn current_processing_time(&self) -> u64 {
+        self.queue.lock().unwrap().current_processing_time()
+    }
+
+    /// the last watermark received by the operator
+    pub fn current_watermark(&self) -> u64 {
+        self.queue.lock().unwrap().current_watermark
+    }
+
+    pub fn register_event_time_timer(&self, timestamp: u64) {
+        self.register(Timer {
+            time_domain: TimeDomain::EventTime,
+            timestamp,
+        });
+    }
+
+    pub fn register_processing_time_timer(&self, timestamp: u64) {
+        self.register(Timer {
+            time_domain: TimeDomain::ProcessingTime,
+            timestamp,
+        });
+    }
+
+    pub fn delete_event_time_timer(&self, timestamp: u64) {
+        self.delete(Timer {
+            time_domain: TimeDomain::EventTime,
+            timestamp,
+        });
+    }
+
+    pub fn delete_processing_time_timer(&self, timestamp: u64) {
+        self.delete(Timer {
+            time_domain: TimeDomain::ProcessingTime,
+            timestamp,
+        });
+    }
+
+    pub fn register(&self, timer: Timer) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.queue_mut(timer.time_domain).insert(timer.timestamp);
+    }
+
+    pub fn delete(&self, timer: Timer) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.queue_mut(timer.time_domain).remove(&timer.timestamp);
+    }
+
+    /// advance the watermark and return the expired event-time timers
+    pub(crate) fn advance_watermark(&self, watermark: u64) -> Vec<Timer> {
+        let mut queue = self.queue.lock().unwrap();
+        if watermark > queue.current_watermark {
+            queue.current_watermark = watermark;
+        }
+        queue.pop_expired(TimeDomain::EventTime, watermark)
+    }
+
+    /// return the expired processing-time timers
+    pub(crate) fn advance_processing_time(&self) -> Vec<Timer> {
+        let mut queue = self.queue.lock().unwrap();
+        let processing_time = queue.current_processing_time();
+        queue.pop_expired(TimeDomain::ProcessingTime, processing_time)
+    }
+
+    /// stop following the wall clock, the processing time only changes by this method
+    #[cfg(any(test, feature = "testing"))]
+    pub(crate) fn set_processing_time(&self, timestamp: u64) {
+        self.queue.lock().unwrap().manual_processing_time = Some(timestamp);
+    }
+
+    fn timers(&self) -> Vec<Timer> {
+        let queue = self.queue.lock().unwrap();
+        let event_time_timers = queue.event_time_timers.iter().map(|timestamp| Timer {
+            time_domain: TimeDomain::EventTime,
+            timestamp: *timestamp,
+        });
+        let processing_time_timers = queue.processing_time_timers.iter().map(|timestamp| Timer {
+            time_domain: TimeDomain::ProcessingTime,
+            timestamp: *timestamp,
+        });
+
+        event_time_timers.chain(processing_time_timers).collect()
+    }
+
+    /// attach the pending timers to the function's `CheckpointHandle`
+    pub(crate) fn snapshot(&self, handle: CheckpointHandle) -> CheckpointHandle {
+        let timers = self.timers();
+        if timers.is_empty() {
+            return handle;
+        }
+
+        let timer_handle = TimerCheckpointHandle {
+            handle: handle.handle,
+            timers,
+        };
+        CheckpointHandle {
+            handle: serde_json::to_string(&timer_handle).unwrap(),
+        }
+    }
+
+    /// restore the pending timers from the checkpoint, return the function's `CheckpointHandle`
+    pub(crate) fn restore(&self, handle: Option<CheckpointHandle>) -> Option<CheckpointHandle> {
+        let handle = handle?;
+        match serde_json::from_str::<TimerCheckpointHandle>(handle.handle.as_str()) {
+            Ok(timer_handle) => {
+                for timer in timer_handle.timers {
+                    self.register(timer);
+                }
+                Some(CheckpointHandle {
+                    handle: timer_handle.handle,
+                })
+            }
+            // the checkpoint has no pending timers
+            Err(_e) => Some(handle),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimerCheckpointHandle {
+    #[serde(rename = "fn_handle")]
+    handle: String,
+    timers: Vec<Timer>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::checkpoint::CheckpointHandle;
+    use crate::core::timer::{TimeDomain, Timer, TimerService};
+
+    #[test]
+    pub fn timer_service_test() {
+        let timer_service = TimerService::default();
+        timer_service.register_event_time_timer(20);
+        timer_service.register_event_time_timer(10);
+        timer_service.register_event_time_timer(10);
+        timer_service.register_event_time_timer(30);
+        timer_service.register_processing_time_timer(u64::MAX);
+
+        let timers = timer_service.advance_watermark(20);
+        assert_eq!(
+            timers,
+            vec![
+                Timer {
+                    time_domain: TimeDomain::EventTime,
+                    timestamp: 10
+                },
+                Timer {
+                    time_domain: TimeDomain::EventTime,
+                    timestamp: 20
+                }
+            ]
+        );
+        assert_eq!(timer_service.current_watermark(), 20);
+        assert!(timer_service.advance_processing_time().is_empty());
+
+        let handle = timer_service.snapshot(CheckpointHandle {
+            handle: "offset".to_string(),
+        });
+
+        let restored_service = TimerService::default();
+        let fn_handle = restored_service.restore(Some(handle)).unwrap();
+        assert_eq!(fn_handle.handle, "offset");
+        assert_eq!(restored_service.timers(), timer_service.timers());
+
+        let fn_handle = restored_service.restore(Some(fn_handle)).unwrap();
+        assert_eq!(fn_handle.handle, "offset");
+    }
+}
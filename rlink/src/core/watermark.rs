@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::time::Duration;
 
 use crate::core::checkpoint::CheckpointFunction;
 use crate::core::element::Record;
@@ -73,4 +74,29 @@ pub trait WatermarkStrategy: NamedFunction + CheckpointFunction + Debug + Send +
 
     /// Instantiates a `TimestampAssigner` for assigning timestamps according to this strategy.
     fn create_timestamp_assigner(&mut self) -> Box<dyn TimestampAssigner>;
+
+    /// The watermark alignment group of the sources, `None` means no alignment.
+    fn alignment(&self) -> Option<WatermarkAlignment> {
+        None
+    }
+}
+
+/// Sources sharing the same alignment `group` pause reading when their local watermark gets more
+/// than `max_drift` ahead of the group's minimum watermark.
+///
+/// The alignment only works when the `Watermark` is assigned in the source task,
+/// that is the `WatermarkStrategy` is chained with the `InputFormat`.
+#[derive(Clone, Debug)]
+pub struct WatermarkAlignment {
+    pub group: String,
+    pub max_drift: Duration,
+}
+
+impl WatermarkAlignment {
+    pub fn new(group: &str, max_drift: Duration) -> Self {
+        WatermarkAlignment {
+            group: group.to_string(),
+            max_drift,
+        }
+    }
 }
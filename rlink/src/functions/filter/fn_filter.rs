@@ -0,0 +1,67 @@
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::Record;
+use crate::core::function::{Context, FilterFunction, NamedFunction};
+
+/// Filter the `Record`s with a closure
+pub struct FnFilterFunction<F>
+where
+    F: Fn(&mut Record) -> bool + Send + Sync,
+{
+    f: F,
+}
+
+impl<F> FnFilterFunction<F>
+where
+    F: Fn(&mut Record) -> bool + Send + Sync,
+{
+    pub fn new(f: F) -> Self {
+        FnFilterFunction { f }
+    }
+}
+
+#[async_trait]
+impl<F> FilterFunction for FnFilterFunction<F>
+where
+    F: Fn(&mut Record) -> bool + Send + Sync,
+{
+    async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    async fn filter(&self, record: &mut Record) -> bool {
+        (self.f)(record)
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F> NamedFunction for FnFilterFunction<F>
+where
+    F: Fn(&mut Record) -> bool + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "FnFilterFunction"
+    }
+}
+
+#[async_trait]
+impl<F> CheckpointFunction for FnFilterFunction<F>
+where
+    F: Fn(&mut Record) -> bool + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
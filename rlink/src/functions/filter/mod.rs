@@ -1 +1,7 @@
 pub mod range_window_filter;
+
+pub mod typed_filter;
+pub use typed_filter::TypedFilterFunction;
+
+pub mod fn_filter;
+pub use fn_filter::FnFilterFunction;
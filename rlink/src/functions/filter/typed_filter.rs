@@ -0,0 +1,106 @@
+use std::marker::PhantomData;
+
+use metrics::Counter;
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Record, RlinkRecord};
+use crate::core::function::{Context, FilterFunction, NamedFunction};
+use crate::metrics::register_counter;
+
+/// Filter the `Record`s with a predicate of typed records. The `Record` that can't be read as `I`
+/// is logged and filtered out, and counted by the `TypedFilter_Discard` metric.
+pub struct TypedFilterFunction<I, F>
+where
+    I: RlinkRecord,
+    F: Fn(&I) -> bool + Send + Sync,
+{
+    f: F,
+    discard_counter: Counter,
+    a: PhantomData<fn(&I)>,
+}
+
+impl<I, F> TypedFilterFunction<I, F>
+where
+    I: RlinkRecord,
+    F: Fn(&I) -> bool + Send + Sync,
+{
+    pub fn new(f: F) -> Self {
+        TypedFilterFunction {
+            f,
+            discard_counter: Counter::noop(),
+            a: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<I, F> FilterFunction for TypedFilterFunction<I, F>
+where
+    I: RlinkRecord,
+    F: Fn(&I) -> bool + Send + Sync,
+{
+    async fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let input_schema = context.input_schema.first();
+        if input_schema.as_type_ids() != I::schema().as_type_ids() {
+            return Err(crate::core::Error::from(format!(
+                "the input schema {:?} mismatch with `{}`",
+                input_schema,
+                std::any::type_name::<I>()
+            )));
+        }
+
+        self.discard_counter = register_counter("TypedFilter_Discard", context.task_id.to_tags());
+
+        Ok(())
+    }
+
+    async fn filter(&self, record: &mut Record) -> bool {
+        match I::from_record(record) {
+            Ok(input) => (self.f)(&input),
+            Err(e) => {
+                error!(
+                    "read record as `{}` error, the record is filtered out. {}",
+                    std::any::type_name::<I>(),
+                    e
+                );
+                self.discard_counter.increment(1);
+                false
+            }
+        }
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+}
+
+impl<I, F> NamedFunction for TypedFilterFunction<I, F>
+where
+    I: RlinkRecord,
+    F: Fn(&I) -> bool + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "TypedFilterFunction"
+    }
+}
+
+#[async_trait]
+impl<I, F> CheckpointFunction for TypedFilterFunction<I, F>
+where
+    I: RlinkRecord,
+    F: Fn(&I) -> bool + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
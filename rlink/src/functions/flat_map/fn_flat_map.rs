@@ -0,0 +1,120 @@
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction, SendableElementStream};
+use crate::utils::stream::MemoryStream;
+
+/// Map every `Record` to zero or more `Record`s with a closure,
+/// the output schema is provided by the caller
+pub struct FnFlatMapFunction<F>
+where
+    F: Fn(&mut Record) -> Vec<Record> + Send + Sync,
+{
+    f: F,
+    schema: FnSchema,
+}
+
+impl<F> FnFlatMapFunction<F>
+where
+    F: Fn(&mut Record) -> Vec<Record> + Send + Sync,
+{
+    pub fn new(schema: FnSchema, f: F) -> Self {
+        FnFlatMapFunction { f, schema }
+    }
+}
+
+#[async_trait]
+impl<F> FlatMapFunction for FnFlatMapFunction<F>
+where
+    F: Fn(&mut Record) -> Vec<Record> + Send + Sync,
+{
+    async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    async fn flat_map_element(&mut self, element: Element) -> SendableElementStream {
+        let mut record = element.into_record();
+
+        let mut outputs = (self.f)(&mut record);
+        // keep the timestamp, partition and windows of the input `Record` as `FnMapFunction` does
+        for output in &mut outputs {
+            output.partition_num = record.partition_num;
+            output.timestamp = record.timestamp;
+            output.channel_key = record.channel_key;
+            output.location_windows = record.location_windows.clone();
+            output.trigger_window = record.trigger_window.clone();
+        }
+
+        Box::pin(MemoryStream::new(outputs))
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        self.schema.clone()
+    }
+}
+
+impl<F> NamedFunction for FnFlatMapFunction<F>
+where
+    F: Fn(&mut Record) -> Vec<Record> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "FnFlatMapFunction"
+    }
+}
+
+#[async_trait]
+impl<F> CheckpointFunction for FnFlatMapFunction<F>
+where
+    F: Fn(&mut Record) -> Vec<Record> + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::element::{FnSchema, Record};
+    use crate::core::window::{TimeWindow, Window};
+    use crate::functions::flat_map::FnFlatMapFunction;
+    use crate::testing::FlatMapHarness;
+
+    #[tokio::test]
+    pub async fn fn_flat_map_function_test() {
+        let function = FnFlatMapFunction::new(FnSchema::Empty, |_record: &mut Record| {
+            vec![Record::new(), Record::new()]
+        });
+        let mut harness = FlatMapHarness::new(function);
+        harness.open().await.unwrap();
+
+        let window = Window::TimeWindow(TimeWindow::new(1000, 2000));
+        let mut record = Record::new();
+        record.partition_num = 3;
+        record.timestamp = 1500;
+        record.location_windows = Some(vec![window.clone()]);
+        record.trigger_window = Some(window.clone());
+        harness.process_record(record).await;
+
+        let outputs = harness.take_records();
+        assert_eq!(outputs.len(), 2);
+        for output in outputs {
+            assert_eq!(output.partition_num, 3);
+            assert_eq!(output.timestamp, 1500);
+            assert_eq!(output.location_windows, Some(vec![window.clone()]));
+            assert_eq!(output.trigger_window, Some(window.clone()));
+        }
+    }
+}
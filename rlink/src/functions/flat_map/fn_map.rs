@@ -0,0 +1,79 @@
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction, SendableElementStream};
+use crate::utils::stream::MemoryStream;
+
+/// Map every `Record` to another with a closure, the output schema is provided by the caller
+pub struct FnMapFunction<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    f: F,
+    schema: FnSchema,
+}
+
+impl<F> FnMapFunction<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    pub fn new(schema: FnSchema, f: F) -> Self {
+        FnMapFunction { f, schema }
+    }
+}
+
+#[async_trait]
+impl<F> FlatMapFunction for FnMapFunction<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    async fn flat_map_element(&mut self, element: Element) -> SendableElementStream {
+        let mut record = element.into_record();
+
+        let output = (self.f)(&mut record);
+        // reuse the input `Record` to keep the timestamp, partition and windows
+        record.values = output.values;
+
+        Box::pin(MemoryStream::new(vec![record]))
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        self.schema.clone()
+    }
+}
+
+impl<F> NamedFunction for FnMapFunction<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "FnMapFunction"
+    }
+}
+
+#[async_trait]
+impl<F> CheckpointFunction for FnMapFunction<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
@@ -3,3 +3,12 @@ pub use broadcast_flat_map::BroadcastFlagMapFunction;
 
 pub mod round_robin_flat_map;
 pub use round_robin_flat_map::RoundRobinFlagMapFunction;
+
+pub mod typed_map;
+pub use typed_map::TypedMapFunction;
+
+pub mod fn_map;
+pub use fn_map::FnMapFunction;
+
+pub mod fn_flat_map;
+pub use fn_flat_map::FnFlatMapFunction;
@@ -0,0 +1,123 @@
+use std::marker::PhantomData;
+
+use metrics::Counter;
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Element, FnSchema, RlinkRecord};
+use crate::core::function::{Context, FlatMapFunction, NamedFunction, SendableElementStream};
+use crate::metrics::register_counter;
+use crate::utils::stream::MemoryStream;
+
+/// Map every `Record` to another with a closure of typed records. The `Record` that can't be read
+/// as `I` is logged and dropped, and counted by the `TypedMap_Discard` metric.
+pub struct TypedMapFunction<I, O, F>
+where
+    I: RlinkRecord,
+    O: RlinkRecord,
+    F: Fn(I) -> O + Send + Sync,
+{
+    f: F,
+    discard_counter: Counter,
+    a: PhantomData<fn(I) -> O>,
+}
+
+impl<I, O, F> TypedMapFunction<I, O, F>
+where
+    I: RlinkRecord,
+    O: RlinkRecord,
+    F: Fn(I) -> O + Send + Sync,
+{
+    pub fn new(f: F) -> Self {
+        TypedMapFunction {
+            f,
+            discard_counter: Counter::noop(),
+            a: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<I, O, F> FlatMapFunction for TypedMapFunction<I, O, F>
+where
+    I: RlinkRecord,
+    O: RlinkRecord,
+    F: Fn(I) -> O + Send + Sync,
+{
+    async fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        let input_schema = context.input_schema.first();
+        if input_schema.as_type_ids() != I::schema().as_type_ids() {
+            return Err(crate::core::Error::from(format!(
+                "the input schema {:?} mismatch with `{}`",
+                input_schema,
+                std::any::type_name::<I>()
+            )));
+        }
+
+        self.discard_counter = register_counter("TypedMap_Discard", context.task_id.to_tags());
+
+        Ok(())
+    }
+
+    async fn flat_map_element(&mut self, element: Element) -> SendableElementStream {
+        let mut record = element.into_record();
+
+        let input = match I::from_record(&mut record) {
+            Ok(input) => input,
+            Err(e) => {
+                error!(
+                    "read record as `{}` error, the record is dropped. {}",
+                    std::any::type_name::<I>(),
+                    e
+                );
+                self.discard_counter.increment(1);
+                return Box::pin(MemoryStream::new(vec![]));
+            }
+        };
+        let output = (self.f)(input).to_record();
+        // reuse the input `Record` to keep the timestamp, partition and windows
+        record.values = output.values;
+
+        Box::pin(MemoryStream::new(vec![record]))
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&O::schema())
+    }
+}
+
+impl<I, O, F> NamedFunction for TypedMapFunction<I, O, F>
+where
+    I: RlinkRecord,
+    O: RlinkRecord,
+    F: Fn(I) -> O + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "TypedMapFunction"
+    }
+}
+
+#[async_trait]
+impl<I, O, F> CheckpointFunction for TypedMapFunction<I, O, F>
+where
+    I: RlinkRecord,
+    O: RlinkRecord,
+    F: Fn(I) -> O + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
@@ -0,0 +1,72 @@
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{FnSchema, Record};
+use crate::core::function::{Context, KeySelectorFunction, NamedFunction};
+
+/// Select the key of every `Record` with a closure, the key schema is provided by the caller
+pub struct FnKeySelector<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    f: F,
+    key_schema: FnSchema,
+}
+
+impl<F> FnKeySelector<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    pub fn new(key_schema: FnSchema, f: F) -> Self {
+        FnKeySelector { f, key_schema }
+    }
+}
+
+#[async_trait]
+impl<F> KeySelectorFunction for FnKeySelector<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    async fn get_key(&self, record: &mut Record) -> Record {
+        (self.f)(record)
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn key_schema(&self, _input_schema: FnSchema) -> FnSchema {
+        self.key_schema.clone()
+    }
+}
+
+impl<F> NamedFunction for FnKeySelector<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    fn name(&self) -> &str {
+        "FnKeySelector"
+    }
+}
+
+#[async_trait]
+impl<F> CheckpointFunction for FnKeySelector<F>
+where
+    F: Fn(&mut Record) -> Record + Send + Sync,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
@@ -1,2 +1,5 @@
 pub mod schema_key_selector;
 pub use schema_key_selector::SchemaKeySelector;
+
+pub mod fn_key_selector;
+pub use fn_key_selector::FnKeySelector;
@@ -0,0 +1,48 @@
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use lru::LruCache;
+
+use crate::core::element::Record;
+
+/// The LRU cache of the looked up values, keyed by the bytes of the key records. Each value
+/// expires after the `ttl`, and the absent keys are also cached to avoid the repeated misses.
+pub struct LookupCache {
+    cache: LruCache<Vec<u8>, (u64, Option<Record>)>,
+    ttl: Duration,
+}
+
+impl LookupCache {
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        let max_size = NonZeroUsize::new(max_size).unwrap_or(NonZeroUsize::MIN);
+        LookupCache {
+            cache: LruCache::new(max_size),
+            ttl,
+        }
+    }
+
+    /// The outer `None` is a cache miss, and the inner one is a cached absent key
+    pub fn get(&mut self, key: &[u8], now: u64) -> Option<Option<Record>> {
+        match self.cache.get(key) {
+            Some((expire_timestamp, value)) if *expire_timestamp > now => Some(value.clone()),
+            Some(_) => {
+                self.cache.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn put(&mut self, key: Vec<u8>, value: Option<Record>, now: u64) {
+        let expire_timestamp = now + self.ttl.as_millis() as u64;
+        self.cache.put(key, (expire_timestamp, value));
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use metrics::Counter;
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::data_types::{DataType, Schema};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{
+    Context, FlatMapFunction, LookupSource, NamedFunction, SendableElementStream,
+};
+use crate::functions::lookup::LookupCache;
+use crate::metrics::register_counter;
+use crate::utils::date_time::current_timestamp_millis;
+use crate::utils::stream::MemoryStream;
+
+/// How the record is joined when its key is absent in the `LookupSource`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinType {
+    /// the record is dropped
+    Inner,
+    /// the record is emitted with the default values of the lookup fields, such as `0` and the
+    /// empty string
+    Left,
+}
+
+#[derive(Clone, Debug)]
+pub struct LookupJoinOptions {
+    join_type: JoinType,
+    batch_size: usize,
+    cache_size: usize,
+    cache_ttl: Duration,
+}
+
+impl Default for LookupJoinOptions {
+    fn default() -> Self {
+        LookupJoinOptions {
+            join_type: JoinType::Inner,
+            batch_size: 1,
+            cache_size: 0,
+            cache_ttl: Duration::from_secs(0),
+        }
+    }
+}
+
+impl LookupJoinOptions {
+    /// `JoinType::Inner` by default
+    pub fn join_type(mut self, join_type: JoinType) -> Self {
+        self.join_type = join_type;
+        self
+    }
+
+    /// Look up the keys of at most `batch_size` records at a time, the records are buffered
+    /// until the batch is full or a barrier, a watermark or a stream status arrives. `1` by
+    /// default, that is no buffering
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Cache at most `max_size` looked up values, each for the `ttl`
+    pub fn cache(mut self, max_size: usize, ttl: Duration) -> Self {
+        self.cache_size = max_size;
+        self.cache_ttl = ttl;
+        self
+    }
+}
+
+/// A record waiting for the batch lookup
+struct PendingRecord {
+    record: Record,
+    key_record: Record,
+    key: Vec<u8>,
+    /// the cached value if hit
+    value: Option<Option<Record>>,
+}
+
+/// Join every record with the value of its key columns looked up in the `LookupSource`, the
+/// fields of the value are appended to the record.
+///
+/// The hits and misses of the cache are counted by the `LookupJoin_CacheHit_{name}` and
+/// `LookupJoin_CacheMiss_{name}` counters of the `LookupSource` name. The lookup errors fail the
+/// task to recover from the last checkpoint.
+pub struct LookupJoinFunction<L>
+where
+    L: LookupSource,
+{
+    lookup_source: L,
+    key_columns: Vec<String>,
+    options: LookupJoinOptions,
+
+    schema: Schema,
+    key_schema: Schema,
+    key_indies: Vec<usize>,
+    value_schema: Schema,
+    output_schema: Schema,
+    /// the value of the absent keys for the left join
+    default_value: Record,
+
+    cache: Option<LookupCache>,
+    buffer: Vec<PendingRecord>,
+
+    hit_counter: Counter,
+    miss_counter: Counter,
+}
+
+impl<L> LookupJoinFunction<L>
+where
+    L: LookupSource,
+{
+    pub fn new(lookup_source: L, key_columns: &[&str], options: LookupJoinOptions) -> Self {
+        LookupJoinFunction {
+            lookup_source,
+            key_columns: key_columns.iter().map(|x| x.to_string()).collect(),
+            options,
+            schema: Schema::empty(),
+            key_schema: Schema::empty(),
+            key_indies: vec![],
+            value_schema: Schema::empty(),
+            output_schema: Schema::empty(),
+            default_value: Record::new(),
+            cache: None,
+            buffer: vec![],
+            hit_counter: Counter::noop(),
+            miss_counter: Counter::noop(),
+        }
+    }
+
+    fn init(&mut self, schema: Schema) -> crate::core::Result<()> {
+        let mut key_indies = Vec::with_capacity(self.key_columns.len());
+        for key_column in &self.key_columns {
+            let index = schema.index_of(key_column.as_str()).ok_or_else(|| {
+                crate::core::Error::from(format!("lookup key column `{}` not found", key_column))
+            })?;
+            key_indies.push(index);
+        }
+
+        self.key_schema = schema.sub_schema(key_indies.as_slice());
+        self.key_indies = key_indies;
+        self.value_schema = self.lookup_source.schema();
+        self.output_schema = schema.clone();
+        self.output_schema.merge(&self.value_schema);
+        self.default_value = default_record(&self.value_schema)?;
+        self.schema = schema;
+
+        if self.options.cache_size > 0 {
+            self.cache = Some(LookupCache::new(
+                self.options.cache_size,
+                self.options.cache_ttl,
+            ));
+        }
+        Ok(())
+    }
+
+    fn key_of(&self, record: &mut Record) -> (Record, Vec<u8>) {
+        let mut key_record = Record::new();
+        let mut writer = key_record.as_writer(self.key_schema.as_type_ids());
+        let reader = record.as_reader(self.schema.as_type_ids());
+        for index in &self.key_indies {
+            writer
+                .set_bytes_raw(reader.get_bytes_raw(*index).unwrap())
+                .unwrap();
+        }
+
+        let key = key_record.as_buffer().as_slice().to_vec();
+        (key_record, key)
+    }
+
+    /// Append the fields of the `value` to the record, keep the timestamp, partition and windows
+    /// of the record
+    fn join(&self, mut record: Record, value: Option<Record>) -> Option<Record> {
+        let mut value = match value {
+            Some(value) => value,
+            None if self.options.join_type == JoinType::Left => self.default_value.clone(),
+            None => return None,
+        };
+
+        let mut output = Record::with_capacity(record.len() + value.len());
+        let mut writer = output.as_writer(self.output_schema.as_type_ids());
+        let reader = record.as_reader(self.schema.as_type_ids());
+        for index in 0..self.schema.fields().len() {
+            writer
+                .set_bytes_raw(reader.get_bytes_raw(index).unwrap())
+                .unwrap();
+        }
+        let value_reader = value.as_reader(self.value_schema.as_type_ids());
+        for index in 0..self.value_schema.fields().len() {
+            writer
+                .set_bytes_raw(value_reader.get_bytes_raw(index).unwrap())
+                .unwrap();
+        }
+
+        record.values = output.values;
+        Some(record)
+    }
+
+    /// Look up the distinct keys of the cache misses in the buffer, and join the buffered
+    /// records in order
+    async fn lookup_buffer(&mut self) -> Vec<Record> {
+        let pending = std::mem::take(&mut self.buffer);
+
+        let mut key_indies = HashMap::new();
+        let mut keys = Vec::new();
+        for pending_record in &pending {
+            if pending_record.value.is_none() && !key_indies.contains_key(&pending_record.key) {
+                key_indies.insert(pending_record.key.clone(), keys.len());
+                keys.push(pending_record.key_record.clone());
+            }
+        }
+
+        let values = if keys.is_empty() {
+            vec![]
+        } else {
+            let values = self
+                .lookup_source
+                .lookup(keys.as_slice())
+                .await
+                .unwrap_or_else(|e| panic!("lookup `{}` error. {}", self.lookup_source.name(), e));
+            if values.len() != keys.len() {
+                panic!(
+                    "lookup `{}` returns {} values of {} keys",
+                    self.lookup_source.name(),
+                    values.len(),
+                    keys.len()
+                );
+            }
+            values
+        };
+
+        if let Some(cache) = self.cache.as_mut() {
+            let now = current_timestamp_millis();
+            for (key, index) in &key_indies {
+                cache.put(key.clone(), values[*index].clone(), now);
+            }
+        }
+
+        pending
+            .into_iter()
+            .filter_map(|pending_record| {
+                let value = match pending_record.value {
+                    Some(value) => value,
+                    None => values[key_indies[&pending_record.key]].clone(),
+                };
+                self.join(pending_record.record, value)
+            })
+            .collect()
+    }
+}
+
+/// The record of the default values of the `schema`
+fn default_record(schema: &Schema) -> anyhow::Result<Record> {
+    let mut record = Record::new();
+    let mut writer = record.as_writer(schema.as_type_ids());
+    for field in schema.fields() {
+        match field.data_type() {
+            DataType::Boolean => writer.set_bool(false),
+            DataType::Int8 => writer.set_i8(0),
+            DataType::UInt8 => writer.set_u8(0),
+            DataType::Int16 => writer.set_i16(0),
+            DataType::UInt16 => writer.set_u16(0),
+            DataType::Int32 => writer.set_i32(0),
+            DataType::UInt32 => writer.set_u32(0),
+            DataType::Int64 => writer.set_i64(0),
+            DataType::UInt64 => writer.set_u64(0),
+            DataType::Float32 => writer.set_f32(0.0),
+            DataType::Float64 => writer.set_f64(0.0),
+            DataType::Binary => writer.set_binary(&[]),
+            DataType::String => writer.set_str(""),
+        }?;
+    }
+    Ok(record)
+}
+
+#[async_trait]
+impl<L> FlatMapFunction for LookupJoinFunction<L>
+where
+    L: LookupSource,
+{
+    async fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.init(context.input_schema.first().clone())?;
+
+        let name = self.lookup_source.name().to_string();
+        self.hit_counter = register_counter(
+            format!("LookupJoin_CacheHit_{}", name),
+            context.task_id.to_tags(),
+        );
+        self.miss_counter = register_counter(
+            format!("LookupJoin_CacheMiss_{}", name),
+            context.task_id.to_tags(),
+        );
+
+        self.lookup_source.open(context).await
+    }
+
+    async fn flat_map_element(&mut self, element: Element) -> SendableElementStream {
+        let mut record = element.into_record();
+        let (key_record, key) = self.key_of(&mut record);
+
+        let now = current_timestamp_millis();
+        let value = self
+            .cache
+            .as_mut()
+            .and_then(|cache| cache.get(key.as_slice(), now));
+        match &value {
+            Some(_) => self.hit_counter.increment(1),
+            None => self.miss_counter.increment(1),
+        }
+
+        // the cache hits are emitted directly unless there are records buffered before them
+        if let (Some(value), true) = (&value, self.buffer.is_empty()) {
+            let records = self.join(record, value.clone()).into_iter().collect();
+            return Box::pin(MemoryStream::new(records));
+        }
+
+        self.buffer.push(PendingRecord {
+            record,
+            key_record,
+            key,
+            value,
+        });
+        if self.buffer.len() < self.options.batch_size {
+            return Box::pin(MemoryStream::new(vec![]));
+        }
+
+        let records = self.lookup_buffer().await;
+        Box::pin(MemoryStream::new(records))
+    }
+
+    async fn flush(&mut self) -> SendableElementStream {
+        let records = if self.buffer.is_empty() {
+            vec![]
+        } else {
+            self.lookup_buffer().await
+        };
+        Box::pin(MemoryStream::new(records))
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        if !self.buffer.is_empty() {
+            warn!("{} buffered records are not joined", self.buffer.len());
+        }
+        self.lookup_source.close().await
+    }
+
+    fn schema(&self, input_schema: FnSchema) -> FnSchema {
+        let mut schema = input_schema.first().clone();
+        schema.merge(&self.lookup_source.schema());
+        FnSchema::from(&schema)
+    }
+}
+
+impl<L> NamedFunction for LookupJoinFunction<L>
+where
+    L: LookupSource,
+{
+    fn name(&self) -> &str {
+        "LookupJoinFunction"
+    }
+}
+
+#[async_trait]
+impl<L> CheckpointFunction for LookupJoinFunction<L>
+where
+    L: LookupSource,
+{
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    /// The buffer is flushed before the barrier, nothing to snapshot
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::{Element, Record};
+    use crate::core::function::{
+        Context, FlatMapFunction, LookupSource, NamedFunction, SendableElementStream,
+    };
+    use crate::functions::lookup::{JoinType, LookupJoinFunction, LookupJoinOptions};
+
+    /// user id -> user name
+    struct UserLookup {
+        users: HashMap<i64, String>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    impl NamedFunction for UserLookup {
+        fn name(&self) -> &str {
+            "UserLookup"
+        }
+    }
+
+    #[async_trait]
+    impl LookupSource for UserLookup {
+        async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        async fn lookup(&mut self, keys: &[Record]) -> crate::core::Result<Vec<Option<Record>>> {
+            self.lookups.fetch_add(keys.len(), Ordering::SeqCst);
+            let key_schema = Schema::new(vec![Field::new("user_id", DataType::Int64)]);
+            let values = keys
+                .iter()
+                .map(|key| {
+                    let mut key = key.clone();
+                    let user_id = key.as_reader(key_schema.as_type_ids()).get_i64(0).unwrap();
+                    self.users.get(&user_id).map(|name| {
+                        let mut value = Record::new();
+                        let value_schema = self.schema();
+                        let mut writer = value.as_writer(value_schema.as_type_ids());
+                        writer.set_str(name.as_str()).unwrap();
+                        value
+                    })
+                })
+                .collect();
+            Ok(values)
+        }
+
+        async fn close(&mut self) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn schema(&self) -> Schema {
+            Schema::new(vec![Field::new("user_name", DataType::String)])
+        }
+    }
+
+    async fn collect(output_schema: &Schema, stream: SendableElementStream) -> Vec<(i64, String)> {
+        stream
+            .map(|element| {
+                let mut record = element.into_record();
+                let reader = record.as_reader(output_schema.as_type_ids());
+                (
+                    reader.get_i64(1).unwrap(),
+                    reader.get_str(2).unwrap().to_string(),
+                )
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    pub async fn lookup_join_test() {
+        let schema = Schema::new(vec![
+            Field::new("order_id", DataType::Int64),
+            Field::new("user_id", DataType::Int64),
+        ]);
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let lookup_source = UserLookup {
+            users: HashMap::from([(1, "a".to_string()), (2, "b".to_string())]),
+            lookups: lookups.clone(),
+        };
+        let options = LookupJoinOptions::default()
+            .join_type(JoinType::Left)
+            .batch_size(3)
+            .cache(10, Duration::from_secs(60));
+        let mut function = LookupJoinFunction::new(lookup_source, &["user_id"], options);
+        function.init(schema.clone()).unwrap();
+        let output_schema = function.output_schema.clone();
+
+        let element = |order_id: i64, user_id: i64| {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(schema.as_type_ids());
+            writer.set_i64(order_id).unwrap();
+            writer.set_i64(user_id).unwrap();
+            Element::Record(record)
+        };
+
+        // buffered until the batch is full, the duplicated keys are looked up once
+        let stream = function.flat_map_element(element(1, 1)).await;
+        assert!(collect(&output_schema, stream).await.is_empty());
+        let stream = function.flat_map_element(element(2, 1)).await;
+        assert!(collect(&output_schema, stream).await.is_empty());
+        let stream = function.flat_map_element(element(3, 3)).await;
+        assert_eq!(
+            collect(&output_schema, stream).await,
+            vec![
+                (1, "a".to_string()),
+                (1, "a".to_string()),
+                (3, "".to_string())
+            ]
+        );
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+
+        // the cache hits, including the absent key, are emitted directly
+        let stream = function.flat_map_element(element(4, 3)).await;
+        assert_eq!(
+            collect(&output_schema, stream).await,
+            vec![(3, "".to_string())]
+        );
+        let stream = function.flat_map_element(element(5, 1)).await;
+        assert_eq!(
+            collect(&output_schema, stream).await,
+            vec![(1, "a".to_string())]
+        );
+
+        // flushed
+        let stream = function.flat_map_element(element(6, 2)).await;
+        assert!(collect(&output_schema, stream).await.is_empty());
+        let stream = function.flush().await;
+        assert_eq!(
+            collect(&output_schema, stream).await,
+            vec![(2, "b".to_string())]
+        );
+        assert_eq!(lookups.load(Ordering::SeqCst), 3);
+
+        // the absent key is dropped by the inner join
+        function.options = function.options.clone().join_type(JoinType::Inner);
+        let stream = function.flat_map_element(element(7, 3)).await;
+        assert!(collect(&output_schema, stream).await.is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod cache;
+pub use cache::LookupCache;
+
+pub mod lookup_join;
+pub use lookup_join::{JoinType, LookupJoinFunction, LookupJoinOptions};
@@ -2,6 +2,7 @@ pub mod column_locate;
 pub mod filter;
 pub mod flat_map;
 pub mod key_selector;
+pub mod lookup;
 pub mod percentile;
 pub mod reduce;
 pub mod sink;
@@ -122,8 +122,10 @@ impl<'a> PercentileWriter<'a> {
     }
 
     pub fn merge(&mut self, percentile: &PercentileReader) {
-        for i in 0..self.count_container.len() {
-            self.count_container[i] += percentile.count_container[i];
+        // merge field by field, include the latest counter field
+        for index in (0..self.count_container.len()).step_by(8) {
+            let n = self.read(index) + percentile.read(index);
+            self.write(index, n);
         }
     }
 }
@@ -314,4 +316,33 @@ mod tests {
         let line_95 = percentile_reader.get_result(95);
         assert_eq!(line_95, 2_f64);
     }
+    #[test]
+    pub fn percentile_merge_test() {
+        let scale = get_scale2();
+
+        let mut left = vec![0u8; get_percentile_capacity(scale)];
+        let mut right = vec![0u8; get_percentile_capacity(scale)];
+        {
+            let mut percentile_writer = PercentileWriter::new(scale, left.as_mut_slice());
+            for _ in 0..300 {
+                percentile_writer.accumulate(1f64);
+            }
+        }
+        {
+            let mut percentile_writer = PercentileWriter::new(scale, right.as_mut_slice());
+            for _ in 0..700 {
+                percentile_writer.accumulate(20f64);
+            }
+        }
+
+        {
+            let mut percentile_writer = PercentileWriter::new(scale, left.as_mut_slice());
+            percentile_writer.merge(&PercentileReader::new(scale, right.as_slice()));
+        }
+
+        let percentile_reader = PercentileReader::new(scale, left.as_slice());
+        assert_eq!(percentile_reader.get_counter(), 1000);
+        assert_eq!(percentile_reader.get_result(50), 20_f64);
+        assert_eq!(percentile_reader.get_result(20), 1_f64);
+    }
 }
@@ -6,7 +6,7 @@ use crate::core::data_types::{DataType, Field, Schema};
 use crate::core::element::{BufferMutReader, BufferReader, BufferWriter, FnSchema, Record};
 use crate::core::function::{Context, NamedFunction, ReduceFunction};
 use crate::functions::column_locate::{ColumnLocate, ColumnLocateBuilder};
-use crate::functions::percentile::{get_percentile_capacity, PercentileWriter};
+use crate::functions::percentile::{get_percentile_capacity, PercentileReader, PercentileWriter};
 
 pub fn count() -> AggregationDescriptor {
     AggregationDescriptor::Count
@@ -75,6 +75,14 @@ pub trait Aggregation: Debug + Send + Sync {
         value_index: usize,
         record_reader: &mut BufferReader,
     );
+    /// merge a partial value, both `value_reader` and `partial_reader` have the output schema
+    fn merge(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    );
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -118,6 +126,21 @@ impl Aggregation for CountAggregation {
         };
         writer.set_u64(agg_value).unwrap();
     }
+
+    fn merge(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = partial_reader.get_u64(value_index).unwrap();
+        let agg_value = match value_reader {
+            Some(value_reader) => value_reader.get_u64(value_index).unwrap() + partial_value,
+            None => partial_value,
+        };
+        writer.set_u64(agg_value).unwrap();
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -217,6 +240,26 @@ impl<T: ValueAgg> BasicAggregation<T> {
             value_agg: T::default(),
         }
     }
+
+    fn aggregate(&self, basic_value: T, record_value: T) -> T {
+        match self.agg_type {
+            BasicAggType::Sum => basic_value + record_value,
+            BasicAggType::Max => {
+                if basic_value > record_value {
+                    basic_value
+                } else {
+                    record_value
+                }
+            }
+            BasicAggType::Min => {
+                if basic_value > record_value {
+                    record_value
+                } else {
+                    basic_value
+                }
+            }
+        }
+    }
 }
 
 impl<T: ValueAgg> Aggregation for BasicAggregation<T> {
@@ -240,28 +283,30 @@ impl<T: ValueAgg> Aggregation for BasicAggregation<T> {
         let agg_value = match value_reader {
             Some(value_reader) => {
                 let basic_value = self.value_agg.read_value(value_reader, value_index);
-                match self.agg_type {
-                    BasicAggType::Sum => basic_value + record_value,
-                    BasicAggType::Max => {
-                        if basic_value > record_value {
-                            basic_value
-                        } else {
-                            record_value
-                        }
-                    }
-                    BasicAggType::Min => {
-                        if basic_value > record_value {
-                            record_value
-                        } else {
-                            basic_value
-                        }
-                    }
-                }
+                self.aggregate(basic_value, record_value)
             }
             None => record_value,
         };
         self.value_agg.write_record(writer, agg_value)
     }
+
+    fn merge(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = self.value_agg.read_record(partial_reader, value_index);
+        let agg_value = match value_reader {
+            Some(value_reader) => {
+                let basic_value = self.value_agg.read_value(value_reader, value_index);
+                self.aggregate(basic_value, partial_value)
+            }
+            None => partial_value,
+        };
+        self.value_agg.write_record(writer, agg_value)
+    }
 }
 
 pub trait ValueAgg: Add<Output = Self> + PartialOrd + Default + Debug + Send + Sync {
@@ -482,6 +527,29 @@ impl Aggregation for PctAggregation {
             }
         }
     }
+
+    fn merge(
+        &self,
+        writer: &mut BufferWriter,
+        value_reader: Option<&mut BufferMutReader>,
+        value_index: usize,
+        partial_reader: &mut BufferReader,
+    ) {
+        let partial_value = partial_reader.get_binary(value_index).unwrap();
+        match value_reader {
+            Some(value_reader) => {
+                let stat_value = value_reader.get_binary_mut(value_index).unwrap();
+
+                let mut percentile = PercentileWriter::new(self.scale, stat_value);
+                percentile.merge(&PercentileReader::new(self.scale, partial_value));
+
+                writer.set_binary(stat_value).unwrap();
+            }
+            None => {
+                writer.set_binary(partial_value).unwrap();
+            }
+        }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -575,6 +643,39 @@ impl ReduceFunction for SchemaReduceFunction {
         record_rt
     }
 
+    fn merge(&self, value: Option<&mut Record>, partial: &mut Record) -> Record {
+        let mut record_rt = Record::with_capacity(self.val_len);
+        let mut writer = record_rt.as_writer(self.val_schema.as_type_ids());
+
+        let mut partial_reader = partial.as_reader(self.val_schema.as_type_ids());
+
+        match value {
+            Some(state_value) => {
+                let mut stat_reader = state_value.as_reader_mut(self.val_schema.as_type_ids());
+
+                for index in 0..self.agg_operators.len() {
+                    self.agg_operators[index].merge(
+                        writer.borrow_mut(),
+                        Some(stat_reader.borrow_mut()),
+                        index,
+                        partial_reader.borrow_mut(),
+                    )
+                }
+            }
+            None => {
+                for index in 0..self.agg_operators.len() {
+                    self.agg_operators[index].merge(
+                        writer.borrow_mut(),
+                        None,
+                        index,
+                        partial_reader.borrow_mut(),
+                    )
+                }
+            }
+        }
+        record_rt
+    }
+
     async fn close(&mut self) -> crate::core::Result<()> {
         Ok(())
     }
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use futures::Stream;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::data_types::{DataType, Schema};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{
+    Context, ElementStream, InputFormat, InputSplit, InputSplitSource, NamedFunction,
+    SendableElementStream,
+};
+use crate::utils::date_time::current_timestamp_millis;
+
+const RATE_LIMIT_TICK: Duration = Duration::from_millis(10);
+
+/// How the values of a field are generated, the values are converted to the field type, such
+/// as the number of a `String` field is written as its string form
+#[derive(Clone, Debug)]
+pub enum FieldGenerator {
+    /// `start`, `start + step`, `start + 2 * step` ... of all the tasks, the values are unique
+    /// across the tasks and continue from the checkpoint after recovery
+    Sequence { start: i64, step: i64 },
+    /// a random number in `[min, max)`, an integer for the integer fields
+    Random { min: f64, max: f64 },
+    /// a random one of the values
+    Choice(Vec<String>),
+    /// a string of the pattern, `#` is replaced by a random digit, `?` by a random lowercase
+    /// letter and `*` by a random alphanumeric character
+    Pattern(String),
+    /// the current timestamp in milliseconds minus a random delay in `[0, max_disorder)`, so the
+    /// event times are out of order within the `max_disorder`
+    EventTime { max_disorder: Duration },
+}
+
+impl FieldGenerator {
+    pub fn sequence(start: i64, step: i64) -> Self {
+        FieldGenerator::Sequence { start, step }
+    }
+
+    pub fn random(min: f64, max: f64) -> Self {
+        FieldGenerator::Random { min, max }
+    }
+
+    pub fn choice(values: &[&str]) -> Self {
+        FieldGenerator::Choice(values.iter().map(|x| x.to_string()).collect())
+    }
+
+    pub fn pattern(pattern: &str) -> Self {
+        FieldGenerator::Pattern(pattern.to_string())
+    }
+
+    pub fn event_time(max_disorder: Duration) -> Self {
+        FieldGenerator::EventTime { max_disorder }
+    }
+
+    /// The generator of the fields without one, `0` or `1` for `Boolean`, `[0, 100)` for the
+    /// numbers and 8 random letters for `String` and `Binary`
+    fn default_of(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Boolean => FieldGenerator::random(0.0, 2.0),
+            DataType::Float32 | DataType::Float64 => FieldGenerator::random(0.0, 100.0),
+            DataType::String | DataType::Binary => FieldGenerator::pattern("????????"),
+            _ => FieldGenerator::random(0.0, 100.0),
+        }
+    }
+
+    fn generate(&self, data_type: &DataType, row: u64, rng: &mut StdRng) -> Value {
+        match self {
+            Self::Sequence { start, step } => {
+                Value::Int(start.wrapping_add((row as i64).wrapping_mul(*step)))
+            }
+            Self::Random { min, max } if min >= max => Value::Float(*min),
+            Self::Random { min, max } => match data_type {
+                DataType::Float32 | DataType::Float64 => Value::Float(rng.gen_range(*min..*max)),
+                _ => Value::Int(rng.gen_range(*min..*max).floor() as i64),
+            },
+            Self::Choice(values) => match values.choose(rng) {
+                Some(value) => Value::Str(value.clone()),
+                None => Value::Str("".to_string()),
+            },
+            Self::Pattern(pattern) => Value::Str(
+                pattern
+                    .chars()
+                    .map(|c| match c {
+                        '#' => rng.gen_range(b'0'..=b'9') as char,
+                        '?' => rng.gen_range(b'a'..=b'z') as char,
+                        '*' => rng.sample(rand::distributions::Alphanumeric) as char,
+                        c => c,
+                    })
+                    .collect(),
+            ),
+            Self::EventTime { max_disorder } => {
+                let max_disorder = max_disorder.as_millis() as u64;
+                let delay = if max_disorder == 0 {
+                    0
+                } else {
+                    rng.gen_range(0..max_disorder)
+                };
+                Value::Int(current_timestamp_millis().saturating_sub(delay) as i64)
+            }
+        }
+    }
+}
+
+/// A generated value before converted to the field type
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl Value {
+    fn as_i64(&self) -> i64 {
+        match self {
+            Value::Int(v) => *v,
+            Value::Float(v) => *v as i64,
+            Value::Str(v) => v.parse().unwrap_or_default(),
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Value::Int(v) => *v as f64,
+            Value::Float(v) => *v,
+            Value::Str(v) => v.parse().unwrap_or_default(),
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Value::Int(v) => v.to_string(),
+            Value::Float(v) => v.to_string(),
+            Value::Str(v) => v,
+        }
+    }
+}
+
+/// Generate the rows of a task, the `row` of the task is the `task_number + row * num_tasks`
+/// row of all the tasks
+struct RowGenerator {
+    schema: Schema,
+    generators: Vec<FieldGenerator>,
+    task_number: u16,
+    num_tasks: u16,
+    rng: StdRng,
+}
+
+impl RowGenerator {
+    fn global_row(&self, row: u64) -> u64 {
+        self.task_number as u64 + row * self.num_tasks as u64
+    }
+
+    fn generate(&mut self, row: u64) -> Record {
+        let global_row = self.global_row(row);
+
+        let mut record = Record::new();
+        let mut writer = record.as_writer(self.schema.as_type_ids());
+        for (field, generator) in self.schema.fields().iter().zip(self.generators.iter()) {
+            let value = generator.generate(field.data_type(), global_row, &mut self.rng);
+            let rt = match field.data_type() {
+                DataType::Boolean => writer.set_bool(value.as_i64() != 0),
+                DataType::Int8 => writer.set_i8(value.as_i64() as i8),
+                DataType::UInt8 => writer.set_u8(value.as_i64() as u8),
+                DataType::Int16 => writer.set_i16(value.as_i64() as i16),
+                DataType::UInt16 => writer.set_u16(value.as_i64() as u16),
+                DataType::Int32 => writer.set_i32(value.as_i64() as i32),
+                DataType::UInt32 => writer.set_u32(value.as_i64() as u32),
+                DataType::Int64 => writer.set_i64(value.as_i64()),
+                DataType::UInt64 => writer.set_u64(value.as_i64() as u64),
+                DataType::Float32 => writer.set_f32(value.as_f64() as f32),
+                DataType::Float64 => writer.set_f64(value.as_f64()),
+                DataType::Binary => writer.set_binary(value.into_string().as_bytes()),
+                DataType::String => writer.set_str(value.into_string().as_str()),
+            };
+            rt.unwrap();
+        }
+
+        record
+    }
+}
+
+/// Generate the records of the `Schema` with the `FieldGenerator` of each field, for the load
+/// testing and the demos.
+///
+/// The rows are distributed among the tasks, and the rows of all the tasks are limited by
+/// `rows_per_second` and `number_of_rows` if set, otherwise the generation is as fast as
+/// possible and never ends. The number of the rows generated by each task is checkpointed, so
+/// the sequences continue after recovery.
+pub struct DataGenInputFormat {
+    schema: Schema,
+    parallelism: u16,
+    generators: HashMap<String, FieldGenerator>,
+    rows_per_second: Option<u64>,
+    number_of_rows: Option<u64>,
+
+    task_number: u16,
+    num_tasks: u16,
+    /// the number of the rows emitted by the task
+    emitted_rows: Arc<AtomicU64>,
+}
+
+impl DataGenInputFormat {
+    pub fn new(schema: Schema, parallelism: u16) -> Self {
+        DataGenInputFormat {
+            schema,
+            parallelism,
+            generators: HashMap::new(),
+            rows_per_second: None,
+            number_of_rows: None,
+            task_number: 0,
+            num_tasks: 1,
+            emitted_rows: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Set the generator of the field `name`, the fields without one are random values
+    pub fn field(mut self, name: &str, generator: FieldGenerator) -> Self {
+        self.generators.insert(name.to_string(), generator);
+        self
+    }
+
+    /// The rate of all the tasks
+    pub fn rows_per_second(mut self, rows_per_second: u64) -> Self {
+        self.rows_per_second = Some(rows_per_second);
+        self
+    }
+
+    /// The total rows of all the tasks, the stream ends after all the rows are emitted
+    pub fn number_of_rows(mut self, number_of_rows: u64) -> Self {
+        self.number_of_rows = Some(number_of_rows);
+        self
+    }
+
+    /// The number of the rows of the task
+    fn task_rows(&self) -> Option<u64> {
+        self.number_of_rows.map(|number_of_rows| {
+            let num_tasks = self.num_tasks as u64;
+            let task_number = self.task_number as u64;
+            number_of_rows / num_tasks + (task_number < number_of_rows % num_tasks) as u64
+        })
+    }
+
+    fn row_generator(&self) -> RowGenerator {
+        let generators = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| match self.generators.get(field.name()) {
+                Some(generator) => generator.clone(),
+                None => FieldGenerator::default_of(field.data_type()),
+            })
+            .collect();
+
+        RowGenerator {
+            schema: self.schema.clone(),
+            generators,
+            task_number: self.task_number,
+            num_tasks: self.num_tasks,
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl InputSplitSource for DataGenInputFormat {}
+
+#[async_trait]
+impl InputFormat for DataGenInputFormat {
+    async fn open(
+        &mut self,
+        _input_split: InputSplit,
+        context: &Context,
+    ) -> crate::core::Result<()> {
+        for name in self.generators.keys() {
+            if self.schema.index_of(name.as_str()).is_none() {
+                return Err(crate::core::Error::from(format!(
+                    "the field `{}` of the generator is not found",
+                    name
+                )));
+            }
+        }
+
+        self.task_number = context.task_id.task_number();
+        self.num_tasks = context.task_id.num_tasks();
+        self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+            .await;
+
+        Ok(())
+    }
+
+    async fn element_stream(&mut self) -> SendableElementStream {
+        let rate_limiter = self.rows_per_second.map(|rows_per_second| {
+            RateLimiter::new(rows_per_second as f64 / self.num_tasks as f64)
+        });
+
+        Box::pin(DataGenStream {
+            generator: self.row_generator(),
+            rate_limiter,
+            task_rows: self.task_rows(),
+            emitted_rows: self.emitted_rows.clone(),
+        })
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::from(&self.schema)
+    }
+
+    fn parallelism(&self) -> u16 {
+        self.parallelism
+    }
+}
+
+impl NamedFunction for DataGenInputFormat {
+    fn name(&self) -> &str {
+        "DataGenInputFormat"
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for DataGenInputFormat {
+    async fn initialize_state(
+        &mut self,
+        context: &FunctionSnapshotContext,
+        handle: &Option<CheckpointHandle>,
+    ) {
+        if let Some(handle) = handle {
+            match handle.handle.parse::<u64>() {
+                Ok(emitted_rows) => {
+                    info!(
+                        "load data gen source from checkpoint({:?}), emitted rows: {}",
+                        context.checkpoint_id, emitted_rows
+                    );
+                    self.emitted_rows.store(emitted_rows, Ordering::SeqCst);
+                }
+                Err(e) => error!("parse data gen checkpoint {} error. {}", handle.handle, e),
+            }
+        }
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        Some(CheckpointHandle {
+            handle: self.emitted_rows.load(Ordering::SeqCst).to_string(),
+        })
+    }
+}
+
+/// Allow `rows_per_second` rows on average, the permits are refilled every tick
+struct RateLimiter {
+    interval: Interval,
+    rows_per_tick: f64,
+    permits: f64,
+}
+
+impl RateLimiter {
+    fn new(rows_per_second: f64) -> Self {
+        let mut interval = tokio::time::interval(RATE_LIMIT_TICK);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        RateLimiter {
+            interval,
+            rows_per_tick: rows_per_second * RATE_LIMIT_TICK.as_secs_f64(),
+            permits: 0.0,
+        }
+    }
+
+    fn poll_acquire(&mut self, cx: &mut std::task::Context<'_>) -> Poll<()> {
+        loop {
+            if self.permits >= 1.0 {
+                self.permits -= 1.0;
+                return Poll::Ready(());
+            }
+
+            match self.interval.poll_tick(cx) {
+                // the unused permits of a tick are kept, but not accumulated when idle
+                Poll::Ready(_) => {
+                    self.permits = (self.permits + self.rows_per_tick).min(self.rows_per_tick + 1.0)
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+struct DataGenStream {
+    generator: RowGenerator,
+    rate_limiter: Option<RateLimiter>,
+    /// the stream ends after the rows are emitted if bounded
+    task_rows: Option<u64>,
+    emitted_rows: Arc<AtomicU64>,
+}
+
+impl ElementStream for DataGenStream {}
+
+impl Stream for DataGenStream {
+    type Item = Element;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let row = self.emitted_rows.load(Ordering::SeqCst);
+        if let Some(task_rows) = self.task_rows {
+            if row >= task_rows {
+                return Poll::Ready(None);
+            }
+        }
+
+        if let Some(rate_limiter) = self.rate_limiter.as_mut() {
+            if rate_limiter.poll_acquire(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        let record = self.generator.generate(row);
+        self.emitted_rows.store(row + 1, Ordering::SeqCst);
+        Poll::Ready(Some(Element::Record(record)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::functions::source::data_gen_input_format::FieldGenerator;
+    use crate::functions::source::DataGenInputFormat;
+    use crate::utils::date_time::current_timestamp_millis;
+
+    #[test]
+    pub fn data_gen_test() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("city", DataType::String),
+            Field::new("code", DataType::String),
+            Field::new("price", DataType::Float64),
+            Field::new("timestamp", DataType::UInt64),
+            Field::new("flag", DataType::Boolean),
+        ]);
+        let mut input_format = DataGenInputFormat::new(schema.clone(), 2)
+            .field("id", FieldGenerator::sequence(100, 10))
+            .field("city", FieldGenerator::choice(&["bj", "sh"]))
+            .field("code", FieldGenerator::pattern("A-##?"))
+            .field("price", FieldGenerator::random(1.0, 2.0))
+            .field(
+                "timestamp",
+                FieldGenerator::event_time(Duration::from_secs(5)),
+            )
+            .number_of_rows(5);
+        input_format.task_number = 1;
+        input_format.num_tasks = 2;
+        assert_eq!(input_format.task_rows(), Some(2));
+
+        let mut generator = input_format.row_generator();
+        let now = current_timestamp_millis();
+        for row in 0..2 {
+            let mut record = generator.generate(row);
+            let reader = record.as_reader(schema.as_type_ids());
+
+            // the rows 1 and 3 of all the tasks
+            assert_eq!(reader.get_i64(0).unwrap(), 100 + (1 + row as i64 * 2) * 10);
+            assert!(["bj", "sh"].contains(&reader.get_str(1).unwrap()));
+
+            let code = reader.get_str(2).unwrap();
+            assert_eq!(code.len(), 5);
+            assert!(code.starts_with("A-"));
+            assert!(code[2..4].chars().all(|c| c.is_ascii_digit()));
+            assert!(code[4..].chars().all(|c| c.is_ascii_lowercase()));
+
+            let price = reader.get_f64(3).unwrap();
+            assert!((1.0..2.0).contains(&price));
+
+            let timestamp = reader.get_u64(4).unwrap();
+            assert!(timestamp + 5000 > now && timestamp <= now + 1000);
+        }
+
+        // integer random values
+        let mut rng = StdRng::seed_from_u64(0);
+        for row in 0..100 {
+            let value = FieldGenerator::random(0.0, 3.0).generate(&DataType::Int32, row, &mut rng);
+            assert!((0..3).contains(&value.as_i64()));
+        }
+    }
+}
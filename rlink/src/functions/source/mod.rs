@@ -1,2 +1,5 @@
 pub mod vec_input_format;
 pub use vec_input_format::*;
+
+pub mod data_gen_input_format;
+pub use data_gen_input_format::{DataGenInputFormat, FieldGenerator};
@@ -1,4 +1,5 @@
 pub mod keyed_state_flat_map;
 pub mod system_input_format;
 pub mod system_output_format;
-pub mod window_base_reduce;
\ No newline at end of file
+pub mod window_base_reduce;
+pub mod window_combine_function;
@@ -9,7 +9,8 @@ use crate::core::element::{FnSchema, Record};
 use crate::core::function::{BaseReduceFunction, Context, NamedFunction, ReduceFunction};
 use crate::core::properties::SystemProperties;
 use crate::core::runtime::CheckpointId;
-use crate::core::window::{TWindow, Window};
+use crate::core::window::{TWindow, Window, WindowAssigner};
+use crate::functions::system::window_combine_function::WindowCombineFunction;
 use crate::metrics::register_gauge;
 use crate::runtime::worker::runnable::reduce_runnable::ReduceCheckpointHandle;
 use crate::storage::keyed_state::{TWindowState, WindowState};
@@ -17,6 +18,8 @@ use crate::utils::date_time::timestamp_str;
 
 pub(crate) struct WindowBaseReduceFunction {
     reduce: Box<dyn ReduceFunction>,
+    /// buffer size of the map-side combiner, `None` if the combiner is disabled
+    combine_buffer_size: Option<usize>,
 
     state: Option<WindowState>,
 
@@ -30,6 +33,7 @@ impl WindowBaseReduceFunction {
     pub fn new(reduce: Box<dyn ReduceFunction>) -> Self {
         WindowBaseReduceFunction {
             reduce,
+            combine_buffer_size: None,
             state: None,
             window_checkpoints: BTreeMap::new(),
             skip_windows: Vec::new(),
@@ -37,6 +41,12 @@ impl WindowBaseReduceFunction {
         }
     }
 
+    pub fn with_combiner(reduce: Box<dyn ReduceFunction>, max_buffer_size: usize) -> Self {
+        let mut reduce_function = Self::new(reduce);
+        reduce_function.combine_buffer_size = Some(max_buffer_size);
+        reduce_function
+    }
+
     fn filter_skip_window(&self, windows: &mut Vec<Window>) -> Vec<Window> {
         windows
             .iter()
@@ -96,7 +106,14 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
 
         let state = self.state.as_mut().unwrap();
         let reduce_func = &self.reduce;
-        let window_count = state.merge(key, record, |val1, val2| reduce_func.reduce(val1, val2));
+        let combined = self.combine_buffer_size.is_some();
+        let window_count = state.merge(key, record, |val1, val2| {
+            if combined {
+                reduce_func.merge(val1, val2)
+            } else {
+                reduce_func.reduce(val1, val2)
+            }
+        });
         self.windows_gauge.set(window_count as f64);
     }
 
@@ -160,6 +177,20 @@ impl BaseReduceFunction for WindowBaseReduceFunction {
         //     Schema::Empty => panic!("unreached!"),
         // }
     }
+
+    fn is_combined(&self) -> bool {
+        self.combine_buffer_size.is_some()
+    }
+
+    fn into_combiner(
+        self: Box<Self>,
+        window_assigner: Box<dyn WindowAssigner>,
+    ) -> Option<WindowCombineFunction> {
+        let reduce_function = *self;
+        reduce_function.combine_buffer_size.map(|max_buffer_size| {
+            WindowCombineFunction::new(reduce_function.reduce, window_assigner, max_buffer_size)
+        })
+    }
 }
 
 impl NamedFunction for WindowBaseReduceFunction {
@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::BytesMut;
+use serbuffer::types;
+
+use crate::core::element::{Buffer, Partition, Record};
+use crate::core::function::{Context, ReduceFunction};
+use crate::core::window::{TWindow, TimeWindow, Window, WindowAssigner, WindowAssignerContext};
+
+/// field types of a partial `Record`: [window start, window end, key, value]
+const PARTIAL_DATA_TYPES: [u8; 4] = [types::U64, types::U64, types::BINARY, types::BINARY];
+
+/// Map-side combiner of a windowed reduce.
+///
+/// Pre-aggregates the records per key and window inside the upstream task with the user
+/// `ReduceFunction`, the buffered partial values are sent to the final reduce task when the
+/// buffer is full or a watermark/barrier/stream status arrives.
+pub(crate) struct WindowCombineFunction {
+    reduce: Box<dyn ReduceFunction>,
+    window_assigner: Box<dyn WindowAssigner>,
+
+    max_buffer_size: usize,
+    buffer_size: usize,
+    // window -> key -> (partition, value)
+    buffer: HashMap<Window, BTreeMap<Record, (u16, Record)>>,
+}
+
+impl WindowCombineFunction {
+    pub fn new(
+        reduce: Box<dyn ReduceFunction>,
+        window_assigner: Box<dyn WindowAssigner>,
+        max_buffer_size: usize,
+    ) -> Self {
+        WindowCombineFunction {
+            reduce,
+            window_assigner,
+            max_buffer_size,
+            buffer_size: 0,
+            buffer: HashMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.reduce.name()
+    }
+
+    pub async fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+        self.reduce.open(context).await
+    }
+
+    pub async fn close(&mut self) -> crate::core::Result<()> {
+        self.reduce.close().await
+    }
+
+    pub fn combine(&mut self, partition_num: u16, key: Record, record: &mut Record) {
+        let windows = self
+            .window_assigner
+            .assign_windows(record.timestamp, WindowAssignerContext {});
+
+        for window in windows {
+            let state = self.buffer.entry(window).or_default();
+            match state.get_mut(&key) {
+                Some((_partition_num, value)) => {
+                    let new_value = self.reduce.reduce(Some(value), record);
+                    *value = new_value;
+                }
+                None => {
+                    let value = self.reduce.reduce(None, record);
+                    state.insert(key.clone(), (partition_num, value));
+                    self.buffer_size += 1;
+                }
+            }
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.buffer_size >= self.max_buffer_size
+    }
+
+    /// drain the buffer as partial `Record`s, the partition of every `Record` is already set
+    pub fn flush(&mut self) -> Vec<Record> {
+        let mut partials = Vec::with_capacity(self.buffer_size);
+        for (window, state) in self.buffer.drain() {
+            for (key, (partition_num, value)) in state {
+                let mut partial = encode_partial(&window, &key, &value);
+                partial.set_partition(partition_num);
+                partials.push(partial);
+            }
+        }
+
+        self.buffer_size = 0;
+        partials
+    }
+}
+
+pub(crate) fn encode_partial(window: &Window, key: &Record, value: &Record) -> Record {
+    let capacity = 16 + 8 + key.len() + value.len();
+    let mut partial = Record::with_capacity(capacity);
+    partial.timestamp = window.min_timestamp();
+
+    let mut writer = partial.as_writer(&PARTIAL_DATA_TYPES);
+    writer.set_u64(window.min_timestamp()).unwrap();
+    writer.set_u64(window.max_timestamp()).unwrap();
+    writer.set_binary(key.values.as_slice()).unwrap();
+    writer.set_binary(value.values.as_slice()).unwrap();
+
+    partial
+}
+
+/// split a partial `Record` into `(key, value)`, the value's location window is restored
+pub(crate) fn decode_partial(partial: &mut Record) -> (Record, Record) {
+    let timestamp = partial.timestamp;
+    let reader = partial.as_reader(&PARTIAL_DATA_TYPES);
+
+    let window = Window::TimeWindow(TimeWindow::new(
+        reader.get_u64(0).unwrap(),
+        reader.get_u64(1).unwrap(),
+    ));
+    let key = Record {
+        values: Buffer::from(BytesMut::from(reader.get_binary(2).unwrap())),
+        ..Record::new()
+    };
+    let mut value = Record {
+        timestamp,
+        values: Buffer::from(BytesMut::from(reader.get_binary(3).unwrap())),
+        ..Record::new()
+    };
+    value.set_location_windows(vec![window]);
+
+    (key, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serbuffer::types;
+
+    use crate::core::element::Record;
+    use crate::core::window::{TimeWindow, Window};
+    use crate::functions::system::window_combine_function::{decode_partial, encode_partial};
+
+    #[test]
+    pub fn partial_serde_test() {
+        let data_types = [types::STRING, types::U64];
+
+        let mut key = Record::new();
+        key.as_writer(&data_types[0..1]).set_str("rlink").unwrap();
+
+        let mut value = Record::new();
+        value.as_writer(&data_types[1..2]).set_u64(10).unwrap();
+
+        let window = Window::TimeWindow(TimeWindow::new(1000, 2000));
+        let mut partial = encode_partial(&window, &key, &value);
+
+        let (mut de_key, mut de_value) = decode_partial(&mut partial);
+        assert_eq!(de_key, key);
+        assert_eq!(de_value, value);
+        assert_eq!(de_value.location_windows(), &vec![window]);
+        assert_eq!(
+            de_key.as_reader(&data_types[0..1]).get_str(0).unwrap(),
+            "rlink"
+        );
+        assert_eq!(
+            de_value.as_reader(&data_types[1..2]).get_u64(0).unwrap(),
+            10
+        );
+    }
+}
@@ -3,7 +3,9 @@ use std::time::Duration;
 
 use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
 use crate::core::function::NamedFunction;
-use crate::core::watermark::{TimestampAssigner, WatermarkGenerator, WatermarkStrategy};
+use crate::core::watermark::{
+    TimestampAssigner, WatermarkAlignment, WatermarkGenerator, WatermarkStrategy,
+};
 use crate::functions::column_locate::ColumnLocateBuilder;
 use crate::functions::watermark::watermarks_with_idleness::WatermarksWithIdleness;
 use crate::functions::watermark::{
@@ -13,6 +15,7 @@ use crate::functions::watermark::{
 pub struct DefaultWatermarkStrategy {
     watermark_generator: Option<Box<dyn WatermarkGenerator>>,
     timestamp_assigner: Option<Box<dyn TimestampAssigner>>,
+    alignment: Option<WatermarkAlignment>,
 }
 
 impl DefaultWatermarkStrategy {
@@ -20,6 +23,7 @@ impl DefaultWatermarkStrategy {
         DefaultWatermarkStrategy {
             watermark_generator: None,
             timestamp_assigner: None,
+            alignment: None,
         }
     }
 
@@ -55,6 +59,13 @@ impl DefaultWatermarkStrategy {
         }
     }
 
+    /// Pause the source when its watermark gets more than `max_drift` ahead of the minimum
+    /// watermark of the sources in the same `group`
+    pub fn with_watermark_alignment(mut self, group: &str, max_drift: Duration) -> Self {
+        self.alignment = Some(WatermarkAlignment::new(group, max_drift));
+        self
+    }
+
     pub fn for_schema_timestamp_assigner<T: ColumnLocateBuilder>(mut self, column: T) -> Self {
         self.timestamp_assigner = Some(Box::new(SchemaTimestampAssigner::new(column)));
         self
@@ -85,6 +96,10 @@ impl WatermarkStrategy for DefaultWatermarkStrategy {
     fn create_timestamp_assigner(&mut self) -> Box<dyn TimestampAssigner> {
         self.timestamp_assigner.take().unwrap()
     }
+
+    fn alignment(&self) -> Option<WatermarkAlignment> {
+        self.alignment.clone()
+    }
 }
 
 impl NamedFunction for DefaultWatermarkStrategy {
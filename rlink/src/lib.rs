@@ -21,4 +21,6 @@ pub mod channel;
 pub mod core;
 pub mod functions;
 pub mod metrics;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utils;
@@ -68,6 +68,14 @@ pub(crate) fn insert(target_task_id: TaskId, channel_size: usize) {
     });
 }
 
+/// Remove all channels, the channels of the stopped tasks are dropped before the tasks are
+/// restarted in the same process
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn clear() {
+    let mut guard = MEMORY_CHANNELS.lock().unwrap();
+    guard.clear();
+}
+
 pub(crate) fn get_receiver(target_task_id: &TaskId) -> Option<ElementReceiver> {
     let memory_channels: &Mutex<HashMap<TaskId, (ElementSender, Option<ElementReceiver>)>> =
         &*MEMORY_CHANNELS;
@@ -93,6 +93,13 @@ fn remove_network_channel(key: &ChannelKey) {
     network_channels.remove(key);
 }
 
+/// Remove all channels, the channels of the stopped tasks are dropped before the tasks are
+/// restarted in the same process
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn clear_network_channels() {
+    NETWORK_CHANNELS.clear();
+}
+
 /// Check whether all channels have been removed.
 /// Used to determine whether the `TaskManager` instance can be closed.
 #[allow(dead_code)]
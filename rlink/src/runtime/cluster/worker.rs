@@ -7,6 +7,7 @@ use std::time::Duration;
 use tokio::task::JoinHandle;
 
 use crate::core::env::StreamApp;
+use crate::core::properties::SystemProperties;
 use crate::core::runtime::{ClusterDescriptor, ManagerStatus, WorkerManagerDescriptor};
 use crate::dag::metadata::DagMetadata;
 use crate::pub_sub::network;
@@ -130,7 +131,14 @@ async fn start_heartbeat_task(
     };
     heartbeat_publish.report(status).await;
 
-    heartbeat_publish.start_heartbeat_timer().await;
+    let heartbeat_interval = cluster_descriptor
+        .coordinator_manager
+        .application_properties
+        .get_heartbeat_interval()
+        .unwrap_or_else(|_e| Duration::from_secs(10));
+    heartbeat_publish
+        .start_heartbeat_timer(heartbeat_interval)
+        .await;
 
     Arc::new(heartbeat_publish)
 }
@@ -207,6 +207,16 @@ impl CheckpointAlignManager {
         Ok(())
     }
 
+    /// The id of the latest checkpoint aligned by all the operators
+    #[cfg(any(test, feature = "testing"))]
+    pub fn completed_checkpoint_id(&self) -> Option<CheckpointId> {
+        self.finish_operator_cks
+            .values()
+            .flat_map(|operator_checkpoint| operator_checkpoint.current_cks.values())
+            .map(|ck| ck.checkpoint_id)
+            .max()
+    }
+
     fn unreached_operators(&self) -> Vec<&OperatorCheckpoint> {
         let align_operators: Vec<&OperatorCheckpoint> = self
             .operator_cks
@@ -359,3 +369,89 @@ impl CheckpointManager {
         ck_align_manager.load().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::core::backend::CheckpointBackend;
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+    use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
+    use crate::runtime::coordinator::checkpoint_manager::{
+        CheckpointAlignManager, OperatorCheckpoint,
+    };
+    use crate::storage::checkpoint::CheckpointStorage;
+
+    fn checkpoint(
+        task_number: u16,
+        checkpoint_id: u64,
+        completed_checkpoint_id: Option<u64>,
+    ) -> Checkpoint {
+        Checkpoint {
+            operator_id: OperatorId(1),
+            task_id: TaskId {
+                job_id: JobId(0),
+                task_number,
+                num_tasks: 2,
+            },
+            checkpoint_id: CheckpointId(checkpoint_id),
+            completed_checkpoint_id: completed_checkpoint_id.map(CheckpointId),
+            handle: CheckpointHandle {
+                handle: format!("{}-{}", task_number, checkpoint_id),
+            },
+        }
+    }
+
+    #[tokio::test]
+    pub async fn checkpoint_align_manager_load_test() {
+        let mut operator_cks = HashMap::new();
+        operator_cks.insert(
+            OperatorId(1),
+            OperatorCheckpoint::new(JobId(0), OperatorId(1), "source".to_string(), 2),
+        );
+
+        let mut manager = CheckpointAlignManager {
+            application_name: "test".to_string(),
+            application_id: "test".to_string(),
+            checkpoint_ttl: Duration::from_secs(60),
+            current_ck_id: CheckpointId::default(),
+            operator_cks,
+            finish_operator_cks: HashMap::new(),
+            storage: Some(CheckpointStorage::new(&CheckpointBackend::Memory)),
+        };
+
+        // nothing to restore before the first completed checkpoint
+        assert!(manager.load().await.unwrap().is_empty());
+
+        manager.apply(checkpoint(0, 1000, None)).await.unwrap();
+        manager.apply(checkpoint(1, 1000, None)).await.unwrap();
+        assert_eq!(manager.completed_checkpoint_id(), Some(CheckpointId(1000)));
+
+        manager
+            .apply(checkpoint(0, 2000, Some(1000)))
+            .await
+            .unwrap();
+        manager
+            .apply(checkpoint(1, 2000, Some(1000)))
+            .await
+            .unwrap();
+        // the un-align checkpoint is not saved
+        manager
+            .apply(checkpoint(0, 3000, Some(2000)))
+            .await
+            .unwrap();
+
+        // the tasks are restored from the checkpoint completed before the latest saved one
+        let operator_checkpoints = manager.load().await.unwrap();
+        let cks = operator_checkpoints.get(&OperatorId(1)).unwrap();
+        assert_eq!(cks.len(), 2);
+        for ck in cks {
+            assert_eq!(ck.checkpoint_id, CheckpointId(1000));
+            assert_eq!(
+                ck.handle.handle,
+                format!("{}-{}", ck.task_id.task_number, 1000)
+            );
+        }
+    }
+}
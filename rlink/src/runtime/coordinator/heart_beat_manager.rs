@@ -13,6 +13,7 @@ pub enum HeartbeatResult {
 /// heartbeat timeout check
 pub(crate) async fn start_heartbeat_timer(
     metadata_storage_mode: MetadataStorageType,
+    heartbeat_timeout: Duration,
 ) -> HeartbeatResult {
     let metadata_storage = MetadataStorage::new(&metadata_storage_mode);
     loop {
@@ -44,7 +45,7 @@ pub(crate) async fn start_heartbeat_timer(
                 task_manager_descriptor.task_manager_address
             );
 
-            if dur.as_secs() > 50 {
+            if dur > heartbeat_timeout {
                 error!(
                     "heartbeat's timestamp {} lag {}s from TaskManager {}, and break heartbeat",
                     task_manager_descriptor.latest_heart_beat_ts,
@@ -1,15 +1,16 @@
 use std::borrow::BorrowMut;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::core::checkpoint::CheckpointHandle;
+use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
 use crate::core::cluster::MetadataStorageType;
 use crate::core::cluster::TaskResourceInfo;
 use crate::core::env::{StreamApp, StreamExecutionEnvironment};
 use crate::core::properties::{InnerSystemProperties, Properties, SystemProperties};
-use crate::core::runtime::{ClusterDescriptor, ManagerStatus};
+use crate::core::runtime::{ClusterDescriptor, ManagerStatus, OperatorId};
 use crate::dag::metadata::DagMetadata;
 use crate::dag::DagManager;
 use crate::deployment::TResourceManager;
@@ -29,6 +30,7 @@ use metrics::Gauge;
 pub mod checkpoint_manager;
 pub mod heart_beat_manager;
 pub mod task_distribution;
+pub mod watermark_alignment_manager;
 pub mod web_server;
 
 pub(crate) struct CoordinatorTask<S, R>
@@ -86,17 +88,17 @@ where
         let mut cluster_descriptor = self.build_metadata(&dag_manager, &application_properties);
         debug!("ApplicationDescriptor : {}", cluster_descriptor.to_string());
 
-        let ck_manager = self
-            .build_checkpoint_manager(
-                &dag_metadata,
-                &application_properties,
-                cluster_descriptor.borrow_mut(),
-            )
+        let mut ck_manager = self
+            .build_checkpoint_manager(&dag_metadata, &application_properties, &cluster_descriptor)
             .await;
         info!("start CheckpointManager align task");
 
-        self.web_serve(cluster_descriptor.borrow_mut(), ck_manager, dag_metadata)
-            .await;
+        self.web_serve(
+            cluster_descriptor.borrow_mut(),
+            ck_manager.clone(),
+            dag_metadata,
+        )
+        .await;
         info!(
             "serve coordinator web ui {}",
             &cluster_descriptor.coordinator_manager.web_address
@@ -108,10 +110,18 @@ where
 
         self.gauge_startup(&cluster_descriptor);
 
+        let heartbeat_timeout = application_properties
+            .get_heartbeat_timeout()
+            .unwrap_or_else(|_e| Duration::from_secs(50));
+
         // loop restart all tasks when some task is failure
         loop {
             self.gauge_startup_number(cluster_descriptor.borrow_mut());
 
+            // restore the tasks from the completed checkpoint, also on restarting
+            self.load_checkpoints(&mut ck_manager, cluster_descriptor.borrow_mut())
+                .await;
+
             // save metadata to storage
             self.save_metadata(&cluster_descriptor).await;
             info!("save metadata to storage");
@@ -130,8 +140,11 @@ where
             info!("all worker status is fine");
 
             // heartbeat check. blocking util heartbeat timeout
-            let heartbeat_result =
-                heart_beat_manager::start_heartbeat_timer(self.metadata_storage_mode.clone()).await;
+            let heartbeat_result = heart_beat_manager::start_heartbeat_timer(
+                self.metadata_storage_mode.clone(),
+                heartbeat_timeout,
+            )
+            .await;
             info!("heartbeat timer has interrupted");
 
             // heartbeat timeout and stop all worker's tasks
@@ -194,48 +207,28 @@ where
         &self,
         dag_manager: &DagMetadata,
         application_properties: &Properties,
-        cluster_descriptor: &mut ClusterDescriptor,
+        cluster_descriptor: &ClusterDescriptor,
     ) -> CheckpointManager {
         let checkpoint_ttl = application_properties
             .get_checkpoint_ttl()
             .unwrap_or_else(|_e| Duration::from_secs(1 * 60 * 60));
 
-        let mut ck_manager = CheckpointManager::new(
+        CheckpointManager::new(
             dag_manager,
             &self.context,
             cluster_descriptor,
             checkpoint_ttl,
         )
-        .await;
-        let operator_checkpoints = ck_manager.load().await.expect("load checkpoints error");
-        if operator_checkpoints.len() == 0 {
-            return ck_manager;
-        }
-
-        for task_manager_descriptor in &mut cluster_descriptor.worker_managers {
-            for task_descriptor in &mut task_manager_descriptor.task_descriptors {
-                let task_number = task_descriptor.task_id.task_number;
-                for operator in &mut task_descriptor.operators {
-                    let cks = operator_checkpoints.get(&operator.operator_id).unwrap();
-                    if cks.len() == 0 {
-                        debug!("operator {:?} checkpoint not found", operator.operator_id);
-                        continue;
-                    }
-
-                    let ck = cks
-                        .iter()
-                        .find(|ck| ck.task_id.task_number == task_number)
-                        .unwrap();
-                    operator.checkpoint_id = ck.checkpoint_id;
-                    operator.checkpoint_handle = Some(CheckpointHandle {
-                        handle: ck.handle.handle.clone(),
-                    });
-                    info!("operator {:?} checkpoint loaded", operator);
-                }
-            }
-        }
+        .await
+    }
 
-        ck_manager
+    async fn load_checkpoints(
+        &self,
+        ck_manager: &mut CheckpointManager,
+        cluster_descriptor: &mut ClusterDescriptor,
+    ) {
+        let operator_checkpoints = ck_manager.load().await.expect("load checkpoints error");
+        apply_checkpoints(cluster_descriptor, &operator_checkpoints);
     }
 
     async fn web_serve(
@@ -338,3 +331,128 @@ where
             .set(cluster_descriptor.coordinator_manager.startup_number as f64);
     }
 }
+
+/// Set the checkpoint of each operator task to the `cluster_descriptor`, the tasks are started
+/// from the checkpoints
+fn apply_checkpoints(
+    cluster_descriptor: &mut ClusterDescriptor,
+    operator_checkpoints: &HashMap<OperatorId, Vec<Checkpoint>>,
+) {
+    if operator_checkpoints.len() == 0 {
+        return;
+    }
+
+    for task_manager_descriptor in &mut cluster_descriptor.worker_managers {
+        for task_descriptor in &mut task_manager_descriptor.task_descriptors {
+            let task_number = task_descriptor.task_id.task_number;
+            for operator in &mut task_descriptor.operators {
+                let cks = operator_checkpoints.get(&operator.operator_id).unwrap();
+                if cks.len() == 0 {
+                    debug!("operator {:?} checkpoint not found", operator.operator_id);
+                    continue;
+                }
+
+                let ck = cks
+                    .iter()
+                    .find(|ck| ck.task_id.task_number == task_number)
+                    .unwrap();
+                operator.checkpoint_id = ck.checkpoint_id;
+                operator.checkpoint_handle = Some(CheckpointHandle {
+                    handle: ck.handle.handle.clone(),
+                });
+                info!("operator {:?} checkpoint loaded", operator);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::core::checkpoint::{Checkpoint, CheckpointHandle};
+    use crate::core::function::InputSplit;
+    use crate::core::properties::Properties;
+    use crate::core::runtime::{
+        CheckpointId, ClusterDescriptor, HeartBeatStatus, JobId, ManagerStatus, OperatorDescriptor,
+        OperatorId, TaskDescriptor, TaskId, WorkerManagerDescriptor,
+    };
+    use crate::runtime::coordinator::apply_checkpoints;
+
+    fn task_descriptor(task_number: u16) -> TaskDescriptor {
+        TaskDescriptor {
+            task_id: TaskId {
+                job_id: JobId(0),
+                task_number,
+                num_tasks: 2,
+            },
+            operators: vec![OperatorDescriptor {
+                operator_id: OperatorId(1),
+                checkpoint_id: CheckpointId::default(),
+                completed_checkpoint_id: None,
+                checkpoint_handle: None,
+            }],
+            input_split: InputSplit::new(task_number, Properties::new()),
+            daemon: false,
+            terminated: false,
+        }
+    }
+
+    fn checkpoint(task_number: u16, checkpoint_id: u64) -> Checkpoint {
+        Checkpoint {
+            operator_id: OperatorId(1),
+            task_id: TaskId {
+                job_id: JobId(0),
+                task_number,
+                num_tasks: 2,
+            },
+            checkpoint_id: CheckpointId(checkpoint_id),
+            completed_checkpoint_id: None,
+            handle: CheckpointHandle {
+                handle: format!("offset-{}-{}", task_number, checkpoint_id),
+            },
+        }
+    }
+
+    #[test]
+    pub fn apply_checkpoints_test() {
+        let mut cluster_descriptor = ClusterDescriptor::default();
+        cluster_descriptor
+            .worker_managers
+            .push(WorkerManagerDescriptor {
+                status: ManagerStatus::Registered,
+                latest_heart_beat_ts: 0,
+                latest_heart_beat_status: HeartBeatStatus::Ok,
+                task_manager_id: "worker-0".to_string(),
+                task_manager_address: "".to_string(),
+                web_address: "".to_string(),
+                task_descriptors: vec![task_descriptor(0), task_descriptor(1)],
+            });
+
+        // the first start without any checkpoint
+        apply_checkpoints(&mut cluster_descriptor, &HashMap::new());
+        for task_descriptor in &cluster_descriptor.worker_managers[0].task_descriptors {
+            assert!(task_descriptor.operators[0].checkpoint_handle.is_none());
+        }
+
+        // the restart after the checkpoints completed
+        for checkpoint_id in [1000, 2000] {
+            let mut operator_checkpoints = HashMap::new();
+            operator_checkpoints.insert(
+                OperatorId(1),
+                vec![checkpoint(1, checkpoint_id), checkpoint(0, checkpoint_id)],
+            );
+            apply_checkpoints(&mut cluster_descriptor, &operator_checkpoints);
+
+            for task_descriptor in &cluster_descriptor.worker_managers[0].task_descriptors {
+                let task_number = task_descriptor.task_id.task_number;
+                let operator = &task_descriptor.operators[0];
+                assert_eq!(operator.checkpoint_id, CheckpointId(checkpoint_id));
+                assert_eq!(
+                    operator.checkpoint_handle.as_ref().unwrap().handle,
+                    format!("offset-{}-{}", task_number, checkpoint_id)
+                );
+            }
+        }
+    }
+}
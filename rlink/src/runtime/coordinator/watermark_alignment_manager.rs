@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::core::runtime::TaskId;
+use crate::runtime::WatermarkAlignmentRequest;
+
+/// Track the local watermark of every source task in a watermark alignment group
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WatermarkAlignmentManager {
+    // group -> active task -> watermark
+    groups: Arc<Mutex<HashMap<String, HashMap<TaskId, u64>>>>,
+}
+
+impl WatermarkAlignmentManager {
+    /// update the task's watermark and return the minimum watermark of the active tasks in the
+    /// group, `u64::MAX` if all the tasks are idle
+    pub fn apply(&self, request: &WatermarkAlignmentRequest) -> u64 {
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups.entry(request.group.clone()).or_default();
+        if request.idle {
+            group.remove(&request.task_id);
+        } else {
+            group.insert(request.task_id, request.watermark);
+        }
+
+        group.values().min().cloned().unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::runtime::{JobId, TaskId};
+    use crate::runtime::coordinator::watermark_alignment_manager::WatermarkAlignmentManager;
+    use crate::runtime::WatermarkAlignmentRequest;
+
+    #[test]
+    pub fn watermark_alignment_manager_test() {
+        let manager = WatermarkAlignmentManager::default();
+        let request = |task_number: u16, watermark: u64, idle: bool| WatermarkAlignmentRequest {
+            group: "g0".to_string(),
+            task_id: TaskId {
+                job_id: JobId(0),
+                task_number,
+                num_tasks: 2,
+            },
+            watermark,
+            idle,
+        };
+
+        assert_eq!(manager.apply(&request(0, 100, false)), 100);
+        assert_eq!(manager.apply(&request(1, 50, false)), 50);
+        assert_eq!(manager.apply(&request(0, 200, false)), 50);
+        assert_eq!(manager.apply(&request(1, 300, false)), 200);
+
+        // the idle task doesn't hold back the group
+        assert_eq!(manager.apply(&request(0, 200, true)), 300);
+        assert_eq!(manager.apply(&request(1, 300, true)), u64::MAX);
+    }
+}
@@ -21,7 +21,8 @@ use crate::dag::metadata::DagMetadata;
 use crate::metrics::metric_handle;
 use crate::metrics::worker_proxy::collect_worker_metrics;
 use crate::runtime::coordinator::checkpoint_manager::CheckpointManager;
-use crate::runtime::HeartbeatRequest;
+use crate::runtime::coordinator::watermark_alignment_manager::WatermarkAlignmentManager;
+use crate::runtime::{HeartbeatRequest, WatermarkAlignmentRequest};
 use crate::storage::metadata::{MetadataStorage, TMetadataStorage};
 use crate::utils::fs::read_binary;
 use crate::utils::http::server::{as_ok_json, page_not_found};
@@ -40,6 +41,7 @@ pub(crate) async fn web_launch(
             context,
             metadata_mode,
             checkpoint_manager,
+            watermark_alignment_manager: WatermarkAlignmentManager::default(),
             dag_metadata,
         });
         serve_with_rand_port(web_context, ip, tx).await;
@@ -53,6 +55,7 @@ struct WebContext {
     context: Arc<crate::runtime::context::Context>,
     metadata_mode: MetadataStorageType,
     checkpoint_manager: CheckpointManager,
+    watermark_alignment_manager: WatermarkAlignmentManager,
     dag_metadata: DagMetadata,
 }
 
@@ -128,6 +131,7 @@ async fn route(req: Request<Body>, web_context: Arc<WebContext>) -> anyhow::Resu
             match path {
                 "/api/heartbeat" => heartbeat(req, web_context).await,
                 "/api/checkpoint" => checkpoint(req, web_context).await,
+                "/api/watermark" => watermark(req, web_context).await,
                 _ => page_not_found().await,
             }
         } else {
@@ -261,6 +265,16 @@ async fn checkpoint(
     as_ok_json(&StdResponse::ok(Some(resp.to_string())))
 }
 
+async fn watermark(req: Request<Body>, context: Arc<WebContext>) -> anyhow::Result<Response<Body>> {
+    let whole_body = hyper::body::aggregate(req).await?;
+    let request: WatermarkAlignmentRequest = serde_json::from_reader(whole_body.reader())?;
+
+    debug!("<watermark> alignment report {:?}", &request);
+    let group_watermark = context.watermark_alignment_manager.apply(&request);
+
+    as_ok_json(&StdResponse::ok(Some(group_watermark)))
+}
+
 async fn static_file(
     req: Request<Body>,
     context: Arc<WebContext>,
@@ -105,6 +105,18 @@ pub(crate) struct HeartbeatRequest {
     pub change_items: Vec<HeartbeatItem>,
 }
 
+/// report the local watermark of a source task to the coordinator,
+/// the response is the minimum watermark of the active tasks in the alignment group
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) struct WatermarkAlignmentRequest {
+    pub group: String,
+    pub task_id: TaskId,
+    pub watermark: u64,
+    /// the idle task is not counted in the group watermark
+    #[serde(default)]
+    pub idle: bool,
+}
+
 pub async fn run<S>(stream_app: S) -> anyhow::Result<()>
 where
     S: StreamApp + 'static,
@@ -1,6 +1,7 @@
 use std::fmt::{Debug, Formatter};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::core::cluster::StdResponse;
 use crate::core::runtime::AtomicManagerStatus;
@@ -31,7 +32,7 @@ impl HeartbeatPublish {
         }
     }
 
-    pub async fn start_heartbeat_timer(&self) {
+    pub async fn start_heartbeat_timer(&self, interval: Duration) {
         info!("heartbeat timer starting...");
 
         let heartbeat_publish = self.clone();
@@ -40,7 +41,7 @@ impl HeartbeatPublish {
                 let status = HeartbeatItem::HeartBeatStatus(HeartBeatStatus::Ok);
                 heartbeat_publish.report_heartbeat(vec![status]).await;
 
-                tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                tokio::time::sleep(interval).await;
             }
         });
     }
@@ -1,6 +1,6 @@
 use std::borrow::BorrowMut;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use tokio::task::JoinHandle;
 
@@ -11,6 +11,7 @@ use crate::core::operator::{DefaultStreamOperator, StreamOperator};
 use crate::core::runtime::{ClusterDescriptor, JobId, ManagerStatus, OperatorId, TaskDescriptor};
 use crate::dag::metadata::DagMetadata;
 use crate::dag::OperatorType;
+use crate::functions::system::window_combine_function::WindowCombineFunction;
 use crate::runtime::context::Context;
 use crate::runtime::timer::WindowTimer;
 use crate::runtime::worker::checkpoint::CheckpointPublish;
@@ -20,10 +21,12 @@ use crate::runtime::worker::runnable::{
     FilterRunnable, FlatMapRunnable, KeyByRunnable, ReduceRunnable, Runnable, RunnableContext,
     SinkRunnable, SourceRunnable, WatermarkAssignerRunnable, WindowAssignerRunnable,
 };
+use crate::runtime::worker::watermark_alignment::WatermarkAligner;
 
 pub mod checkpoint;
 pub mod heart_beat;
 pub mod runnable;
+pub mod watermark_alignment;
 pub mod web_server;
 
 #[derive(Clone, Debug)]
@@ -38,6 +41,9 @@ pub(crate) struct WorkerTaskContext {
     checkpoint_publish: Arc<CheckpointPublish>,
     #[allow(unused)]
     heartbeat_publish: Arc<HeartbeatPublish>,
+
+    /// registered by the `WatermarkAssignerRunnable` if the watermark alignment is enabled
+    watermark_aligner: OnceLock<Arc<WatermarkAligner>>,
 }
 
 impl WorkerTaskContext {
@@ -58,6 +64,7 @@ impl WorkerTaskContext {
             window_timer,
             checkpoint_publish,
             heartbeat_publish,
+            watermark_aligner: OnceLock::new(),
         }
     }
 
@@ -97,6 +104,16 @@ impl WorkerTaskContext {
     pub fn heartbeat_publish(&self) -> Arc<HeartbeatPublish> {
         self.heartbeat_publish.clone()
     }
+
+    pub(crate) fn register_watermark_aligner(&self, watermark_aligner: Arc<WatermarkAligner>) {
+        if self.watermark_aligner.set(watermark_aligner).is_err() {
+            warn!("watermark aligner has registered, the later is ignored");
+        }
+    }
+
+    pub(crate) fn watermark_aligner(&self) -> Option<Arc<WatermarkAligner>> {
+        self.watermark_aligner.get().cloned()
+    }
 }
 
 pub(crate) type FunctionContext = crate::core::function::Context;
@@ -203,7 +220,9 @@ where
                     op
                 }
                 StreamOperator::StreamKeyBy(stream_operator) => {
-                    let op = KeyByRunnable::new(operator_id, stream_operator, None);
+                    let combiner =
+                        self.get_dependency_combiner(operators.borrow_mut(), job_node.job_id);
+                    let op = KeyByRunnable::new(operator_id, stream_operator, combiner, None);
                     let op: Box<dyn Runnable> = Box::new(op);
                     op
                 }
@@ -249,6 +268,48 @@ where
         }
     }
 
+    fn get_dependency_combiner(
+        &self,
+        operators: &mut HashMap<OperatorId, StreamOperator>,
+        job_id: JobId,
+    ) -> Option<WindowCombineFunction> {
+        let job_children = self.task_context.dag_metadata.child_jobs(job_id);
+        if job_children.len() != 1 {
+            return None;
+        }
+
+        let (job_node, _) = job_children[0];
+        let reduce_node = job_node
+            .stream_nodes
+            .iter()
+            .find(|x| x.operator_type == OperatorType::Reduce)?;
+        let window_node = job_node
+            .stream_nodes
+            .iter()
+            .find(|x| x.operator_type == OperatorType::WindowAssigner)?;
+
+        match operators.get(&reduce_node.id) {
+            Some(StreamOperator::StreamReduce(stream_operator))
+                if stream_operator.operator_fn.is_combined() => {}
+            _ => return None,
+        }
+
+        let window_operator = operators.remove(&window_node.id);
+        let reduce_operator = operators.remove(&reduce_node.id);
+        match (window_operator, reduce_operator) {
+            (
+                Some(StreamOperator::StreamWindowAssigner(window_operator)),
+                Some(StreamOperator::StreamReduce(reduce_operator)),
+            ) => reduce_operator
+                .operator_fn
+                .into_combiner(window_operator.operator_fn),
+            _ => {
+                error!("dependency StreamWindowAssigner not found");
+                None
+            }
+        }
+    }
+
     fn get_dependency_key_by(
         &self,
         operators: &mut HashMap<OperatorId, StreamOperator>,
@@ -7,6 +7,7 @@ use crate::core::element::Element;
 use crate::core::function::CoProcessFunction;
 use crate::core::operator::DefaultStreamOperator;
 use crate::core::runtime::{JobId, OperatorId};
+use crate::core::timer::{Timer, TimerService};
 use crate::runtime::worker::runnable::{Runnable, RunnableContext};
 
 pub(crate) struct CoProcessRunnable {
@@ -15,6 +16,7 @@ pub(crate) struct CoProcessRunnable {
     next_runnable: Option<Box<dyn Runnable>>,
 
     context: Option<RunnableContext>,
+    timer_service: TimerService,
 
     /// key: JobId,
     /// value: DataStream index  
@@ -34,9 +36,19 @@ impl CoProcessRunnable {
             stream_co_process,
             next_runnable,
             context: None,
+            timer_service: TimerService::default(),
             parent_jobs: HashMap::new(),
         }
     }
+
+    async fn fire_timers(&mut self, timers: Vec<Timer>) {
+        for timer in timers {
+            let mut element_stream = self.stream_co_process.operator_fn.on_timer(timer).await;
+            while let Some(element) = element_stream.next().await {
+                self.next_runnable.as_mut().unwrap().run(element).await;
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -72,7 +84,7 @@ impl Runnable for CoProcessRunnable {
             self.parent_jobs.insert(parent_job_id, index);
         }
 
-        let fun_context = context.to_fun_context(self.operator_id);
+        let fun_context = context.to_timer_fun_context(self.operator_id, &self.timer_service);
         self.stream_co_process
             .operator_fn
             .open(&fun_context)
@@ -121,6 +133,16 @@ impl Runnable for CoProcessRunnable {
                     .run(Element::Barrier(barrier))
                     .await;
             }
+            Element::Watermark(watermark) => {
+                let timers = self.timer_service.advance_watermark(watermark.timestamp);
+                self.fire_timers(timers).await;
+
+                self.next_runnable
+                    .as_mut()
+                    .unwrap()
+                    .run(Element::Watermark(watermark))
+                    .await;
+            }
             _ => {
                 self.next_runnable.as_mut().unwrap().run(element).await;
             }
@@ -136,6 +158,17 @@ impl Runnable for CoProcessRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        let timers = self.timer_service.advance_processing_time();
+        self.fire_timers(timers).await;
+
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_co_process
@@ -143,6 +176,7 @@ impl Runnable for CoProcessRunnable {
             .snapshot_state(&snapshot_context)
             .await
             .unwrap_or(CheckpointHandle::default());
+        let handle = self.timer_service.snapshot(handle);
 
         let ck = Checkpoint {
             operator_id: snapshot_context.operator_id,
@@ -77,6 +77,14 @@ impl Runnable for FilterRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_filter
@@ -8,6 +8,7 @@ use crate::core::element::Element;
 use crate::core::function::FlatMapFunction;
 use crate::core::operator::DefaultStreamOperator;
 use crate::core::runtime::{OperatorId, TaskId};
+use crate::core::timer::{Timer, TimerService};
 use crate::metrics::register_counter;
 use crate::runtime::worker::runnable::{Runnable, RunnableContext};
 
@@ -19,6 +20,7 @@ pub(crate) struct FlatMapRunnable {
     next_runnable: Option<Box<dyn Runnable>>,
 
     context: Option<RunnableContext>,
+    timer_service: TimerService,
 
     counter: Counter,
 }
@@ -37,9 +39,36 @@ impl FlatMapRunnable {
             stream_map,
             next_runnable,
             context: None,
+            timer_service: TimerService::default(),
             counter: Counter::noop(),
         }
     }
+
+    async fn fire_timers(&mut self, timers: Vec<Timer>) {
+        for timer in timers {
+            let mut elements = self.stream_map.operator_fn.on_timer(timer).await;
+
+            let mut len = 0;
+            while let Some(ele) = elements.next().await {
+                self.next_runnable.as_mut().unwrap().run(ele).await;
+                len += 1;
+            }
+
+            self.counter.increment(len);
+        }
+    }
+
+    async fn flush(&mut self) {
+        let mut elements = self.stream_map.operator_fn.flush().await;
+
+        let mut len = 0;
+        while let Some(ele) = elements.next().await {
+            self.next_runnable.as_mut().unwrap().run(ele).await;
+            len += 1;
+        }
+
+        self.counter.increment(len);
+    }
 }
 
 #[async_trait]
@@ -51,7 +80,7 @@ impl Runnable for FlatMapRunnable {
 
         self.task_id = context.task_context.task_descriptor.task_id;
 
-        let fun_context = context.to_fun_context(self.operator_id);
+        let fun_context = context.to_timer_fun_context(self.operator_id, &self.timer_service);
         self.stream_map.operator_fn.open(&fun_context).await?;
 
         self.counter = register_counter(
@@ -81,6 +110,8 @@ impl Runnable for FlatMapRunnable {
                 self.counter.increment(len);
             }
             Element::Barrier(barrier) => {
+                self.flush().await;
+
                 let checkpoint_id = barrier.checkpoint_id;
                 let snapshot_context = {
                     let context = self.context.as_ref().unwrap();
@@ -90,7 +121,16 @@ impl Runnable for FlatMapRunnable {
 
                 self.next_runnable.as_mut().unwrap().run(element).await;
             }
+            Element::Watermark(watermark) => {
+                self.flush().await;
+
+                let timers = self.timer_service.advance_watermark(watermark.timestamp);
+                self.fire_timers(timers).await;
+
+                self.next_runnable.as_mut().unwrap().run(element).await;
+            }
             _ => {
+                self.flush().await;
                 self.next_runnable.as_mut().unwrap().run(element).await;
             }
         }
@@ -105,6 +145,17 @@ impl Runnable for FlatMapRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        let timers = self.timer_service.advance_processing_time();
+        self.fire_timers(timers).await;
+
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_map
@@ -112,6 +163,7 @@ impl Runnable for FlatMapRunnable {
             .snapshot_state(&snapshot_context)
             .await
             .unwrap_or(CheckpointHandle::default());
+        let handle = self.timer_service.snapshot(handle);
 
         let ck = Checkpoint {
             operator_id: snapshot_context.operator_id,
@@ -128,3 +180,124 @@ impl Runnable for FlatMapRunnable {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+    use crate::core::element::{Element, FnSchema, Record};
+    use crate::core::function::{Context, FlatMapFunction, NamedFunction, SendableElementStream};
+    use crate::core::operator::{DefaultStreamOperator, FunctionCreator};
+    use crate::core::runtime::OperatorId;
+    use crate::core::timer::Timer;
+    use crate::runtime::worker::runnable::flat_map_runnable::FlatMapRunnable;
+    use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+    use crate::utils::date_time::current_timestamp_millis;
+    use crate::utils::stream::MemoryStream;
+
+    /// Emit a `Record` of the timer's timestamp when the timer fires
+    struct TimerFlatMapFunction {}
+
+    #[async_trait]
+    impl FlatMapFunction for TimerFlatMapFunction {
+        async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        async fn flat_map_element(&mut self, _element: Element) -> SendableElementStream {
+            Box::pin(MemoryStream::new(vec![]))
+        }
+
+        async fn on_timer(&mut self, timer: Timer) -> SendableElementStream {
+            let mut record = Record::new();
+            record.timestamp = timer.timestamp;
+            Box::pin(MemoryStream::new(vec![record]))
+        }
+
+        async fn close(&mut self) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+            FnSchema::Empty
+        }
+    }
+
+    impl NamedFunction for TimerFlatMapFunction {
+        fn name(&self) -> &str {
+            "TimerFlatMapFunction"
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointFunction for TimerFlatMapFunction {
+        async fn initialize_state(
+            &mut self,
+            _context: &FunctionSnapshotContext,
+            _handle: &Option<CheckpointHandle>,
+        ) {
+        }
+
+        async fn snapshot_state(
+            &mut self,
+            _context: &FunctionSnapshotContext,
+        ) -> Option<CheckpointHandle> {
+            None
+        }
+    }
+
+    struct CollectRunnable {
+        elements: Arc<Mutex<Vec<Element>>>,
+    }
+
+    #[async_trait]
+    impl Runnable for CollectRunnable {
+        async fn open(&mut self, _context: &RunnableContext) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn run(&mut self, element: Element) {
+            self.elements.lock().unwrap().push(element);
+        }
+
+        async fn close(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn set_next_runnable(&mut self, _next_runnable: Option<Box<dyn Runnable>>) {}
+
+        async fn advance_processing_time(&mut self) {}
+
+        async fn checkpoint(&mut self, _snapshot_context: FunctionSnapshotContext) {}
+    }
+
+    #[tokio::test]
+    pub async fn processing_time_timer_on_idle_stream_test() {
+        let elements = Arc::new(Mutex::new(Vec::new()));
+        let mut runnable = FlatMapRunnable::new(
+            OperatorId(1),
+            DefaultStreamOperator::new(1, FunctionCreator::User, Box::new(TimerFlatMapFunction {})),
+            Some(Box::new(CollectRunnable {
+                elements: elements.clone(),
+            })),
+        );
+
+        let timestamp = current_timestamp_millis() + 100;
+        runnable
+            .timer_service
+            .register_processing_time_timer(timestamp);
+
+        runnable.advance_processing_time().await;
+        assert!(elements.lock().unwrap().is_empty());
+
+        // no element arrives, the timer is fired by the periodic processing time advance
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        runnable.advance_processing_time().await;
+
+        let elements = elements.lock().unwrap();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].as_record().timestamp, timestamp);
+    }
+}
@@ -7,6 +7,7 @@ use crate::core::element::{Element, Partition};
 use crate::core::function::KeySelectorFunction;
 use crate::core::operator::DefaultStreamOperator;
 use crate::core::runtime::{OperatorId, TaskId};
+use crate::functions::system::window_combine_function::WindowCombineFunction;
 use crate::metrics::register_counter;
 
 use crate::runtime::worker::runnable::{Runnable, RunnableContext};
@@ -17,28 +18,50 @@ pub(crate) struct KeyByRunnable {
     task_id: TaskId,
 
     stream_key_by: DefaultStreamOperator<dyn KeySelectorFunction>,
+    /// map-side combiner of the child reduce job
+    combiner: Option<WindowCombineFunction>,
     next_runnable: Option<Box<dyn Runnable>>,
     partition_size: u16,
 
     context: Option<RunnableContext>,
 
     counter: Counter,
+    combine_counter: Counter,
 }
 
 impl KeyByRunnable {
     pub fn new(
         operator_id: OperatorId,
         stream_key_by: DefaultStreamOperator<dyn KeySelectorFunction>,
+        combiner: Option<WindowCombineFunction>,
         next_runnable: Option<Box<dyn Runnable>>,
     ) -> Self {
         KeyByRunnable {
             operator_id,
             task_id: TaskId::default(),
             stream_key_by,
+            combiner,
             next_runnable,
             partition_size: 0,
             context: None,
             counter: Counter::noop(),
+            combine_counter: Counter::noop(),
+        }
+    }
+
+    async fn flush_combiner(&mut self) {
+        let partials = match self.combiner.as_mut() {
+            Some(combiner) => combiner.flush(),
+            None => return,
+        };
+
+        self.combine_counter.increment(partials.len() as u64);
+        for partial in partials {
+            self.next_runnable
+                .as_mut()
+                .unwrap()
+                .run(Element::Record(partial))
+                .await;
         }
     }
 }
@@ -61,6 +84,18 @@ impl Runnable for KeyByRunnable {
             self.task_id.to_tags(),
         );
 
+        if let Some(combiner) = self.combiner.as_mut() {
+            // open the combiner with the same input schema as the final reduce
+            let mut combine_context = fun_context.clone();
+            combine_context.input_schema = fun_context.output_schema.clone();
+            combiner.open(&combine_context).await?;
+
+            self.combine_counter = register_counter(
+                format!("KeyBy_Combine_{}", combiner.name()),
+                self.task_id.to_tags(),
+            );
+        }
+
         Ok(())
     }
 
@@ -82,13 +117,25 @@ impl Runnable for KeyByRunnable {
                 //     hash_code,
                 //     self.partition_size,
                 // );
-                record.set_partition(partition_num as u16);
-
-                self.next_runnable.as_mut().unwrap().run(element).await;
-
                 self.counter.increment(1);
+
+                match self.combiner.as_mut() {
+                    Some(combiner) => {
+                        combiner.combine(partition_num as u16, key_row, record);
+                        if combiner.is_full() {
+                            self.flush_combiner().await;
+                        }
+                    }
+                    None => {
+                        record.set_partition(partition_num as u16);
+                        self.next_runnable.as_mut().unwrap().run(element).await;
+                    }
+                }
             }
             Element::Barrier(barrier) => {
+                // the buffered partial values are not a part of the snapshot
+                self.flush_combiner().await;
+
                 let checkpoint_id = barrier.checkpoint_id;
                 let snapshot_context = {
                     let context = self.context.as_ref().unwrap();
@@ -99,12 +146,16 @@ impl Runnable for KeyByRunnable {
                 self.next_runnable.as_mut().unwrap().run(element).await;
             }
             _ => {
+                self.flush_combiner().await;
                 self.next_runnable.as_mut().unwrap().run(element).await;
             }
         }
     }
 
     async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(combiner) = self.combiner.as_mut() {
+            combiner.close().await?;
+        }
         self.stream_key_by.operator_fn.close().await?;
         self.next_runnable.as_mut().unwrap().close().await
     }
@@ -113,6 +164,14 @@ impl Runnable for KeyByRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_key_by
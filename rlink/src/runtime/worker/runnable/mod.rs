@@ -6,6 +6,7 @@ use crate::core::checkpoint::FunctionSnapshotContext;
 use crate::core::element::Element;
 use crate::core::properties::SystemProperties;
 use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
+use crate::core::timer::TimerService;
 use crate::dag::execution_graph::{ExecutionEdge, ExecutionNode};
 use crate::dag::job_graph::{JobEdge, JobNode};
 use crate::dag::metadata::DagMetadata;
@@ -89,9 +90,23 @@ impl RunnableContext {
             children,
 
             task_context: Some(self.task_context.clone()),
+            timer_service: TimerService::default(),
         }
     }
 
+    /// Same as `to_fun_context`, but bind the `timer_service` to the function and restore the
+    /// pending timers from the checkpoint
+    pub(crate) fn to_timer_fun_context(
+        &self,
+        operator_id: OperatorId,
+        timer_service: &TimerService,
+    ) -> FunctionContext {
+        let mut fun_context = self.to_fun_context(operator_id);
+        fun_context.checkpoint_handle = timer_service.restore(fun_context.checkpoint_handle.take());
+        fun_context.timer_service = timer_service.clone();
+        fun_context
+    }
+
     pub(crate) fn checkpoint_context(
         &self,
         operator_id: OperatorId,
@@ -103,7 +118,7 @@ impl RunnableContext {
             self.task_context.task_descriptor.task_id,
             checkpoint_id,
             completed_checkpoint_id,
-            self.task_context.clone(),
+            Some(self.task_context.clone()),
         )
     }
 
@@ -175,5 +190,8 @@ pub(crate) trait Runnable: Send + Sync {
     async fn run(&mut self, element: Element);
     async fn close(&mut self) -> anyhow::Result<()>;
     fn set_next_runnable(&mut self, next_runnable: Option<Box<dyn Runnable>>);
+    /// Fire the expired processing-time timers of the chain, driven by a timer of the
+    /// `SourceRunnable` so that the timers are fired also when no element arrives
+    async fn advance_processing_time(&mut self);
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext);
 }
@@ -8,6 +8,7 @@ use crate::core::function::{BaseReduceFunction, KeySelectorFunction};
 use crate::core::operator::DefaultStreamOperator;
 use crate::core::runtime::{CheckpointId, OperatorId, TaskId};
 use crate::core::window::{TWindow, Window};
+use crate::functions::system::window_combine_function::decode_partial;
 use crate::metrics::register_counter;
 use crate::runtime::worker::runnable::{Runnable, RunnableContext};
 
@@ -86,6 +87,20 @@ impl Runnable for ReduceRunnable {
     async fn run(&mut self, element: Element) {
         match element {
             Element::Record(mut record) => {
+                let key = if self.stream_reduce.operator_fn.is_combined() {
+                    // partial value of the map-side combiner, with the key and window in it
+                    let (key, value) = decode_partial(record.borrow_mut());
+                    record = value;
+                    key
+                } else {
+                    match &self.stream_key_by {
+                        Some(stream_key_by) => {
+                            stream_key_by.operator_fn.get_key(record.borrow_mut()).await
+                        }
+                        None => Record::with_capacity(0),
+                    }
+                };
+
                 // Record expiration check
                 let min_window_timestamp = self.limited_watermark_window.min_timestamp();
                 let acceptable = record
@@ -105,13 +120,6 @@ impl Runnable for ReduceRunnable {
                     return;
                 }
 
-                let key = match &self.stream_key_by {
-                    Some(stream_key_by) => {
-                        stream_key_by.operator_fn.get_key(record.borrow_mut()).await
-                    }
-                    None => Record::with_capacity(0),
-                };
-
                 self.stream_reduce
                     .operator_fn
                     .as_mut()
@@ -186,6 +194,14 @@ impl Runnable for ReduceRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_reduce
@@ -131,6 +131,8 @@ impl Runnable for SinkRunnable {
         unimplemented!()
     }
 
+    async fn advance_processing_time(&mut self) {}
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_sink
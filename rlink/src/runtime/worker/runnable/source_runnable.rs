@@ -7,6 +7,7 @@ use std::time::Duration;
 
 use futures::StreamExt;
 use metrics::Counter;
+use tokio::time::MissedTickBehavior;
 
 use crate::channel::named_channel;
 use crate::channel::sender::ChannelSender;
@@ -23,6 +24,9 @@ use crate::runtime::worker::runnable::{Runnable, RunnableContext};
 use crate::runtime::worker::WorkerTaskContext;
 use crate::runtime::HeartbeatItem;
 
+/// The period to fire the processing-time timers of the runnables chained after the source
+const PROCESSING_TIME_INTERVAL: Duration = Duration::from_millis(100);
+
 pub(crate) struct SourceRunnable {
     operator_id: OperatorId,
     context: Option<RunnableContext>,
@@ -175,8 +179,26 @@ impl Runnable for SourceRunnable {
             FunctionCreator::System => record_stream,
         };
 
+        let mut processing_time_interval = tokio::time::interval(PROCESSING_TIME_INTERVAL);
+        processing_time_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         let mut end_flags = 0;
-        while let Some(element) = element_stream.next().await {
+        loop {
+            let element = tokio::select! {
+                element = element_stream.next() => match element {
+                    Some(element) => element,
+                    None => break,
+                },
+                _ = processing_time_interval.tick() => {
+                    self.next_runnable
+                        .as_mut()
+                        .unwrap()
+                        .advance_processing_time()
+                        .await;
+                    continue;
+                }
+            };
+
             match element {
                 Element::Record(_) => {
                     self.next_runnable.as_mut().unwrap().run(element).await;
@@ -259,6 +281,14 @@ impl Runnable for SourceRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .stream_source
@@ -390,7 +420,14 @@ impl RecordEmitter {
 impl ElementEmitter for RecordEmitter {
     async fn emit(&mut self, context: EmitterContext, sender: ChannelSender<Element>) {
         let daemon_task = context.task_context.task_descriptor.daemon;
+        let watermark_aligner = context.task_context.watermark_aligner();
         while let Some(element) = self.stream.next().await {
+            if let Some(watermark_aligner) = &watermark_aligner {
+                watermark_aligner
+                    .wait_aligned(context.op_name.as_str())
+                    .await;
+            }
+
             if let Err(_e) = sender.send(element).await {
                 error!("[{}] channel has closed", context.op_name.as_str());
                 break;
@@ -1,4 +1,5 @@
 use std::borrow::BorrowMut;
+use std::sync::Arc;
 
 use metrics::{Counter, Gauge};
 
@@ -12,6 +13,7 @@ use crate::core::watermark::{
 };
 use crate::metrics::{register_counter, register_gauge};
 use crate::runtime::worker::runnable::{Runnable, RunnableContext};
+use crate::runtime::worker::watermark_alignment::WatermarkAligner;
 
 pub(crate) struct WatermarkAssignerRunnable {
     operator_id: OperatorId,
@@ -25,6 +27,7 @@ pub(crate) struct WatermarkAssignerRunnable {
     watermark: Watermark,
 
     context: Option<RunnableContext>,
+    watermark_aligner: Option<Arc<WatermarkAligner>>,
 
     watermark_gauge: Gauge,
     expire_counter: Counter,
@@ -47,6 +50,7 @@ impl WatermarkAssignerRunnable {
             next_runnable,
             watermark: MIN_WATERMARK,
             context: None,
+            watermark_aligner: None,
             watermark_gauge: Gauge::noop(),
             expire_counter: Counter::noop(),
         }
@@ -54,11 +58,18 @@ impl WatermarkAssignerRunnable {
 
     fn update_watermark_progress(&mut self, watermark: Watermark) {
         if watermark.timestamp > MAX_WATERMARK.timestamp {
+            if let Some(watermark_aligner) = &self.watermark_aligner {
+                watermark_aligner.mark_idle();
+            }
             return;
         }
 
         self.watermark = watermark;
         self.watermark_gauge.set(self.watermark.timestamp as f64);
+
+        if let Some(watermark_aligner) = &self.watermark_aligner {
+            watermark_aligner.update_local_watermark(self.watermark.timestamp);
+        }
     }
 }
 
@@ -83,6 +94,19 @@ impl Runnable for WatermarkAssignerRunnable {
         let fun_context = context.to_fun_context(self.operator_id);
         self.timestamp_assigner.open(&fun_context)?;
 
+        if let Some(alignment) = self.watermark_strategy.operator_fn.alignment() {
+            info!(
+                "watermark alignment enabled, group: {}, max drift: {:?}",
+                alignment.group, alignment.max_drift
+            );
+            let watermark_aligner = Arc::new(WatermarkAligner::new(alignment, self.task_id));
+            watermark_aligner.start_report(context.task_context());
+            context
+                .task_context
+                .register_watermark_aligner(watermark_aligner.clone());
+            self.watermark_aligner = Some(watermark_aligner);
+        }
+
         Ok(())
     }
 
@@ -124,6 +148,11 @@ impl Runnable for WatermarkAssignerRunnable {
             }
             Element::StreamStatus(stream_status) => {
                 if stream_status.end {
+                    if let Some(watermark_aligner) = &self.watermark_aligner {
+                        // the finished task must not hold back the group
+                        watermark_aligner.update_local_watermark(MAX_WATERMARK.timestamp);
+                    }
+
                     let watermark_ele = Element::max_watermark();
                     self.next_runnable
                         .as_mut()
@@ -173,6 +202,14 @@ impl Runnable for WatermarkAssignerRunnable {
         self.next_runnable = next_runnable;
     }
 
+    async fn advance_processing_time(&mut self) {
+        self.next_runnable
+            .as_mut()
+            .unwrap()
+            .advance_processing_time()
+            .await;
+    }
+
     async fn checkpoint(&mut self, snapshot_context: FunctionSnapshotContext) {
         let handle = self
             .watermark_strategy
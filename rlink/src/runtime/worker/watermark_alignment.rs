@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::cluster::StdResponse;
+use crate::core::runtime::TaskId;
+use crate::core::watermark::WatermarkAlignment;
+use crate::runtime::worker::WorkerTaskContext;
+use crate::runtime::WatermarkAlignmentRequest;
+use crate::utils::date_time;
+use crate::utils::http::client::post;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// the group watermark has not been received from the coordinator, or all the tasks of the group
+/// are idle
+const UNKNOWN_WATERMARK: u64 = u64::MAX;
+
+/// Share the local watermark of a source task between the `WatermarkAssignerRunnable`, which
+/// updates it, and the `RecordEmitter`, which is paused when the task's watermark gets more than
+/// `max_drift` ahead of the group's minimum watermark. The idle task is neither paused nor
+/// counted in the group's minimum watermark, so it leaves the idleness by the next record.
+#[derive(Debug)]
+pub(crate) struct WatermarkAligner {
+    alignment: WatermarkAlignment,
+    task_id: TaskId,
+
+    local_watermark: AtomicU64,
+    idle: AtomicBool,
+    group_watermark: AtomicU64,
+}
+
+impl WatermarkAligner {
+    pub fn new(alignment: WatermarkAlignment, task_id: TaskId) -> Self {
+        WatermarkAligner {
+            alignment,
+            task_id,
+            local_watermark: AtomicU64::new(0),
+            idle: AtomicBool::new(false),
+            group_watermark: AtomicU64::new(UNKNOWN_WATERMARK),
+        }
+    }
+
+    /// the task is active, the local watermark never goes back
+    pub fn update_local_watermark(&self, watermark: u64) {
+        self.local_watermark.fetch_max(watermark, Ordering::Relaxed);
+        self.idle.store(false, Ordering::Relaxed);
+    }
+
+    /// the task is idle until the next `update_local_watermark`
+    pub fn mark_idle(&self) {
+        self.idle.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        if self.idle.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let group_watermark = self.group_watermark.load(Ordering::Relaxed);
+        if group_watermark == UNKNOWN_WATERMARK {
+            return false;
+        }
+
+        let max_drift = self.alignment.max_drift.as_millis() as u64;
+        self.local_watermark.load(Ordering::Relaxed) > group_watermark.saturating_add(max_drift)
+    }
+
+    /// block until the local watermark is aligned with the group
+    pub async fn wait_aligned(&self, op_name: &str) {
+        if !self.is_paused() {
+            return;
+        }
+
+        info!(
+            "[{}] paused by watermark alignment group `{}`, local watermark: {}, group watermark: {}",
+            op_name,
+            self.alignment.group,
+            self.local_watermark.load(Ordering::Relaxed),
+            self.group_watermark.load(Ordering::Relaxed),
+        );
+        while self.is_paused() {
+            tokio::time::sleep(PAUSE_CHECK_INTERVAL).await;
+        }
+        info!("[{}] resumed by watermark alignment", op_name);
+    }
+
+    /// periodically report the local watermark and fetch the group watermark
+    pub fn start_report(self: &Arc<Self>, task_context: Arc<WorkerTaskContext>) {
+        let aligner = self.clone();
+        tokio::spawn(async move {
+            let coordinator_address = task_context
+                .cluster_descriptor()
+                .coordinator_manager
+                .web_address
+                .clone();
+            let url = format!("{}/api/watermark", coordinator_address);
+
+            loop {
+                tokio::time::sleep(REPORT_INTERVAL).await;
+                if task_context.get_coordinator_status().is_terminated() {
+                    break;
+                }
+
+                aligner.report(url.as_str()).await;
+            }
+
+            info!(
+                "watermark alignment report stop, task_id={:?}",
+                aligner.task_id
+            );
+        });
+    }
+
+    async fn report(&self, url: &str) {
+        let request = WatermarkAlignmentRequest {
+            group: self.alignment.group.clone(),
+            task_id: self.task_id,
+            watermark: self.local_watermark.load(Ordering::Relaxed),
+            idle: self.idle.load(Ordering::Relaxed),
+        };
+        let body = serde_json::to_string(&request).unwrap();
+
+        let begin_time = date_time::current_timestamp_millis();
+        let resp = post::<StdResponse<u64>>(url.to_string(), body).await;
+        let elapsed = date_time::current_timestamp_millis() - begin_time;
+
+        match resp {
+            Ok(resp) => {
+                if elapsed > 1000 {
+                    warn!("report watermark success, elapsed: {}ms > 1s", elapsed);
+                }
+                if let Some(group_watermark) = resp.data {
+                    self.group_watermark
+                        .store(group_watermark, Ordering::Relaxed);
+                }
+            }
+            Err(e) => {
+                error!("report watermark error. {}, elapsed: {}ms", e, elapsed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use crate::core::runtime::TaskId;
+    use crate::core::watermark::WatermarkAlignment;
+    use crate::runtime::worker::watermark_alignment::WatermarkAligner;
+
+    #[test]
+    pub fn watermark_aligner_test() {
+        let alignment = WatermarkAlignment::new("g0", Duration::from_secs(10));
+        let aligner = WatermarkAligner::new(alignment, TaskId::default());
+
+        aligner.update_local_watermark(100_000);
+        assert!(!aligner.is_paused());
+
+        aligner.group_watermark.store(80_000, Ordering::Relaxed);
+        assert!(aligner.is_paused());
+
+        aligner.group_watermark.store(90_000, Ordering::Relaxed);
+        assert!(!aligner.is_paused());
+
+        // the idle task is not paused, and leaves the idleness by the next watermark
+        aligner.group_watermark.store(80_000, Ordering::Relaxed);
+        aligner.mark_idle();
+        assert!(!aligner.is_paused());
+        aligner.update_local_watermark(50_000);
+        assert_eq!(aligner.local_watermark.load(Ordering::Relaxed), 100_000);
+        assert!(aligner.is_paused());
+    }
+}
@@ -59,20 +59,33 @@ impl TCheckpointStorage for MemoryCheckpointStorage {
         Ok(())
     }
 
+    /// Load the latest checkpoint saved in the process, the tasks restarted by the coordinator
+    /// are restored from the memory
     async fn load(
         &mut self,
         _application_name: &str,
         _application_id: &str,
     ) -> anyhow::Result<Vec<Checkpoint>> {
-        Ok(vec![])
+        let cks = self
+            .history_cks
+            .iter()
+            .max_by_key(|(ck_id, _cks)| ck_id.0)
+            .map(|(_ck_id, cks)| cks.clone())
+            .unwrap_or_default();
+        Ok(cks)
     }
 
     async fn load_by_checkpoint_id(
         &mut self,
         _application_name: &str,
         _application_id: &str,
-        _checkpoint_id: CheckpointId,
+        checkpoint_id: CheckpointId,
     ) -> anyhow::Result<Vec<Checkpoint>> {
-        Ok(vec![])
+        let cks = self
+            .history_cks
+            .get(&checkpoint_id)
+            .cloned()
+            .unwrap_or_default();
+        Ok(cks)
     }
 }
@@ -0,0 +1,148 @@
+use crate::core::checkpoint::Checkpoint;
+use crate::core::element::{Barrier, Element, Record, Watermark};
+use crate::core::function::CoProcessFunction;
+use crate::core::runtime::CheckpointId;
+use crate::core::timer::{Timer, TimerService};
+use crate::testing::{to_checkpoint, HarnessContext, Output};
+
+/// Drive a `CoProcessFunction` as the `CoProcessRunnable` does, the records of the connected
+/// streams are fed by `process_left` and `process_right`. The processing time starts at `0`.
+pub struct CoProcessHarness<F>
+where
+    F: CoProcessFunction,
+{
+    function: F,
+    context: HarnessContext,
+    timer_service: TimerService,
+    output: Output,
+    /// the checkpoint of the last barrier
+    last_checkpoint: Option<Checkpoint>,
+}
+
+impl<F> CoProcessHarness<F>
+where
+    F: CoProcessFunction,
+{
+    pub fn new(function: F) -> Self {
+        Self::with_context(function, HarnessContext::new())
+    }
+
+    pub fn with_context(function: F, context: HarnessContext) -> Self {
+        let timer_service = TimerService::default();
+        timer_service.set_processing_time(0);
+
+        CoProcessHarness {
+            function,
+            context,
+            timer_service,
+            output: Output::default(),
+            last_checkpoint: None,
+        }
+    }
+
+    pub async fn open(&mut self) -> crate::core::Result<()> {
+        let output_schema = self.function.schema(self.context.input_schema.clone());
+        let fun_context = self
+            .context
+            .to_fun_context(output_schema, &self.timer_service);
+        self.function.open(&fun_context).await
+    }
+
+    /// Process a record of the left stream, the one `connect` is called on
+    pub async fn process_left(&mut self, record: Record) {
+        self.fire_processing_time_timers().await;
+
+        let elements = self.function.process_left(record).await;
+        self.output.collect(elements).await;
+    }
+
+    /// Process a record of the `stream_seq` right stream, the index in the `connect` streams
+    pub async fn process_right(&mut self, stream_seq: usize, record: Record) {
+        self.fire_processing_time_timers().await;
+
+        let elements = self.function.process_right(stream_seq, record).await;
+        self.output.collect(elements).await;
+    }
+
+    /// Process an element except the records, which are fed by `process_left` and
+    /// `process_right` for the stream they come from
+    pub async fn process_element(&mut self, element: Element) {
+        self.fire_processing_time_timers().await;
+
+        match element {
+            Element::Record(_) => panic!("use `process_left` or `process_right` for the records"),
+            Element::Barrier(barrier) => {
+                let snapshot_context = self.context.checkpoint_context(barrier.checkpoint_id);
+                let handle = self.function.snapshot_state(&snapshot_context).await;
+                self.last_checkpoint =
+                    to_checkpoint(&snapshot_context, handle, &self.timer_service);
+
+                self.output.push(Element::Barrier(barrier));
+            }
+            Element::Watermark(watermark) => {
+                let timers = self.timer_service.advance_watermark(watermark.timestamp);
+                self.fire_timers(timers).await;
+
+                self.output.push(Element::Watermark(watermark));
+            }
+            _ => self.output.push(element),
+        }
+    }
+
+    pub async fn process_watermark(&mut self, timestamp: u64) {
+        self.process_element(Element::Watermark(Watermark::new(timestamp)))
+            .await;
+    }
+
+    /// Move the processing time to `timestamp` and fire the expired processing-time timers
+    pub async fn set_processing_time(&mut self, timestamp: u64) {
+        self.timer_service.set_processing_time(timestamp);
+        self.fire_processing_time_timers().await;
+    }
+
+    /// Process a `Barrier` of the `checkpoint_id`, return the checkpoint to restore a new harness
+    /// with, `None` if the function declines the checkpoint
+    pub async fn snapshot(&mut self, checkpoint_id: u64) -> Option<Checkpoint> {
+        let barrier = Barrier::new(CheckpointId(checkpoint_id));
+        self.process_element(Element::Barrier(barrier)).await;
+        self.last_checkpoint.take()
+    }
+
+    pub async fn close(&mut self) -> crate::core::Result<()> {
+        self.function.close().await
+    }
+
+    /// Take the elements emitted since the last call
+    pub fn take_output(&mut self) -> Vec<Element> {
+        self.output.take_elements()
+    }
+
+    /// Take the records emitted since the last call, the other elements are dropped
+    pub fn take_records(&mut self) -> Vec<Record> {
+        self.output.take_records()
+    }
+
+    pub fn timer_service(&self) -> &TimerService {
+        &self.timer_service
+    }
+
+    pub fn function(&self) -> &F {
+        &self.function
+    }
+
+    pub fn function_mut(&mut self) -> &mut F {
+        &mut self.function
+    }
+
+    async fn fire_processing_time_timers(&mut self) {
+        let timers = self.timer_service.advance_processing_time();
+        self.fire_timers(timers).await;
+    }
+
+    async fn fire_timers(&mut self, timers: Vec<Timer>) {
+        for timer in timers {
+            let elements = self.function.on_timer(timer).await;
+            self.output.collect(elements).await;
+        }
+    }
+}
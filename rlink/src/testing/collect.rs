@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, NamedFunction, OutputFormat};
+
+lazy_static! {
+    static ref COLLECTED_RECORDS: Mutex<HashMap<String, Vec<Record>>> = Mutex::new(HashMap::new());
+}
+
+/// Collect the records of all the tasks into the process by the `name`, the records are read by
+/// `collected`. The records written before a restart are kept.
+pub struct CollectOutputFormat {
+    name: String,
+}
+
+impl CollectOutputFormat {
+    pub fn new(name: &str) -> Self {
+        CollectOutputFormat {
+            name: name.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl OutputFormat for CollectOutputFormat {
+    async fn open(&mut self, _context: &Context) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    async fn write_element(&mut self, element: Element) {
+        let record = element.into_record();
+        let mut collected_records = COLLECTED_RECORDS.lock().unwrap();
+        collected_records
+            .entry(self.name.clone())
+            .or_default()
+            .push(record);
+    }
+
+    async fn close(&mut self) -> crate::core::Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+        FnSchema::Empty
+    }
+}
+
+impl NamedFunction for CollectOutputFormat {
+    fn name(&self) -> &str {
+        "CollectOutputFormat"
+    }
+}
+
+#[async_trait]
+impl CheckpointFunction for CollectOutputFormat {
+    async fn initialize_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+        _handle: &Option<CheckpointHandle>,
+    ) {
+    }
+
+    async fn snapshot_state(
+        &mut self,
+        _context: &FunctionSnapshotContext,
+    ) -> Option<CheckpointHandle> {
+        None
+    }
+}
+
+/// The records written to the `CollectOutputFormat` of the `name`
+pub fn collected(name: &str) -> Vec<Record> {
+    let collected_records = COLLECTED_RECORDS.lock().unwrap();
+    collected_records.get(name).cloned().unwrap_or_default()
+}
+
+/// Remove the records of the `name`, for a new run of the application
+pub fn clear_collected(name: &str) {
+    let mut collected_records = COLLECTED_RECORDS.lock().unwrap();
+    collected_records.remove(name);
+}
@@ -0,0 +1,274 @@
+use crate::core::checkpoint::Checkpoint;
+use crate::core::element::{Barrier, Element, Record, Watermark};
+use crate::core::function::FlatMapFunction;
+use crate::core::runtime::CheckpointId;
+use crate::core::timer::{Timer, TimerService};
+use crate::testing::{to_checkpoint, HarnessContext, Output};
+
+/// Drive a `FlatMapFunction` as the `FlatMapRunnable` does. The processing time starts at `0`.
+pub struct FlatMapHarness<F>
+where
+    F: FlatMapFunction,
+{
+    function: F,
+    context: HarnessContext,
+    timer_service: TimerService,
+    output: Output,
+    /// the checkpoint of the last barrier
+    last_checkpoint: Option<Checkpoint>,
+}
+
+impl<F> FlatMapHarness<F>
+where
+    F: FlatMapFunction,
+{
+    pub fn new(function: F) -> Self {
+        Self::with_context(function, HarnessContext::new())
+    }
+
+    pub fn with_context(function: F, context: HarnessContext) -> Self {
+        let timer_service = TimerService::default();
+        timer_service.set_processing_time(0);
+
+        FlatMapHarness {
+            function,
+            context,
+            timer_service,
+            output: Output::default(),
+            last_checkpoint: None,
+        }
+    }
+
+    pub async fn open(&mut self) -> crate::core::Result<()> {
+        let output_schema = self.function.schema(self.context.input_schema.clone());
+        let fun_context = self
+            .context
+            .to_fun_context(output_schema, &self.timer_service);
+        self.function.open(&fun_context).await
+    }
+
+    /// Process an element as the runtime does, the barriers and watermarks are forwarded to the
+    /// output after the function's outputs
+    pub async fn process_element(&mut self, element: Element) {
+        let timers = self.timer_service.advance_processing_time();
+        self.fire_timers(timers).await;
+
+        match element {
+            Element::Record(_) => {
+                let elements = self.function.flat_map_element(element).await;
+                self.output.collect(elements).await;
+            }
+            Element::Barrier(barrier) => {
+                self.last_checkpoint = self.checkpoint(barrier.checkpoint_id).await;
+                self.output.push(Element::Barrier(barrier));
+            }
+            Element::Watermark(watermark) => {
+                self.flush().await;
+
+                let timers = self.timer_service.advance_watermark(watermark.timestamp);
+                self.fire_timers(timers).await;
+
+                self.output.push(Element::Watermark(watermark));
+            }
+            _ => {
+                self.flush().await;
+                self.output.push(element);
+            }
+        }
+    }
+
+    pub async fn process_record(&mut self, record: Record) {
+        self.process_element(Element::Record(record)).await;
+    }
+
+    pub async fn process_watermark(&mut self, timestamp: u64) {
+        self.process_element(Element::Watermark(Watermark::new(timestamp)))
+            .await;
+    }
+
+    /// Move the processing time to `timestamp` and fire the expired processing-time timers
+    pub async fn set_processing_time(&mut self, timestamp: u64) {
+        self.timer_service.set_processing_time(timestamp);
+        let timers = self.timer_service.advance_processing_time();
+        self.fire_timers(timers).await;
+    }
+
+    /// Process a `Barrier` of the `checkpoint_id`, return the checkpoint to restore a new harness
+    /// with, `None` if the function declines the checkpoint
+    pub async fn snapshot(&mut self, checkpoint_id: u64) -> Option<Checkpoint> {
+        let barrier = Barrier::new(CheckpointId(checkpoint_id));
+        self.process_element(Element::Barrier(barrier)).await;
+        self.last_checkpoint.take()
+    }
+
+    pub async fn close(&mut self) -> crate::core::Result<()> {
+        self.function.close().await
+    }
+
+    /// Take the elements emitted since the last call
+    pub fn take_output(&mut self) -> Vec<Element> {
+        self.output.take_elements()
+    }
+
+    /// Take the records emitted since the last call, the other elements are dropped
+    pub fn take_records(&mut self) -> Vec<Record> {
+        self.output.take_records()
+    }
+
+    pub fn timer_service(&self) -> &TimerService {
+        &self.timer_service
+    }
+
+    pub fn function(&self) -> &F {
+        &self.function
+    }
+
+    pub fn function_mut(&mut self) -> &mut F {
+        &mut self.function
+    }
+
+    async fn checkpoint(&mut self, checkpoint_id: CheckpointId) -> Option<Checkpoint> {
+        self.flush().await;
+
+        let snapshot_context = self.context.checkpoint_context(checkpoint_id);
+        let handle = self.function.snapshot_state(&snapshot_context).await;
+        to_checkpoint(&snapshot_context, handle, &self.timer_service)
+    }
+
+    async fn flush(&mut self) {
+        let elements = self.function.flush().await;
+        self.output.collect(elements).await;
+    }
+
+    async fn fire_timers(&mut self, timers: Vec<Timer>) {
+        for timer in timers {
+            let elements = self.function.on_timer(timer).await;
+            self.output.collect(elements).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::checkpoint::{CheckpointFunction, CheckpointHandle, FunctionSnapshotContext};
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::{Element, FnSchema, Record};
+    use crate::core::function::{Context, FlatMapFunction, NamedFunction, SendableElementStream};
+    use crate::core::timer::{Timer, TimerService};
+    use crate::testing::{FlatMapHarness, HarnessContext};
+    use crate::utils::stream::MemoryStream;
+
+    /// Count the records, and emit the count when the event-time timer 10 fires
+    struct CountFlatMap {
+        count: i64,
+        timer_service: TimerService,
+    }
+
+    impl CountFlatMap {
+        fn new() -> Self {
+            CountFlatMap {
+                count: 0,
+                timer_service: TimerService::default(),
+            }
+        }
+
+        fn schema() -> Schema {
+            Schema::new(vec![Field::new("count", DataType::Int64)])
+        }
+    }
+
+    #[async_trait]
+    impl FlatMapFunction for CountFlatMap {
+        async fn open(&mut self, context: &Context) -> crate::core::Result<()> {
+            self.timer_service = context.timer_service();
+            self.initialize_state(&context.checkpoint_context(), &context.checkpoint_handle)
+                .await;
+            Ok(())
+        }
+
+        async fn flat_map_element(&mut self, _element: Element) -> SendableElementStream {
+            self.count += 1;
+            self.timer_service.register_event_time_timer(10);
+            Box::pin(MemoryStream::new(vec![]))
+        }
+
+        async fn on_timer(&mut self, _timer: Timer) -> SendableElementStream {
+            let mut record = Record::new();
+            let schema = Self::schema();
+            let mut writer = record.as_writer(schema.as_type_ids());
+            writer.set_i64(self.count).unwrap();
+            Box::pin(MemoryStream::new(vec![record]))
+        }
+
+        async fn close(&mut self) -> crate::core::Result<()> {
+            Ok(())
+        }
+
+        fn schema(&self, _input_schema: FnSchema) -> FnSchema {
+            FnSchema::from(&Self::schema())
+        }
+    }
+
+    impl NamedFunction for CountFlatMap {
+        fn name(&self) -> &str {
+            "CountFlatMap"
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointFunction for CountFlatMap {
+        async fn initialize_state(
+            &mut self,
+            _context: &FunctionSnapshotContext,
+            handle: &Option<CheckpointHandle>,
+        ) {
+            if let Some(handle) = handle {
+                self.count = handle.handle.parse().unwrap();
+            }
+        }
+
+        async fn snapshot_state(
+            &mut self,
+            _context: &FunctionSnapshotContext,
+        ) -> Option<CheckpointHandle> {
+            Some(CheckpointHandle {
+                handle: self.count.to_string(),
+            })
+        }
+    }
+
+    fn counts(records: Vec<Record>) -> Vec<i64> {
+        let schema = CountFlatMap::schema();
+        records
+            .into_iter()
+            .map(|mut record| record.as_reader(schema.as_type_ids()).get_i64(0).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    pub async fn flat_map_harness_test() {
+        let mut harness = FlatMapHarness::new(CountFlatMap::new());
+        harness.open().await.unwrap();
+
+        harness.process_record(Record::new()).await;
+        harness.process_record(Record::new()).await;
+        harness.process_watermark(5).await;
+        assert!(harness.take_records().is_empty());
+
+        // the pending timer and the count are in the checkpoint
+        let checkpoint = harness.snapshot(1).await.unwrap();
+        let output = harness.take_output();
+        assert_eq!(output.len(), 1);
+        assert!(output[0].is_barrier());
+
+        let context = HarnessContext::new().restore(&checkpoint);
+        let mut restored = FlatMapHarness::with_context(CountFlatMap::new(), context);
+        restored.open().await.unwrap();
+        assert_eq!(restored.function().count, 2);
+
+        restored.process_record(Record::new()).await;
+        restored.process_watermark(10).await;
+        assert_eq!(counts(restored.take_records()), vec![3]);
+        restored.close().await.unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::runtime::Runtime;
+use tokio::sync::OwnedMutexGuard;
+use tokio::task::JoinHandle;
+
+use crate::core::cluster::{ClusterConfig, MetadataStorageType, StdResponse, TaskResourceInfo};
+use crate::core::element::Record;
+use crate::core::env::StreamApp;
+use crate::core::runtime::{CheckpointId, ClusterDescriptor};
+use crate::deployment::TResourceManager;
+use crate::pub_sub::{memory, network};
+use crate::runtime::context::Context;
+use crate::runtime::coordinator::checkpoint_manager::CheckpointAlignManager;
+use crate::runtime::coordinator::CoordinatorTask;
+use crate::runtime::{cluster, ClusterMode, ManagerType};
+use crate::storage::metadata::mem_metadata_storage::METADATA_STORAGE;
+use crate::storage::metadata::{MetadataStorage, TMetadataStorage};
+use crate::testing::collect;
+use crate::utils::generator::gen_with_ts;
+use crate::utils::http::client::get;
+
+lazy_static! {
+    /// Held by the running `MiniCluster`, from `start` to `shutdown`
+    static ref MINI_CLUSTER_LOCK: Arc<tokio::sync::Mutex<()>> =
+        Arc::new(tokio::sync::Mutex::new(()));
+}
+
+/// task_manager_id -> the runtime the worker runs on
+type WorkerRuntimes = Arc<Mutex<HashMap<String, Runtime>>>;
+
+/// Run the coordinator and `num_workers` workers of a `StreamApp` in the test process. The
+/// coordinator and each worker run on their own tokio runtime, the workers exchange the
+/// elements by the network channels on the loopback, as the workers of a cluster do.
+///
+/// A killed worker is detected by the heartbeat timeout of the coordinator, then all the workers
+/// are restarted from the latest completed checkpoint. Set a short `heartbeat_interval` and
+/// `heartbeat_timeout` in the `prepare_properties` of the application to restart in seconds.
+///
+/// The metadata and the channels of the runtime are global in the process, only one
+/// `MiniCluster` can run at a time, `start` waits for the running one to be shutdown. The channels
+/// are cleared on the shutdown of the workers, so the other applications running in the same
+/// process (e.g. the other tests) are broken, run the tests of `MiniCluster` in an integration
+/// test binary, which has its own process.
+pub struct MiniCluster<S>
+where
+    S: StreamApp + 'static,
+{
+    stream_app: S,
+    num_workers: u32,
+    workers: WorkerRuntimes,
+    coordinator: Option<(Runtime, JoinHandle<anyhow::Result<()>>)>,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl<S> MiniCluster<S>
+where
+    S: StreamApp + 'static,
+{
+    pub fn new(stream_app: S, num_workers: u32) -> Self {
+        MiniCluster {
+            stream_app,
+            num_workers,
+            workers: Arc::new(Mutex::new(HashMap::new())),
+            coordinator: None,
+            guard: None,
+        }
+    }
+
+    /// Start the coordinator, the workers are started by the coordinator
+    pub async fn start(&mut self) -> anyhow::Result<()> {
+        if self.coordinator.is_some() {
+            return Err(anyhow!("the MiniCluster has started"));
+        }
+
+        self.guard = Some(MINI_CLUSTER_LOCK.clone().lock_owned().await);
+
+        // the metadata of the last run in the process
+        *METADATA_STORAGE.lock().await = None;
+
+        let context = Arc::new(Context::new(
+            gen_with_ts(),
+            "coordinator".to_string(),
+            "127.0.0.1".to_string(),
+            ClusterMode::Local,
+            self.num_workers,
+            ManagerType::Coordinator,
+            ClusterConfig::new_local(),
+            "".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "".to_string(),
+            0,
+            0,
+            "".to_string(),
+            "".to_string(),
+        ));
+        let resource_manager =
+            MiniClusterResourceManager::new(context.clone(), self.workers.clone());
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("coordinator")
+            .enable_all()
+            .build()?;
+        let stream_app = self.stream_app.clone();
+        let join_handle = runtime.spawn(async move {
+            let mut coordinator_task = CoordinatorTask::new(context, stream_app, resource_manager);
+            coordinator_task.run().await
+        });
+
+        self.coordinator = Some((runtime, join_handle));
+        Ok(())
+    }
+
+    /// The task_manager_id of the running workers
+    pub fn worker_ids(&self) -> Vec<String> {
+        let workers = self.workers.lock().unwrap();
+        let mut worker_ids: Vec<String> = workers.keys().cloned().collect();
+        worker_ids.sort();
+        worker_ids
+    }
+
+    /// Kill the worker as the process is killed, all the tasks and connections of the worker
+    /// are dropped without the notification to the coordinator
+    pub fn kill_worker(&self, task_manager_id: &str) -> anyhow::Result<()> {
+        let runtime = self
+            .workers
+            .lock()
+            .unwrap()
+            .remove(task_manager_id)
+            .ok_or(anyhow!("worker `{}` is not running", task_manager_id))?;
+        runtime.shutdown_background();
+
+        info!("kill worker {}", task_manager_id);
+        Ok(())
+    }
+
+    /// Wait until a checkpoint of the id `checkpoint_id` or later is completed by all the
+    /// operators, return the id of the completed checkpoint
+    pub async fn wait_for_checkpoint(
+        &self,
+        checkpoint_id: CheckpointId,
+        timeout: Duration,
+    ) -> anyhow::Result<CheckpointId> {
+        let wait = async {
+            loop {
+                if let Some(completed_checkpoint_id) = self.completed_checkpoint_id().await {
+                    if completed_checkpoint_id >= checkpoint_id {
+                        return completed_checkpoint_id;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.map_err(|_e| {
+            anyhow!(
+                "checkpoint {:?} not completed in {}ms",
+                checkpoint_id,
+                timeout.as_millis()
+            )
+        })
+    }
+
+    /// Wait until all the tasks of a bounded application are finished
+    pub async fn wait_for_termination(&mut self, timeout: Duration) -> anyhow::Result<()> {
+        let (_runtime, join_handle) = self
+            .coordinator
+            .as_mut()
+            .ok_or(anyhow!("the MiniCluster is not started"))?;
+
+        let rt = tokio::time::timeout(timeout, join_handle)
+            .await
+            .map_err(|_e| anyhow!("not terminated in {}ms", timeout.as_millis()))?;
+        self.shutdown();
+
+        rt.map_err(|e| anyhow!("coordinator error. {}", e))?
+    }
+
+    /// The records written to the `CollectOutputFormat` of the `name`
+    pub fn collected(&self, name: &str) -> Vec<Record> {
+        collect::collected(name)
+    }
+
+    /// Stop the coordinator and the workers
+    pub fn shutdown(&mut self) {
+        if let Some((runtime, _join_handle)) = self.coordinator.take() {
+            runtime.shutdown_background();
+        }
+        shutdown_workers(&self.workers);
+        self.guard = None;
+    }
+
+    async fn completed_checkpoint_id(&self) -> Option<CheckpointId> {
+        let metadata_storage = MetadataStorage::new(&MetadataStorageType::Memory);
+        let cluster_descriptor = metadata_storage.load().await.ok()?;
+
+        let url = format!(
+            "{}/api/checkpoints",
+            cluster_descriptor.coordinator_manager.web_address
+        );
+        let body = get(url.as_str()).await.ok()?;
+        let response: StdResponse<CheckpointAlignManager> =
+            serde_json::from_str(body.as_str()).ok()?;
+        response
+            .data
+            .and_then(|ck_manager| ck_manager.completed_checkpoint_id())
+    }
+}
+
+impl<S> Drop for MiniCluster<S>
+where
+    S: StreamApp + 'static,
+{
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Shutdown the runtimes of the workers, and drop the channels of the tasks for the restart
+fn shutdown_workers(workers: &WorkerRuntimes) {
+    let runtimes: Vec<(String, Runtime)> = workers.lock().unwrap().drain().collect();
+    for (task_manager_id, runtime) in runtimes {
+        runtime.shutdown_background();
+        info!("stop worker {}", task_manager_id);
+    }
+
+    memory::clear();
+    network::server::clear_network_channels();
+}
+
+/// Start the workers in the process as the `LocalResourceManager` does, but each worker on its
+/// own runtime, so the worker can be killed and the workers are stopped on restarting
+#[derive(Clone)]
+struct MiniClusterResourceManager {
+    context: Arc<Context>,
+    cluster_descriptor: Option<ClusterDescriptor>,
+    workers: WorkerRuntimes,
+}
+
+impl MiniClusterResourceManager {
+    fn new(context: Arc<Context>, workers: WorkerRuntimes) -> Self {
+        MiniClusterResourceManager {
+            context,
+            cluster_descriptor: None,
+            workers,
+        }
+    }
+}
+
+#[async_trait]
+impl TResourceManager for MiniClusterResourceManager {
+    fn prepare(&mut self, _context: &Context, cluster_descriptor: &ClusterDescriptor) {
+        self.cluster_descriptor = Some(cluster_descriptor.clone());
+    }
+
+    async fn worker_allocate<S>(&self, stream_app: &S) -> anyhow::Result<Vec<TaskResourceInfo>>
+    where
+        S: StreamApp + 'static,
+    {
+        let cluster_descriptor = self.cluster_descriptor.as_ref().unwrap();
+        let mut workers = self.workers.lock().unwrap();
+        for task_manager_descriptor in &cluster_descriptor.worker_managers {
+            let task_manager_id = task_manager_descriptor.task_manager_id.clone();
+
+            let mut context = self.context.deref().clone();
+            context.manager_type = ManagerType::Worker;
+            context.task_manager_id = task_manager_id.clone();
+            context.coordinator_address =
+                cluster_descriptor.coordinator_manager.web_address.clone();
+
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .thread_name(task_manager_id.as_str())
+                .enable_all()
+                .build()?;
+            let stream_app = stream_app.clone();
+            runtime.spawn(async move {
+                if let Err(e) = cluster::run_task(Arc::new(context), stream_app).await {
+                    error!("worker error. {}", e);
+                }
+            });
+            info!("start worker {}", task_manager_id);
+
+            if let Some(runtime) = workers.insert(task_manager_id, runtime) {
+                runtime.shutdown_background();
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    async fn stop_workers(&self, _task_ids: Vec<TaskResourceInfo>) -> anyhow::Result<()> {
+        shutdown_workers(&self.workers);
+        Ok(())
+    }
+}
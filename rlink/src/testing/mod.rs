@@ -0,0 +1,191 @@
+//! Harnesses to test a single function without the `StreamApp`, the elements are fed into the
+//! function directly, and the outputs are collected for assertions.
+//!
+//! The harnesses drive the functions as the runtime does: the barriers snapshot the function
+//! and its timers, the watermarks fire the event-time timers, and the processing time is moved
+//! by `set_processing_time` instead of the wall clock. A snapshot is restored by creating a new
+//! harness with `HarnessContext::restore`.
+//!
+//! The `MiniCluster` runs a whole `StreamApp` with the coordinator and the workers in the
+//! process, the outputs are collected by the `CollectOutputFormat`.
+//!
+//! The module is compiled only with the `testing` feature, add it to the `dev-dependencies`:
+//! `rlink = { version = "0.6", features = ["testing"] }`.
+//!
+//! ```ignore
+//! let mut harness = FlatMapHarness::new(MyFlatMap::new());
+//! harness.open().await?;
+//! harness.process_record(record).await;
+//! let checkpoint = harness.snapshot(1).await.unwrap();
+//!
+//! let mut restored =
+//!     FlatMapHarness::with_context(MyFlatMap::new(), HarnessContext::new().restore(&checkpoint));
+//! restored.open().await?;
+//! ```
+
+use futures::StreamExt;
+
+use crate::core::checkpoint::{Checkpoint, CheckpointHandle, FunctionSnapshotContext};
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{Context, SendableElementStream};
+use crate::core::properties::Properties;
+use crate::core::runtime::{CheckpointId, JobId, OperatorId, TaskId};
+use crate::core::timer::TimerService;
+
+pub mod co_process;
+pub mod collect;
+pub mod flat_map;
+pub mod mini_cluster;
+pub mod reduce;
+
+pub use co_process::CoProcessHarness;
+pub use collect::{clear_collected, collected, CollectOutputFormat};
+pub use flat_map::FlatMapHarness;
+pub use mini_cluster::MiniCluster;
+pub use reduce::ReduceHarness;
+
+/// The `Context` of the function in a harness
+#[derive(Clone, Debug)]
+pub struct HarnessContext {
+    application_properties: Properties,
+    operator_id: OperatorId,
+    task_id: TaskId,
+    checkpoint_id: CheckpointId,
+    completed_checkpoint_id: Option<CheckpointId>,
+    checkpoint_handle: Option<CheckpointHandle>,
+    input_schema: FnSchema,
+}
+
+impl HarnessContext {
+    pub fn new() -> Self {
+        HarnessContext {
+            application_properties: Properties::new(),
+            operator_id: OperatorId::default(),
+            task_id: TaskId {
+                job_id: JobId::default(),
+                task_number: 0,
+                num_tasks: 1,
+            },
+            checkpoint_id: CheckpointId::default(),
+            completed_checkpoint_id: None,
+            checkpoint_handle: None,
+            input_schema: FnSchema::Empty,
+        }
+    }
+
+    pub fn application_properties(mut self, application_properties: Properties) -> Self {
+        self.application_properties = application_properties;
+        self
+    }
+
+    /// Run the function as the `task_number` task of the `num_tasks` tasks
+    pub fn task(mut self, task_number: u16, num_tasks: u16) -> Self {
+        self.task_id.task_number = task_number;
+        self.task_id.num_tasks = num_tasks;
+        self
+    }
+
+    pub fn input_schema(mut self, input_schema: FnSchema) -> Self {
+        self.input_schema = input_schema;
+        self
+    }
+
+    /// Open the function from the `checkpoint` taken by `snapshot` of a harness, the checkpoint
+    /// is regarded as completed
+    pub fn restore(mut self, checkpoint: &Checkpoint) -> Self {
+        self.operator_id = checkpoint.operator_id;
+        self.task_id = checkpoint.task_id;
+        self.checkpoint_id = checkpoint.checkpoint_id;
+        self.completed_checkpoint_id = Some(checkpoint.checkpoint_id);
+        self.checkpoint_handle = Some(checkpoint.handle.clone());
+        self
+    }
+
+    /// Build the `Context` of the function, the pending timers in the checkpoint are restored
+    /// to the `timer_service`
+    pub(crate) fn to_fun_context(
+        &self,
+        output_schema: FnSchema,
+        timer_service: &TimerService,
+    ) -> Context {
+        Context {
+            application_id: "harness".to_string(),
+            application_properties: self.application_properties.clone(),
+            operator_id: self.operator_id,
+            task_id: self.task_id,
+            checkpoint_id: self.checkpoint_id,
+            completed_checkpoint_id: self.completed_checkpoint_id,
+            checkpoint_handle: timer_service.restore(self.checkpoint_handle.clone()),
+            input_schema: self.input_schema.clone(),
+            output_schema,
+            children: vec![],
+            parents: vec![],
+            task_context: None,
+            timer_service: timer_service.clone(),
+        }
+    }
+
+    pub(crate) fn checkpoint_context(
+        &self,
+        checkpoint_id: CheckpointId,
+    ) -> FunctionSnapshotContext {
+        FunctionSnapshotContext::new(self.operator_id, self.task_id, checkpoint_id, None, None)
+    }
+}
+
+impl Default for HarnessContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The elements emitted by the function in a harness
+#[derive(Debug, Default)]
+pub(crate) struct Output {
+    elements: Vec<Element>,
+}
+
+impl Output {
+    pub async fn collect(&mut self, mut elements: SendableElementStream) {
+        while let Some(element) = elements.next().await {
+            self.elements.push(element);
+        }
+    }
+
+    pub fn push(&mut self, element: Element) {
+        self.elements.push(element);
+    }
+
+    pub fn take_elements(&mut self) -> Vec<Element> {
+        std::mem::take(&mut self.elements)
+    }
+
+    pub fn take_records(&mut self) -> Vec<Record> {
+        self.take_elements()
+            .into_iter()
+            .filter(|element| element.is_record())
+            .map(|element| element.into_record())
+            .collect()
+    }
+}
+
+/// Build the `Checkpoint` of the function `handle` and the pending timers, `None` if the
+/// checkpoint is declined by the function
+pub(crate) fn to_checkpoint(
+    snapshot_context: &FunctionSnapshotContext,
+    handle: Option<CheckpointHandle>,
+    timer_service: &TimerService,
+) -> Option<Checkpoint> {
+    if snapshot_context.is_declined() {
+        return None;
+    }
+
+    let handle = timer_service.snapshot(handle.unwrap_or_default());
+    Some(Checkpoint {
+        operator_id: snapshot_context.operator_id,
+        task_id: snapshot_context.task_id,
+        checkpoint_id: snapshot_context.checkpoint_id,
+        completed_checkpoint_id: snapshot_context.completed_checkpoint_id,
+        handle,
+    })
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::core::data_types::Schema;
+use crate::core::element::{Element, FnSchema, Record};
+use crate::core::function::{KeySelectorFunction, ReduceFunction};
+use crate::core::timer::TimerService;
+use crate::core::window::{TWindow, Window, WindowAssigner, WindowAssignerContext};
+use crate::testing::{HarnessContext, Output};
+
+/// Drive a windowed `ReduceFunction` as the `WindowAssignerRunnable` and `ReduceRunnable` do,
+/// the values of a window are emitted when the watermark passes the window.
+///
+/// The emitted records are the key fields followed by the value fields, with the
+/// `trigger_window` set. The `input_schema` of the `HarnessContext` is the record schema.
+pub struct ReduceHarness<R>
+where
+    R: ReduceFunction,
+{
+    reduce: R,
+    key_selector: Option<Box<dyn KeySelectorFunction>>,
+    window_assigner: Box<dyn WindowAssigner>,
+    context: HarnessContext,
+
+    /// window -> key -> value
+    windows: HashMap<Window, BTreeMap<Record, Record>>,
+    /// the records in the windows before this window are late
+    limited_watermark_window: Window,
+    late_records: usize,
+    output: Output,
+}
+
+impl<R> ReduceHarness<R>
+where
+    R: ReduceFunction,
+{
+    pub fn new<W>(reduce: R, window_assigner: W) -> Self
+    where
+        W: WindowAssigner + 'static,
+    {
+        Self::with_context(reduce, window_assigner, HarnessContext::new())
+    }
+
+    pub fn with_context<W>(reduce: R, window_assigner: W, context: HarnessContext) -> Self
+    where
+        W: WindowAssigner + 'static,
+    {
+        ReduceHarness {
+            reduce,
+            key_selector: None,
+            window_assigner: Box::new(window_assigner),
+            context,
+            windows: HashMap::new(),
+            limited_watermark_window: Window::default(),
+            late_records: 0,
+            output: Output::default(),
+        }
+    }
+
+    /// Reduce the records by the key, all the records are of one key if not set
+    pub fn key_by<K>(mut self, key_selector: K) -> Self
+    where
+        K: KeySelectorFunction + 'static,
+    {
+        self.key_selector = Some(Box::new(key_selector));
+        self
+    }
+
+    pub async fn open(&mut self) -> crate::core::Result<()> {
+        let record_schema = self.context.input_schema.clone();
+        let input_schema = match &self.key_selector {
+            Some(key_selector) => {
+                let key_schema = key_selector.key_schema(record_schema.clone());
+                FnSchema::Tuple(record_schema.into(), key_schema.into())
+            }
+            None => record_schema,
+        };
+
+        let value_schema: Schema = self.reduce.schema(input_schema.clone()).into();
+        let output_schema = match input_schema.clone() {
+            FnSchema::Tuple(_record_schema, mut key_schema) => {
+                key_schema.merge(&value_schema);
+                FnSchema::Tuple(Schema::empty(), key_schema)
+            }
+            _ => FnSchema::Tuple(Schema::empty(), value_schema),
+        };
+
+        let context = self.context.clone().input_schema(input_schema);
+        let fun_context = context.to_fun_context(output_schema, &TimerService::default());
+
+        if let Some(key_selector) = self.key_selector.as_mut() {
+            key_selector.open(&fun_context).await?;
+        }
+        self.reduce.open(&fun_context).await
+    }
+
+    /// Process a record of the event time `timestamp`
+    pub async fn process_record(&mut self, timestamp: u64, mut record: Record) {
+        let windows = self
+            .window_assigner
+            .assign_windows(timestamp, WindowAssignerContext {});
+
+        let min_window_timestamp = self.limited_watermark_window.min_timestamp();
+        let acceptable = windows
+            .last()
+            .map(|window| window.min_timestamp() >= min_window_timestamp)
+            .unwrap_or(true);
+        if !acceptable {
+            self.late_records += 1;
+            return;
+        }
+
+        let key = match &self.key_selector {
+            Some(key_selector) => key_selector.get_key(&mut record).await,
+            None => Record::with_capacity(0),
+        };
+
+        for window in windows {
+            let values = self.windows.entry(window).or_default();
+            let value = match values.get_mut(&key) {
+                Some(value) => self.reduce.reduce(Some(value), &mut record),
+                None => self.reduce.reduce(None, &mut record),
+            };
+            values.insert(key.clone(), value);
+        }
+    }
+
+    /// Emit the values of the windows the watermark passes, in the order of the window end
+    pub async fn process_watermark(&mut self, timestamp: u64) {
+        let windows = self
+            .window_assigner
+            .assign_windows(timestamp, WindowAssignerContext {});
+        let min_watermark_window = match windows.first() {
+            Some(window) => window.clone(),
+            None => return,
+        };
+        let watermark_timestamp = min_watermark_window.min_timestamp();
+        self.limited_watermark_window = min_watermark_window;
+
+        let mut drop_windows: Vec<Window> = self
+            .windows
+            .keys()
+            .filter(|window| window.max_timestamp() <= watermark_timestamp)
+            .cloned()
+            .collect();
+        drop_windows.sort_by_key(|window| window.max_timestamp());
+
+        for window in drop_windows {
+            let values = self.windows.remove(&window).unwrap();
+            for (mut key, value) in values {
+                key.extend(value).expect("key value merge error");
+                key.trigger_window = Some(window.clone());
+                self.output.push(Element::Record(key));
+            }
+        }
+    }
+
+    pub async fn close(&mut self) -> crate::core::Result<()> {
+        if let Some(key_selector) = self.key_selector.as_mut() {
+            key_selector.close().await?;
+        }
+        self.reduce.close().await
+    }
+
+    /// Take the records emitted since the last call
+    pub fn take_records(&mut self) -> Vec<Record> {
+        self.output.take_records()
+    }
+
+    /// The number of the records dropped for the windows already emitted
+    pub fn late_records(&self) -> usize {
+        self.late_records
+    }
+
+    pub fn function(&self) -> &R {
+        &self.reduce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::data_types::{DataType, Field, Schema};
+    use crate::core::element::{FnSchema, Record};
+    use crate::core::window::{TWindow, Window};
+    use crate::functions::key_selector::SchemaKeySelector;
+    use crate::functions::reduce::{count, sum, SchemaReduceFunction};
+    use crate::functions::window::SlidingEventTimeWindows;
+    use crate::testing::{HarnessContext, ReduceHarness};
+
+    #[tokio::test]
+    pub async fn reduce_harness_test() {
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::String),
+            Field::new("value", DataType::Int64),
+        ]);
+        let window_assigner = SlidingEventTimeWindows::new(
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            None,
+        );
+        let context = HarnessContext::new().input_schema(FnSchema::from(&schema));
+        let mut harness = ReduceHarness::with_context(
+            SchemaReduceFunction::new(vec![sum("value"), count()], 1),
+            window_assigner,
+            context,
+        )
+        .key_by(SchemaKeySelector::new(vec!["name"]));
+        harness.open().await.unwrap();
+
+        for (timestamp, name, value) in [(1, "a", 1), (2, "b", 2), (3, "a", 3), (12, "a", 4)] {
+            let mut record = Record::new();
+            let mut writer = record.as_writer(schema.as_type_ids());
+            writer.set_str(name).unwrap();
+            writer.set_i64(value).unwrap();
+            harness.process_record(timestamp, record).await;
+        }
+
+        // the window [0, 10) is emitted when the watermark reaches the next window
+        harness.process_watermark(9).await;
+        assert!(harness.take_records().is_empty());
+        harness.process_watermark(10).await;
+
+        let output_schema = Schema::new(vec![
+            Field::new("name", DataType::String),
+            Field::new("sum(value)", DataType::Int64),
+            Field::new("count", DataType::UInt64),
+        ]);
+        let values: Vec<(String, i64, u64, u64)> = harness
+            .take_records()
+            .into_iter()
+            .map(|mut record| {
+                let window: Window = record.trigger_window().unwrap();
+                let reader = record.as_reader(output_schema.as_type_ids());
+                (
+                    reader.get_str(0).unwrap().to_string(),
+                    reader.get_i64(1).unwrap(),
+                    reader.get_u64(2).unwrap(),
+                    window.max_timestamp(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![("a".to_string(), 4, 2, 10), ("b".to_string(), 2, 1, 10)]
+        );
+
+        // late for the emitted window
+        harness.process_record(5, Record::new()).await;
+        assert_eq!(harness.late_records(), 1);
+    }
+}
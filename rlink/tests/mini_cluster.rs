@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rlink::core::backend::{CheckpointBackend, KeyedStateBackend};
+use rlink::core::data_stream::{TDataStream, TKeyedStream};
+use rlink::core::data_types::{DataType, Field, Schema};
+use rlink::core::env::{StreamApp, StreamExecutionEnvironment};
+use rlink::core::properties::{Properties, SystemProperties};
+use rlink::core::runtime::{CheckpointId, ClusterDescriptor};
+use rlink::functions::key_selector::SchemaKeySelector;
+use rlink::functions::source::{DataGenInputFormat, FieldGenerator};
+use rlink::testing::{clear_collected, CollectOutputFormat, MiniCluster};
+use rlink::utils::date_time::current_timestamp_millis;
+
+const NUMBER_OF_ROWS: i64 = 400;
+
+#[derive(Clone, Debug)]
+struct KeyByApp {}
+
+impl KeyByApp {
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int64),
+            Field::new("name", DataType::String),
+        ])
+    }
+}
+
+#[async_trait]
+impl StreamApp for KeyByApp {
+    async fn prepare_properties(&self, properties: &mut Properties) {
+        properties.set_application_name("mini-cluster-test");
+        properties.set_keyed_state_backend(KeyedStateBackend::Memory);
+        properties.set_checkpoint_interval(Duration::from_secs(1));
+        properties.set_checkpoint(CheckpointBackend::Memory);
+        properties.set_heartbeat_interval(Duration::from_millis(500));
+        properties.set_heartbeat_timeout(Duration::from_secs(2));
+    }
+
+    fn build_stream(&self, _properties: &Properties, env: &mut StreamExecutionEnvironment) {
+        let input_format = DataGenInputFormat::new(Self::schema(), 2)
+            .field("id", FieldGenerator::sequence(0, 1))
+            .field("name", FieldGenerator::choice(&["a", "b", "c"]))
+            .rows_per_second(40)
+            .number_of_rows(NUMBER_OF_ROWS as u64);
+
+        // the key_by is a network edge between the workers
+        env.register_source(input_format)
+            .key_by(SchemaKeySelector::new(vec!["name"]))
+            .add_sink(CollectOutputFormat::new("mini_cluster_test"));
+    }
+
+    async fn pre_worker_startup(&self, _cluster_descriptor: &ClusterDescriptor) {}
+}
+
+#[tokio::test]
+pub async fn mini_cluster_test() {
+    clear_collected("mini_cluster_test");
+
+    let mut cluster = MiniCluster::new(KeyByApp {}, 2);
+    cluster.start().await.unwrap();
+
+    let timeout = Duration::from_secs(30);
+    cluster
+        .wait_for_checkpoint(CheckpointId::default(), timeout)
+        .await
+        .unwrap();
+
+    // the checkpoint ids are the timestamps, a checkpoint after the kill is completed by the
+    // restarted workers
+    let worker_ids = cluster.worker_ids();
+    assert_eq!(worker_ids.len(), 2);
+    cluster.kill_worker(worker_ids[0].as_str()).unwrap();
+    assert_eq!(cluster.worker_ids().len(), 1);
+
+    let kill_timestamp = current_timestamp_millis();
+    cluster
+        .wait_for_checkpoint(CheckpointId(kill_timestamp), timeout)
+        .await
+        .unwrap();
+    assert_eq!(cluster.worker_ids(), worker_ids);
+
+    cluster
+        .wait_for_termination(Duration::from_secs(60))
+        .await
+        .unwrap();
+
+    // at-least-once, the records after the checkpoint are replayed
+    let schema = KeyByApp::schema();
+    let ids: BTreeSet<i64> = cluster
+        .collected("mini_cluster_test")
+        .into_iter()
+        .map(|mut record| record.as_reader(schema.as_type_ids()).get_i64(0).unwrap())
+        .collect();
+    assert_eq!(ids, (0..NUMBER_OF_ROWS).collect());
+}